@@ -10,7 +10,13 @@ module processing_unit #(
     parameter J = 0,
     parameter K = 0,
     parameter CODE_DISTANCE = 5,
-    parameter INIT_BOUNDARY_COST = 2
+    parameter INIT_BOUNDARY_COST = 2,
+    // generated designs (`qecp-cli tool generate-fpga`) give the address, boundary and channel addresses of each PU explicitly,
+    // instead of deriving them from (I, J, K) of a standard planar code
+    parameter USE_GENERATED_ADDRESSES = 0,
+    parameter [ADDRESS_WIDTH-1:0] INIT_ADDRESS = 0,
+    parameter INIT_HAS_BOUNDARY = 0,
+    parameter [(ADDRESS_WIDTH * (NEIGHBOR_COUNT + FAST_CHANNEL_COUNT))-1:0] CHANNEL_ADDRESSES = 0
 ) (
     clk,
     reset,
@@ -98,13 +104,19 @@ output is_processing;
 assign is_processing = union_out_channels_valid | (|union_in_channels_valid) | (|direct_out_channels_valid) | (|direct_in_channels_valid) | pending_direct_message_valid_delayed | my_stored_direct_message_valid;
 
 wire [ADDRESS_WIDTH-1:0] init_address;
-assign init_address[ADDRESS_WIDTH-1:PER_DIMENSION_WIDTH*2] = K;
-assign init_address[PER_DIMENSION_WIDTH*2-1:PER_DIMENSION_WIDTH] = I;
-assign init_address[PER_DIMENSION_WIDTH-1:0] = J;
-
 `define init_has_boundary(i, j, k) ((j==0) || (j==(CODE_DISTANCE-2)) || k==0)
 wire init_has_boundary;
-assign init_has_boundary = `init_has_boundary(I, J, K);
+generate
+    if (USE_GENERATED_ADDRESSES) begin: generated_init_address
+        assign init_address = INIT_ADDRESS;
+        assign init_has_boundary = INIT_HAS_BOUNDARY;
+    end else begin: planar_init_address
+        assign init_address[ADDRESS_WIDTH-1:PER_DIMENSION_WIDTH*2] = K;
+        assign init_address[PER_DIMENSION_WIDTH*2-1:PER_DIMENSION_WIDTH] = I;
+        assign init_address[PER_DIMENSION_WIDTH-1:0] = J;
+        assign init_has_boundary = `init_has_boundary(I, J, K);
+    end
+endgenerate
 
 wire [BOUNDARY_WIDTH-1:0] init_boundary_cost;
 assign init_boundary_cost = INIT_BOUNDARY_COST;
@@ -150,7 +162,7 @@ reg [ADDRESS_WIDTH-1:0] compare_solver_result_stored;
 // tree compare solver
 tree_compare_solver #(
     .DATA_WIDTH(ADDRESS_WIDTH),
-    .CHANNEL_COUNT(NEIGHBOR_COUNT)
+    .CHANNEL_COUNT(CHANNEL_COUNT)
 ) u_tree_compare_solver (
     .default_value(compare_solver_default_addr),
     .values(compare_solver_addrs),
@@ -165,6 +177,10 @@ wire [(ADDRESS_WIDTH * CHANNEL_COUNT)-1:0] channel_addresses;
 
 // Todo simplify this logic. This is not easily understandable in debugging
 generate
+    if (USE_GENERATED_ADDRESSES) begin: generated_channel_addresses
+    // neighbors first and then fast channels, in the same order as the channels connected to this PU
+    assign channel_addresses = CHANNEL_ADDRESSES;
+    end else begin: planar_channel_addresses
     // address order: top, bottom, left, right, down, up
     if (I>0) begin
         if (I<(CODE_DISTANCE-1)) begin
@@ -514,11 +530,12 @@ generate
             end
         end  // if (j>0)
     end  // if (i>0)
+    end  // if (USE_GENERATED_ADDRESSES)
 endgenerate
 
 tree_distance_3d_solver #(
     .PER_DIMENSION_WIDTH(PER_DIMENSION_WIDTH),
-    .CHANNEL_COUNT(NEIGHBOR_COUNT)
+    .CHANNEL_COUNT(CHANNEL_COUNT)
 ) u_tree_distance_3d_solver (
     .points(channel_addresses),
     .target(distance_solver_target),
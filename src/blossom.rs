@@ -0,0 +1,929 @@
+//! # Blossom
+//!
+//! Exact weighted matching in pure Rust, used by the MWPM decoders when the `blossom_v` feature is not enabled.
+//! This is the O(n^3) primal-dual blossom algorithm of Edmonds and Galil, ported from the public domain `mwmatching.py`
+//! by Joris van Rantwijk. All computations are on integers: with integer weights, the slack of an edge between two
+//! S-vertices is always even, so the dual variables never become fractional.
+//!
+
+/// the maximum weight matching of a general graph, return the peer of each vertex;
+/// if `max_cardinality` is true, only the matchings of maximum cardinality are considered
+pub fn maximum_weight_matching(
+    node_num: usize,
+    weighted_edges: &[(usize, usize, i64)],
+    max_cardinality: bool,
+) -> Vec<Option<usize>> {
+    if weighted_edges.is_empty() {
+        return vec![None; node_num];
+    }
+    let mut solver = BlossomSolver::new(node_num, weighted_edges);
+    solver.solve(max_cardinality);
+    solver
+        .mate
+        .iter()
+        .map(|mate| mate.map(|endpoint| solver.endpoint[endpoint]))
+        .collect()
+}
+
+/// the minimum weight perfect matching, return the peer of each vertex; panic if no perfect matching exists
+pub fn minimum_weight_perfect_matching(node_num: usize, weighted_edges: &[(usize, usize, i64)]) -> Vec<usize> {
+    // among the matchings of maximum cardinality, maximizing `offset - weight` is the same as minimizing `weight`
+    let offset = weighted_edges.iter().map(|(_, _, weight)| *weight).max().unwrap_or(0) + 1;
+    let complement_edges: Vec<(usize, usize, i64)> =
+        weighted_edges.iter().map(|&(i, j, weight)| (i, j, offset - weight)).collect();
+    maximum_weight_matching(node_num, &complement_edges, true)
+        .into_iter()
+        .enumerate()
+        .map(|(vertex, mate)| {
+            mate.unwrap_or_else(|| panic!("vertex {vertex} is not matched, perfect matching doesn't exist"))
+        })
+        .collect()
+}
+
+/// label of a top-level blossom or a vertex
+const FREE: i8 = 0;
+const S_LABEL: i8 = 1;
+const T_LABEL: i8 = 2;
+/// temporary breadcrumb of [`BlossomSolver::scan_blossom`]
+const BREADCRUMB: i8 = 5;
+
+/// the state of the algorithm; vertices are `0..n` and non-trivial blossoms are `n..2n`,
+/// an endpoint `p` refers to vertex `endpoint[p]` of edge `p / 2`, and `p ^ 1` is the other end
+struct BlossomSolver {
+    vertex_num: usize,
+    edges: Vec<(usize, usize, i64)>,
+    endpoint: Vec<usize>,
+    /// the remote endpoints of the edges incident to each vertex
+    neighbor_endpoints: Vec<Vec<usize>>,
+    /// the remote endpoint of the matched edge of each vertex
+    mate: Vec<Option<usize>>,
+    label: Vec<i8>,
+    /// the endpoint through which a labeled vertex or blossom got its label
+    label_end: Vec<Option<usize>>,
+    /// the top-level blossom containing each vertex
+    in_blossom: Vec<usize>,
+    blossom_parent: Vec<Option<usize>>,
+    /// the sub-blossoms of a blossom in cyclic order, starting from the one containing the base
+    blossom_children: Vec<Vec<usize>>,
+    blossom_base: Vec<Option<usize>>,
+    /// `blossom_endpoints[b][i]` connects `blossom_children[b][i]` and `blossom_children[b][i + 1]`
+    blossom_endpoints: Vec<Vec<usize>>,
+    /// the least-slack edge to a different S-blossom
+    best_edge: Vec<Option<usize>>,
+    /// the least-slack edges to each neighboring S-blossom of a non-trivial S-blossom
+    blossom_best_edges: Vec<Option<Vec<usize>>>,
+    unused_blossoms: Vec<usize>,
+    dual_variables: Vec<i64>,
+    /// whether the edge has zero slack, only valid in the current stage
+    allowed_edge: Vec<bool>,
+    queue: Vec<usize>,
+}
+
+/// index a cyclic list with a possibly negative index
+fn cyclic<T: Copy>(list: &[T], index: isize) -> T {
+    list[index.rem_euclid(list.len() as isize) as usize]
+}
+
+impl BlossomSolver {
+    fn new(node_num: usize, weighted_edges: &[(usize, usize, i64)]) -> Self {
+        let edges = weighted_edges.to_vec();
+        let vertex_num = edges.iter().fold(node_num, |num, &(i, j, _)| num.max(i + 1).max(j + 1));
+        let max_weight = edges.iter().map(|(_, _, weight)| *weight).max().unwrap_or(0).max(0);
+        let endpoint: Vec<usize> = (0..2 * edges.len())
+            .map(|p| if p % 2 == 0 { edges[p / 2].0 } else { edges[p / 2].1 })
+            .collect();
+        let mut neighbor_endpoints = vec![vec![]; vertex_num];
+        for (k, &(i, j, _)) in edges.iter().enumerate() {
+            assert_ne!(i, j, "invalid edge between the same vertex {i}");
+            neighbor_endpoints[i].push(2 * k + 1);
+            neighbor_endpoints[j].push(2 * k);
+        }
+        let mut dual_variables = vec![max_weight; vertex_num];
+        dual_variables.extend(std::iter::repeat(0).take(vertex_num));
+        Self {
+            vertex_num,
+            endpoint,
+            neighbor_endpoints,
+            mate: vec![None; vertex_num],
+            label: vec![FREE; 2 * vertex_num],
+            label_end: vec![None; 2 * vertex_num],
+            in_blossom: (0..vertex_num).collect(),
+            blossom_parent: vec![None; 2 * vertex_num],
+            blossom_children: vec![vec![]; 2 * vertex_num],
+            blossom_base: (0..vertex_num)
+                .map(Some)
+                .chain(std::iter::repeat(None).take(vertex_num))
+                .collect(),
+            blossom_endpoints: vec![vec![]; 2 * vertex_num],
+            best_edge: vec![None; 2 * vertex_num],
+            blossom_best_edges: vec![None; 2 * vertex_num],
+            unused_blossoms: (vertex_num..2 * vertex_num).collect(),
+            dual_variables,
+            allowed_edge: vec![false; edges.len()],
+            queue: vec![],
+            edges,
+        }
+    }
+
+    /// twice the slack of an edge, which is never negative
+    fn slack(&self, k: usize) -> i64 {
+        let (i, j, weight) = self.edges[k];
+        self.dual_variables[i] + self.dual_variables[j] - 2 * weight
+    }
+
+    fn blossom_leaves(&self, b: usize) -> Vec<usize> {
+        let mut leaves = vec![];
+        let mut stack = vec![b];
+        while let Some(t) = stack.pop() {
+            if t < self.vertex_num {
+                leaves.push(t);
+            } else {
+                stack.extend(self.blossom_children[t].iter().rev());
+            }
+        }
+        leaves
+    }
+
+    /// assign label `t` to the top-level blossom containing vertex `w`, reached through endpoint `p`
+    fn assign_label(&mut self, w: usize, t: i8, p: Option<usize>) {
+        let b = self.in_blossom[w];
+        debug_assert!(self.label[w] == FREE && self.label[b] == FREE);
+        self.label[w] = t;
+        self.label[b] = t;
+        self.label_end[w] = p;
+        self.label_end[b] = p;
+        self.best_edge[w] = None;
+        self.best_edge[b] = None;
+        if t == S_LABEL {
+            let leaves = self.blossom_leaves(b);
+            self.queue.extend(leaves);
+        } else {
+            // the mate of the base of a T-blossom becomes an S-vertex
+            let base = self.blossom_base[b].unwrap();
+            let mate = self.mate[base].expect("base of a T-blossom must be matched");
+            self.assign_label(self.endpoint[mate], S_LABEL, Some(mate ^ 1));
+        }
+    }
+
+    /// trace back from S-vertices `v` and `w` to find a new blossom, return its base or `None` for an augmenting path
+    fn scan_blossom(&mut self, v: usize, w: usize) -> Option<usize> {
+        let mut path = vec![];
+        let mut base = None;
+        let (mut v, mut w) = (Some(v), Some(w));
+        while let Some(current) = v {
+            let b = self.in_blossom[current];
+            if self.label[b] == BREADCRUMB {
+                base = self.blossom_base[b];
+                break;
+            }
+            debug_assert_eq!(self.label[b], S_LABEL);
+            path.push(b);
+            self.label[b] = BREADCRUMB;
+            v = match self.label_end[b] {
+                None => None, // the root of an alternating tree
+                Some(p) => {
+                    let t = self.in_blossom[self.endpoint[p]];
+                    debug_assert_eq!(self.label[t], T_LABEL);
+                    Some(self.endpoint[self.label_end[t].unwrap()])
+                }
+            };
+            // alternate between the two paths
+            if w.is_some() {
+                std::mem::swap(&mut v, &mut w);
+            }
+        }
+        for b in path.into_iter() {
+            self.label[b] = S_LABEL;
+        }
+        base
+    }
+
+    /// construct a new blossom with the given base, containing edge `k` which connects two S-vertices
+    fn add_blossom(&mut self, base: usize, k: usize) {
+        let (mut v, mut w, _) = self.edges[k];
+        let bb = self.in_blossom[base];
+        let mut bv = self.in_blossom[v];
+        let mut bw = self.in_blossom[w];
+        let b = self.unused_blossoms.pop().expect("at most n non-trivial blossoms");
+        self.blossom_base[b] = Some(base);
+        self.blossom_parent[b] = None;
+        self.blossom_parent[bb] = Some(b);
+        let mut path = vec![];
+        let mut endpoints = vec![];
+        // trace back from v to base
+        while bv != bb {
+            self.blossom_parent[bv] = Some(b);
+            path.push(bv);
+            let label_end = self.label_end[bv].unwrap();
+            endpoints.push(label_end);
+            v = self.endpoint[label_end];
+            bv = self.in_blossom[v];
+        }
+        path.push(bb);
+        path.reverse();
+        endpoints.reverse();
+        endpoints.push(2 * k);
+        // trace back from w to base
+        while bw != bb {
+            self.blossom_parent[bw] = Some(b);
+            path.push(bw);
+            let label_end = self.label_end[bw].unwrap();
+            endpoints.push(label_end ^ 1);
+            w = self.endpoint[label_end];
+            bw = self.in_blossom[w];
+        }
+        debug_assert_eq!(self.label[bb], S_LABEL);
+        self.label[b] = S_LABEL;
+        self.label_end[b] = self.label_end[bb];
+        self.dual_variables[b] = 0;
+        for leaf in self.blossom_leaves_of(&path) {
+            if self.label[self.in_blossom[leaf]] == T_LABEL {
+                // former T-vertices are now S-vertices inside the blossom
+                self.queue.push(leaf);
+            }
+            self.in_blossom[leaf] = b;
+        }
+        // compute the least-slack edges to the neighboring S-blossoms
+        let mut best_edge_to: Vec<Option<usize>> = vec![None; 2 * self.vertex_num];
+        for &sub_blossom in path.iter() {
+            let neighbor_lists: Vec<Vec<usize>> = match self.blossom_best_edges[sub_blossom].take() {
+                Some(best_edges) => vec![best_edges],
+                None => self
+                    .blossom_leaves(sub_blossom)
+                    .into_iter()
+                    .map(|leaf| self.neighbor_endpoints[leaf].iter().map(|p| p / 2).collect())
+                    .collect(),
+            };
+            for neighbor_list in neighbor_lists.iter() {
+                for &k in neighbor_list.iter() {
+                    // the end of the edge which is outside of the new blossom
+                    let (i, j, _) = self.edges[k];
+                    let j = if self.in_blossom[j] == b { i } else { j };
+                    let bj = self.in_blossom[j];
+                    if bj != b
+                        && self.label[bj] == S_LABEL
+                        && best_edge_to[bj].map_or(true, |best| self.slack(k) < self.slack(best))
+                    {
+                        best_edge_to[bj] = Some(k);
+                    }
+                }
+            }
+            self.best_edge[sub_blossom] = None;
+        }
+        let best_edges: Vec<usize> = best_edge_to.into_iter().flatten().collect();
+        self.best_edge[b] = None;
+        for &k in best_edges.iter() {
+            if self.best_edge[b].map_or(true, |best| self.slack(k) < self.slack(best)) {
+                self.best_edge[b] = Some(k);
+            }
+        }
+        self.blossom_best_edges[b] = Some(best_edges);
+        self.blossom_children[b] = path;
+        self.blossom_endpoints[b] = endpoints;
+    }
+
+    fn blossom_leaves_of(&self, blossoms: &[usize]) -> Vec<usize> {
+        blossoms.iter().flat_map(|&b| self.blossom_leaves(b)).collect()
+    }
+
+    /// expand the given top-level blossom
+    fn expand_blossom(&mut self, b: usize, end_stage: bool) {
+        let children = self.blossom_children[b].clone();
+        for &s in children.iter() {
+            self.blossom_parent[s] = None;
+            if s < self.vertex_num {
+                self.in_blossom[s] = s;
+            } else if end_stage && self.dual_variables[s] == 0 {
+                // recursively expand this sub-blossom
+                self.expand_blossom(s, end_stage);
+            } else {
+                for leaf in self.blossom_leaves(s) {
+                    self.in_blossom[leaf] = s;
+                }
+            }
+        }
+        // if we expand a T-blossom during a stage, its sub-blossoms must be relabeled
+        if !end_stage && self.label[b] == T_LABEL {
+            let endpoints = self.blossom_endpoints[b].clone();
+            let label_end = self.label_end[b].unwrap();
+            // start at the sub-blossom through which the expanding blossom obtained its label,
+            // and relabel sub-blossoms until we reach the base
+            let entry_child = self.in_blossom[self.endpoint[label_end ^ 1]];
+            let mut j = children.iter().position(|&child| child == entry_child).unwrap() as isize;
+            let (j_step, endpoint_trick): (isize, usize) = if j & 1 == 1 {
+                // start index is odd; go forward and wrap
+                j -= children.len() as isize;
+                (1, 0)
+            } else {
+                // start index is even; go backward
+                (-1, 1)
+            };
+            let mut p = label_end;
+            while j != 0 {
+                // relabel the T-sub-blossom
+                self.label[self.endpoint[p ^ 1]] = FREE;
+                let q = cyclic(&endpoints, j - endpoint_trick as isize) ^ endpoint_trick ^ 1;
+                self.label[self.endpoint[q]] = FREE;
+                self.assign_label(self.endpoint[p ^ 1], T_LABEL, Some(p));
+                // step to the next S-sub-blossom and note its forward endpoint
+                self.allowed_edge[cyclic(&endpoints, j - endpoint_trick as isize) / 2] = true;
+                j += j_step;
+                p = cyclic(&endpoints, j - endpoint_trick as isize) ^ endpoint_trick;
+                // step to the next T-sub-blossom
+                self.allowed_edge[p / 2] = true;
+                j += j_step;
+            }
+            // relabel the base T-sub-blossom without stepping through to its mate
+            let bv = cyclic(&children, j);
+            self.label[self.endpoint[p ^ 1]] = T_LABEL;
+            self.label[bv] = T_LABEL;
+            self.label_end[self.endpoint[p ^ 1]] = Some(p);
+            self.label_end[bv] = Some(p);
+            self.best_edge[bv] = None;
+            // continue along the blossom until we get back to the entry child
+            j += j_step;
+            while cyclic(&children, j) != entry_child {
+                let bv = cyclic(&children, j);
+                if self.label[bv] == S_LABEL {
+                    // this sub-blossom just got label S through one of its neighbors
+                    j += j_step;
+                    continue;
+                }
+                // if the sub-blossom contains a reachable vertex, assign label T to it
+                let leaves = self.blossom_leaves(bv);
+                let v = *leaves
+                    .iter()
+                    .find(|&&leaf| self.label[leaf] != FREE)
+                    .unwrap_or(leaves.last().unwrap());
+                if self.label[v] != FREE {
+                    debug_assert_eq!(self.label[v], T_LABEL);
+                    debug_assert_eq!(self.in_blossom[v], bv);
+                    self.label[v] = FREE;
+                    let base_mate = self.mate[self.blossom_base[bv].unwrap()].unwrap();
+                    self.label[self.endpoint[base_mate]] = FREE;
+                    self.assign_label(v, T_LABEL, self.label_end[v]);
+                }
+                j += j_step;
+            }
+        }
+        // recycle the blossom number
+        self.label[b] = -1;
+        self.label_end[b] = None;
+        self.blossom_children[b] = vec![];
+        self.blossom_endpoints[b] = vec![];
+        self.blossom_base[b] = None;
+        self.blossom_best_edges[b] = None;
+        self.best_edge[b] = None;
+        self.unused_blossoms.push(b);
+    }
+
+    /// swap matched and unmatched edges over an alternating path through blossom `b` between vertex `v` and the base
+    fn augment_blossom(&mut self, b: usize, v: usize) {
+        // bubble up through the blossom tree from vertex v to an immediate sub-blossom of b
+        let mut t = v;
+        while self.blossom_parent[t] != Some(b) {
+            t = self.blossom_parent[t].unwrap();
+        }
+        if t >= self.vertex_num {
+            self.augment_blossom(t, v);
+        }
+        let children = self.blossom_children[b].clone();
+        let endpoints = self.blossom_endpoints[b].clone();
+        let i = children.iter().position(|&child| child == t).unwrap();
+        let mut j = i as isize;
+        let (j_step, endpoint_trick): (isize, usize) = if i & 1 == 1 {
+            j -= children.len() as isize;
+            (1, 0)
+        } else {
+            (-1, 1)
+        };
+        // move along the blossom until we get to the base
+        while j != 0 {
+            j += j_step;
+            let t = cyclic(&children, j);
+            let p = cyclic(&endpoints, j - endpoint_trick as isize) ^ endpoint_trick;
+            if t >= self.vertex_num {
+                self.augment_blossom(t, self.endpoint[p]);
+            }
+            j += j_step;
+            let t = cyclic(&children, j);
+            if t >= self.vertex_num {
+                self.augment_blossom(t, self.endpoint[p ^ 1]);
+            }
+            // match the edge connecting those sub-blossoms
+            self.mate[self.endpoint[p]] = Some(p ^ 1);
+            self.mate[self.endpoint[p ^ 1]] = Some(p);
+        }
+        // rotate the list of sub-blossoms to put the new base at the front
+        self.blossom_children[b] = children[i..].iter().chain(children[..i].iter()).cloned().collect();
+        self.blossom_endpoints[b] = endpoints[i..].iter().chain(endpoints[..i].iter()).cloned().collect();
+        self.blossom_base[b] = self.blossom_base[self.blossom_children[b][0]];
+        debug_assert_eq!(self.blossom_base[b], Some(v));
+    }
+
+    /// swap matched and unmatched edges over an alternating path between two single vertices through edge `k`
+    fn augment_matching(&mut self, k: usize) {
+        let (v, w, _) = self.edges[k];
+        for (mut s, mut p) in [(v, 2 * k + 1), (w, 2 * k)] {
+            // match vertex s to remote endpoint p, then trace back from s until we find a single vertex
+            loop {
+                let bs = self.in_blossom[s];
+                debug_assert_eq!(self.label[bs], S_LABEL);
+                if bs >= self.vertex_num {
+                    self.augment_blossom(bs, s);
+                }
+                self.mate[s] = Some(p);
+                let Some(label_end) = self.label_end[bs] else {
+                    // reached a single vertex
+                    break;
+                };
+                let t = self.endpoint[label_end];
+                let bt = self.in_blossom[t];
+                debug_assert_eq!(self.label[bt], T_LABEL);
+                let bt_label_end = self.label_end[bt].unwrap();
+                s = self.endpoint[bt_label_end];
+                let j = self.endpoint[bt_label_end ^ 1];
+                debug_assert_eq!(self.blossom_base[bt], Some(t));
+                if bt >= self.vertex_num {
+                    self.augment_blossom(bt, j);
+                }
+                self.mate[j] = Some(bt_label_end);
+                p = bt_label_end ^ 1;
+            }
+        }
+    }
+
+    fn solve(&mut self, max_cardinality: bool) {
+        let vertex_num = self.vertex_num;
+        // each stage augments the matching by one edge
+        for _ in 0..vertex_num {
+            self.label.iter_mut().for_each(|label| *label = FREE);
+            self.best_edge.iter_mut().for_each(|best_edge| *best_edge = None);
+            self.blossom_best_edges[vertex_num..]
+                .iter_mut()
+                .for_each(|best_edges| *best_edges = None);
+            self.allowed_edge.iter_mut().for_each(|allowed| *allowed = false);
+            self.queue.clear();
+            // label single blossoms and vertices with S and put them in the queue
+            for v in 0..vertex_num {
+                if self.mate[v].is_none() && self.label[self.in_blossom[v]] == FREE {
+                    self.assign_label(v, S_LABEL, None);
+                }
+            }
+            let mut augmented = false;
+            loop {
+                // grow the alternating trees by scanning the neighbors of S-vertices
+                while !augmented {
+                    let Some(v) = self.queue.pop() else {
+                        break;
+                    };
+                    debug_assert_eq!(self.label[self.in_blossom[v]], S_LABEL);
+                    for index in 0..self.neighbor_endpoints[v].len() {
+                        let p = self.neighbor_endpoints[v][index];
+                        let k = p / 2;
+                        let w = self.endpoint[p];
+                        if self.in_blossom[v] == self.in_blossom[w] {
+                            // this edge is internal to a blossom
+                            continue;
+                        }
+                        let mut k_slack = 0;
+                        if !self.allowed_edge[k] {
+                            k_slack = self.slack(k);
+                            if k_slack <= 0 {
+                                self.allowed_edge[k] = true;
+                            }
+                        }
+                        if self.allowed_edge[k] {
+                            if self.label[self.in_blossom[w]] == FREE {
+                                // w is a free vertex or an unreached vertex inside a T-blossom
+                                self.assign_label(w, T_LABEL, Some(p ^ 1));
+                            } else if self.label[self.in_blossom[w]] == S_LABEL {
+                                match self.scan_blossom(v, w) {
+                                    Some(base) => self.add_blossom(base, k),
+                                    None => {
+                                        self.augment_matching(k);
+                                        augmented = true;
+                                        break;
+                                    }
+                                }
+                            } else if self.label[w] == FREE {
+                                // w is inside a T-blossom, but w itself has not yet been reached from outside
+                                debug_assert_eq!(self.label[self.in_blossom[w]], T_LABEL);
+                                self.label[w] = T_LABEL;
+                                self.label_end[w] = Some(p ^ 1);
+                            }
+                        } else if self.label[self.in_blossom[w]] == S_LABEL {
+                            // keep track of the least-slack non-allowable edge to a different S-blossom
+                            let b = self.in_blossom[v];
+                            if self.best_edge[b].map_or(true, |best| k_slack < self.slack(best)) {
+                                self.best_edge[b] = Some(k);
+                            }
+                        } else if self.label[w] == FREE {
+                            // w is a free vertex or an unreached vertex inside a T-blossom
+                            if self.best_edge[w].map_or(true, |best| k_slack < self.slack(best)) {
+                                self.best_edge[w] = Some(k);
+                            }
+                        }
+                    }
+                }
+                if augmented {
+                    break;
+                }
+                // there is no augmenting path under these constraints, compute the dual update
+                let mut delta: Option<(i64, u8, usize)> = None; // (delta, type, edge or blossom)
+                if !max_cardinality {
+                    // type 1: the minimum value of any vertex dual
+                    delta = Some((*self.dual_variables[..vertex_num].iter().min().unwrap(), 1, 0));
+                }
+                // type 2: the minimum slack on any edge between an S-vertex and a free vertex
+                for v in 0..vertex_num {
+                    if self.label[self.in_blossom[v]] == FREE {
+                        if let Some(best) = self.best_edge[v] {
+                            let d = self.slack(best);
+                            if delta.map_or(true, |(delta, _, _)| d < delta) {
+                                delta = Some((d, 2, best));
+                            }
+                        }
+                    }
+                }
+                // type 3: half the minimum slack on any edge between a pair of S-blossoms
+                for b in 0..2 * vertex_num {
+                    if self.blossom_parent[b].is_none() && self.label[b] == S_LABEL {
+                        if let Some(best) = self.best_edge[b] {
+                            let k_slack = self.slack(best);
+                            debug_assert_eq!(k_slack % 2, 0);
+                            let d = k_slack / 2;
+                            if delta.map_or(true, |(delta, _, _)| d < delta) {
+                                delta = Some((d, 3, best));
+                            }
+                        }
+                    }
+                }
+                // type 4: the minimum z variable of any T-blossom
+                for b in vertex_num..2 * vertex_num {
+                    if self.blossom_base[b].is_some()
+                        && self.blossom_parent[b].is_none()
+                        && self.label[b] == T_LABEL
+                        && delta.map_or(true, |(delta, _, _)| self.dual_variables[b] < delta)
+                    {
+                        delta = Some((self.dual_variables[b], 4, b));
+                    }
+                }
+                let (delta, delta_type, delta_target) = delta.unwrap_or_else(|| {
+                    // no further improvement possible, max-cardinality optimum reached; do a final delta update to make
+                    // the optimum verifiable
+                    debug_assert!(max_cardinality);
+                    ((*self.dual_variables[..vertex_num].iter().min().unwrap()).max(0), 1, 0)
+                });
+                // update the dual variables
+                for v in 0..vertex_num {
+                    match self.label[self.in_blossom[v]] {
+                        S_LABEL => self.dual_variables[v] -= delta,
+                        T_LABEL => self.dual_variables[v] += delta,
+                        _ => {}
+                    }
+                }
+                for b in vertex_num..2 * vertex_num {
+                    if self.blossom_base[b].is_some() && self.blossom_parent[b].is_none() {
+                        match self.label[b] {
+                            S_LABEL => self.dual_variables[b] += delta,
+                            T_LABEL => self.dual_variables[b] -= delta,
+                            _ => {}
+                        }
+                    }
+                }
+                match delta_type {
+                    1 => break, // no further improvement possible
+                    2 | 3 => {
+                        let k = delta_target;
+                        self.allowed_edge[k] = true;
+                        let (mut i, j, _) = self.edges[k];
+                        if self.label[self.in_blossom[i]] == FREE {
+                            i = j;
+                        }
+                        debug_assert_eq!(self.label[self.in_blossom[i]], S_LABEL);
+                        self.queue.push(i);
+                    }
+                    _ => self.expand_blossom(delta_target, false),
+                }
+            }
+            if !augmented {
+                // no more augmenting path can be found, the matching is optimal
+                break;
+            }
+            // end of a stage; expand all S-blossoms which have zero dual
+            for b in vertex_num..2 * vertex_num {
+                if self.blossom_parent[b].is_none()
+                    && self.blossom_base[b].is_some()
+                    && self.label[b] == S_LABEL
+                    && self.dual_variables[b] == 0
+                {
+                    self.expand_blossom(b, true);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::{Rng, SeedableRng};
+    use crate::reproducible_rand::Xoroshiro128StarStar;
+
+    /// exhaustive search of the minimum weight perfect matching, only for small graphs
+    fn brute_force_minimum_weight(node_num: usize, weighted_edges: &[(usize, usize, i64)]) -> Option<i64> {
+        let mut weights = vec![vec![None; node_num]; node_num];
+        for &(i, j, weight) in weighted_edges.iter() {
+            weights[i][j] = Some(weight);
+            weights[j][i] = Some(weight);
+        }
+        fn search(weights: &[Vec<Option<i64>>], matched: &mut Vec<bool>) -> Option<i64> {
+            let Some(i) = matched.iter().position(|&matched| !matched) else {
+                return Some(0);
+            };
+            matched[i] = true;
+            let mut best: Option<i64> = None;
+            for j in 0..matched.len() {
+                if let (false, Some(weight)) = (matched[j], weights[i][j]) {
+                    matched[j] = true;
+                    if let Some(rest) = search(weights, matched) {
+                        best = Some(best.map_or(weight + rest, |best| best.min(weight + rest)));
+                    }
+                    matched[j] = false;
+                }
+            }
+            matched[i] = false;
+            best
+        }
+        search(&weights, &mut vec![false; node_num])
+    }
+
+    /// run the matching and convert the result into the `-1`-terminated format of `mwmatching.py`
+    fn python_style_matching(weighted_edges: &[(usize, usize, i64)], max_cardinality: bool) -> Vec<isize> {
+        maximum_weight_matching(0, weighted_edges, max_cardinality)
+            .into_iter()
+            .map(|mate| mate.map_or(-1, |mate| mate as isize))
+            .collect()
+    }
+
+    #[test]
+    fn blossom_maximum_weight_matching() {
+        // cargo test blossom_maximum_weight_matching -- --nocapture
+        // the test cases of `mwmatching.py`
+        assert_eq!(python_style_matching(&[(0, 1, 1)], false), vec![1, 0]);
+        assert_eq!(python_style_matching(&[(1, 2, 10), (2, 3, 11)], false), vec![-1, -1, 3, 2]);
+        assert_eq!(
+            python_style_matching(&[(1, 2, 5), (2, 3, 11), (3, 4, 5)], false),
+            vec![-1, -1, 3, 2, -1]
+        );
+        assert_eq!(
+            python_style_matching(&[(1, 2, 5), (2, 3, 11), (3, 4, 5)], true),
+            vec![-1, 2, 1, 4, 3]
+        );
+        // negative weights
+        assert_eq!(
+            python_style_matching(&[(1, 2, 2), (1, 3, -2), (2, 3, 1), (2, 4, -1), (3, 4, -6)], false),
+            vec![-1, 2, 1, -1, -1]
+        );
+        assert_eq!(
+            python_style_matching(&[(1, 2, 2), (1, 3, -2), (2, 3, 1), (2, 4, -1), (3, 4, -6)], true),
+            vec![-1, 3, 4, 1, 2]
+        );
+        // create S-blossom and use it for augmentation
+        assert_eq!(
+            python_style_matching(&[(1, 2, 8), (1, 3, 9), (2, 3, 10), (3, 4, 7)], false),
+            vec![-1, 2, 1, 4, 3]
+        );
+        assert_eq!(
+            python_style_matching(&[(1, 2, 8), (1, 3, 9), (2, 3, 10), (3, 4, 7), (1, 6, 5), (4, 5, 6)], false),
+            vec![-1, 6, 3, 2, 5, 4, 1]
+        );
+        // create S-blossom, relabel as T-blossom, use for augmentation
+        assert_eq!(
+            python_style_matching(&[(1, 2, 9), (1, 3, 8), (2, 3, 10), (1, 4, 5), (4, 5, 4), (1, 6, 3)], false),
+            vec![-1, 6, 3, 2, 5, 4, 1]
+        );
+        assert_eq!(
+            python_style_matching(&[(1, 2, 9), (1, 3, 8), (2, 3, 10), (1, 4, 5), (4, 5, 3), (1, 6, 4)], false),
+            vec![-1, 6, 3, 2, 5, 4, 1]
+        );
+        assert_eq!(
+            python_style_matching(&[(1, 2, 9), (1, 3, 8), (2, 3, 10), (1, 4, 5), (4, 5, 3), (3, 6, 4)], false),
+            vec![-1, 2, 1, 6, 5, 4, 3]
+        );
+        // create nested S-blossom, use for augmentation
+        assert_eq!(
+            python_style_matching(
+                &[(1, 2, 9), (1, 3, 9), (2, 3, 10), (2, 4, 8), (3, 5, 8), (4, 5, 10), (5, 6, 6)],
+                false
+            ),
+            vec![-1, 3, 4, 1, 2, 6, 5]
+        );
+        // create S-blossom, relabel as S, include in nested S-blossom
+        assert_eq!(
+            python_style_matching(
+                &[
+                    (1, 2, 10),
+                    (1, 7, 10),
+                    (2, 3, 12),
+                    (3, 4, 20),
+                    (3, 5, 20),
+                    (4, 5, 25),
+                    (5, 6, 10),
+                    (6, 7, 10),
+                    (7, 8, 8)
+                ],
+                false
+            ),
+            vec![-1, 2, 1, 4, 3, 6, 5, 8, 7]
+        );
+        // create nested S-blossom, augment, expand recursively
+        assert_eq!(
+            python_style_matching(
+                &[
+                    (1, 2, 8),
+                    (1, 3, 8),
+                    (2, 3, 10),
+                    (2, 4, 12),
+                    (3, 5, 12),
+                    (4, 5, 14),
+                    (4, 6, 12),
+                    (5, 7, 12),
+                    (6, 7, 14),
+                    (7, 8, 12)
+                ],
+                false
+            ),
+            vec![-1, 2, 1, 5, 6, 3, 4, 8, 7]
+        );
+        // create S-blossom, relabel as T, expand
+        assert_eq!(
+            python_style_matching(
+                &[
+                    (1, 2, 23),
+                    (1, 5, 22),
+                    (1, 6, 15),
+                    (2, 3, 25),
+                    (3, 4, 22),
+                    (4, 5, 25),
+                    (4, 8, 14),
+                    (5, 7, 13)
+                ],
+                false
+            ),
+            vec![-1, 6, 3, 2, 8, 7, 1, 5, 4]
+        );
+        // create nested S-blossom, relabel as T, expand
+        assert_eq!(
+            python_style_matching(
+                &[
+                    (1, 2, 19),
+                    (1, 3, 20),
+                    (1, 8, 8),
+                    (2, 3, 25),
+                    (2, 4, 18),
+                    (3, 5, 18),
+                    (4, 5, 13),
+                    (4, 7, 7),
+                    (5, 6, 7)
+                ],
+                false
+            ),
+            vec![-1, 8, 3, 2, 7, 6, 5, 4, 1]
+        );
+        // create blossom, relabel as T in more than one way, expand, augment
+        assert_eq!(
+            python_style_matching(
+                &[
+                    (1, 2, 45),
+                    (1, 5, 45),
+                    (2, 3, 50),
+                    (3, 4, 45),
+                    (4, 5, 50),
+                    (1, 6, 30),
+                    (3, 9, 35),
+                    (4, 8, 35),
+                    (5, 7, 26),
+                    (9, 10, 5)
+                ],
+                false
+            ),
+            vec![-1, 6, 3, 2, 8, 7, 1, 5, 4, 10, 9]
+        );
+        // again but slightly different
+        assert_eq!(
+            python_style_matching(
+                &[
+                    (1, 2, 45),
+                    (1, 5, 45),
+                    (2, 3, 50),
+                    (3, 4, 45),
+                    (4, 5, 50),
+                    (1, 6, 30),
+                    (3, 9, 35),
+                    (4, 8, 26),
+                    (5, 7, 40),
+                    (9, 10, 5)
+                ],
+                false
+            ),
+            vec![-1, 6, 3, 2, 8, 7, 1, 5, 4, 10, 9]
+        );
+        // create blossom, relabel as T, expand such that a new least-slack S-to-free edge is produced, augment
+        assert_eq!(
+            python_style_matching(
+                &[
+                    (1, 2, 45),
+                    (1, 5, 45),
+                    (2, 3, 50),
+                    (3, 4, 45),
+                    (4, 5, 50),
+                    (1, 6, 30),
+                    (3, 9, 35),
+                    (4, 8, 28),
+                    (5, 7, 26),
+                    (9, 10, 5)
+                ],
+                false
+            ),
+            vec![-1, 6, 3, 2, 8, 7, 1, 5, 4, 10, 9]
+        );
+        // create nested blossom, relabel as T in more than one way, expand outer blossom such that inner blossom ends up
+        // on an augmenting path
+        assert_eq!(
+            python_style_matching(
+                &[
+                    (1, 2, 45),
+                    (1, 7, 45),
+                    (2, 3, 50),
+                    (3, 4, 45),
+                    (4, 5, 95),
+                    (4, 6, 94),
+                    (5, 6, 94),
+                    (6, 7, 50),
+                    (1, 8, 30),
+                    (3, 11, 35),
+                    (5, 9, 36),
+                    (7, 10, 26),
+                    (11, 12, 5)
+                ],
+                false
+            ),
+            vec![-1, 8, 3, 2, 6, 9, 4, 10, 1, 5, 7, 12, 11]
+        );
+        // create nested S-blossom, relabel as S, expand recursively
+        assert_eq!(
+            python_style_matching(
+                &[
+                    (1, 2, 40),
+                    (1, 3, 40),
+                    (2, 3, 60),
+                    (2, 4, 55),
+                    (3, 5, 55),
+                    (4, 5, 50),
+                    (1, 8, 15),
+                    (5, 7, 30),
+                    (7, 6, 10),
+                    (8, 10, 10),
+                    (4, 9, 30)
+                ],
+                false
+            ),
+            vec![-1, 2, 1, 5, 9, 3, 7, 6, 10, 4, 8]
+        );
+    }
+
+    #[test]
+    fn blossom_minimum_weight_perfect_matching() {
+        // cargo test blossom_minimum_weight_perfect_matching -- --nocapture
+        let mut rng = Xoroshiro128StarStar::seed_from_u64(123);
+        for _ in 0..300 {
+            let node_num = 2 * rng.gen_range(1..=5);
+            let mut weighted_edges = vec![];
+            for i in 0..node_num {
+                for j in i + 1..node_num {
+                    if rng.gen_bool(0.7) {
+                        weighted_edges.push((i, j, rng.gen_range(-20..100)));
+                    }
+                }
+            }
+            let Some(expected) = brute_force_minimum_weight(node_num, &weighted_edges) else {
+                continue;
+            };
+            let matching = minimum_weight_perfect_matching(node_num, &weighted_edges);
+            let mut weight = 0;
+            for (i, &j) in matching.iter().enumerate() {
+                assert_eq!(matching[j], i, "matching must be symmetric");
+                if i < j {
+                    weight += weighted_edges
+                        .iter()
+                        .find(|&&(a, b, _)| (a, b) == (i, j) || (a, b) == (j, i))
+                        .expect("matched edge must exist")
+                        .2;
+                }
+            }
+            assert_eq!(weight, expected, "{weighted_edges:?}");
+        }
+    }
+}
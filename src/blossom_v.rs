@@ -13,8 +13,17 @@ cfg_if::cfg_if! {
 
     } else {
 
-        fn minimum_weight_perfect_matching(_node_num: c_int, _edge_num: c_int, _edges: *const c_int, _weights: *const c_int, _matched: *mut c_int) {
-            unimplemented!("need blossom V library, see README.md")
+        /// fall back to the pure-Rust solver with the same interface as the Blossom V library
+        unsafe fn minimum_weight_perfect_matching(node_num: c_int, edge_num: c_int, edges: *const c_int, weights: *const c_int, matched: *mut c_int) {
+            let edges = std::slice::from_raw_parts(edges, 2 * edge_num as usize);
+            let weights = std::slice::from_raw_parts(weights, edge_num as usize);
+            let weighted_edges: Vec<(usize, usize, i64)> = (0..edge_num as usize)
+                .map(|e| (edges[2 * e] as usize, edges[2 * e + 1] as usize, weights[e] as i64))
+                .collect();
+            let matched = std::slice::from_raw_parts_mut(matched, node_num as usize);
+            for (output, peer) in matched.iter_mut().zip(super::blossom::minimum_weight_perfect_matching(node_num as usize, &weighted_edges)) {
+                *output = peer as c_int;
+            }
         }
 
     }
@@ -53,6 +53,8 @@ pub enum TestCommands {
 pub enum ToolCommands {
     /// built-in tests
     Benchmark(BenchmarkParameters),
+    /// generate Verilog of the distributed union-find decoder and its Verilator co-simulation harness
+    GenerateFpga(GenerateFpgaParameters),
 }
 
 #[derive(Clone)]
@@ -213,6 +215,19 @@ pub struct BenchmarkParameters {
     /// a json object describing the noise model details
     #[clap(long, default_value_t = json!({}), value_parser = ValueParser::new(SerdeJsonParser))]
     pub noise_model_configuration: serde_json::Value,
+    /// a json object describing how the simulated noise varies across shots (drift and burst events), see noise_schedule.rs;
+    /// the decoders are not aware of it and keep using the static noise model
+    #[clap(long, value_parser = ValueParser::new(SerdeJsonParser))]
+    pub noise_schedule: Option<serde_json::Value>,
+    /// a json object configuring sliding-window decoding on top of the selected decoder, e.g. `{"commit_rounds":3,"buffer_rounds":3}`,
+    /// see decoder_windowed.rs; the selected decoder only decodes a single window at a time
+    #[clap(long, value_parser = ValueParser::new(SerdeJsonParser))]
+    pub windowed_decoding: Option<serde_json::Value>,
+    /// [g1,g2,g3,...,gn] post-select the shots by the complementary gap reported by the decoder, see complementary_gap.rs; for each
+    /// gap threshold, a shot whose gap is below it is aborted, and an additional line reports the logical error rate of the accepted
+    /// shots and the abort rate
+    #[clap(long, value_parser = ValueParser::new(VecF64Parser))]
+    pub post_selection: Option<std::vec::Vec<f64>>,
     /// wait for some time for threads to end, otherwise print out the unstopped threads and detach them; useful when debugging rare deadlock cases; if set to negative value, no timeout and no thread debug information recording for maximum performance
     #[clap(long, default_value_t = 60.)]
     pub thread_timeout: f64,
@@ -269,6 +284,54 @@ pub struct BenchmarkParameters {
     pub error_pattern: Option<serde_json::Value>,
 }
 
+#[derive(Parser, Clone, Serialize, Deserialize, Debug)]
+pub struct GenerateFpgaParameters {
+    /// code distance
+    pub d: usize,
+    /// number of noisy measurement rounds, 0 for code capacity
+    #[clap(long, default_value_t = 0)]
+    pub noisy_measurements: usize,
+    /// fast channels connect nodes at distance `fast_channel_interval^k`, 0 or 1 disables fast channels
+    #[clap(long, default_value_t = 2)]
+    pub fast_channel_interval: usize,
+    /// select the code type
+    #[clap(short = 'c', long, value_enum, default_value_t = code_builder::CodeType::StandardPlanarCode)]
+    pub code_type: code_builder::CodeType,
+    /// physical error rate, used to build the decoding graph and to sample the co-simulation cases
+    #[clap(short = 'p', long, default_value_t = 0.01)]
+    pub p: f64,
+    /// bias_eta = pz / (px + py) and px = py, px + py + pz = p. default to 1/2, which means px = pz = py
+    #[clap(long, default_value_t = 0.5)]
+    pub bias_eta: f64,
+    /// select the noise model
+    #[clap(long, alias = "noise-model")]
+    pub noise_model_builder: Option<noise_model_builder::NoiseModelBuilder>,
+    /// customized noise model configuration
+    #[clap(long, default_value_t = json!({}), value_parser = ValueParser::new(SerdeJsonParser))]
+    pub noise_model_configuration: serde_json::Value,
+    /// configuration of the distributed union-find decoder, e.g. `{"mhw":2}`; `fast_channel_interval` takes precedence
+    #[clap(long, default_value_t = json!({}), value_parser = ValueParser::new(SerdeJsonParser))]
+    pub decoder_config: serde_json::Value,
+    /// name of the generated top module
+    #[clap(long, default_value_t = ("generated_distributed_union_find").to_string())]
+    pub module_name: String,
+    /// directory of the generated Verilog, co-simulation harness and cases
+    #[clap(short = 'o', long, default_value_t = ("./tmp/fpga").to_string())]
+    pub output_dir: String,
+    /// number of random syndromes checked in the co-simulation
+    #[clap(long, default_value_t = 100)]
+    pub cosim_cases: usize,
+    /// use deterministic seed for the co-simulation cases
+    #[clap(long)]
+    pub deterministic_seed: Option<u64>,
+    /// build the co-simulation with Verilator and run it after generating the files
+    #[clap(long, action)]
+    pub run_verilator: bool,
+    /// directory of the processing unit and channel modules
+    #[clap(long, default_value_t = ("./backend/verilated").to_string())]
+    pub verilated_dir: String,
+}
+
 #[derive(Parser, Clone, Debug)]
 pub struct ServerParameters {
     /// listening on <addr>:<port>, default to 8066
@@ -74,7 +74,11 @@ impl CodeType {
     /// get position on the left of (i, j), note that this position may be invalid for open-boundary code if it doesn't exist
     pub fn get_left(&self, i: usize, j: usize, code_size: &CodeSize) -> (usize, usize) {
         match self {
-            &CodeType::RotatedTailoredCode | &CodeType::RotatedTailoredCodeBellInit => {
+            &CodeType::StandardTailoredCode
+            | &CodeType::RotatedTailoredCode
+            | &CodeType::RotatedTailoredCodeBellInit
+            | &CodeType::StandardXZZXCode
+            | &CodeType::RotatedXZZXCode => {
                 if j > 0 {
                     (i, j - 1)
                 } else {
@@ -100,7 +104,11 @@ impl CodeType {
     /// get position up the position (i, j), note that this position may be invalid for open-boundary code if it doesn't exist
     pub fn get_up(&self, i: usize, j: usize, code_size: &CodeSize) -> (usize, usize) {
         match self {
-            &CodeType::RotatedTailoredCode | &CodeType::RotatedTailoredCodeBellInit => {
+            &CodeType::StandardTailoredCode
+            | &CodeType::RotatedTailoredCode
+            | &CodeType::RotatedTailoredCodeBellInit
+            | &CodeType::StandardXZZXCode
+            | &CodeType::RotatedXZZXCode => {
                 if i > 0 {
                     (i - 1, j)
                 } else {
@@ -128,7 +136,11 @@ impl CodeType {
     /// get position on the right of (i, j), note that this position may be invalid for open-boundary code if it doesn't exist
     pub fn get_right(&self, i: usize, j: usize, code_size: &CodeSize) -> (usize, usize) {
         match self {
-            &CodeType::RotatedTailoredCode | &CodeType::RotatedTailoredCodeBellInit => (i, j + 1),
+            &CodeType::StandardTailoredCode
+            | &CodeType::RotatedTailoredCode
+            | &CodeType::RotatedTailoredCodeBellInit
+            | &CodeType::StandardXZZXCode
+            | &CodeType::RotatedXZZXCode => (i, j + 1),
             &CodeType::PeriodicRotatedTailoredCode => {
                 let dp = code_size.di;
                 let dn = code_size.dj;
@@ -148,7 +160,11 @@ impl CodeType {
     /// get position down the position (i, j), note that this position may be invalid for open-boundary code if it doesn't exist
     pub fn get_down(&self, i: usize, j: usize, code_size: &CodeSize) -> (usize, usize) {
         match self {
-            &CodeType::RotatedTailoredCode | &CodeType::RotatedTailoredCodeBellInit => (i + 1, j),
+            &CodeType::StandardTailoredCode
+            | &CodeType::RotatedTailoredCode
+            | &CodeType::RotatedTailoredCodeBellInit
+            | &CodeType::StandardXZZXCode
+            | &CodeType::RotatedXZZXCode => (i + 1, j),
             &CodeType::PeriodicRotatedTailoredCode => {
                 let dp = code_size.di;
                 let dn = code_size.dj;
@@ -1031,6 +1047,30 @@ pub fn build_code(simulator: &mut Simulator) {
                             };
                             let mut gate_type = GateType::None;
                             let mut gate_peer = None;
+                            // the same corners as the tailored surface code, used in its residual decoding
+                            let (is_corner, peer_corner): (bool, Option<Position>) = if is_rotated {
+                                if i == 0 && j == dj {
+                                    (true, Some(pos!(t, 1, dj + 1)))
+                                } else if j == 0 && i == dj {
+                                    (true, Some(pos!(t, dj - 1, 1)))
+                                } else if i == vertical - 1 && j == di {
+                                    (true, Some(pos!(t, vertical - 2, di - 1)))
+                                } else if i == di && j == vertical - 1 {
+                                    (true, Some(pos!(t, di + 1, vertical - 2)))
+                                } else {
+                                    (false, None)
+                                }
+                            } else if i == 0 && j == 1 {
+                                (true, Some(pos!(t, 1, 0)))
+                            } else if i == 1 && j == horizontal - 1 {
+                                (true, Some(pos!(t, 0, horizontal - 2)))
+                            } else if i == vertical - 2 && j == 0 {
+                                (true, Some(pos!(t, vertical - 1, 1)))
+                            } else if i == vertical - 1 && j == horizontal - 2 {
+                                (true, Some(pos!(t, vertical - 2, horizontal - 1)))
+                            } else {
+                                (false, None)
+                            };
                             match t % simulator.measurement_cycles {
                                 1 => {
                                     // initialization
@@ -1113,10 +1153,16 @@ pub fn build_code(simulator: &mut Simulator) {
                                 _ => unreachable!(),
                             }
                             row_j.push(Some(Box::new(
-                                SimulatorNode::new(qubit_type, gate_type, gate_peer.clone()).set_virtual(
-                                    is_virtual(i, j),
-                                    gate_peer.map_or(false, |peer| is_virtual(peer.i, peer.j)),
-                                ),
+                                SimulatorNode::new(qubit_type, gate_type, gate_peer.clone())
+                                    .set_virtual(
+                                        is_virtual(i, j),
+                                        gate_peer.map_or(false, |peer| is_virtual(peer.i, peer.j)),
+                                    )
+                                    .with_miscellaneous(if is_corner {
+                                        Some(json!({ "is_corner": true, "peer_corner": peer_corner.unwrap() }))
+                                    } else {
+                                        None
+                                    }),
                             )));
                         } else {
                             row_j.push(None);
@@ -1393,11 +1439,14 @@ pub fn code_builder_validate_correction(simulator: &mut Simulator, correction: &
         &CodeType::RotatedXZZXCode => {
             let dp = code_size.di;
             let dn = code_size.dj;
+            // the logical operators alternate between X and Z along the boundary, because every stabilizer acts as X on its
+            // horizontal neighbors and as Z on its vertical neighbors
             // check cardinality of top boundary for logical_i
             let mut top_cardinality = 0;
             for delta in 0..dn {
                 let node = simulator.get_node_unwrap(&pos!(top_t, dn - delta, 1 + delta));
-                if node.propagated == X || node.propagated == Y {
+                let anti_commute = if delta % 2 == 0 { X } else { Z };
+                if node.propagated == anti_commute || node.propagated == Y {
                     top_cardinality += 1;
                 }
             }
@@ -1406,7 +1455,8 @@ pub fn code_builder_validate_correction(simulator: &mut Simulator, correction: &
             let mut left_cardinality = 0;
             for delta in 0..dp {
                 let node = simulator.get_node_unwrap(&pos!(top_t, dn + delta, 1 + delta));
-                if node.propagated == Z || node.propagated == Y {
+                let anti_commute = if delta % 2 == 0 { Z } else { X };
+                if node.propagated == anti_commute || node.propagated == Y {
                     left_cardinality += 1;
                 }
             }
@@ -1715,6 +1765,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn code_builder_rotated_xzzx_code_logical_operators() {
+        // cargo test code_builder_rotated_xzzx_code_logical_operators -- --nocapture
+        let d = 5;
+        let mut simulator = Simulator::new(CodeType::RotatedXZZXCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let top_t = simulator.height - 1;
+        // the logical operators along the boundaries alternate between X and Z
+        let build_logical_operator = |positions: Vec<Position>, first: ErrorType, second: ErrorType| {
+            let mut correction = SparseCorrection::new();
+            for (delta, position) in positions.into_iter().enumerate() {
+                correction.add(position, if delta % 2 == 0 { first } else { second });
+            }
+            correction
+        };
+        let top_logical = build_logical_operator((0..d).map(|delta| pos!(top_t, d - delta, 1 + delta)).collect(), Z, X);
+        let left_logical = build_logical_operator((0..d).map(|delta| pos!(top_t, d + delta, 1 + delta)).collect(), X, Z);
+        code_builder_sanity_check_correction(&mut simulator, &top_logical).unwrap();
+        code_builder_sanity_check_correction(&mut simulator, &left_logical).unwrap();
+        assert_eq!(
+            code_builder_validate_correction(&mut simulator, &top_logical),
+            Some((false, true))
+        );
+        assert_eq!(
+            code_builder_validate_correction(&mut simulator, &left_logical),
+            Some((true, false))
+        );
+        // the weight-2 stabilizers on the top and left boundaries are not logical errors
+        for (position1, error1, position2, error2) in [
+            (pos!(top_t, 1, 5), X, pos!(top_t, 2, 4), Z),
+            (pos!(top_t, 5, 1), Z, pos!(top_t, 6, 2), X),
+        ] {
+            let mut stabilizer = SparseCorrection::new();
+            stabilizer.add(position1, error1);
+            stabilizer.add(position2, error2);
+            code_builder_sanity_check_correction(&mut simulator, &stabilizer).unwrap();
+            assert_eq!(
+                code_builder_validate_correction(&mut simulator, &stabilizer),
+                Some((false, false))
+            );
+        }
+        let mut both_logical = top_logical.clone();
+        both_logical.extend(&left_logical);
+        assert_eq!(
+            code_builder_validate_correction(&mut simulator, &both_logical),
+            Some((true, true))
+        );
+    }
+
     #[test]
     fn code_builder_visualize_standard_planar_code() {
         // cargo test code_builder_visualize_standard_planar_code -- --nocapture
@@ -0,0 +1,363 @@
+//! # Complementary Gap
+//!
+//! a per-shot confidence of the matching decoders: the complementary gap is the weight difference between the best decoding
+//! result and the best decoding result in the complementary logical class, i.e. re-decoding with the logical boundary parity
+//! flipped. a small gap means the decoder is not confident about the logical outcome, which makes it useful for post-selection.
+//!
+//! for each logical observable, the boundary edges of the model graph are split into two sides, such that a decoding result
+//! flips the observable if and only if an odd number of its paths end at one of the sides, up to a parity fixed by the syndrome.
+//! the complementary graph moves all the boundary edges of that side to a single logical vertex; decoding the syndrome with and
+//! without the logical vertex as an additional defect gives the best decoding result in each of the two logical classes.
+//!
+
+use super::model_graph::*;
+use super::simulator::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// the name of the logical observables in the runtime statistics
+pub const LOGICAL_OBSERVABLE_NAMES: [&str; 2] = ["logical_i", "logical_j"];
+
+/// the model graph with the boundary edges of one logical side moved to a logical vertex
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplementaryGraph {
+    /// 0 for the logical observable of the i axis and 1 for that of the j axis
+    pub logical_index: usize,
+    /// the vertex connecting to all the boundary edges of one logical side, which is a virtual node in the original simulator
+    pub logical_vertex: Position,
+    /// the modified model graph
+    pub model_graph: Arc<ModelGraph>,
+    /// the original simulator, except that the logical vertex becomes a real node
+    #[serde(skip)]
+    pub simulator: Arc<Simulator>,
+}
+
+impl ComplementaryGraph {
+    /// build the complementary graph of every logical observable that the decoding result can flip; an observable is skipped if
+    /// no boundary edge changes its parity, or if the edges don't define consistent sides, e.g. an edge is itself a logical operator
+    pub fn build(simulator: &Simulator, model_graph: &ModelGraph) -> Vec<Self> {
+        let mut positions = Vec::<Position>::new();
+        simulator_iter!(simulator, position, delta_t => simulator.measurement_cycles, if model_graph.is_node_exist(position) {
+            positions.push(position.clone());
+        });
+        let indices: HashMap<Position, usize> = positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| (position.clone(), index))
+            .collect();
+        // the logical class of every edge is that of its correction
+        let mut clean_simulator = simulator.clone();
+        clean_simulator.clear_all_errors();
+        let mut edge_classes = vec![Vec::<(usize, (bool, bool))>::new(); positions.len()];
+        let mut boundary_classes = Vec::<Option<(bool, bool)>>::with_capacity(positions.len());
+        for (index, position) in positions.iter().enumerate() {
+            let node = model_graph.get_node_unwrap(position);
+            for (peer_position, edge) in node.edges.iter() {
+                let peer_index = indices[peer_position];
+                if index < peer_index {
+                    let classes = clean_simulator.validate_correction(&edge.correction);
+                    edge_classes[index].push((peer_index, classes));
+                    edge_classes[peer_index].push((index, classes));
+                }
+            }
+            let boundary_class = node
+                .boundary
+                .as_ref()
+                .map(|boundary| clean_simulator.validate_correction(&boundary.correction));
+            boundary_classes.push(boundary_class);
+        }
+        (0..LOGICAL_OBSERVABLE_NAMES.len())
+            .filter_map(|logical_index| {
+                let class = |classes: &(bool, bool)| if logical_index == 0 { classes.0 } else { classes.1 };
+                let edge_classes: Vec<Vec<(usize, bool)>> = edge_classes
+                    .iter()
+                    .map(|neighbors| {
+                        neighbors
+                            .iter()
+                            .map(|(peer_index, classes)| (*peer_index, class(classes)))
+                            .collect()
+                    })
+                    .collect();
+                let boundary_classes: Vec<Option<bool>> =
+                    boundary_classes.iter().map(|classes| classes.as_ref().map(class)).collect();
+                let logical_side = Self::logical_side(&edge_classes, &boundary_classes)?;
+                Self::new(logical_index, simulator, model_graph, &positions, &logical_side)
+            })
+            .collect()
+    }
+
+    /// find the boundary edges on one logical side; `edge_classes` lists the neighbors of each vertex and `boundary_classes`
+    /// the boundary edge, each with whether it flips the logical observable
+    fn logical_side(edge_classes: &[Vec<(usize, bool)>], boundary_classes: &[Option<bool>]) -> Option<Vec<bool>> {
+        let mut potentials: Vec<Option<bool>> = vec![None; edge_classes.len()];
+        let mut logical_side = vec![false; edge_classes.len()];
+        for root in 0..edge_classes.len() {
+            if potentials[root].is_some() {
+                continue;
+            }
+            // the potential of a vertex is the logical class of any path from the root of its connected component
+            potentials[root] = Some(false);
+            let mut component = vec![root];
+            let mut cursor = 0;
+            while cursor < component.len() {
+                let index = component[cursor];
+                cursor += 1;
+                let potential = potentials[index].unwrap();
+                for &(peer_index, class) in edge_classes[index].iter() {
+                    match potentials[peer_index] {
+                        None => {
+                            potentials[peer_index] = Some(potential ^ class);
+                            component.push(peer_index);
+                        }
+                        Some(peer_potential) if peer_potential != potential ^ class => return None,
+                        _ => {}
+                    }
+                }
+            }
+            // the path from the root to the boundary through a vertex; when all of them are the same in a component, the number
+            // of paths ending at the boundary has the same parity as the number of defects in the component and doesn't matter
+            let sides: Vec<(usize, bool)> = component
+                .iter()
+                .filter_map(|&index| boundary_classes[index].map(|class| (index, potentials[index].unwrap() ^ class)))
+                .collect();
+            if sides.iter().any(|(_, side)| *side) && sides.iter().any(|(_, side)| !*side) {
+                for (index, side) in sides {
+                    logical_side[index] = side;
+                }
+            }
+        }
+        logical_side.iter().any(|side| *side).then_some(logical_side)
+    }
+
+    fn new(
+        logical_index: usize,
+        simulator: &Simulator,
+        model_graph: &ModelGraph,
+        positions: &[Position],
+        logical_side: &[bool],
+    ) -> Option<Self> {
+        let logical_vertex = positions
+            .iter()
+            .zip(logical_side.iter())
+            .filter(|(_, side)| **side)
+            .find_map(|(position, _)| model_graph.get_node_unwrap(position).boundary.as_ref()?.virtual_node.clone())?;
+        let mut complementary_model_graph = model_graph.clone();
+        let mut logical_node = ModelGraphNode {
+            all_edges: BTreeMap::new(),
+            edges: BTreeMap::new(),
+            all_boundaries: vec![],
+            boundary: None,
+        };
+        for (position, _) in positions.iter().zip(logical_side.iter()).filter(|(_, side)| **side) {
+            let node = complementary_model_graph.get_node_mut_unwrap(position);
+            let boundary = node.boundary.take().expect("logical side is only defined for boundary edges");
+            let edge = ModelGraphEdge {
+                probability: boundary.probability,
+                weight: boundary.weight,
+                error_pattern: boundary.error_pattern,
+                correction: boundary.correction,
+            };
+            node.edges.insert(logical_vertex.clone(), edge.clone());
+            logical_node.edges.insert(position.clone(), edge);
+        }
+        complementary_model_graph.nodes[logical_vertex.t][logical_vertex.i][logical_vertex.j] = Some(Box::new(logical_node));
+        let mut complementary_simulator = simulator.clone();
+        complementary_simulator.get_node_mut_unwrap(&logical_vertex).is_virtual = false;
+        Some(Self {
+            logical_index,
+            logical_vertex,
+            model_graph: Arc::new(complementary_model_graph),
+            simulator: Arc::new(complementary_simulator),
+        })
+    }
+
+    /// the total weight of the model graph edges chosen by a decoder on this graph
+    pub fn subgraph_weight(&self, subgraph: &SparseSubgraph) -> f64 {
+        subgraph
+            .iter()
+            .map(|(defect_vertices, _)| match defect_vertices.as_slice() {
+                [position] => {
+                    let node = self.model_graph.get_node_unwrap(position);
+                    node.boundary.as_ref().expect("boundary must exist").weight
+                }
+                [position1, position2] => self.model_graph.get_node_unwrap(position1).edges[position2].weight,
+                _ => unreachable!("the model graph only has edges and boundaries"),
+            })
+            .sum()
+    }
+}
+
+/// decode the syndrome in both logical classes of every complementary graph, and report the gap of each logical observable in
+/// `complementary_gaps` and the minimum of them in `complementary_gap` of the runtime statistics
+pub fn record_complementary_gaps<D, F>(
+    complementary_decoders: &mut [(Arc<ComplementaryGraph>, D)],
+    sparse_measurement: &SparseMeasurement,
+    runtime_statistics: &mut serde_json::Value,
+    mut decode_subgraph: F,
+) where
+    F: FnMut(&mut D, &SparseMeasurement) -> SparseSubgraph,
+{
+    let begin = Instant::now();
+    let mut gaps = serde_json::Map::new();
+    let mut minimum_gap = f64::INFINITY;
+    for (complementary_graph, decoder) in complementary_decoders.iter_mut() {
+        let weight = complementary_graph.subgraph_weight(&decode_subgraph(decoder, sparse_measurement));
+        let mut flipped_measurement = sparse_measurement.clone();
+        flipped_measurement.insert_defect_measurement(&complementary_graph.logical_vertex);
+        let flipped_weight = complementary_graph.subgraph_weight(&decode_subgraph(decoder, &flipped_measurement));
+        let gap = (flipped_weight - weight).abs();
+        gaps.insert(
+            LOGICAL_OBSERVABLE_NAMES[complementary_graph.logical_index].to_string(),
+            json!(gap),
+        );
+        minimum_gap = minimum_gap.min(gap);
+    }
+    runtime_statistics["complementary_gap"] = json!(minimum_gap); // null if no logical observable can be flipped
+    runtime_statistics["complementary_gaps"] = json!(gaps);
+    runtime_statistics["time_complementary_gap"] = json!(begin.elapsed().as_secs_f64());
+}
+
+/// the confidence of a shot used in post-selection, i.e. the minimum complementary gap of the logical observables that are not
+/// ignored; an observable without complementary graph cannot be flipped by the decoder and is thus not considered
+pub fn post_selection_gap(runtime_statistics: &serde_json::Value, ignore_logical_i: bool, ignore_logical_j: bool) -> f64 {
+    let gaps = runtime_statistics["complementary_gaps"]
+        .as_object()
+        .expect("decoder doesn't report the complementary gap");
+    let mut minimum_gap = f64::INFINITY;
+    for (name, ignored) in LOGICAL_OBSERVABLE_NAMES.iter().zip([ignore_logical_i, ignore_logical_j]) {
+        if let Some(gap) = gaps.get(*name).and_then(|gap| gap.as_f64()) {
+            if !ignored {
+                minimum_gap = minimum_gap.min(gap);
+            }
+        }
+    }
+    minimum_gap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::clap::Parser;
+    use super::super::cli::*;
+    use super::super::code_builder::*;
+    #[cfg(feature = "fusion_blossom")]
+    use super::super::decoder_fusion::*;
+    use super::super::decoder_mwpm::*;
+    use super::super::decoder_union_find::*;
+    use super::super::noise_model::*;
+    use super::super::noise_model_builder::*;
+    use super::super::types::*;
+    use super::*;
+
+    fn code_capacity_simulator(d: usize, p: f64) -> (Simulator, Arc<NoiseModel>) {
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        NoiseModelBuilder::Phenomenological.apply(&mut simulator, &mut noise_model, &json!({}), p, 0.5, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        (simulator, Arc::new(noise_model))
+    }
+
+    #[cfg(feature = "fusion_blossom")]
+    #[test]
+    fn complementary_gap_minimum_logical_operator() {
+        // cargo test complementary_gap_minimum_logical_operator -- --nocapture
+        let d = 5;
+        let (mut simulator, noise_model) = code_capacity_simulator(d, 0.05);
+        let decoder_config = json!({ "complementary_gap": true });
+        let mut fusion_decoder = FusionDecoder::new(&simulator, Arc::clone(&noise_model), &decoder_config, 1, false);
+        assert_eq!(fusion_decoder.complementary_decoders.len(), 2);
+        // all the edges have the same weight in the code-capacity noise model
+        let model_graph = Arc::clone(&fusion_decoder.complementary_decoders[0].0.model_graph);
+        let mut weights = vec![];
+        simulator_iter!(simulator, position, delta_t => simulator.measurement_cycles, if model_graph.is_node_exist(position) {
+            let node = model_graph.get_node_unwrap(position);
+            weights.extend(node.edges.values().map(|edge| edge.weight));
+            weights.extend(node.boundary.iter().map(|boundary| boundary.weight));
+        });
+        let weight = weights[0];
+        assert!(weights.iter().all(|&edge_weight| (edge_weight - weight).abs() < 1e-6));
+        // without any defect, the complementary class is a minimum-weight logical operator of d edges
+        let (_, runtime_statistics) = fusion_decoder.decode(&SparseMeasurement::new());
+        for name in LOGICAL_OBSERVABLE_NAMES {
+            let gap = runtime_statistics["complementary_gaps"][name].as_f64().unwrap();
+            assert!((gap - d as f64 * weight).abs() < 1e-6, "{name}: {gap} != {d} * {weight}");
+        }
+        // a single error in the middle is corrected by two edges, and the complementary class needs d - 2 edges
+        for error in [ErrorType::X, ErrorType::Z] {
+            simulator.clear_all_errors();
+            simulator.set_error_check(&noise_model, &pos!(0, 5, 5), &error);
+            simulator.propagate_errors();
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, runtime_statistics) = fusion_decoder.decode(&sparse_measurement);
+            assert_eq!(simulator.validate_correction(&correction), (false, false));
+            let gap = runtime_statistics["complementary_gap"].as_f64().unwrap();
+            assert!((gap - (d - 2) as f64 * weight).abs() < 1e-6, "{gap} != {} * {weight}", d - 2);
+            assert_eq!(post_selection_gap(&runtime_statistics, false, false), gap);
+        }
+    }
+
+    #[test]
+    fn complementary_gap_matching_decoders() {
+        // cargo test complementary_gap_matching_decoders -- --nocapture
+        let d = 5;
+        let (mut simulator, noise_model) = code_capacity_simulator(d, 0.1);
+        let decoder_config = json!({ "cg": true });
+        let mut union_find_decoder = UnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &decoder_config, 1, false);
+        #[cfg(feature = "fusion_blossom")]
+        let mut fusion_decoder = FusionDecoder::new(&simulator, Arc::clone(&noise_model), &decoder_config, 1, false);
+        let mut mwpm_decoder = MWPMDecoder::new(&simulator, Arc::clone(&noise_model), &decoder_config, 1, false);
+        for _ in 0..100 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (_, runtime_statistics) = union_find_decoder.decode(&sparse_measurement);
+            let gap = runtime_statistics["complementary_gap"].as_f64().unwrap();
+            // ignoring both logical observables, the shot is never aborted
+            assert_eq!(post_selection_gap(&runtime_statistics, true, true), f64::INFINITY);
+            assert_eq!(post_selection_gap(&runtime_statistics, false, false), gap);
+            #[cfg(feature = "fusion_blossom")]
+            let fusion_gap = {
+                let (_, fusion_statistics) = fusion_decoder.decode(&sparse_measurement);
+                fusion_statistics["complementary_gap"].as_f64().unwrap()
+            };
+            #[cfg(feature = "fusion_blossom")]
+            {
+                // both find the minimum-weight decoding result in each logical class
+                let (_, mwpm_statistics) = mwpm_decoder.decode(&sparse_measurement);
+                let mwpm_gap = mwpm_statistics["complementary_gap"].as_f64().unwrap();
+                assert!((mwpm_gap - fusion_gap).abs() < 1e-6, "{mwpm_gap} != {fusion_gap}");
+            }
+            #[cfg(feature = "fusion_blossom")]
+            assert!(fusion_gap >= 0.);
+        }
+    }
+
+    #[test]
+    fn complementary_gap_post_selection_benchmark() {
+        // cargo test complementary_gap_post_selection_benchmark -- --nocapture
+        let benchmark = |extra_arguments: &[&str]| -> Result<String, String> {
+            let mut arguments = vec![
+                "benchmark",
+                "[3]",
+                "[0]",
+                "[0.05]",
+                "--decoder-config",
+                r#"{"cg":true}"#,
+                "--post-selection",
+                "[1000]",
+                "--max-repeats",
+                "20",
+            ];
+            arguments.extend(extra_arguments);
+            BenchmarkParameters::parse_from(arguments).run()
+        };
+        // a threshold above any gap aborts every shot, leaving no accepted shot to compute the logical error rate
+        let output = benchmark(&[]).unwrap();
+        assert!(output.contains("ps 1000 0 0 nan 1 nan"), "{output}");
+        // erasures are rejected before the simulation starts
+        let error = benchmark(&["--pes", "[0.01]"]).unwrap_err();
+        assert!(error.contains("complementary gap"), "{error}");
+    }
+}
@@ -214,6 +214,62 @@ impl CompleteModelGraph {
     pub fn build_correction_matching(&mut self, source: &Position, target: &Position) -> SparseCorrection {
         let model_graph = Arc::clone(&self.model_graph);
         let mut correction = SparseCorrection::new();
+        for (position, next) in self.build_path_matching(source, target).iter() {
+            let model_graph_node = model_graph.get_node_unwrap(position);
+            let next_edge = model_graph_node.edges.get(next);
+            let next_correction = &next_edge.as_ref().unwrap().correction;
+            correction.extend(next_correction);
+        }
+        correction
+    }
+
+    /// build correction with boundary
+    pub fn build_correction_boundary(&mut self, position: &Position) -> SparseCorrection {
+        let model_graph = Arc::clone(&self.model_graph);
+        let mut correction = SparseCorrection::new();
+        let (path, boundary_position) = self.build_path_boundary(position);
+        for (position, next) in path.iter() {
+            let model_graph_node = model_graph.get_node_unwrap(position);
+            let next_edge = model_graph_node.edges.get(next);
+            let next_correction = &next_edge.as_ref().unwrap().correction;
+            correction.extend(next_correction);
+        }
+        let model_graph_node = model_graph.get_node_unwrap(&boundary_position);
+        let boundary_correction = &model_graph_node.boundary.as_ref().unwrap().correction;
+        correction.extend(boundary_correction);
+        correction
+    }
+
+    /// add the model graph edges of the matching path to the subgraph
+    pub fn build_subgraph_matching(&mut self, source: &Position, target: &Position, subgraph: &mut SparseSubgraph) {
+        let model_graph = Arc::clone(&self.model_graph);
+        for (position, next) in self.build_path_matching(source, target).into_iter() {
+            let correction = model_graph.get_node_unwrap(&position).edges[&next].correction.clone();
+            subgraph.push(vec![position, next], correction);
+        }
+    }
+
+    /// add the model graph edges of the boundary path, including the boundary edge, to the subgraph
+    pub fn build_subgraph_boundary(&mut self, position: &Position, subgraph: &mut SparseSubgraph) {
+        let model_graph = Arc::clone(&self.model_graph);
+        let (path, boundary_position) = self.build_path_boundary(position);
+        for (position, next) in path.into_iter() {
+            let correction = model_graph.get_node_unwrap(&position).edges[&next].correction.clone();
+            subgraph.push(vec![position, next], correction);
+        }
+        let correction = model_graph
+            .get_node_unwrap(&boundary_position)
+            .boundary
+            .as_ref()
+            .unwrap()
+            .correction
+            .clone();
+        subgraph.push(vec![boundary_position], correction);
+    }
+
+    /// the model graph edges along the shortest path between two nodes
+    pub fn build_path_matching(&mut self, source: &Position, target: &Position) -> Vec<(Position, Position)> {
+        let mut path = vec![];
         let mut source = source.clone();
         if self.precompute_complete_model_graph {
             while &source != target {
@@ -224,14 +280,10 @@ impl CompleteModelGraph {
                     println!("target_edge none: source: {source:?}, target: {target:?}");
                 }
                 let edge = target_edge.as_ref().unwrap();
-                let next = &edge.next;
-                let model_graph_node = model_graph.get_node_unwrap(&source);
-                let next_edge = model_graph_node.edges.get(next);
-                let next_correction = &next_edge.as_ref().unwrap().correction;
-                correction.extend(next_correction);
-                source = next.clone();
+                let next = edge.next.clone();
+                path.push((source, next.clone()));
+                source = next;
             }
-            correction
         } else {
             self.precompute_dijkstra_with_end_position(target, &source);
             // logic is different from what's happening if `precompute_complete_model_graph` is set
@@ -242,43 +294,32 @@ impl CompleteModelGraph {
                     "after running `precompute_dijkstra`, this node must be visited"
                 );
                 let next: Position = (**(node.previous.as_ref().expect("must exist a path"))).clone();
-                let model_graph_node = model_graph.get_node_unwrap(&source);
-                let next_edge = model_graph_node.edges.get(&next);
-                let next_correction = &next_edge.as_ref().unwrap().correction;
-                correction.extend(next_correction);
+                path.push((source, next.clone()));
                 source = next;
             }
             Arc::get_mut(self.get_node_mut_unwrap(target).precomputed.as_mut().unwrap())
                 .unwrap()
                 .clear_edges(); // free memory immediately
-            correction
         }
+        path
     }
 
-    /// build correction with boundary
-    pub fn build_correction_boundary(&mut self, position: &Position) -> SparseCorrection {
-        let model_graph = Arc::clone(&self.model_graph);
-        let mut correction = SparseCorrection::new();
+    /// the model graph edges along the shortest path to the boundary, and the node whose boundary edge is used
+    pub fn build_path_boundary(&mut self, position: &Position) -> (Vec<(Position, Position)>, Position) {
+        let mut path = vec![];
         let mut position = position.clone();
         loop {
             let node = self.get_node_unwrap(&position);
             let precomputed = node.precomputed.as_ref().unwrap();
             let boundary = precomputed.boundary.as_ref().unwrap();
-            let next = &boundary.next;
-            let model_graph_node = model_graph.get_node_unwrap(&position);
-            if next == &position {
+            let next = boundary.next.clone();
+            if next == position {
                 // this is the boundary
-                let boundary_correction = &model_graph_node.boundary.as_ref().unwrap().correction;
-                correction.extend(boundary_correction);
-                break;
-            } else {
-                let next_edge = model_graph_node.edges.get(next);
-                let next_correction = &next_edge.as_ref().unwrap().correction;
-                correction.extend(next_correction);
-                position = next.clone();
+                return (path, position);
             }
+            path.push((position, next.clone()));
+            position = next;
         }
-        correction
     }
 
     /// run full Dijkstra's algorithm and identify the active region
@@ -0,0 +1,624 @@
+//! belief propagation decoder with ordered statistics post-processing (BP+OSD) on the model hypergraph
+//!
+//! the parity check matrix is given by [`ModelHypergraph`]: each check is a (real) measurement vertex, and each variable is a
+//! hyperedge with its probability. belief propagation either converges to a valid correction, or its soft output is used to
+//! rank the variables for OSD, which finds the most likely solution among those that only flip the `osd_order` least
+//! reliable variables outside of the information set.
+//!
+//! the same belief propagation is also used by belief-matching, where the posterior of each hyperedge is used to reweight
+//! the matching graph in every shot, see [`BeliefMatching`].
+//!
+
+use super::decoder_mwpm::*;
+use super::model_graph::*;
+use super::noise_model::*;
+use super::simulator::*;
+use crate::model_hypergraph::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// BP+OSD decoder, initialized and cloned for multiple threads
+#[derive(Debug, Clone, Serialize)]
+pub struct BPOSDDecoder {
+    /// model hypergraph
+    pub model_hypergraph: Arc<ModelHypergraph>,
+    /// save configuration for later usage
+    pub config: BPOSDDecoderConfig,
+    /// belief propagation on the parity check matrix given by the model hypergraph
+    pub belief_propagation: BeliefPropagation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BPOSDDecoderConfig {
+    /// combined probability can improve accuracy, but will cause probabilities differ a lot even in the case of i.i.d. noise model
+    #[serde(alias = "ucp")] // abbreviation
+    #[serde(default = "mwpm_default_configs::use_combined_probability")]
+    pub use_combined_probability: bool,
+    /// the maximum number of belief propagation iterations before falling back to OSD
+    #[serde(alias = "mi")] // abbreviation
+    #[serde(default = "bp_osd_default_configs::max_iterations")]
+    pub max_iterations: usize,
+    /// the message update rule of belief propagation
+    #[serde(alias = "bpm")] // abbreviation
+    #[serde(default = "bp_osd_default_configs::bp_method")]
+    pub bp_method: BeliefPropagationMethod,
+    /// the scaling factor of check-to-variable messages in the min-sum algorithm, usually within [0.5, 1]
+    #[serde(alias = "sf")] // abbreviation
+    #[serde(default = "bp_osd_default_configs::scaling_factor")]
+    pub scaling_factor: f64,
+    /// the order of exhaustive OSD, i.e. trying all the 2^order configurations of the least reliable non-pivot variables;
+    /// 0 means OSD-0
+    #[serde(alias = "osd")] // abbreviation
+    #[serde(default = "bp_osd_default_configs::osd_order")]
+    pub osd_order: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BeliefPropagationConfig {
+    /// the maximum number of belief propagation iterations
+    #[serde(alias = "mi")] // abbreviation
+    #[serde(default = "bp_osd_default_configs::max_iterations")]
+    pub max_iterations: usize,
+    /// the message update rule of belief propagation
+    #[serde(alias = "bpm")] // abbreviation
+    #[serde(default = "bp_osd_default_configs::bp_method")]
+    pub bp_method: BeliefPropagationMethod,
+    /// the scaling factor of check-to-variable messages in the min-sum algorithm, usually within [0.5, 1]
+    #[serde(alias = "sf")] // abbreviation
+    #[serde(default = "bp_osd_default_configs::scaling_factor")]
+    pub scaling_factor: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BeliefPropagationMethod {
+    /// approximate the check node update using the minimum magnitude, scaled by `scaling_factor`
+    MinSum,
+    /// the exact check node update using `tanh`
+    ProductSum,
+}
+
+pub mod bp_osd_default_configs {
+    use super::*;
+    pub fn max_iterations() -> usize {
+        30
+    }
+    pub fn bp_method() -> BeliefPropagationMethod {
+        BeliefPropagationMethod::MinSum
+    }
+    pub fn scaling_factor() -> f64 {
+        0.625
+    }
+    pub fn osd_order() -> usize {
+        0
+    }
+}
+
+/// sparse parity check matrix where every non-zero entry has an index, used to store the messages
+#[derive(Debug, Clone, Serialize)]
+pub struct SparseParityCheck {
+    /// the variables of each check, (variable, entry index)
+    pub check_neighbors: Vec<Vec<(usize, usize)>>,
+    /// the checks of each variable, (check, entry index)
+    pub variable_neighbors: Vec<Vec<(usize, usize)>>,
+    /// the prior log-likelihood ratio ln((1-p)/p) of each variable
+    pub priors: Vec<f64>,
+    /// the variables caused by a single-qubit error at each position, used to handle erasures
+    pub position_variables: HashMap<Position, Vec<usize>>,
+}
+
+impl SparseParityCheck {
+    pub fn new(model_hypergraph: &ModelHypergraph) -> Self {
+        let mut check_neighbors = vec![vec![]; model_hypergraph.vertex_positions.len()];
+        let mut variable_neighbors = Vec::with_capacity(model_hypergraph.weighted_edges.len());
+        let mut priors = Vec::with_capacity(model_hypergraph.weighted_edges.len());
+        let mut position_variables = HashMap::<Position, Vec<usize>>::new();
+        let mut entry_count = 0;
+        for (variable, (defect_vertices, hyperedge_group)) in model_hypergraph.weighted_edges.iter().enumerate() {
+            let mut checks = Vec::with_capacity(defect_vertices.0.len());
+            for position in defect_vertices.0.iter() {
+                let check = model_hypergraph.vertex_indices[position];
+                check_neighbors[check].push((variable, entry_count));
+                checks.push((check, entry_count));
+                entry_count += 1;
+            }
+            variable_neighbors.push(checks);
+            let probability = hyperedge_group.hyperedge.probability.clamp(1e-300, 1. - 1e-16);
+            priors.push(((1. - probability) / probability).ln());
+            let mut positions = BTreeSet::new();
+            for hyperedge in hyperedge_group.all_hyperedges.iter() {
+                if hyperedge.error_pattern.len() == 1 {
+                    positions.extend(hyperedge.error_pattern.errors.keys().cloned());
+                }
+            }
+            for position in positions.into_iter() {
+                position_variables.entry(position).or_default().push(variable);
+            }
+        }
+        Self {
+            check_neighbors,
+            variable_neighbors,
+            priors,
+            position_variables,
+        }
+    }
+
+    /// the defect of each check given the measurement results
+    pub fn syndrome(model_hypergraph: &ModelHypergraph, sparse_measurement: &SparseMeasurement) -> Vec<bool> {
+        let mut syndrome = vec![false; model_hypergraph.vertex_positions.len()];
+        for position in sparse_measurement.iter() {
+            let check = *model_hypergraph
+                .vertex_indices
+                .get(position)
+                .expect("measurement cannot happen at impossible position");
+            syndrome[check] = true;
+        }
+        syndrome
+    }
+
+    /// an erasure removes the prior knowledge of the errors at that position
+    pub fn priors_with_erasures(&self, sparse_detected_erasures: &SparseErasures) -> Vec<f64> {
+        let mut priors = self.priors.clone();
+        for position in sparse_detected_erasures.iter() {
+            if let Some(variables) = self.position_variables.get(position) {
+                for &variable in variables.iter() {
+                    priors[variable] = priors[variable].min(0.);
+                }
+            }
+        }
+        priors
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.variable_neighbors.iter().map(|checks| checks.len()).sum()
+    }
+
+    /// whether the hard decision satisfies the syndrome
+    pub fn is_satisfied(&self, syndrome: &[bool], decision: &[bool]) -> bool {
+        self.check_neighbors.iter().zip(syndrome.iter()).all(|(variables, &defect)| {
+            variables.iter().filter(|(variable, _)| decision[*variable]).count() % 2 == (defect as usize)
+        })
+    }
+}
+
+/// belief propagation on a sparse parity check matrix, each thread maintains its own messages
+#[derive(Debug, Clone, Serialize)]
+pub struct BeliefPropagation {
+    /// the sparse parity check matrix (immutable shared)
+    pub parity_check: Arc<SparseParityCheck>,
+    pub config: BeliefPropagationConfig,
+    /// the message from each variable to each check, indexed by the non-zero entries of the parity check matrix
+    #[serde(skip)]
+    variable_to_check: Vec<f64>,
+    /// the message from each check to each variable, indexed by the non-zero entries of the parity check matrix
+    #[serde(skip)]
+    check_to_variable: Vec<f64>,
+    /// the posterior log-likelihood ratio of each variable
+    #[serde(skip)]
+    pub posterior: Vec<f64>,
+}
+
+impl BeliefPropagation {
+    pub fn new(parity_check: Arc<SparseParityCheck>, config: BeliefPropagationConfig) -> Self {
+        let entry_count = parity_check.entry_count();
+        let variable_count = parity_check.variable_neighbors.len();
+        Self {
+            parity_check,
+            config,
+            variable_to_check: vec![0.; entry_count],
+            check_to_variable: vec![0.; entry_count],
+            posterior: vec![0.; variable_count],
+        }
+    }
+
+    /// run belief propagation and return the number of iterations and the hard decision if it satisfies the syndrome;
+    /// the posterior log-likelihood ratios are kept in `posterior`
+    pub fn run(&mut self, syndrome: &[bool], priors: &[f64]) -> (usize, Option<Vec<bool>>) {
+        let parity_check = Arc::clone(&self.parity_check);
+        for (variable, checks) in parity_check.variable_neighbors.iter().enumerate() {
+            self.posterior[variable] = priors[variable];
+            for &(_, entry) in checks.iter() {
+                self.variable_to_check[entry] = priors[variable];
+            }
+        }
+        let mut decision = vec![false; priors.len()];
+        for iteration in 1..=self.config.max_iterations {
+            // check node update
+            for (check, variables) in parity_check.check_neighbors.iter().enumerate() {
+                let sign = if syndrome[check] { -1. } else { 1. };
+                match self.config.bp_method {
+                    BeliefPropagationMethod::MinSum => {
+                        let mut total_sign = sign;
+                        let (mut min_1, mut min_2, mut min_entry) = (f64::INFINITY, f64::INFINITY, usize::MAX);
+                        for &(_, entry) in variables.iter() {
+                            let message = self.variable_to_check[entry];
+                            if message < 0. {
+                                total_sign = -total_sign;
+                            }
+                            let magnitude = message.abs();
+                            if magnitude < min_1 {
+                                min_2 = min_1;
+                                min_1 = magnitude;
+                                min_entry = entry;
+                            } else if magnitude < min_2 {
+                                min_2 = magnitude;
+                            }
+                        }
+                        for &(_, entry) in variables.iter() {
+                            let message = self.variable_to_check[entry];
+                            let others_sign = if message < 0. { -total_sign } else { total_sign };
+                            let magnitude = if entry == min_entry { min_2 } else { min_1 };
+                            self.check_to_variable[entry] = others_sign * self.config.scaling_factor * magnitude;
+                        }
+                    }
+                    BeliefPropagationMethod::ProductSum => {
+                        // leave-one-out products without division, which is unstable when a message is close to 0
+                        let tanh_values: Vec<f64> = variables
+                            .iter()
+                            .map(|&(_, entry)| (self.variable_to_check[entry] / 2.).tanh())
+                            .collect();
+                        let mut prefix = 1.;
+                        for (index, &(_, entry)) in variables.iter().enumerate() {
+                            self.check_to_variable[entry] = prefix;
+                            prefix *= tanh_values[index];
+                        }
+                        let mut suffix = 1.;
+                        for (index, &(_, entry)) in variables.iter().enumerate().rev() {
+                            let product = (sign * self.check_to_variable[entry] * suffix).clamp(-1. + 1e-15, 1. - 1e-15);
+                            self.check_to_variable[entry] = 2. * product.atanh();
+                            suffix *= tanh_values[index];
+                        }
+                    }
+                }
+            }
+            // variable node update and hard decision
+            for (variable, checks) in parity_check.variable_neighbors.iter().enumerate() {
+                let mut posterior = priors[variable];
+                for &(_, entry) in checks.iter() {
+                    posterior += self.check_to_variable[entry];
+                }
+                for &(_, entry) in checks.iter() {
+                    self.variable_to_check[entry] = posterior - self.check_to_variable[entry];
+                }
+                self.posterior[variable] = posterior;
+                decision[variable] = posterior < 0.;
+            }
+            if parity_check.is_satisfied(syndrome, &decision) {
+                return (iteration, Some(decision));
+            }
+        }
+        (self.config.max_iterations, None)
+    }
+}
+
+impl BPOSDDecoder {
+    /// create a new BP+OSD decoder with decoder configuration
+    pub fn new(
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        decoder_configuration: &serde_json::Value,
+        parallel: usize,
+        use_brief_edge: bool,
+    ) -> Self {
+        // read attribute of decoder configuration
+        let config: BPOSDDecoderConfig = serde_json::from_value(decoder_configuration.clone()).unwrap();
+        assert!(
+            config.osd_order <= 20,
+            "OSD order {} is too large to be exhaustive",
+            config.osd_order
+        );
+        // build model hypergraph; weights are not used by belief propagation
+        let mut simulator = simulator.clone();
+        let mut model_hypergraph = ModelHypergraph::new(&simulator);
+        model_hypergraph.build(
+            &mut simulator,
+            Arc::clone(&noise_model),
+            &WeightFunction::AutotuneImproved,
+            parallel,
+            config.use_combined_probability,
+            use_brief_edge,
+        );
+        let parity_check = SparseParityCheck::new(&model_hypergraph);
+        let belief_propagation = BeliefPropagation::new(
+            Arc::new(parity_check),
+            BeliefPropagationConfig {
+                max_iterations: config.max_iterations,
+                bp_method: config.bp_method,
+                scaling_factor: config.scaling_factor,
+            },
+        );
+        Self {
+            model_hypergraph: Arc::new(model_hypergraph),
+            config,
+            belief_propagation,
+        }
+    }
+
+    /// decode given measurement results
+    #[allow(dead_code)]
+    pub fn decode(&mut self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_erasure(sparse_measurement, &SparseErasures::new())
+    }
+
+    /// decode given measurement results and detected erasures; an erasure removes the prior knowledge of the errors at
+    /// that position
+    pub fn decode_with_erasure(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        if sparse_measurement.is_empty() {
+            return (SparseCorrection::new(), json!({}));
+        }
+        let (decision, mut runtime_statistics) = self.decide(sparse_measurement, sparse_detected_erasures);
+        // build correction
+        let begin = Instant::now();
+        let mut correction = SparseCorrection::new();
+        for (variable, &flipped) in decision.iter().enumerate() {
+            if flipped {
+                correction.extend(&self.model_hypergraph.weighted_edges[variable].1.hyperedge.correction);
+            }
+        }
+        runtime_statistics["time_build_correction"] = json!(begin.elapsed().as_secs_f64());
+        (correction, runtime_statistics)
+    }
+
+    /// decode given measurement results and detected erasures, and return the chosen hyperedges
+    pub fn decode_subgraph(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseSubgraph, serde_json::Value) {
+        let mut subgraph = SparseSubgraph::new();
+        if sparse_measurement.is_empty() {
+            return (subgraph, json!({}));
+        }
+        let (decision, runtime_statistics) = self.decide(sparse_measurement, sparse_detected_erasures);
+        for (variable, &flipped) in decision.iter().enumerate() {
+            if flipped {
+                let (defect_vertices, group) = &self.model_hypergraph.weighted_edges[variable];
+                subgraph.push(defect_vertices.0.clone(), group.hyperedge.correction.clone());
+            }
+        }
+        (subgraph, runtime_statistics)
+    }
+
+    /// the flipped variables given by belief propagation, or ordered statistics decoding if it doesn't converge
+    fn decide(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (Vec<bool>, serde_json::Value) {
+        let begin = Instant::now();
+        let syndrome = SparseParityCheck::syndrome(&self.model_hypergraph, sparse_measurement);
+        let priors = self
+            .belief_propagation
+            .parity_check
+            .priors_with_erasures(sparse_detected_erasures);
+        let (iterations, decision) = self.belief_propagation.run(&syndrome, &priors);
+        let bp_converged = decision.is_some();
+        let decision = decision.unwrap_or_else(|| self.ordered_statistics_decoding(&syndrome, &priors));
+        let time_decode = begin.elapsed().as_secs_f64();
+        (
+            decision,
+            json!({
+                "bp_iterations": iterations,
+                "bp_converged": bp_converged,
+                "time_decode": time_decode,
+            }),
+        )
+    }
+
+    /// find a solution by Gaussian elimination over the most likely variables given the posterior of belief propagation
+    fn ordered_statistics_decoding(&self, syndrome: &[bool], priors: &[f64]) -> Vec<bool> {
+        let parity_check = &self.belief_propagation.parity_check;
+        let posterior = &self.belief_propagation.posterior;
+        let variable_count = parity_check.variable_neighbors.len();
+        let check_count = parity_check.check_neighbors.len();
+        // the most likely flipped variables come first
+        let mut order: Vec<usize> = (0..variable_count).collect();
+        order.sort_by(|a, b| posterior[*a].partial_cmp(&posterior[*b]).unwrap().then(a.cmp(b)));
+        let mut rank_of = vec![0; variable_count];
+        for (rank, &variable) in order.iter().enumerate() {
+            rank_of[variable] = rank;
+        }
+        // dense matrix with permuted columns and the syndrome as the augmented column
+        let words = (variable_count + 63) / 64;
+        let mut rows = vec![vec![0u64; words]; check_count];
+        for (check, variables) in parity_check.check_neighbors.iter().enumerate() {
+            for &(variable, _) in variables.iter() {
+                let column = rank_of[variable];
+                rows[check][column / 64] ^= 1 << (column % 64);
+            }
+        }
+        let mut augmented = syndrome.to_vec();
+        let get_bit = |row: &Vec<u64>, column: usize| (row[column / 64] >> (column % 64)) & 1 == 1;
+        // reduced row echelon form
+        let mut pivots = Vec::with_capacity(check_count);
+        for column in 0..variable_count {
+            let rank = pivots.len();
+            if rank == check_count {
+                break;
+            }
+            let Some(pivot_row) = (rank..check_count).find(|&row| get_bit(&rows[row], column)) else {
+                continue;
+            };
+            rows.swap(rank, pivot_row);
+            augmented.swap(rank, pivot_row);
+            for row in 0..check_count {
+                if row != rank && get_bit(&rows[row], column) {
+                    for word in column / 64..words {
+                        let value = rows[rank][word];
+                        rows[row][word] ^= value;
+                    }
+                    augmented[row] ^= augmented[rank];
+                }
+            }
+            pivots.push(column);
+        }
+        // the non-pivot columns to be searched exhaustively, the least reliable ones first
+        let pivot_set: BTreeSet<usize> = pivots.iter().cloned().collect();
+        let search_columns: Vec<usize> = (0..variable_count)
+            .filter(|column| !pivot_set.contains(column))
+            .take(self.config.osd_order)
+            .collect();
+        let mut best_decision = vec![false; variable_count];
+        let mut best_cost = f64::INFINITY;
+        for configuration in 0..(1usize << search_columns.len()) {
+            let mut decision = vec![false; variable_count];
+            let mut target = augmented.clone();
+            for (index, &column) in search_columns.iter().enumerate() {
+                if configuration & (1 << index) != 0 {
+                    decision[order[column]] = true;
+                    for (row, target_bit) in target.iter_mut().enumerate().take(pivots.len()) {
+                        *target_bit ^= get_bit(&rows[row], column);
+                    }
+                }
+            }
+            for (row, &column) in pivots.iter().enumerate() {
+                if target[row] {
+                    decision[order[column]] = true;
+                }
+            }
+            let cost: f64 = (0..variable_count)
+                .filter(|&variable| decision[variable])
+                .map(|variable| priors[variable])
+                .sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_decision = decision;
+            }
+        }
+        best_decision
+    }
+}
+
+/// belief-matching: the posterior of each hyperedge given by belief propagation is decomposed onto the edges of a matching
+/// graph, so that the matching decoder can be reweighted in every shot, capturing the correlations between the edges
+/// (e.g. Y errors and hook errors) that plain matching ignores; `T` is the edge identifier of the matching decoder
+#[derive(Debug, Clone, Serialize)]
+pub struct BeliefMatching<T: Clone> {
+    /// model hypergraph
+    pub model_hypergraph: Arc<ModelHypergraph>,
+    /// belief propagation on the parity check matrix given by the model hypergraph
+    pub belief_propagation: BeliefPropagation,
+    /// the decomposition of hyperedges onto the matching graph (immutable shared)
+    pub decomposition: Arc<HyperedgeDecomposition<T>>,
+}
+
+impl<T: Clone> BeliefMatching<T> {
+    /// avoid infinite weights when belief propagation is very confident
+    pub const MIN_PROBABILITY: f64 = 1e-15;
+
+    /// `graph_edge_of` returns the edge in the matching graph given the real defect vertices, see
+    /// [`HyperedgeDecomposition::new`]; a hyperedge is ignored if it cannot be decomposed onto the matching graph
+    pub fn new<F>(
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        config: &BeliefPropagationConfig,
+        parallel: usize,
+        use_combined_probability: bool,
+        use_brief_edge: bool,
+        graph_edge_of: F,
+    ) -> Self
+    where
+        F: Fn(&[Position]) -> Option<T>,
+    {
+        let mut simulator = simulator.clone();
+        let mut model_hypergraph = ModelHypergraph::new(&simulator);
+        model_hypergraph.build(
+            &mut simulator,
+            noise_model,
+            &WeightFunction::AutotuneImproved,
+            parallel,
+            use_combined_probability,
+            use_brief_edge,
+        );
+        let decomposition = HyperedgeDecomposition::new(&model_hypergraph, &simulator, graph_edge_of);
+        let parity_check = SparseParityCheck::new(&model_hypergraph);
+        Self {
+            model_hypergraph: Arc::new(model_hypergraph),
+            belief_propagation: BeliefPropagation::new(Arc::new(parity_check), config.clone()),
+            decomposition: Arc::new(decomposition),
+        }
+    }
+
+    /// run belief propagation and return the posterior probability of each edge in the decomposition, bounded within
+    /// [`BeliefMatching::MIN_PROBABILITY`, 0.5] so that the weight is always non-negative and finite
+    pub fn edge_probabilities(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> Vec<f64> {
+        let syndrome = SparseParityCheck::syndrome(&self.model_hypergraph, sparse_measurement);
+        let priors = self
+            .belief_propagation
+            .parity_check
+            .priors_with_erasures(sparse_detected_erasures);
+        self.belief_propagation.run(&syndrome, &priors);
+        let posterior = &self.belief_propagation.posterior;
+        self.decomposition
+            .graph_edges
+            .iter()
+            .map(|(_, hyperedges)| {
+                let mut probability = 0.;
+                for &hyperedge_index in hyperedges.iter() {
+                    let marginal = 1. / (1. + posterior[hyperedge_index].exp());
+                    probability = probability * (1. - marginal) + marginal * (1. - probability);
+                }
+                probability.clamp(Self::MIN_PROBABILITY, 0.5)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::code_builder::*;
+    use super::super::types::ErrorType::*;
+    use super::*;
+
+    #[test]
+    fn bp_osd_decoder_code_capacity() {
+        // cargo test bp_osd_decoder_code_capacity -- --nocapture
+        let d = 5;
+        let noisy_measurements = 0; // perfect measurement
+        let p = 0.001;
+        // build simulator
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        // build noise model
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p, p, p, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        for decoder_config in [
+            json!({}),
+            json!({ "bp_method": "ProductSum" }),
+            json!({ "max_iterations": 0, "osd_order": 3 }), // OSD only
+        ] {
+            let mut bp_osd_decoder = BPOSDDecoder::new(
+                &Arc::new(simulator.clone()),
+                Arc::clone(&noise_model),
+                &decoder_config,
+                1,
+                false,
+            );
+            // two correctable errors
+            simulator.clear_all_errors();
+            simulator.set_error_check(&noise_model, &pos!(0, 4, 6), &Z);
+            simulator.set_error_check(&noise_model, &pos!(0, 7, 1), &X);
+            simulator.propagate_errors();
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, runtime_statistics) = bp_osd_decoder.decode(&sparse_measurement);
+            println!("{decoder_config}: {runtime_statistics}");
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert!(!logical_i && !logical_j);
+        }
+    }
+}
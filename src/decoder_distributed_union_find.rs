@@ -0,0 +1,512 @@
+//! # Distributed Union-Find Decoder
+//!
+//! Runs the clock-cycle-accurate simulation of [`DistributedUnionFind`] on the circuit-level [`ModelGraph`] of any code.
+//! Each node in the model graph maps to a processing unit, each model graph edge to a neighbor link of scaled length,
+//! and fast channels connect nodes at distance `fast_channel_interval^k` along a single axis of the spacetime lattice.
+//! The number of clock cycles to reach a stable state is reported in the runtime statistics of every shot,
+//! and the correction is built from the resulting clusters in the same way as the sequential union-find decoder.
+//!
+
+use super::complete_model_graph::*;
+use super::decoder_mwpm::*;
+use super::decoder_union_find::*;
+use super::distributed_uf_decoder::*;
+use super::erasure_graph::*;
+use super::model_graph::*;
+use super::noise_model::*;
+use super::serde_json;
+use super::simulator::*;
+use super::union_find::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// distributed union-find decoder, initialized and cloned for multiple threads; the processing units are not thread-safe,
+/// so they're instantiated from the immutable inputs for every shot
+#[derive(Debug, Clone, Serialize)]
+pub struct DistributedUnionFindDecoder {
+    /// model graph is immutably shared
+    pub model_graph: Arc<ModelGraph>,
+    /// erasure graph is immutably shared
+    pub erasure_graph: Arc<ErasureGraph>,
+    /// complete model graph each thread maintain its own precomputed data
+    pub complete_model_graph: CompleteModelGraph,
+    /// index to position mapping (immutable shared), index is the one used by the processing units
+    pub index_to_position: Arc<Vec<Position>>,
+    /// position to index mapping (immutable shared)
+    pub position_to_index: Arc<HashMap<Position, usize>>,
+    /// the scaled cost of matching each node to the boundary, `None` if not connected to the boundary
+    pub boundary_costs: Arc<Vec<Option<usize>>>,
+    /// the links between neighbor nodes, sorted so that an erased edge can be found by binary search
+    pub input_neighbors: Arc<Vec<InputNeighbor>>,
+    /// the fast channels between distant nodes
+    pub input_fast_channels: Arc<Vec<InputFastChannel>>,
+    /// the time steps between two measurement rounds, used to compute the distance between nodes
+    pub measurement_cycles: usize,
+    /// save configuration for later usage
+    pub config: DistributedUnionFindDecoderConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DistributedUnionFindDecoderConfig {
+    /// build complete model graph at first, but this will consume O(N^2) memory and increase initialization time,
+    /// disable this when you're simulating large code
+    #[serde(alias = "pcmg")] // abbreviation
+    #[serde(default = "mwpm_default_configs::precompute_complete_model_graph")]
+    pub precompute_complete_model_graph: bool,
+    /// weight function, by default using [`WeightFunction::AutotuneImproved`]
+    #[serde(alias = "wf")] // abbreviation
+    #[serde(default = "mwpm_default_configs::weight_function")]
+    pub weight_function: WeightFunction,
+    /// combined probability can improve accuracy, but will cause probabilities differ a lot even in the case of i.i.d. noise model
+    #[serde(alias = "ucp")] // abbreviation
+    #[serde(default = "mwpm_default_configs::use_combined_probability")]
+    pub use_combined_probability: bool,
+    /// maximum length of a neighbor link will be 2 * max_half_weight, each growing iteration takes a few clock cycles,
+    /// so a larger value trades latency for accuracy; by default is 1: unweighted
+    #[serde(alias = "mhw")] // abbreviation
+    #[serde(default = "union_find_default_configs::max_half_weight")]
+    pub max_half_weight: usize,
+    /// fast channels connect two nodes if they're at exact distance of `fast_channel_interval^k` (k >= 1) stabilizers along one axis
+    /// and identical in the other axes; 0 or 1 disables fast channels, which falls back to the naive O(d^2) design
+    #[serde(alias = "fci")] // abbreviation
+    #[serde(default = "distributed_union_find_default_configs::fast_channel_interval")]
+    pub fast_channel_interval: usize,
+    /// the latency of fast channels in clock cycles, while the neighbor links always have a latency of 1
+    #[serde(alias = "fcl")] // abbreviation
+    #[serde(default = "distributed_union_find_default_configs::fast_channel_latency")]
+    pub fast_channel_latency: usize,
+    /// skip building correction, only for benchmarking the clock cycles
+    #[serde(alias = "bsbc")] // abbreviation
+    #[serde(default = "union_find_default_configs::benchmark_skip_building_correction")]
+    pub benchmark_skip_building_correction: bool,
+}
+
+pub mod distributed_union_find_default_configs {
+    pub fn fast_channel_interval() -> usize {
+        2
+    }
+    pub fn fast_channel_latency() -> usize {
+        1
+    }
+}
+
+/// the Manhattan distance between two nodes counted in stabilizers, used to route direct messages
+pub fn lattice_distance(a: &Position, b: &Position, measurement_cycles: usize) -> usize {
+    a.t.abs_diff(b.t) / measurement_cycles + (a.i.abs_diff(b.i) + a.j.abs_diff(b.j)) / 2
+}
+
+impl DistributedUnionFindDecoder {
+    /// create a new distributed union-find decoder with decoder configuration
+    pub fn new(
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        decoder_configuration: &serde_json::Value,
+        parallel: usize,
+        use_brief_edge: bool,
+    ) -> Self {
+        // read attribute of decoder configuration
+        let config: DistributedUnionFindDecoderConfig = serde_json::from_value(decoder_configuration.clone()).unwrap();
+        assert!(config.max_half_weight >= 1, "max_half_weight must be positive");
+        assert!(config.fast_channel_latency >= 1, "latency must be at least 1 clock cycle");
+        // build model graph
+        let mut simulator = simulator.clone();
+        let mut model_graph = ModelGraph::new(&simulator);
+        model_graph.build(
+            &mut simulator,
+            Arc::clone(&noise_model),
+            &config.weight_function,
+            parallel,
+            config.use_combined_probability,
+            use_brief_edge,
+        );
+        let model_graph = Arc::new(model_graph);
+        // build erasure graph
+        let mut erasure_graph = ErasureGraph::new(&simulator);
+        erasure_graph.build(&mut simulator, Arc::clone(&noise_model), parallel);
+        let erasure_graph = Arc::new(erasure_graph);
+        // build complete model graph
+        let mut complete_model_graph = CompleteModelGraph::new(&simulator, Arc::clone(&model_graph));
+        complete_model_graph.optimize_weight_greater_than_sum_boundary = false; // disable this optimization for any matching pair to exist
+        complete_model_graph.precompute(&simulator, config.precompute_complete_model_graph, parallel);
+        // build nodes
+        let mut index_to_position = Vec::<Position>::new();
+        simulator_iter!(simulator, position, delta_t => simulator.measurement_cycles, if model_graph.is_node_exist(position) {
+            index_to_position.push(position.clone());
+        });
+        let position_to_index: HashMap<Position, usize> = index_to_position
+            .iter()
+            .enumerate()
+            .map(|(index, position)| (position.clone(), index))
+            .collect();
+        // calculate scaling factor of edges
+        let mut maximum_weight = 0.;
+        for position in index_to_position.iter() {
+            let model_graph_node = model_graph.get_node_unwrap(position);
+            for edge in model_graph_node.edges.values() {
+                if edge.probability > 0. && edge.weight > maximum_weight {
+                    maximum_weight = edge.weight;
+                }
+            }
+            if let Some(boundary) = &model_graph_node.boundary {
+                if boundary.probability > 0. && boundary.weight > maximum_weight {
+                    maximum_weight = boundary.weight;
+                }
+            }
+        }
+        let scale_weight = |weight: f64| -> usize {
+            if maximum_weight == 0. {
+                // pure erasure channel could lead to this, all possible errors has weight = 0
+                2 * config.max_half_weight
+            } else {
+                let half_weight = ((config.max_half_weight as f64) * weight / maximum_weight).round() as usize;
+                2 * half_weight.clamp(1, config.max_half_weight)
+            }
+        };
+        // build neighbor links and boundaries, while recording the connected components so that fast channels never cross them
+        let mut neighbors = Vec::<InputNeighbor>::new();
+        let mut boundary_costs = Vec::<Option<usize>>::with_capacity(index_to_position.len());
+        let mut components = DefaultUnionFind::new(index_to_position.len());
+        for (index, position) in index_to_position.iter().enumerate() {
+            let model_graph_node = model_graph.get_node_unwrap(position);
+            for (peer_position, edge) in model_graph_node.edges.iter() {
+                let peer_index = position_to_index[peer_position];
+                if edge.probability > 0. && index < peer_index {
+                    neighbors.push(InputNeighbor::new(index, peer_index, 0, scale_weight(edge.weight), 1));
+                    components.union(index, peer_index);
+                }
+            }
+            boundary_costs.push(match &model_graph_node.boundary {
+                Some(boundary) if boundary.probability > 0. => Some(scale_weight(boundary.weight)),
+                _ => None,
+            });
+        }
+        neighbors.sort_unstable();
+        // build fast channels along each axis; stabilizers of the same type are 2 apart in space and a measurement cycle apart in time
+        let mut fast_channels = Vec::<InputFastChannel>::new();
+        if config.fast_channel_interval > 1 {
+            let units = [simulator.measurement_cycles, 2, 2];
+            let bounds = [simulator.height, simulator.vertical, simulator.horizontal];
+            for (index, position) in index_to_position.iter().enumerate() {
+                for axis in 0..3 {
+                    let mut interval = config.fast_channel_interval;
+                    loop {
+                        let mut coordinates = [position.t, position.i, position.j];
+                        coordinates[axis] += interval * units[axis];
+                        if coordinates[axis] >= bounds[axis] {
+                            break;
+                        }
+                        let peer_position = Position::new(coordinates[0], coordinates[1], coordinates[2]);
+                        if let Some(&peer_index) = position_to_index.get(&peer_position) {
+                            if components.find(index) == components.find(peer_index) {
+                                fast_channels.push(InputFastChannel::new(index, peer_index, config.fast_channel_latency));
+                            }
+                        }
+                        interval *= config.fast_channel_interval;
+                    }
+                }
+            }
+        }
+        Self {
+            model_graph,
+            erasure_graph,
+            complete_model_graph,
+            index_to_position: Arc::new(index_to_position),
+            position_to_index: Arc::new(position_to_index),
+            boundary_costs: Arc::new(boundary_costs),
+            input_neighbors: Arc::new(neighbors),
+            input_fast_channels: Arc::new(fast_channels),
+            measurement_cycles: simulator.measurement_cycles,
+            config,
+        }
+    }
+
+    /// decode given measurement results
+    #[allow(dead_code)]
+    pub fn decode(&mut self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_erasure(sparse_measurement, &SparseErasures::new())
+    }
+
+    /// instantiate the processing units given the syndrome, with the erased edges fully grown
+    pub fn build_distributed_union_find(
+        &self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> DistributedUnionFind<Position> {
+        let mut nodes: Vec<InputNode<Position>> = self
+            .index_to_position
+            .iter()
+            .zip(self.boundary_costs.iter())
+            .map(|(position, boundary_cost)| InputNode::new(position.clone(), false, *boundary_cost))
+            .collect();
+        for position in sparse_measurement.iter() {
+            nodes[self.position_to_index[position]].is_error_syndrome = true;
+        }
+        let mut neighbors = (*self.input_neighbors).clone();
+        let mut erased_boundaries = Vec::<usize>::new();
+        if !sparse_detected_erasures.is_empty() {
+            for erasure_edge in sparse_detected_erasures.get_erasure_edges(&self.erasure_graph).iter() {
+                match erasure_edge {
+                    ErasureEdge::Connection(position1, position2) => {
+                        let index1 = self.position_to_index[position1];
+                        let index2 = self.position_to_index[position2];
+                        let neighbor_index = neighbors
+                            .binary_search(&InputNeighbor::new(index1, index2, 0, 0, 1))
+                            .expect("neighbor must exist");
+                        let neighbor = &mut neighbors[neighbor_index];
+                        neighbor.increased = neighbor.length;
+                    }
+                    ErasureEdge::Boundary(position) => {
+                        erased_boundaries.push(self.position_to_index[position]);
+                    }
+                }
+            }
+        }
+        let measurement_cycles = self.measurement_cycles;
+        let mut distributed_union_find = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            (*self.input_fast_channels).clone(),
+            move |a: &Position, b: &Position| lattice_distance(a, b, measurement_cycles),
+            |a: &Position, b: &Position| a.cmp(b),
+        );
+        for index in erased_boundaries.into_iter() {
+            let boundary_cost = self.boundary_costs[index].expect("boundary must exist");
+            distributed_union_find.processing_units[index].boundary_increased = boundary_cost;
+        }
+        distributed_union_find
+    }
+
+    /// run the processing units until there is no odd cluster, return (clock cycles, number of growing iterations)
+    pub fn run_to_stable(distributed_union_find: &mut DistributedUnionFind<Position>) -> (usize, usize) {
+        let (mut clock_cycles, mut has_odd_cluster) = distributed_union_find.reach_consistent_state();
+        let mut count_iteration = 0;
+        while has_odd_cluster {
+            let (iteration_clock_cycles, iteration_has_odd_cluster) = distributed_union_find.run_single_iteration();
+            clock_cycles += iteration_clock_cycles;
+            has_odd_cluster = iteration_has_odd_cluster;
+            count_iteration += 1;
+        }
+        (clock_cycles, count_iteration)
+    }
+
+    /// decode given measurement results and detected erasures
+    pub fn decode_with_erasure(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        let begin = Instant::now();
+        let mut distributed_union_find = self.build_distributed_union_find(sparse_measurement, sparse_detected_erasures);
+        let time_prepare_decoders = begin.elapsed().as_secs_f64();
+        let begin = Instant::now();
+        let (clock_cycles, count_iteration) = Self::run_to_stable(&mut distributed_union_find);
+        let time_run_to_stable = begin.elapsed().as_secs_f64();
+        // build correction based on the clusters
+        let begin = Instant::now();
+        let mut correction = SparseCorrection::new();
+        if !self.config.benchmark_skip_building_correction {
+            // invalidate previous cache to save memory
+            self.complete_model_graph.invalidate_previous_dijkstra();
+            let processing_units = &distributed_union_find.processing_units;
+            let mut cluster_nodes = BTreeMap::<usize, Vec<usize>>::new();
+            for position in sparse_measurement.iter() {
+                let index = self.position_to_index[position];
+                cluster_nodes
+                    .entry(processing_units[index].updated_root)
+                    .or_default()
+                    .push(index);
+            }
+            // any node that is bleeding into the boundary can be used to match an odd cluster to the boundary
+            let mut cluster_boundary_index = HashMap::<usize, usize>::new();
+            for (index, processing_unit) in processing_units.iter().enumerate() {
+                if let Some(boundary_cost) = self.boundary_costs[index] {
+                    if processing_unit.boundary_increased >= boundary_cost {
+                        cluster_boundary_index.entry(processing_unit.updated_root).or_insert(index);
+                    }
+                }
+            }
+            for (root, mut error_syndromes) in cluster_nodes.into_iter() {
+                debug_assert_eq!(processing_units[root].is_odd_cardinality, error_syndromes.len() % 2 == 1);
+                if error_syndromes.len() % 2 == 1 {
+                    let boundary_index = *cluster_boundary_index
+                        .get(&root)
+                        .expect("boundary of odd cluster must exists");
+                    // connect to a boundary and others internally
+                    error_syndromes.push(boundary_index);
+                    let boundary_correction = self
+                        .complete_model_graph
+                        .build_correction_boundary(&self.index_to_position[boundary_index]);
+                    correction.extend(&boundary_correction);
+                }
+                let half_len = error_syndromes.len() / 2;
+                for i in 0..half_len {
+                    let index1 = error_syndromes[i];
+                    let index2 = error_syndromes[i + half_len];
+                    if index1 != index2 {
+                        let matching_correction = self
+                            .complete_model_graph
+                            .build_correction_matching(&self.index_to_position[index1], &self.index_to_position[index2]);
+                        correction.extend(&matching_correction);
+                    }
+                }
+            }
+        }
+        let time_build_correction = begin.elapsed().as_secs_f64();
+        (
+            correction,
+            json!({
+                "time_prepare_decoders": time_prepare_decoders,
+                "time_run_to_stable": time_run_to_stable,
+                "time_build_correction": time_build_correction,
+                "clock_cycles": clock_cycles,
+                "count_iteration": count_iteration,
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::code_builder::*;
+    use super::super::noise_model_builder::*;
+    use super::super::types::ErrorType::*;
+    use super::*;
+    use crate::rand::SeedableRng;
+    use crate::reproducible_rand::Xoroshiro128StarStar;
+    use std::collections::VecDeque;
+
+    fn build_noise_model(
+        simulator: &mut Simulator,
+        noise_model_builder: Option<NoiseModelBuilder>,
+        p: f64,
+    ) -> Arc<NoiseModel> {
+        let mut noise_model = NoiseModel::new(simulator);
+        match noise_model_builder {
+            Some(noise_model_builder) => {
+                noise_model_builder.apply(simulator, &mut noise_model, &json!({}), p, 1., 0.);
+            }
+            None => {
+                simulator.set_error_rates(&mut noise_model, p, p, p, 0.);
+            }
+        }
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(simulator, &noise_model).unwrap();
+        Arc::new(noise_model)
+    }
+
+    #[test]
+    fn distributed_union_find_decoder_code_capacity() {
+        // cargo test distributed_union_find_decoder_code_capacity -- --nocapture
+        let d = 5;
+        let p = 0.001;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let noise_model = build_noise_model(&mut simulator, None, p);
+        let mut decoder = DistributedUnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        // a single error is always corrected
+        simulator.clear_all_errors();
+        simulator.set_error_check(&noise_model, &pos!(0, 5, 5), &Z);
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        let (correction, runtime_statistics) = decoder.decode(&sparse_measurement);
+        println!("runtime_statistics: {runtime_statistics}");
+        code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        let (logical_i, logical_j) = simulator.validate_correction(&correction);
+        assert!(!logical_i && !logical_j);
+        assert_eq!(runtime_statistics["count_iteration"], 1);
+        // two errors next to each other are matched together without touching the boundary
+        simulator.clear_all_errors();
+        simulator.set_error_check(&noise_model, &pos!(0, 4, 4), &Z);
+        simulator.set_error_check(&noise_model, &pos!(0, 6, 4), &Z);
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        let (correction, _runtime_statistics) = decoder.decode(&sparse_measurement);
+        code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        let (logical_i, logical_j) = simulator.validate_correction(&correction);
+        assert!(!logical_i && !logical_j);
+    }
+
+    /// direct messages are routed greedily by [`lattice_distance`], which requires that every node has a neighbor or a fast channel
+    /// closer to any other node in the same connected component
+    #[test]
+    fn distributed_union_find_decoder_greedy_routing() {
+        // cargo test distributed_union_find_decoder_greedy_routing -- --nocapture
+        let d = 3;
+        let noisy_measurements = 2;
+        for code_type in [
+            CodeType::StandardPlanarCode,
+            CodeType::RotatedPlanarCode,
+            CodeType::StandardXZZXCode,
+            CodeType::RotatedXZZXCode,
+        ] {
+            for noise_model_builder in [None, Some(NoiseModelBuilder::Phenomenological)] {
+                let mut simulator = Simulator::new(code_type, CodeSize::new(noisy_measurements, d, d));
+                let noise_model = build_noise_model(&mut simulator, noise_model_builder, 0.001);
+                let decoder = DistributedUnionFindDecoder::new(&simulator, noise_model, &json!({}), 1, false);
+                let distributed_union_find =
+                    decoder.build_distributed_union_find(&SparseMeasurement::new(), &SparseErasures::new());
+                let node_num = decoder.index_to_position.len();
+                for receiver in 0..node_num {
+                    // all nodes reachable from the receiver
+                    let mut reachable = vec![false; node_num];
+                    reachable[receiver] = true;
+                    let mut queue = VecDeque::from([receiver]);
+                    while let Some(index) = queue.pop_front() {
+                        for neighbor in distributed_union_find.processing_units[index].neighbors.iter() {
+                            if !reachable[neighbor.address] {
+                                reachable[neighbor.address] = true;
+                                queue.push_back(neighbor.address);
+                            }
+                        }
+                    }
+                    for sender in (0..node_num).filter(|&sender| sender != receiver && reachable[sender]) {
+                        let distance = distributed_union_find.get_node_distance(sender, receiver);
+                        let has_closer_peer = distributed_union_find.processing_units[sender]
+                            .direct_out_channels
+                            .iter()
+                            .any(|(peer, _)| distributed_union_find.get_node_distance(*peer, receiver) < distance);
+                        assert!(
+                            has_closer_peer,
+                            "{code_type:?}: cannot route from {} to {}",
+                            decoder.index_to_position[sender], decoder.index_to_position[receiver]
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn distributed_union_find_decoder_circuit_level() {
+        // cargo test distributed_union_find_decoder_circuit_level -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let p = 0.005;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let noise_model = build_noise_model(&mut simulator, Some(NoiseModelBuilder::StimNoiseModel), p);
+        let mut decoder =
+            DistributedUnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &json!({"mhw": 4}), 1, false);
+        let mut naive_decoder =
+            DistributedUnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &json!({"mhw": 4, "fci": 0}), 1, false);
+        assert!(naive_decoder.input_fast_channels.is_empty());
+        assert!(!decoder.input_fast_channels.is_empty());
+        simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+        let (mut clock_cycles, mut naive_clock_cycles) = (0, 0);
+        for _ in 0..50 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, runtime_statistics) = decoder.decode(&sparse_measurement);
+            let (naive_correction, naive_runtime_statistics) = naive_decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            // fast channels only change the latency, not the clusters
+            assert!(correction.iter().eq(naive_correction.iter()));
+            clock_cycles += runtime_statistics["clock_cycles"].as_u64().unwrap();
+            naive_clock_cycles += naive_runtime_statistics["clock_cycles"].as_u64().unwrap();
+        }
+        println!("clock cycles: {clock_cycles} with fast channels, {naive_clock_cycles} without");
+        assert!(clock_cycles <= naive_clock_cycles);
+    }
+}
@@ -0,0 +1,390 @@
+//! # Ensemble Decoder
+//!
+//! run several decoders on the same syndrome and choose one of their corrections. in [`EnsembleMode::MinimumWeight`], each
+//! decoder reports the chosen edges (see [`GeneralDecoder::decode_subgraph`]) and the correction with the lowest total weight
+//! under the [`ModelGraph`] wins, which approaches the accuracy of a maximum-likelihood decoder when the decoders make different
+//! mistakes. in [`EnsembleMode::MajorityVote`], any decoder can be used and the logical class chosen by the most decoders wins.
+//! ties are broken in favor of the decoder listed first.
+//!
+
+use super::erasure_graph::*;
+use super::model_graph::*;
+use super::noise_model::*;
+use super::simulator::*;
+use super::tool::*;
+use crate::decoder_mwpm::mwpm_default_configs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// ensemble decoder, initialized and cloned for multiple threads
+#[derive(Clone)]
+pub struct EnsembleDecoder {
+    /// the decoders that run on the same syndrome, in the same order as [`EnsembleDecoderConfig::decoders`]
+    pub decoders: Vec<GeneralDecoder>,
+    /// model graph is immutably shared, used to evaluate the weight of the decoding results
+    pub model_graph: Arc<ModelGraph>,
+    /// erasure graph is immutably shared, used to evaluate the weight of the decoding results with erasures
+    pub erasure_graph: Arc<ErasureGraph>,
+    /// a simulator without errors, used to compute the logical class of the corrections
+    pub clean_simulator: Simulator,
+    /// save configuration for later usage
+    pub config: EnsembleDecoderConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnsembleDecoderConfig {
+    /// the decoders in the ensemble
+    #[serde(alias = "d")] // abbreviation
+    pub decoders: Vec<EnsembleMemberConfig>,
+    /// how to choose the correction, by default [`EnsembleMode::MinimumWeight`]
+    #[serde(default = "ensemble_default_configs::mode")]
+    pub mode: EnsembleMode,
+    /// weight function of the model graph that evaluates the decoding results, by default [`WeightFunction::AutotuneImproved`]
+    #[serde(alias = "wf")] // abbreviation
+    #[serde(default = "mwpm_default_configs::weight_function")]
+    pub weight_function: WeightFunction,
+    /// combined probability can improve accuracy, but will cause probabilities differ a lot even in the case of i.i.d. noise model
+    #[serde(alias = "ucp")] // abbreviation
+    #[serde(default = "mwpm_default_configs::use_combined_probability")]
+    pub use_combined_probability: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnsembleMemberConfig {
+    pub decoder: BenchmarkDecoder,
+    /// the decoder configuration, the same as `--decoder-config` when the decoder runs alone
+    #[serde(alias = "c")] // abbreviation
+    #[serde(default = "ensemble_default_configs::decoder_config")]
+    pub decoder_config: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnsembleMode {
+    /// the correction with the lowest total weight of the chosen edges; requires every decoder to report the chosen edges
+    MinimumWeight,
+    /// the logical class chosen by the most decoders
+    MajorityVote,
+}
+
+pub mod ensemble_default_configs {
+    use super::*;
+    pub fn mode() -> EnsembleMode {
+        EnsembleMode::MinimumWeight
+    }
+    pub fn decoder_config() -> serde_json::Value {
+        json!({})
+    }
+}
+
+impl EnsembleDecoder {
+    /// `decoders` are built from `config.decoders` on the same simulator and noise model
+    pub fn new(
+        decoders: Vec<GeneralDecoder>,
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        config: EnsembleDecoderConfig,
+        parallel: usize,
+        use_brief_edge: bool,
+    ) -> Result<Self, String> {
+        if decoders.is_empty() {
+            return Err("ensemble decoder requires at least 1 decoder".to_string());
+        }
+        assert_eq!(
+            decoders.len(),
+            config.decoders.len(),
+            "each decoder should have its configuration"
+        );
+        if config.mode == EnsembleMode::MinimumWeight {
+            for (decoder, member) in decoders.iter().zip(config.decoders.iter()) {
+                if !decoder.supports_subgraph() {
+                    return Err(format!(
+                        "decoder {:?} doesn't report the chosen edges, thus its weight is unknown; try `MajorityVote`",
+                        member.decoder
+                    ));
+                }
+            }
+        }
+        // build model graph
+        let mut simulator = simulator.clone();
+        let mut model_graph = ModelGraph::new(&simulator);
+        model_graph.build(
+            &mut simulator,
+            Arc::clone(&noise_model),
+            &config.weight_function,
+            parallel,
+            config.use_combined_probability,
+            use_brief_edge,
+        );
+        // build erasure graph
+        let mut erasure_graph = ErasureGraph::new(&simulator);
+        erasure_graph.build(&mut simulator, noise_model, parallel);
+        simulator.clear_all_errors();
+        Ok(Self {
+            decoders,
+            model_graph: Arc::new(model_graph),
+            erasure_graph: Arc::new(erasure_graph),
+            clean_simulator: simulator,
+            config,
+        })
+    }
+
+    /// the total weight of the chosen edges, where an erased edge has a reduced weight; an edge that is neither in the
+    /// model graph nor erased has infinite weight, so that the correction never wins
+    pub fn subgraph_weight(&self, subgraph: &SparseSubgraph, erased_weights: &HashMap<Vec<Position>, f64>) -> f64 {
+        let mut weight = 0.;
+        for (defect_vertices, _) in subgraph.iter() {
+            let mut key = defect_vertices.clone();
+            key.sort();
+            let model_graph_weight = match defect_vertices.as_slice() {
+                [position] => self
+                    .model_graph
+                    .get_node_unwrap(position)
+                    .boundary
+                    .as_ref()
+                    .map(|boundary| boundary.weight),
+                [position1, position2] => self
+                    .model_graph
+                    .get_node_unwrap(position1)
+                    .edges
+                    .get(position2)
+                    .map(|edge| edge.weight),
+                _ => None,
+            }
+            .unwrap_or(f64::INFINITY);
+            weight += match erased_weights.get(&key) {
+                Some(erased_weight) => erased_weight.min(model_graph_weight),
+                None => model_graph_weight,
+            };
+        }
+        weight
+    }
+
+    /// decode given measurement results
+    pub fn decode(&mut self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_erasure(sparse_measurement, &SparseErasures::new())
+    }
+
+    /// decode given measurement results and detected erasures
+    pub fn decode_with_erasure(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        let begin = Instant::now();
+        let mut corrections = Vec::with_capacity(self.decoders.len());
+        let mut decoder_statistics = Vec::with_capacity(self.decoders.len());
+        let (winner, mut runtime_statistics) = match self.config.mode {
+            EnsembleMode::MinimumWeight => {
+                let mut erased_weights = HashMap::<Vec<Position>, f64>::new();
+                for (erasure_edge, probability) in sparse_detected_erasures.get_weighted_erasure_edges(&self.erasure_graph) {
+                    let mut key = match erasure_edge {
+                        ErasureEdge::Connection(position1, position2) => vec![position1, position2],
+                        ErasureEdge::Boundary(position) => vec![position],
+                    };
+                    key.sort();
+                    let erasure_weight = erasure_edge_weight(probability);
+                    let weight = erased_weights.entry(key).or_insert(erasure_weight);
+                    *weight = weight.min(erasure_weight);
+                }
+                let mut weights = Vec::with_capacity(self.decoders.len());
+                for index in 0..self.decoders.len() {
+                    let (subgraph, statistics) =
+                        self.decoders[index].decode_subgraph(sparse_measurement, sparse_detected_erasures);
+                    corrections.push(subgraph.to_correction());
+                    decoder_statistics.push(statistics);
+                    weights.push(self.subgraph_weight(&subgraph, &erased_weights));
+                }
+                let mut winner = 0;
+                for (index, weight) in weights.iter().enumerate() {
+                    if *weight < weights[winner] {
+                        winner = index;
+                    }
+                }
+                (winner, json!({ "weights": weights }))
+            }
+            EnsembleMode::MajorityVote => {
+                let mut logical_classes = Vec::with_capacity(self.decoders.len());
+                for decoder in self.decoders.iter_mut() {
+                    let (correction, statistics) = decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures);
+                    // corrections of the same syndrome differ by a logical operator only if they have different classes
+                    logical_classes.push(self.clean_simulator.validate_correction(&correction));
+                    corrections.push(correction);
+                    decoder_statistics.push(statistics);
+                }
+                let votes: Vec<usize> = logical_classes
+                    .iter()
+                    .map(|class| logical_classes.iter().filter(|other| *other == class).count())
+                    .collect();
+                let mut winner = 0;
+                for (index, vote) in votes.iter().enumerate() {
+                    if *vote > votes[winner] {
+                        winner = index;
+                    }
+                }
+                (winner, json!({ "logical_classes": logical_classes, "votes": votes[winner] }))
+            }
+        };
+        let runtime_statistics_map = runtime_statistics.as_object_mut().unwrap();
+        runtime_statistics_map.insert("winner".to_string(), json!(winner));
+        runtime_statistics_map.insert("winner_decoder".to_string(), json!(self.config.decoders[winner].decoder));
+        runtime_statistics_map.insert("decoders".to_string(), json!(decoder_statistics));
+        runtime_statistics_map.insert("time_ensemble_decode".to_string(), json!(begin.elapsed().as_secs_f64()));
+        (corrections.swap_remove(winner), runtime_statistics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::clap::Parser;
+    use super::super::cli::*;
+    use super::super::code_builder::*;
+    use super::*;
+    use crate::rand::SeedableRng;
+    use crate::reproducible_rand::Xoroshiro128StarStar;
+
+    /// build the ensemble decoder the same way as `--decoder ensemble --decoder-config <decoder_config>`
+    fn build_ensemble(
+        pe: f64,
+        decoder_config: serde_json::Value,
+    ) -> (Simulator, Arc<NoiseModel>, Result<GeneralDecoder, String>) {
+        let pes = format!("[{pe}]");
+        let decoder_config = decoder_config.to_string();
+        let parameters = BenchmarkParameters::parse_from([
+            "benchmark",
+            "[5]",
+            "[0]",
+            "[0.08]",
+            "--pes",
+            pes.as_str(),
+            "--code-type",
+            "rotated-planar-code",
+            "--decoder",
+            "ensemble",
+            "--decoder-config",
+            decoder_config.as_str(),
+        ]);
+        let configs = parameters.fill_in_default_parameters().unwrap();
+        let config = &parameters.extract_simulation_configurations(&configs)[0];
+        let mut simulator = Simulator::new(parameters.code_type, CodeSize::new(0, 5, 5));
+        let noise_model = parameters
+            .construct_noise_model(&mut simulator, &configs, config, false)
+            .unwrap();
+        let general_decoder = GeneralDecoder::from_parameters(&parameters, &configs, config, &simulator, &noise_model);
+        simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+        (simulator, noise_model, general_decoder)
+    }
+
+    #[test]
+    fn ensemble_decoder_minimum_weight() {
+        // cargo test ensemble_decoder_minimum_weight -- --nocapture
+        let (mut simulator, noise_model, ensemble_decoder) = build_ensemble(
+            0.05,
+            json!({
+                "decoders": [
+                    { "decoder": "UnionFind" },
+                    { "decoder": "MWPM" },
+                ],
+            }),
+        );
+        let mut ensemble_decoder = ensemble_decoder.unwrap();
+        let mut mwpm_wins = 0;
+        for _ in 0..200 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+            let (correction, runtime_statistics) =
+                ensemble_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            // MWPM always finds the minimum weight, which is never higher than that of union-find
+            let weights: Vec<f64> = serde_json::from_value(runtime_statistics["weights"].clone()).unwrap();
+            assert!(weights[1] <= weights[0] + 1e-6, "weights: {weights:?}");
+            if runtime_statistics["winner"] == json!(1) {
+                mwpm_wins += 1;
+                assert_eq!(runtime_statistics["winner_decoder"], json!("MWPM"));
+            }
+        }
+        assert!(mwpm_wins > 0, "union-find should be suboptimal in some of the shots");
+    }
+
+    #[test]
+    fn ensemble_decoder_majority_vote() {
+        // cargo test ensemble_decoder_majority_vote -- --nocapture
+        // hypergraph union-find doesn't report the chosen edges, which is only allowed in majority vote
+        let mut config = json!({
+            "decoders": [
+                { "decoder": "MWPM", "decoder_config": { "pcmg": true } },
+                { "decoder": "UnionFind" },
+                { "decoder": "HyperUnionFind" },
+            ],
+        });
+        let (_, _, ensemble_decoder) = build_ensemble(0., config.clone());
+        assert!(ensemble_decoder.err().unwrap().contains("MajorityVote"));
+        config["mode"] = json!("MajorityVote");
+        let (mut simulator, noise_model, ensemble_decoder) = build_ensemble(0., config);
+        let mut ensemble_decoder = ensemble_decoder.unwrap();
+        for _ in 0..200 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, runtime_statistics) =
+                ensemble_decoder.decode_with_erasure(&sparse_measurement, &SparseErasures::new());
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let votes = runtime_statistics["votes"].as_u64().unwrap();
+            // the winner has the logical class of the majority
+            let logical_classes: Vec<(bool, bool)> =
+                serde_json::from_value(runtime_statistics["logical_classes"].clone()).unwrap();
+            let winner = runtime_statistics["winner"].as_u64().unwrap() as usize;
+            let winner_class = logical_classes[winner];
+            assert_eq!(
+                logical_classes.iter().filter(|class| **class == winner_class).count() as u64,
+                votes
+            );
+        }
+    }
+
+    #[test]
+    fn ensemble_decoder_invalid_members() {
+        // cargo test ensemble_decoder_invalid_members -- --nocapture
+        let (_, _, ensemble_decoder) = build_ensemble(0., json!({ "decoders": [] }));
+        assert!(ensemble_decoder.is_err());
+        let (_, _, ensemble_decoder) = build_ensemble(
+            0.,
+            json!({
+                "decoders": [
+                    { "decoder": "MWPM" },
+                    { "decoder": "Ensemble", "decoder_config": { "decoders": [{ "decoder": "MWPM" }] } },
+                ],
+            }),
+        );
+        assert!(ensemble_decoder.err().unwrap().contains("nested"));
+    }
+
+    #[test]
+    fn ensemble_decoder_edge_not_in_model_graph() {
+        // cargo test ensemble_decoder_edge_not_in_model_graph -- --nocapture
+        let (simulator, _, ensemble_decoder) = build_ensemble(0., json!({ "decoders": [{ "decoder": "MWPM" }] }));
+        let ensemble_decoder = match ensemble_decoder.unwrap() {
+            GeneralDecoder::Ensemble(ensemble_decoder) => ensemble_decoder,
+            _ => unreachable!(),
+        };
+        let model_graph = &ensemble_decoder.model_graph;
+        let mut vertices = vec![];
+        simulator_iter!(simulator, position, delta_t => simulator.measurement_cycles, if model_graph.is_node_exist(position) {
+            vertices.push(position.clone());
+        });
+        // the two opposite corners are never connected by a single error
+        let (first, last) = (vertices[0].clone(), vertices[vertices.len() - 1].clone());
+        assert!(!model_graph.get_node_unwrap(&first).edges.contains_key(&last));
+        let mut subgraph = SparseSubgraph::new();
+        subgraph.push(vec![first.clone(), last.clone()], Arc::new(SparseCorrection::new()));
+        let erased_weights = HashMap::new();
+        assert_eq!(ensemble_decoder.subgraph_weight(&subgraph, &erased_weights), f64::INFINITY);
+        // unless it's erased
+        let mut erased_weights = HashMap::new();
+        erased_weights.insert(vec![first, last], 0.);
+        assert_eq!(ensemble_decoder.subgraph_weight(&subgraph, &erased_weights), 0.);
+    }
+}
@@ -0,0 +1,523 @@
+//! # External Decoder
+//!
+//! benchmark a decoder that runs as a separate executable, e.g. a machine-learning or C++ decoder, without linking it into
+//! this crate. the executable is launched once and shared by all the threads; the messages are JSON objects, one per line.
+//!
+//! at startup, qecp writes the decoding graph derived from the [`ModelGraph`]:
+//! `{"vertices": ["[t][i][j]", ...], "edges": [{"vertices": [0, 1], "weight": 2.3, "probability": 0.01, "logical_flips": [false, true]}, ...]}`,
+//! where a boundary edge has a single vertex and `logical_flips` is the effect of the edge on the two logical observables.
+//! then for each shot, qecp writes the indices of the defect vertices and the erased edges in this graph, each with the
+//! probability of being flipped given the herald (0.5 for a fully depolarizing erasure): `{"defects": [3, 8], "erasures": [[5, 0.5]]}`.
+//! the executable replies either the indices of the chosen edges `{"edges": [2, 6]}`, or only the logical observables to be
+//! flipped `{"logical_flips": [false, true]}` if `logical_flips` is enabled in the configuration. an optional
+//! `"runtime_statistics"` field in the reply is reported as is. the executable should exit when its stdin is closed.
+//!
+//! when only the logical flips are given, qecp builds a correction that matches the syndrome using a union-find decoder, and
+//! then multiplies it with the logical operators such that it has the requested logical class; each shot is then validated as
+//! usual.
+//!
+
+use super::complementary_gap::*;
+use super::complete_model_graph::*;
+use super::decoder_mwpm::*;
+use super::decoder_union_find::*;
+use super::erasure_graph::*;
+use super::model_graph::*;
+use super::noise_model::*;
+use super::serde_json;
+use super::simulator::*;
+use crate::parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// external decoder, initialized and cloned for multiple threads which share the same external process
+#[derive(Clone)]
+pub struct ExternalDecoder {
+    /// the external process shared by all the threads
+    pub process: Arc<Mutex<ExternalProcess>>,
+    /// erasure graph is immutably shared
+    pub erasure_graph: Arc<ErasureGraph>,
+    /// the position of each vertex, in the same order as sent to the external process
+    pub vertex_indices: Arc<HashMap<Position, usize>>,
+    /// the index of each edge, where a boundary edge is a connection to itself
+    pub edge_indices: Arc<HashMap<(Position, Position), usize>>,
+    /// the correction of each edge
+    pub edge_corrections: Arc<Vec<Arc<SparseCorrection>>>,
+    /// decoding the syndrome in a fixed logical class, only when the external process replies logical flips
+    pub union_find_decoder: Option<Box<UnionFindDecoder>>,
+    /// the logical operators and their logical flips, only when the external process replies logical flips
+    pub logical_operators: Arc<Vec<(SparseCorrection, (bool, bool))>>,
+    /// a simulator without errors, used to compute the logical class of the corrections
+    pub clean_simulator: Simulator,
+    /// save configuration for later usage
+    pub config: ExternalDecoderConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalDecoderConfig {
+    /// the executable followed by its arguments
+    #[serde(alias = "cmd")] // abbreviation
+    pub command: Vec<String>,
+    /// the external process replies the logical flips instead of the chosen edges
+    #[serde(alias = "lf")] // abbreviation
+    #[serde(default = "external_default_configs::logical_flips")]
+    pub logical_flips: bool,
+    /// weight function, by default using [`WeightFunction::AutotuneImproved`]
+    #[serde(alias = "wf")] // abbreviation
+    #[serde(default = "mwpm_default_configs::weight_function")]
+    pub weight_function: WeightFunction,
+    /// combined probability can improve accuracy, but will cause probabilities differ a lot even in the case of i.i.d. noise model
+    #[serde(alias = "ucp")] // abbreviation
+    #[serde(default = "mwpm_default_configs::use_combined_probability")]
+    pub use_combined_probability: bool,
+}
+
+pub mod external_default_configs {
+    pub fn logical_flips() -> bool {
+        false
+    }
+}
+
+/// the running external process
+pub struct ExternalProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl ExternalProcess {
+    pub fn spawn(command: &[String]) -> Result<Self, String> {
+        let (program, args) = command.split_first().ok_or("the command of external decoder is empty")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|error| format!("cannot launch external decoder {program}: {error}"))?;
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(Self { child, stdin, stdout })
+    }
+
+    /// send a message in a single line
+    pub fn send(&mut self, message: &serde_json::Value) -> Result<(), String> {
+        let stdin = self.stdin.as_mut().expect("stdin is open until the process is dropped");
+        writeln!(stdin, "{message}")
+            .and_then(|_| stdin.flush())
+            .map_err(|error| format!("cannot write to external decoder: {error}"))
+    }
+
+    /// receive a message of a single line
+    pub fn receive(&mut self) -> Result<serde_json::Value, String> {
+        let mut line = String::new();
+        let length = self
+            .stdout
+            .read_line(&mut line)
+            .map_err(|error| format!("cannot read from external decoder: {error}"))?;
+        if length == 0 {
+            return Err("external decoder exited unexpectedly".to_string());
+        }
+        serde_json::from_str(&line).map_err(|error| format!("invalid reply from external decoder: {error}, reply: {line}"))
+    }
+}
+
+impl Drop for ExternalProcess {
+    fn drop(&mut self) {
+        self.stdin = None; // close stdin to notify the external process
+        let _ = self.child.wait();
+    }
+}
+
+impl ExternalDecoder {
+    /// create a new external decoder with decoder configuration, which launches the external process
+    pub fn new(
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        decoder_configuration: &serde_json::Value,
+        parallel: usize,
+        use_brief_edge: bool,
+    ) -> Result<Self, String> {
+        // read attribute of decoder configuration
+        let config: ExternalDecoderConfig =
+            serde_json::from_value(decoder_configuration.clone()).map_err(|error| error.to_string())?;
+        // build model graph
+        let mut simulator = simulator.clone();
+        let mut model_graph = ModelGraph::new(&simulator);
+        model_graph.build(
+            &mut simulator,
+            Arc::clone(&noise_model),
+            &config.weight_function,
+            parallel,
+            config.use_combined_probability,
+            use_brief_edge,
+        );
+        // build erasure graph
+        let mut erasure_graph = ErasureGraph::new(&simulator);
+        erasure_graph.build(&mut simulator, Arc::clone(&noise_model), parallel);
+        simulator.clear_all_errors();
+        // list the vertices and edges
+        let mut vertex_positions = Vec::<Position>::new();
+        simulator_iter!(simulator, position, delta_t => simulator.measurement_cycles, if model_graph.is_node_exist(position) {
+            vertex_positions.push(position.clone());
+        });
+        let vertex_indices: HashMap<Position, usize> = vertex_positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| (position.clone(), index))
+            .collect();
+        let mut edge_indices = HashMap::<(Position, Position), usize>::new();
+        let mut edge_corrections = Vec::<Arc<SparseCorrection>>::new();
+        let mut edges = Vec::<serde_json::Value>::new();
+        for (index, position) in vertex_positions.iter().enumerate() {
+            let node = model_graph.get_node_unwrap(position);
+            let mut add_edge =
+                |peer_position: &Position, weight: f64, probability: f64, correction: &Arc<SparseCorrection>| {
+                    let peer_index = vertex_indices[peer_position];
+                    let logical_flips = simulator.validate_correction(correction);
+                    edge_indices.insert((position.clone(), peer_position.clone()), edges.len());
+                    edge_corrections.push(Arc::clone(correction));
+                    let vertices = if peer_index == index {
+                        vec![index]
+                    } else {
+                        vec![index, peer_index]
+                    };
+                    edges.push(json!({
+                        "vertices": vertices,
+                        "weight": weight,
+                        "probability": probability,
+                        "logical_flips": logical_flips,
+                    }));
+                };
+            if let Some(boundary) = node.boundary.as_ref() {
+                add_edge(position, boundary.weight, boundary.probability, &boundary.correction);
+            }
+            for (peer_position, edge) in node.edges.iter() {
+                if vertex_indices[peer_position] > index {
+                    add_edge(peer_position, edge.weight, edge.probability, &edge.correction);
+                }
+            }
+        }
+        // the logical operators are built from the complementary graphs, each connecting both logical sides of the boundary
+        let (union_find_decoder, logical_operators) = if config.logical_flips {
+            let union_find_decoder = UnionFindDecoder::new(&simulator, noise_model, &json!({}), parallel, use_brief_edge);
+            let mut logical_operators = vec![];
+            for complementary_graph in ComplementaryGraph::build(&simulator, &model_graph).into_iter() {
+                let complementary_simulator = &complementary_graph.simulator;
+                let mut complete_model_graph =
+                    CompleteModelGraph::new(complementary_simulator, Arc::clone(&complementary_graph.model_graph));
+                complete_model_graph.precompute(complementary_simulator, false, parallel);
+                let logical_operator = complete_model_graph.build_correction_boundary(&complementary_graph.logical_vertex);
+                let logical_flips = simulator.validate_correction(&logical_operator);
+                logical_operators.push((logical_operator, logical_flips));
+            }
+            (Some(Box::new(union_find_decoder)), logical_operators)
+        } else {
+            (None, vec![])
+        };
+        // launch the external process and send the decoding graph
+        let mut process = ExternalProcess::spawn(&config.command)?;
+        process.send(&json!({
+            "vertices": vertex_positions,
+            "edges": edges,
+        }))?;
+        Ok(Self {
+            process: Arc::new(Mutex::new(process)),
+            erasure_graph: Arc::new(erasure_graph),
+            vertex_indices: Arc::new(vertex_indices),
+            edge_indices: Arc::new(edge_indices),
+            edge_corrections: Arc::new(edge_corrections),
+            union_find_decoder,
+            logical_operators: Arc::new(logical_operators),
+            clean_simulator: simulator,
+            config,
+        })
+    }
+
+    /// decode given measurement results
+    pub fn decode(&mut self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_erasure(sparse_measurement, &SparseErasures::new())
+    }
+
+    /// decode given measurement results and detected erasures
+    pub fn decode_with_erasure(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        let begin = Instant::now();
+        let defects: Vec<usize> = sparse_measurement
+            .iter()
+            .map(|position| self.vertex_indices[position])
+            .collect();
+        // an erased edge that is not in the decoding graph cannot be sent to the external process, thus skipped
+        let erasures: Vec<(usize, f64)> = sparse_detected_erasures
+            .get_weighted_erasure_edges(&self.erasure_graph)
+            .into_iter()
+            .filter_map(|(erasure_edge, probability)| {
+                let key = match erasure_edge {
+                    ErasureEdge::Connection(position1, position2) => {
+                        if self.vertex_indices[&position1] < self.vertex_indices[&position2] {
+                            (position1, position2)
+                        } else {
+                            (position2, position1)
+                        }
+                    }
+                    ErasureEdge::Boundary(position) => (position.clone(), position),
+                };
+                self.edge_indices.get(&key).map(|&edge_index| (edge_index, probability))
+            })
+            .collect();
+        let reply = {
+            let mut process = self.process.lock();
+            process
+                .send(&json!({
+                    "defects": defects,
+                    "erasures": erasures,
+                }))
+                .and_then(|_| process.receive())
+                .unwrap_or_else(|error| panic!("external decoder {:?} failed: {error}", self.config.command))
+        };
+        let time_external = begin.elapsed().as_secs_f64();
+        let correction = if self.config.logical_flips {
+            let logical_flips: (bool, bool) = serde_json::from_value(reply["logical_flips"].clone())
+                .unwrap_or_else(|_| panic!("expecting `logical_flips` from external decoder, reply: {reply}"));
+            let union_find_decoder = self.union_find_decoder.as_mut().unwrap();
+            let (mut correction, _) = union_find_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures);
+            let (flip_i, flip_j) = self.clean_simulator.validate_correction(&correction);
+            let required = (flip_i != logical_flips.0, flip_j != logical_flips.1);
+            // find the logical operators whose product has the required logical flips
+            let operator_num = self.logical_operators.len();
+            let subset = (0..1usize << operator_num)
+                .find(|subset| {
+                    let mut flips = (false, false);
+                    for (index, (_, (flip_i, flip_j))) in self.logical_operators.iter().enumerate() {
+                        if subset & (1 << index) != 0 {
+                            flips = (flips.0 != *flip_i, flips.1 != *flip_j);
+                        }
+                    }
+                    flips == required
+                })
+                .unwrap_or_else(|| panic!("cannot realize logical flips {logical_flips:?} with the logical operators"));
+            for (index, (logical_operator, _)) in self.logical_operators.iter().enumerate() {
+                if subset & (1 << index) != 0 {
+                    correction.extend(logical_operator);
+                }
+            }
+            correction
+        } else {
+            let edges: Vec<usize> = serde_json::from_value(reply["edges"].clone())
+                .unwrap_or_else(|_| panic!("expecting `edges` from external decoder, reply: {reply}"));
+            let mut correction = SparseCorrection::new();
+            for edge_index in edges.into_iter() {
+                let edge_correction = self
+                    .edge_corrections
+                    .get(edge_index)
+                    .unwrap_or_else(|| panic!("edge index {edge_index} from external decoder is out of range"));
+                correction.extend(edge_correction);
+            }
+            correction
+        };
+        (
+            correction,
+            json!({
+                "time_external": time_external,
+                "runtime_statistics": reply.get("runtime_statistics"),
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::code_builder::*;
+    use super::super::types::ErrorType::*;
+    use super::*;
+
+    #[test]
+    fn external_decoder_edges() {
+        // cargo test external_decoder_edges -- --nocapture
+        let d = 3;
+        let p = 0.03;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.1);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let log_filename = std::env::temp_dir().join(format!("qecp_external_decoder_edges_{}.log", std::process::id()));
+        let log_filename = log_filename.to_str().unwrap().to_string();
+        // a decoder that records the messages and always chooses the edge 0
+        let script = format!(r#"tee {log_filename} | {{ read graph; while read shot; do echo '{{"edges": [0]}}'; done; }}"#);
+        let mut external_decoder = ExternalDecoder::new(
+            &simulator,
+            Arc::clone(&noise_model),
+            &json!({ "command": ["sh", "-c", script] }),
+            1,
+            false,
+        )
+        .unwrap();
+        simulator.clear_all_errors();
+        simulator.set_error_check(&noise_model, &pos!(0, 1, 1), &X);
+        simulator.set_erasure_check(&noise_model, &pos!(0, 1, 1), true);
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+        let (correction, _runtime_statistics) =
+            external_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+        assert_eq!(correction.to_vec(), external_decoder.edge_corrections[0].to_vec());
+        drop(external_decoder); // wait for the external process to exit
+        let log = std::fs::read_to_string(&log_filename).unwrap();
+        std::fs::remove_file(&log_filename).unwrap();
+        let messages: Vec<serde_json::Value> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(messages.len(), 2);
+        let vertices = messages[0]["vertices"].as_array().unwrap();
+        let edges = messages[0]["edges"].as_array().unwrap();
+        assert_eq!(vertices.len(), 12); // 6 stabilizers of each type
+        assert_eq!(edges.len(), 26); // each of the 13 data qubits is an edge of each type
+        assert_eq!(
+            edges
+                .iter()
+                .filter(|edge| edge["logical_flips"] != json!([false, false]))
+                .count(),
+            6
+        );
+        let defects: Vec<&Position> = messages[1]["defects"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|index| vertices[index.as_u64().unwrap() as usize].as_str().unwrap())
+            .map(|position| {
+                sparse_measurement
+                    .iter()
+                    .find(|defect| defect.to_string() == position)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(defects.len(), sparse_measurement.len());
+        // an erasure at the corner generates a boundary edge of each type
+        assert_eq!(messages[1]["erasures"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn external_decoder_logical_flips() {
+        // cargo test external_decoder_logical_flips -- --nocapture
+        let d = 3;
+        let p = 0.03;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.1);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let script =
+            r#"read graph; while read shot; do echo '{"logical_flips": [true, false], "runtime_statistics": 1}'; done"#;
+        let mut external_decoder = ExternalDecoder::new(
+            &simulator,
+            Arc::clone(&noise_model),
+            &json!({ "command": ["sh", "-c", script], "logical_flips": true }),
+            1,
+            false,
+        )
+        .unwrap();
+        assert_eq!(external_decoder.logical_operators.len(), 2);
+        for error in [X, Z, Y] {
+            simulator.clear_all_errors();
+            simulator.set_error_check(&noise_model, &pos!(0, 3, 3), &error);
+            simulator.propagate_errors();
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, runtime_statistics) = external_decoder.decode(&sparse_measurement);
+            assert_eq!(runtime_statistics["runtime_statistics"], json!(1));
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            // the decoder intentionally flips the logical observable
+            assert_eq!(simulator.validate_correction(&correction), (true, false));
+        }
+    }
+
+    #[test]
+    fn external_decoder_erased_edge_not_in_graph() {
+        // cargo test external_decoder_erased_edge_not_in_graph -- --nocapture
+        let d = 3;
+        let p = 0.03;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.1);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let log_filename = std::env::temp_dir().join(format!("qecp_external_decoder_erased_{}.log", std::process::id()));
+        let log_filename = log_filename.to_str().unwrap().to_string();
+        let script = format!(r#"tee {log_filename} | {{ read graph; while read shot; do echo '{{"edges": []}}'; done; }}"#);
+        let mut external_decoder = ExternalDecoder::new(
+            &simulator,
+            Arc::clone(&noise_model),
+            &json!({ "command": ["sh", "-c", script] }),
+            1,
+            false,
+        )
+        .unwrap();
+        simulator.clear_all_errors();
+        simulator.set_erasure_check(&noise_model, &pos!(0, 1, 1), true);
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+        // remove one of the erased edges from the decoding graph
+        let mut edge_indices = external_decoder.edge_indices.as_ref().clone();
+        let (erasure_edge, _) =
+            sparse_detected_erasures.get_weighted_erasure_edges(&external_decoder.erasure_graph)[0].clone();
+        let key = match erasure_edge {
+            ErasureEdge::Connection(position1, position2) => {
+                if external_decoder.vertex_indices[&position1] < external_decoder.vertex_indices[&position2] {
+                    (position1, position2)
+                } else {
+                    (position2, position1)
+                }
+            }
+            ErasureEdge::Boundary(position) => (position.clone(), position),
+        };
+        assert!(edge_indices.remove(&key).is_some());
+        external_decoder.edge_indices = Arc::new(edge_indices);
+        external_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+        drop(external_decoder); // wait for the external process to exit
+        let log = std::fs::read_to_string(&log_filename).unwrap();
+        std::fs::remove_file(&log_filename).unwrap();
+        let messages: Vec<serde_json::Value> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(messages[1]["erasures"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"external decoder ["sh", "-c", "read graph; exit 1"] failed"#)]
+    fn external_decoder_process_exited() {
+        // cargo test external_decoder_process_exited -- --nocapture
+        let d = 3;
+        let p = 0.03;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.1);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        // a decoder that crashes after reading the decoding graph
+        let mut external_decoder = ExternalDecoder::new(
+            &simulator,
+            Arc::clone(&noise_model),
+            &json!({ "command": ["sh", "-c", "read graph; exit 1"] }),
+            1,
+            false,
+        )
+        .unwrap();
+        simulator.clear_all_errors();
+        simulator.set_error_check(&noise_model, &pos!(0, 3, 3), &X);
+        simulator.propagate_errors();
+        external_decoder.decode(&simulator.generate_sparse_measurement());
+    }
+}
@@ -1,15 +1,18 @@
 //! minimum-weight perfect matching decoder
 //!
 
+use super::complementary_gap::*;
 use super::model_graph::*;
 use super::noise_model::*;
 use super::serde_json;
 use super::simulator::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use fusion_blossom::pointers::UnsafePtr;
 // use super::erasure_graph::*;
+use super::decoder_bp_osd::*;
 use super::decoder_mwpm::*;
 use super::derivative::*;
 use super::fusion_blossom;
@@ -32,6 +35,13 @@ pub struct FusionDecoder {
     pub fusion_solver: fusion_blossom::mwpm_solver::SolverSerial,
     /// save configuration for later usage
     pub config: FusionDecoderConfig,
+    /// belief propagation that reweights the edges in every shot, if enabled
+    pub belief_matching: Option<BeliefMatching<EdgeIndex>>,
+    /// the correlated partner edges of each edge, if two-pass correlated matching is enabled
+    #[serde(skip)]
+    pub correlated_matching: Option<Arc<CorrelatedMatching<EdgeIndex>>>,
+    /// the decoders on the complementary graph of each logical observable, if the complementary gap is enabled
+    pub complementary_decoders: Vec<(Arc<ComplementaryGraph>, FusionDecoder)>,
 }
 
 impl Clone for FusionDecoder {
@@ -51,6 +61,9 @@ impl Clone for FusionDecoder {
             adaptor: self.adaptor.clone(),
             fusion_solver,
             config: self.config.clone(),
+            belief_matching: self.belief_matching.clone(),
+            correlated_matching: self.correlated_matching.clone(),
+            complementary_decoders: self.complementary_decoders.clone(),
         }
     }
 }
@@ -78,6 +91,19 @@ pub struct FusionDecoderConfig {
     pub log_matchings: bool,
     #[serde(default = "fusion_default_configs::max_tree_size")]
     pub max_tree_size: usize,
+    /// reweight the edges in every shot using the posterior of belief propagation on the model hypergraph,
+    /// i.e. belief-matching
+    #[serde(alias = "bp")] // abbreviation
+    #[serde(default = "mwpm_default_configs::belief_propagation")]
+    pub belief_propagation: Option<BeliefPropagationConfig>,
+    /// two-pass correlated matching, see [`MWPMDecoderConfig::correlated_matching`]
+    #[serde(alias = "cm")] // abbreviation
+    #[serde(default = "mwpm_default_configs::correlated_matching")]
+    pub correlated_matching: bool,
+    /// report the complementary gap in the runtime statistics, see [`MWPMDecoderConfig::complementary_gap`]
+    #[serde(alias = "cg")] // abbreviation
+    #[serde(default = "mwpm_default_configs::complementary_gap")]
+    pub complementary_gap: bool,
 }
 
 pub mod fusion_default_configs {
@@ -113,13 +139,90 @@ impl FusionDecoder {
         // erasure_graph.build(&mut simulator, Arc::clone(&noise_model), parallel);
         // let erasure_graph = Arc::new(erasure_graph);
         // build solver
-        let adaptor = FusionBlossomAdaptor::new(&config, &mut simulator, noise_model, parallel, use_brief_edge);
+        let mut model_graph = ModelGraph::new(&simulator);
+        model_graph.build(
+            &mut simulator,
+            Arc::clone(&noise_model),
+            &config.weight_function,
+            parallel,
+            config.use_combined_probability,
+            use_brief_edge,
+        );
+        let adaptor = FusionBlossomAdaptor::from_model_graph(&config, &simulator, &model_graph);
         let fusion_solver = fusion_blossom::mwpm_solver::SolverSerial::new(&adaptor.initializer);
         fusion_solver.primal_module.write().max_tree_size = config.max_tree_size;
+        let edge_indices = if config.belief_propagation.is_some() || config.correlated_matching {
+            adaptor.real_vertices_to_edge_mapping()
+        } else {
+            HashMap::new()
+        };
+        let belief_matching = config.belief_propagation.as_ref().map(|bp_config| {
+            BeliefMatching::new(
+                &simulator,
+                Arc::clone(&noise_model),
+                bp_config,
+                parallel,
+                config.use_combined_probability,
+                use_brief_edge,
+                |positions| edge_indices.get(positions).cloned(),
+            )
+        });
+        let correlated_matching = config.correlated_matching.then(|| {
+            Arc::new(CorrelatedMatching::new(
+                &simulator,
+                noise_model,
+                &config.weight_function,
+                parallel,
+                config.use_combined_probability,
+                use_brief_edge,
+                |positions| {
+                    let edge_index = *edge_indices.get(positions)?;
+                    Some((edge_index, adaptor.edge_model_weights[edge_index].1))
+                },
+            ))
+        });
+        let complementary_decoders = if config.complementary_gap {
+            ComplementaryGraph::build(&simulator, &model_graph)
+                .into_iter()
+                .map(|complementary_graph| {
+                    let decoder = Self::new_complementary(&complementary_graph, &config);
+                    (Arc::new(complementary_graph), decoder)
+                })
+                .collect()
+        } else {
+            vec![]
+        };
         Self {
             adaptor: Arc::new(adaptor),
             fusion_solver,
             config,
+            belief_matching,
+            correlated_matching,
+            complementary_decoders,
+        }
+    }
+
+    /// a plain fusion decoder on the complementary graph
+    fn new_complementary(complementary_graph: &ComplementaryGraph, config: &FusionDecoderConfig) -> Self {
+        let config = FusionDecoderConfig {
+            skip_decoding: false,
+            log_matchings: false,
+            belief_propagation: None,
+            correlated_matching: false,
+            complementary_gap: false,
+            ..config.clone()
+        };
+        let adaptor =
+            FusionBlossomAdaptor::from_model_graph(&config, &complementary_graph.simulator, &complementary_graph.model_graph);
+        let fusion_solver = fusion_blossom::mwpm_solver::SolverSerial::new(&adaptor.initializer);
+        fusion_solver.primal_module.write().max_tree_size = config.max_tree_size;
+        Self {
+            adaptor: Arc::new(adaptor),
+            fusion_solver,
+            config,
+            belief_matching: None,
+            correlated_matching: None,
+            complementary_decoders: vec![],
         }
     }
 
@@ -134,6 +237,16 @@ impl FusionDecoder {
         &mut self,
         sparse_measurement: &SparseMeasurement,
         sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_soft_readouts(sparse_measurement, sparse_detected_erasures, &SparseSoftReadouts::new())
+    }
+
+    /// decode given measurement results, detected erasures and analog readouts that reweight the measurement edges
+    pub fn decode_with_soft_readouts(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+        sparse_soft_readouts: &SparseSoftReadouts,
     ) -> (SparseCorrection, serde_json::Value) {
         if self.config.skip_decoding {
             return (SparseCorrection::new(), json!({}));
@@ -147,11 +260,7 @@ impl FusionDecoder {
         if !sparse_measurement.is_empty() {
             // run the Blossom algorithm
             let begin = Instant::now();
-            let syndrome_pattern = self
-                .adaptor
-                .generate_syndrome_pattern(sparse_measurement, sparse_detected_erasures);
-            self.fusion_solver.solve(&syndrome_pattern);
-            let subgraph: Vec<usize> = self.fusion_solver.subgraph();
+            let subgraph = self.solve(sparse_measurement, sparse_detected_erasures, sparse_soft_readouts);
             if self.config.log_matchings {
                 // log the subgraph
                 let mut subgraph_edges = vec![];
@@ -202,8 +311,102 @@ impl FusionDecoder {
             let runtime_statistics = runtime_statistics.as_object_mut().unwrap();
             runtime_statistics.insert("log_matchings".to_string(), json!(log_matchings));
         }
+        if self.config.complementary_gap {
+            assert!(sparse_soft_readouts.is_empty(), "complementary gap doesn't support soft readouts yet");
+            record_complementary_gaps(
+                &mut self.complementary_decoders,
+                sparse_measurement,
+                &mut runtime_statistics,
+                |decoder, sparse_measurement| decoder.decode_subgraph(sparse_measurement, &SparseErasures::new()).0,
+            );
+        }
         (correction, runtime_statistics)
     }
+
+    /// decode given measurement results and return the chosen edges, each located by its real defect vertices
+    pub fn decode_subgraph(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseSubgraph, serde_json::Value) {
+        assert!(!self.config.skip_decoding, "cannot report subgraph when decoding is skipped");
+        assert!(
+            sparse_detected_erasures.is_empty(),
+            "fusion decoder doesn't support erasure error yet"
+        );
+        let begin = Instant::now();
+        let mut sparse_subgraph = SparseSubgraph::new();
+        if !sparse_measurement.is_empty() {
+            let subgraph = self.solve(sparse_measurement, sparse_detected_erasures, &SparseSoftReadouts::new());
+            self.fusion_solver.clear();
+            sparse_subgraph = self.adaptor.subgraph_to_sparse_subgraph(&subgraph);
+        }
+        let runtime_statistics = json!({
+            "to_be_matched": sparse_measurement.len(),
+            "time_fusion": begin.elapsed().as_secs_f64(),
+        });
+        (sparse_subgraph, runtime_statistics)
+    }
+
+    /// run the solver (and the correlated second pass if enabled) and return the subgraph without clearing the solver
+    fn solve(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+        sparse_soft_readouts: &SparseSoftReadouts,
+    ) -> Vec<EdgeIndex> {
+        let mut syndrome_pattern = self
+            .adaptor
+            .generate_syndrome_pattern(sparse_measurement, sparse_detected_erasures);
+        syndrome_pattern.dynamic_weights = if let Some(belief_matching) = self.belief_matching.as_mut() {
+            assert!(
+                sparse_soft_readouts.is_empty(),
+                "belief-matching doesn't support analog readouts yet"
+            );
+            let edge_probabilities = belief_matching.edge_probabilities(sparse_measurement, sparse_detected_erasures);
+            let weight_function = &self.config.weight_function;
+            let weight_scale = self.adaptor.weight_scale;
+            belief_matching
+                .decomposition
+                .graph_edges
+                .iter()
+                .zip(edge_probabilities)
+                .map(|(&(edge_index, _), probability)| {
+                    (
+                        edge_index,
+                        FusionBlossomAdaptor::scale_weight(weight_function.weight_of(probability), weight_scale),
+                    )
+                })
+                .collect()
+        } else {
+            self.adaptor.generate_dynamic_weights(sparse_soft_readouts)
+        };
+        self.fusion_solver.solve(&syndrome_pattern);
+        let mut subgraph: Vec<EdgeIndex> = self.fusion_solver.subgraph();
+        if let Some(correlated_matching) = self.correlated_matching.as_ref() {
+            // reduce the weights of the partner edges of the chosen edges, and then match again
+            let partner_weights = correlated_matching.partner_weights(subgraph.iter());
+            if !partner_weights.is_empty() {
+                let mut dynamic_weights: BTreeMap<EdgeIndex, Weight> =
+                    syndrome_pattern.dynamic_weights.iter().cloned().collect();
+                for (edge_index, weight) in partner_weights.into_iter() {
+                    let weight = FusionBlossomAdaptor::scale_weight(weight, self.adaptor.weight_scale);
+                    let current_weight = dynamic_weights
+                        .get(&edge_index)
+                        .cloned()
+                        .unwrap_or(self.adaptor.initializer.weighted_edges[edge_index].2);
+                    if weight < current_weight {
+                        dynamic_weights.insert(edge_index, weight);
+                    }
+                }
+                syndrome_pattern.dynamic_weights = dynamic_weights.into_iter().collect();
+                self.fusion_solver.clear();
+                self.fusion_solver.solve(&syndrome_pattern);
+                subgraph = self.fusion_solver.subgraph();
+            }
+        }
+        subgraph
+    }
 }
 
 // pub type PositionToVertexMap = std::collections::HashMap<Position, usize>;
@@ -224,6 +427,12 @@ pub struct FusionBlossomAdaptor {
     pub initializer: SolverInitializer,
     /// fusion blossom position for visualization
     pub positions: Vec<VisualizePosition>,
+    /// the real weight and probability of each edge, used to reweight edges given soft readouts
+    pub edge_model_weights: Vec<(f64, f64)>,
+    /// the scale from real weights to integer half weights
+    pub weight_scale: f64,
+    /// (smaller vertex, larger vertex) map to edge index
+    pub vertices_to_edge_mapping: BTreeMap<(VertexIndex, VertexIndex), EdgeIndex>,
 }
 
 impl FusionBlossomAdaptor {
@@ -243,6 +452,11 @@ impl FusionBlossomAdaptor {
             config.use_combined_probability,
             use_brief_edge,
         );
+        Self::from_model_graph(config, simulator, &model_graph)
+    }
+
+    /// the real measurement nodes of the simulator must be exactly the nodes of the model graph
+    pub fn from_model_graph(config: &FusionDecoderConfig, simulator: &Simulator, model_graph: &ModelGraph) -> Self {
         let stabilizer_filter = if config.only_stab_z {
             FusionBlossomStabilizerFilter::StabZOnly
         } else {
@@ -273,6 +487,8 @@ impl FusionBlossomAdaptor {
         });
         let mut weighted_edges_unscaled = Vec::<(usize, usize, f64)>::new();
         let mut edge_to_correction_mapping = Vec::new();
+        let mut edge_model_weights = Vec::new();
+        let mut vertices_to_edge_mapping = BTreeMap::new();
         simulator_iter!(simulator, position, node, {
             // then add edges and also virtual nodes
             if position.t != 0
@@ -289,32 +505,33 @@ impl FusionBlossomAdaptor {
                         .expect("virtual boundary required to plot properly in fusion blossom");
                     let virtual_index = position_to_vertex_mapping[virtual_position];
                     weighted_edges_unscaled.push((vertex_index, virtual_index, model_graph_boundary.weight));
+                    edge_model_weights.push((model_graph_boundary.weight, model_graph_boundary.probability));
                     edge_to_correction_mapping.push(model_graph_boundary.correction.as_ref().clone());
                 }
                 for (peer_position, model_graph_edge) in model_graph_node.edges.iter() {
                     let peer_idx = position_to_vertex_mapping[peer_position];
                     if vertex_index < peer_idx {
                         // avoid duplicate edges
+                        vertices_to_edge_mapping.insert((vertex_index, peer_idx), weighted_edges_unscaled.len());
                         weighted_edges_unscaled.push((vertex_index, peer_idx, model_graph_edge.weight));
+                        edge_model_weights.push((model_graph_edge.weight, model_graph_edge.probability));
                         edge_to_correction_mapping.push(model_graph_edge.correction.as_ref().clone());
                     }
                 }
             }
         });
-        initializer.weighted_edges = {
-            // re-weight edges and parse to integer
-            let mut maximum_weight = 0.;
-            for (_, _, weight) in weighted_edges_unscaled.iter() {
-                if weight > &maximum_weight {
-                    maximum_weight = *weight;
-                }
+        // re-weight edges and parse to integer
+        let mut maximum_weight = 0.;
+        for (_, _, weight) in weighted_edges_unscaled.iter() {
+            if weight > &maximum_weight {
+                maximum_weight = *weight;
             }
-            let scale: f64 = config.max_half_weight as f64 / maximum_weight;
-            weighted_edges_unscaled
-                .iter()
-                .map(|(a, b, weight)| (*a, *b, 2 * (weight * scale).ceil() as fusion_blossom::util::Weight))
-                .collect()
-        };
+        }
+        let weight_scale: f64 = config.max_half_weight as f64 / maximum_weight;
+        initializer.weighted_edges = weighted_edges_unscaled
+            .iter()
+            .map(|(a, b, weight)| (*a, *b, Self::scale_weight(*weight, weight_scale)))
+            .collect();
         Self {
             initializer,
             positions,
@@ -322,9 +539,52 @@ impl FusionBlossomAdaptor {
             position_to_vertex_mapping,
             stabilizer_filter,
             edge_to_correction_mapping,
+            edge_model_weights,
+            weight_scale,
+            vertices_to_edge_mapping,
         }
     }
 
+    /// the real vertices of each edge map to the edge index, where a boundary edge has only one real vertex
+    pub fn real_vertices_to_edge_mapping(&self) -> HashMap<Vec<Position>, EdgeIndex> {
+        let virtual_vertices: BTreeSet<VertexIndex> = self.initializer.virtual_vertices.iter().cloned().collect();
+        let mut edge_indices = HashMap::new();
+        for (edge_index, &(vertex_1, vertex_2, _)) in self.initializer.weighted_edges.iter().enumerate() {
+            let mut positions: Vec<Position> = [vertex_1, vertex_2]
+                .iter()
+                .filter(|vertex| !virtual_vertices.contains(vertex))
+                .map(|&vertex| self.vertex_to_position_mapping[vertex].clone())
+                .collect();
+            positions.sort();
+            edge_indices.insert(positions, edge_index);
+        }
+        edge_indices
+    }
+
+    fn scale_weight(weight: f64, weight_scale: f64) -> Weight {
+        2 * (weight * weight_scale).ceil() as Weight
+    }
+
+    /// reweight the measurement edges given the analog readouts
+    pub fn generate_dynamic_weights(&self, sparse_soft_readouts: &SparseSoftReadouts) -> Vec<(EdgeIndex, Weight)> {
+        let mut dynamic_weights = Vec::with_capacity(sparse_soft_readouts.len());
+        for (position, soft_readout) in sparse_soft_readouts.iter() {
+            let (Some(&vertex_1), Some(&vertex_2)) = (
+                self.position_to_vertex_mapping.get(position),
+                self.position_to_vertex_mapping.get(&soft_readout.next_measurement),
+            ) else {
+                continue; // e.g. the stabilizer is filtered
+            };
+            let vertices = (vertex_1.min(vertex_2), vertex_1.max(vertex_2));
+            if let Some(&edge_index) = self.vertices_to_edge_mapping.get(&vertices) {
+                let (weight, probability) = self.edge_model_weights[edge_index];
+                let weight = soft_readout.edge_weight(weight, probability);
+                dynamic_weights.push((edge_index, Self::scale_weight(weight, self.weight_scale)));
+            }
+        }
+        dynamic_weights
+    }
+
     pub fn generate_syndrome_pattern(
         &self,
         sparse_measurement: &SparseMeasurement,
@@ -350,6 +610,22 @@ impl FusionBlossomAdaptor {
         correction
     }
 
+    /// keep the real defect vertices of every chosen edge so that the decoding result can be located in spacetime
+    pub fn subgraph_to_sparse_subgraph(&self, subgraph: &[EdgeIndex]) -> SparseSubgraph {
+        let virtual_vertices: BTreeSet<VertexIndex> = self.initializer.virtual_vertices.iter().cloned().collect();
+        let mut sparse_subgraph = SparseSubgraph::new();
+        for &edge_index in subgraph.iter() {
+            let (vertex_1, vertex_2, _) = self.initializer.weighted_edges[edge_index];
+            let positions: Vec<Position> = [vertex_1, vertex_2]
+                .iter()
+                .filter(|vertex| !virtual_vertices.contains(vertex))
+                .map(|&vertex| self.vertex_to_position_mapping[vertex].clone())
+                .collect();
+            sparse_subgraph.push(positions, Arc::new(self.edge_to_correction_mapping[edge_index].clone()));
+        }
+        sparse_subgraph
+    }
+
     pub fn assert_eq(&self, other: &Self) -> Result<(), String> {
         if self.initializer.vertex_num != other.initializer.vertex_num {
             return Err(format!(
@@ -718,6 +994,8 @@ impl FusionBlossomAdaptorExtender {
 mod tests {
     use super::super::code_builder::*;
     use super::super::noise_model_builder::*;
+    use super::super::types::ErrorType::*;
+    use super::super::types::*;
     use super::*;
 
     #[test]
@@ -839,4 +1117,138 @@ mod tests {
         let ground_truth = build_adaptor(test_noisy_measurement);
         generated.assert_eq(&ground_truth).unwrap();
     }
+
+    #[test]
+    fn fusion_decoder_belief_matching() {
+        // cargo test fusion_decoder_belief_matching -- --nocapture
+        let d = 5;
+        let noisy_measurements = 0; // perfect measurement
+        let p = 0.01;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p, p, p, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let mut fusion_decoder = FusionDecoder::new(&simulator, Arc::clone(&noise_model), &json!({ "bp": {} }), 1, false);
+        // a Y error is decomposed onto both an X edge and a Z edge
+        let belief_matching = fusion_decoder.belief_matching.as_ref().unwrap();
+        assert!(belief_matching
+            .decomposition
+            .graph_edges
+            .iter()
+            .any(|(_, hyperedges)| hyperedges.len() > 1));
+        // a chain of Y errors, whose X and Z components are only correlated in the hypergraph
+        let sparse_error_pattern: SparseErrorPattern =
+            serde_json::from_value(json!({"[0][5][1]":"Y","[0][5][3]":"Y","[0][5][5]":"Y"})).unwrap();
+        simulator.clear_all_errors();
+        simulator
+            .load_sparse_error_pattern(&sparse_error_pattern, &noise_model)
+            .expect("success");
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        // plain matching fails while belief-matching succeeds
+        let mut plain_decoder = FusionDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        for (decoder, expect_logical_error) in [(&mut plain_decoder, true), (&mut fusion_decoder, false)] {
+            let (correction, _runtime_statistics) = decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert_eq!(logical_i || logical_j, expect_logical_error);
+        }
+    }
+
+    #[test]
+    fn fusion_decoder_correlated_matching() {
+        // cargo test fusion_decoder_correlated_matching -- --nocapture
+        let d = 5;
+        let noisy_measurements = 0; // perfect measurement
+        let p = 0.01;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p, p, p, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let mut fusion_decoder = FusionDecoder::new(&simulator, Arc::clone(&noise_model), &json!({ "cm": true }), 1, false);
+        // the X edge and the Z edge of a Y error are partners of each other
+        let correlated_matching = fusion_decoder.correlated_matching.as_ref().unwrap();
+        assert!(!correlated_matching.partners.is_empty());
+        // a vertical chain of errors, where the X components of the Y errors hint at their Z components
+        let sparse_error_pattern: SparseErrorPattern =
+            serde_json::from_value(json!({"[0][1][5]":"Z","[0][5][5]":"Y","[0][9][5]":"Y"})).unwrap();
+        simulator.clear_all_errors();
+        simulator
+            .load_sparse_error_pattern(&sparse_error_pattern, &noise_model)
+            .expect("success");
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        // plain matching fails while correlated matching succeeds
+        let mut plain_decoder = FusionDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        for (decoder, expect_logical_error) in [(&mut plain_decoder, true), (&mut fusion_decoder, false)] {
+            let (correction, _runtime_statistics) = decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert_eq!(logical_i || logical_j, expect_logical_error);
+        }
+    }
+
+    #[test]
+    fn fusion_decoder_soft_readout() {
+        // cargo test fusion_decoder_soft_readout -- --nocapture
+        let d = 3;
+        let noisy_measurements = 1;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        // Z errors on data qubits and accurate readouts, so that a measurement error is less likely than a data error
+        let mut noise_model = NoiseModel::new(&simulator);
+        let mut data_node = NoiseModelNode::new();
+        data_node.pauli_error_rates.error_rate_Z = 0.05;
+        let data_node = Arc::new(data_node);
+        let readout = GaussianReadout::new(0.15);
+        let mut measurement_node = NoiseModelNode::new();
+        measurement_node.soft_readout = Some(readout.clone());
+        let measurement_node = Arc::new(measurement_node);
+        simulator_iter_real!(simulator, position, node, {
+            if (position.t == 0 || position.t == mc) && node.qubit_type == QubitType::Data {
+                noise_model.set_node(position, Some(data_node.clone()));
+            }
+            if position.t == mc && node.gate_type.is_measurement() {
+                noise_model.set_node(position, Some(measurement_node.clone()));
+            }
+        });
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let mut fusion_decoder =
+            FusionDecoder::new(&Arc::new(simulator.clone()), Arc::clone(&noise_model), &json!({}), 1, false);
+        // a data error between [*][2][1] and [*][4][1], followed by a readout error of [*][4][1]
+        simulator.set_error_check(&noise_model, &pos!(mc, 3, 1), &Z);
+        simulator.set_error_check(&noise_model, &pos!(mc - 1, 4, 1), &Y);
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        assert_eq!(sparse_measurement.to_vec(), vec![pos!(mc, 4, 1), pos!(2 * mc, 2, 1)]);
+        // the analog value of the wrong readout is close to the threshold
+        let value = 0.52;
+        let mut sparse_soft_readouts = SparseSoftReadouts::new();
+        sparse_soft_readouts.readouts.insert(
+            pos!(mc, 4, 1),
+            SoftReadout {
+                value,
+                flip_probability: readout.flip_probability(value),
+                prior_flip_probability: readout.hard_flip_probability(),
+                next_measurement: pos!(2 * mc, 4, 1),
+            },
+        );
+        // without the analog value, the two defects are matched to the boundaries
+        for (sparse_soft_readouts, expect_logical_error) in
+            [(SparseSoftReadouts::new(), true), (sparse_soft_readouts, false)]
+        {
+            let (correction, _runtime_statistics) =
+                fusion_decoder.decode_with_soft_readouts(&sparse_measurement, &SparseErasures::new(), &sparse_soft_readouts);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert_eq!(logical_i || logical_j, expect_logical_error);
+        }
+    }
 }
@@ -0,0 +1,309 @@
+//! greedy matching decoder
+//!
+//! a fast approximate baseline of the minimum-weight perfect matching decoder: all the defect pairs and defect-to-boundary pairs
+//! are sorted by their distance in the [`CompleteModelGraph`], and then repeatedly the closest pair whose defects are both
+//! unmatched is chosen, until every defect is matched. it's often used as the hardware-friendly decoder for comparison.
+//!
+
+use super::complete_model_graph::*;
+use super::decoder_mwpm::*;
+use super::model_graph::*;
+use super::noise_model::*;
+use super::serde_json;
+use super::simulator::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// greedy matching decoder, initialized and cloned for multiple threads
+#[derive(Debug, Clone, Serialize)]
+pub struct GreedyDecoder {
+    /// model graph is immutably shared
+    pub model_graph: Arc<ModelGraph>,
+    /// complete model graph each thread maintain its own precomputed data
+    pub complete_model_graph: CompleteModelGraph,
+    /// save configuration for later usage
+    pub config: GreedyDecoderConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GreedyDecoderConfig {
+    /// build complete model graph at first, but this will consume O(N^2) memory and increase initialization time,
+    /// disable this when you're simulating large code
+    #[serde(alias = "pcmg")] // abbreviation
+    #[serde(default = "mwpm_default_configs::precompute_complete_model_graph")]
+    pub precompute_complete_model_graph: bool,
+    /// weight function, by default using [`WeightFunction::AutotuneImproved`]
+    #[serde(alias = "wf")] // abbreviation
+    #[serde(default = "mwpm_default_configs::weight_function")]
+    pub weight_function: WeightFunction,
+    /// combined probability can improve accuracy, but will cause probabilities differ a lot even in the case of i.i.d. noise model
+    #[serde(alias = "ucp")] // abbreviation
+    #[serde(default = "mwpm_default_configs::use_combined_probability")]
+    pub use_combined_probability: bool,
+}
+
+impl GreedyDecoder {
+    /// create a new greedy decoder with decoder configuration
+    pub fn new(
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        decoder_configuration: &serde_json::Value,
+        parallel: usize,
+        use_brief_edge: bool,
+    ) -> Self {
+        // read attribute of decoder configuration
+        let config: GreedyDecoderConfig = serde_json::from_value(decoder_configuration.clone()).unwrap();
+        // build model graph
+        let mut simulator = simulator.clone();
+        let mut model_graph = ModelGraph::new(&simulator);
+        model_graph.build(
+            &mut simulator,
+            noise_model,
+            &config.weight_function,
+            parallel,
+            config.use_combined_probability,
+            use_brief_edge,
+        );
+        let model_graph = Arc::new(model_graph);
+        // build complete model graph
+        let mut complete_model_graph = CompleteModelGraph::new(&simulator, Arc::clone(&model_graph));
+        complete_model_graph.precompute(&simulator, config.precompute_complete_model_graph, parallel);
+        Self {
+            model_graph,
+            complete_model_graph,
+            config,
+        }
+    }
+
+    /// decode given measurement results
+    pub fn decode(&mut self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
+        self.decode_internal(sparse_measurement, None)
+    }
+
+    /// decode given measurement results and detected erasures
+    pub fn decode_with_erasure(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        assert!(
+            sparse_detected_erasures.is_empty(),
+            "greedy decoder doesn't support erasure error yet"
+        );
+        self.decode(sparse_measurement)
+    }
+
+    /// decode given measurement results and detected erasures, and return the chosen model graph edges
+    pub fn decode_subgraph(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseSubgraph, serde_json::Value) {
+        assert!(
+            sparse_detected_erasures.is_empty(),
+            "greedy decoder doesn't support erasure error yet"
+        );
+        let mut subgraph = SparseSubgraph::new();
+        let (_, runtime_statistics) = self.decode_internal(sparse_measurement, Some(&mut subgraph));
+        (subgraph, runtime_statistics)
+    }
+
+    /// the correction is left empty if the subgraph is requested
+    fn decode_internal(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        mut subgraph: Option<&mut SparseSubgraph>,
+    ) -> (SparseCorrection, serde_json::Value) {
+        let mut correction = SparseCorrection::new();
+        let to_be_matched = sparse_measurement.to_vec();
+        // collect all the candidate pairs, where the boundary is represented by `m_len`
+        let m_len = to_be_matched.len();
+        let begin = Instant::now();
+        let mut candidates = Vec::<(f64, usize, usize)>::new();
+        self.complete_model_graph.invalidate_previous_dijkstra();
+        for i in 0..m_len {
+            let (edges, boundary) = self.complete_model_graph.get_edges(&to_be_matched[i], &to_be_matched);
+            if let Some(weight) = boundary {
+                candidates.push((weight, i, m_len));
+            }
+            for (j, weight) in edges.into_iter() {
+                if i < j {
+                    candidates.push((weight, i, j));
+                }
+            }
+        }
+        let time_prepare_graph = begin.elapsed().as_secs_f64();
+        // choose the closest pairs first; the sort is stable so that the result is deterministic
+        let begin = Instant::now();
+        candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let mut peers: Vec<Option<usize>> = vec![None; m_len];
+        let mut matched_count = 0;
+        for &(_, i, j) in candidates.iter() {
+            if matched_count == m_len {
+                break;
+            }
+            if peers[i].is_some() || (j != m_len && peers[j].is_some()) {
+                continue;
+            }
+            peers[i] = Some(j);
+            matched_count += 1;
+            if j != m_len {
+                peers[j] = Some(i);
+                matched_count += 1;
+            }
+        }
+        let time_greedy_matching = begin.elapsed().as_secs_f64();
+        // build correction based on the matching
+        let begin = Instant::now();
+        let mut boundary_matched = 0;
+        for (i, peer) in peers.iter().enumerate() {
+            let a = &to_be_matched[i];
+            let j = peer.unwrap_or_else(|| panic!("defect {a} has no unmatched peer or boundary to match with"));
+            if j == m_len {
+                boundary_matched += 1;
+                if let Some(subgraph) = subgraph.as_mut() {
+                    self.complete_model_graph.build_subgraph_boundary(a, subgraph);
+                } else {
+                    let boundary_correction = self.complete_model_graph.build_correction_boundary(a);
+                    correction.extend(&boundary_correction);
+                }
+            } else if j < i {
+                // only add correction if j < i, so that the same correction is not applied twice
+                let b = &to_be_matched[j];
+                if let Some(subgraph) = subgraph.as_mut() {
+                    self.complete_model_graph.build_subgraph_matching(a, b, subgraph);
+                } else {
+                    let matching_correction = self.complete_model_graph.build_correction_matching(a, b);
+                    correction.extend(&matching_correction);
+                }
+            }
+        }
+        let time_build_correction = begin.elapsed().as_secs_f64();
+        (
+            correction,
+            json!({
+                "to_be_matched": m_len,
+                "boundary_matched": boundary_matched,
+                "time_prepare_graph": time_prepare_graph,
+                "time_greedy_matching": time_greedy_matching,
+                "time_build_correction": time_build_correction,
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::clap::Parser;
+    use super::super::cli::*;
+    use super::super::code_builder::*;
+    use super::super::tool::*;
+    use super::super::types::*;
+    use super::*;
+    use crate::rand::SeedableRng;
+    use crate::reproducible_rand::Xoroshiro128StarStar;
+
+    #[test]
+    fn greedy_decoder_single_errors() {
+        // cargo test greedy_decoder_single_errors -- --nocapture
+        let d = 5;
+        let p = 0.03;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let mut greedy_decoder = GreedyDecoder::new(&simulator, Arc::clone(&noise_model), &json!({"pcmg": true}), 1, false);
+        let mut data_qubits = vec![];
+        simulator_iter!(simulator, position, node, t => 0, if node.qubit_type == QubitType::Data && !node.is_virtual {
+            data_qubits.push(position.clone());
+        });
+        assert_eq!(data_qubits.len(), 25);
+        // any single-qubit error is corrected, just like the minimum-weight perfect matching decoder
+        for position in data_qubits.iter() {
+            for error in ErrorType::all_possible_errors() {
+                simulator.clear_all_errors();
+                simulator.set_error_check(&noise_model, position, &error);
+                simulator.propagate_errors();
+                let sparse_measurement = simulator.generate_sparse_measurement();
+                let (correction, _runtime_statistics) = greedy_decoder.decode(&sparse_measurement);
+                code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+                let (logical_i, logical_j) = simulator.validate_correction(&correction);
+                assert!(!logical_i && !logical_j, "failed to correct {error} at {position}");
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_decoder_compare_mwpm() {
+        // cargo test greedy_decoder_compare_mwpm -- --nocapture
+        let d = 5;
+        let p = 0.1;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+        let mut greedy_decoder = GreedyDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        let mut mwpm_decoder = MWPMDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        let model_graph = Arc::clone(&greedy_decoder.model_graph);
+        let subgraph_weight = |subgraph: &SparseSubgraph| -> f64 {
+            subgraph
+                .iter()
+                .map(|(defect_vertices, _)| match defect_vertices.as_slice() {
+                    [position] => model_graph.get_node_unwrap(position).boundary.as_ref().unwrap().weight,
+                    [position1, position2] => model_graph.get_node_unwrap(position1).edges[position2].weight,
+                    _ => unreachable!(),
+                })
+                .sum()
+        };
+        let mut suboptimal_shots = 0;
+        for _ in 0..200 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (subgraph, _runtime_statistics) =
+                greedy_decoder.decode_subgraph(&sparse_measurement, &SparseErasures::new());
+            code_builder_sanity_check_correction(&mut simulator, &subgraph.to_correction()).unwrap();
+            let (mwpm_subgraph, _runtime_statistics) =
+                mwpm_decoder.decode_subgraph(&sparse_measurement, &SparseErasures::new());
+            let weight = subgraph_weight(&subgraph);
+            let mwpm_weight = subgraph_weight(&mwpm_subgraph);
+            assert!(
+                weight >= mwpm_weight - 1e-6,
+                "greedy {weight} cannot be better than MWPM {mwpm_weight}"
+            );
+            if weight > mwpm_weight + 1e-6 {
+                suboptimal_shots += 1;
+            }
+        }
+        assert!(
+            suboptimal_shots > 0,
+            "greedy matching should be suboptimal in some of the shots"
+        );
+    }
+
+    #[test]
+    fn greedy_decoder_from_parameters() {
+        // cargo test greedy_decoder_from_parameters -- --nocapture
+        for (pes, is_ok) in [("[0]", true), ("[0.01]", false)] {
+            let parameters =
+                BenchmarkParameters::parse_from(["benchmark", "[3]", "[0]", "[0.01]", "--pes", pes, "--decoder", "greedy"]);
+            let configs = parameters.fill_in_default_parameters().unwrap();
+            let config = &parameters.extract_simulation_configurations(&configs)[0];
+            let mut simulator = Simulator::new(parameters.code_type, CodeSize::new(0, 3, 3));
+            let noise_model = parameters
+                .construct_noise_model(&mut simulator, &configs, config, false)
+                .unwrap();
+            let general_decoder = GeneralDecoder::from_parameters(&parameters, &configs, config, &simulator, &noise_model);
+            // the greedy decoder doesn't support erasure errors
+            assert_eq!(general_decoder.is_ok(), is_ok);
+        }
+    }
+}
@@ -1,25 +1,59 @@
 //! union-find decoder on hypergraph
+//!
+//! Clusters grow along the weighted hyperedges of [`ModelHypergraph`] until every cluster is valid, i.e. the defects inside
+//! it can be explained by its fully grown hyperedges; the correction of each cluster is then found by Gaussian elimination
+//! over GF(2), preferring the hyperedges of smaller weight. Detected erasures simply pre-grow the erased hyperedges.
+//!
 
 use super::decoder_mwpm::*;
 use super::model_graph::*;
+use super::model_hypergraph::*;
 use super::noise_model::*;
 use super::simulator::*;
-use crate::model_hypergraph::*;
-use crate::mwpf::mwpf_solver::*;
-use crate::mwpf::util::*;
+use super::union_find::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
 
+#[derive(Clone)]
 pub struct HyperUnionFindDecoder {
     /// model hypergraph
     pub model_hypergraph: Arc<ModelHypergraph>,
     /// save configuration for later usage
     pub config: HyperUnionFindDecoderConfig,
-    /// (approximate) minimum-weight parity subgraph solver
-    pub solver: SolverSerialUnionFind,
-    /// the initializer of the solver, used for customized clone
-    pub initializer: Arc<SolverInitializer>,
+    /// the hyperedges that can be used by the decoder, immutably shared
+    pub edges: Arc<Vec<HyperUnionFindEdge>>,
+    /// the incident edges of each vertex
+    pub vertex_edges: Arc<Vec<Vec<usize>>>,
+    /// the edges that become zero-weight when the qubit at this position is erased
+    pub erasure_edges: Arc<HashMap<Position, Vec<usize>>>,
+    /// the clusters
+    pub union_find: DefaultUnionFind,
+    /// the vertices of each cluster, only valid at the root
+    cluster_vertices: Vec<Vec<usize>>,
+    /// the fully grown edges of each cluster, only valid at the root
+    cluster_edges: Vec<Vec<usize>>,
+    /// whether each vertex is a defect
+    is_defect: Vec<bool>,
+    /// the grown length of each edge
+    growth: Vec<usize>,
+    /// the length of each edge in this decoding, erased edges have zero length
+    lengths: Vec<Option<usize>>,
+    /// the erased error of each edge in this decoding, as an index in [`ModelHyperedgeGroup::all_hyperedges`]
+    erased_hyperedges: Vec<Option<usize>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HyperUnionFindEdge {
+    /// the vertices of the hyperedge
+    pub vertices: Vec<usize>,
+    /// the scaled integer weight of the hyperedge, `None` if it is only possible when erased
+    pub length: Option<usize>,
+    /// the index in [`ModelHypergraph::weighted_edges`]
+    pub hyperedge_index: usize,
+    /// the error positions of each hyperedge in [`ModelHyperedgeGroup::all_hyperedges`], used to decide whether it is erased
+    pub error_positions: Vec<Vec<Position>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,32 +71,16 @@ pub struct HyperUnionFindDecoderConfig {
     #[serde(alias = "mhw")] // abbreviation
     #[serde(default = "hyper_union_find_default_configs::max_weight")]
     pub max_weight: usize,
-    #[serde(default = "hyper_union_find_default_configs::default_hyperion_config")]
-    pub hyperion_config: serde_json::Value,
 }
 
 pub mod hyper_union_find_default_configs {
     pub fn max_weight() -> usize {
         1000000
     }
-    pub fn default_hyperion_config() -> serde_json::Value {
-        json!({})
-    }
-}
-
-impl Clone for HyperUnionFindDecoder {
-    fn clone(&self) -> Self {
-        Self {
-            model_hypergraph: self.model_hypergraph.clone(),
-            config: self.config.clone(),
-            solver: SolverSerialUnionFind::new(&self.initializer, self.config.hyperion_config.clone()),
-            initializer: self.initializer.clone(),
-        }
-    }
 }
 
 impl HyperUnionFindDecoder {
-    /// create a new MWPM decoder with decoder configuration
+    /// create a new hypergraph union-find decoder with decoder configuration
     pub fn new(
         simulator: &Simulator,
         noise_model: Arc<NoiseModel>,
@@ -83,16 +101,165 @@ impl HyperUnionFindDecoder {
             config.use_combined_probability,
             use_brief_edge,
         );
-        let model_hypergraph = Arc::new(model_hypergraph);
-        let (vertex_num, weighted_edges) = model_hypergraph.generate_mwpf_hypergraph(config.max_weight);
-        let initializer = Arc::new(SolverInitializer::new(vertex_num, weighted_edges));
-        let solver = SolverSerialUnionFind::new(&initializer, config.hyperion_config.clone());
+        Self::from_model_hypergraph(Arc::new(model_hypergraph), config)
+    }
+
+    /// create a decoder given the model hypergraph
+    pub fn from_model_hypergraph(model_hypergraph: Arc<ModelHypergraph>, config: HyperUnionFindDecoderConfig) -> Self {
+        // scale all the edges
+        let mut maximum_weight = 0.;
+        for (_, hyperedge_group) in model_hypergraph.weighted_edges.iter() {
+            if hyperedge_group.hyperedge.probability > 0. && hyperedge_group.hyperedge.weight > maximum_weight {
+                maximum_weight = hyperedge_group.hyperedge.weight;
+            }
+        }
+        let mut edges = Vec::with_capacity(model_hypergraph.weighted_edges.len());
+        let mut vertex_edges = vec![vec![]; model_hypergraph.vertex_positions.len()];
+        let mut erasure_edges = HashMap::<Position, Vec<usize>>::new();
+        for (hyperedge_index, (defect_vertices, hyperedge_group)) in model_hypergraph.weighted_edges.iter().enumerate() {
+            let hyperedge = &hyperedge_group.hyperedge;
+            let length = if hyperedge.probability > 0. {
+                let scaled_weight = if maximum_weight > 0. {
+                    hyperedge.weight.max(0.) * config.max_weight as f64 / maximum_weight
+                } else {
+                    0.
+                };
+                assert!(scaled_weight.is_finite(), "weight must be normal");
+                Some(scaled_weight.round() as usize)
+            } else {
+                None // only erasure errors can cause this hyperedge
+            };
+            let edge_index = edges.len();
+            let error_positions: Vec<Vec<Position>> = hyperedge_group
+                .all_hyperedges
+                .iter()
+                .map(|hyperedge| hyperedge.error_pattern.iter().map(|(position, _)| position.clone()).collect())
+                .collect();
+            for position in error_positions.iter().flatten() {
+                let position_edges = erasure_edges.entry(position.clone()).or_default();
+                if position_edges.last() != Some(&edge_index) {
+                    position_edges.push(edge_index);
+                }
+            }
+            let vertices: Vec<usize> = defect_vertices.0.iter().map(|x| model_hypergraph.vertex_indices[x]).collect();
+            for &vertex in vertices.iter() {
+                vertex_edges[vertex].push(edge_index);
+            }
+            edges.push(HyperUnionFindEdge {
+                vertices,
+                length,
+                hyperedge_index,
+                error_positions,
+            });
+        }
+        let vertex_num = model_hypergraph.vertex_positions.len();
+        let edge_num = edges.len();
         Self {
             model_hypergraph,
             config,
-            solver,
-            initializer,
+            edges: Arc::new(edges),
+            vertex_edges: Arc::new(vertex_edges),
+            erasure_edges: Arc::new(erasure_edges),
+            union_find: DefaultUnionFind::new(vertex_num),
+            cluster_vertices: (0..vertex_num).map(|vertex| vec![vertex]).collect(),
+            cluster_edges: vec![vec![]; vertex_num],
+            is_defect: vec![false; vertex_num],
+            growth: vec![0; edge_num],
+            lengths: vec![None; edge_num],
+            erased_hyperedges: vec![None; edge_num],
+        }
+    }
+
+    /// clear the state of the clusters
+    fn clear(&mut self) {
+        self.union_find.clear();
+        for (vertex, cluster_vertices) in self.cluster_vertices.iter_mut().enumerate() {
+            cluster_vertices.clear();
+            cluster_vertices.push(vertex);
+        }
+        self.cluster_edges.iter_mut().for_each(|cluster_edges| cluster_edges.clear());
+        self.is_defect.iter_mut().for_each(|is_defect| *is_defect = false);
+        self.growth.iter_mut().for_each(|growth| *growth = 0);
+        for (length, edge) in self.lengths.iter_mut().zip(self.edges.iter()) {
+            *length = edge.length;
+        }
+        self.erased_hyperedges.iter_mut().for_each(|erased| *erased = None);
+    }
+
+    /// add a fully grown edge to the cluster, merging all its vertices
+    fn add_grown_edge(&mut self, edge_index: usize) {
+        let vertices = &self.edges[edge_index].vertices;
+        let mut root = self.union_find.find(vertices[0]);
+        for &vertex in vertices.iter().skip(1) {
+            let other_root = self.union_find.find(vertex);
+            if other_root == root {
+                continue;
+            }
+            self.union_find.union(root, other_root);
+            let new_root = self.union_find.find(root);
+            let merged_root = if new_root == root { other_root } else { root };
+            let merged_vertices = std::mem::take(&mut self.cluster_vertices[merged_root]);
+            let merged_edges = std::mem::take(&mut self.cluster_edges[merged_root]);
+            self.cluster_vertices[new_root].extend(merged_vertices);
+            self.cluster_edges[new_root].extend(merged_edges);
+            root = new_root;
+        }
+        self.cluster_edges[root].push(edge_index);
+    }
+
+    /// the fully grown edges of a cluster that explain its defects, or `None` if the cluster is invalid
+    fn solve_cluster(&self, root: usize) -> Option<Vec<usize>> {
+        let vertices = &self.cluster_vertices[root];
+        if vertices.iter().all(|&vertex| !self.is_defect[vertex]) {
+            return Some(vec![]);
+        }
+        // prefer the edges of smaller weight as pivots
+        let mut edges = self.cluster_edges[root].clone();
+        edges.sort_by_key(|&edge_index| self.lengths[edge_index]);
+        let local_index: HashMap<usize, usize> = vertices.iter().enumerate().map(|(i, &vertex)| (vertex, i)).collect();
+        // each row is a vertex, the last column is whether it's a defect
+        let column_num = edges.len() + 1;
+        let word_num = (column_num + 63) / 64;
+        let mut rows = vec![vec![0u64; word_num]; vertices.len()];
+        for (column, &edge_index) in edges.iter().enumerate() {
+            for vertex in self.edges[edge_index].vertices.iter() {
+                rows[local_index[vertex]][column / 64] ^= 1 << (column % 64);
+            }
+        }
+        for (row, &vertex) in rows.iter_mut().zip(vertices.iter()) {
+            if self.is_defect[vertex] {
+                row[edges.len() / 64] ^= 1 << (edges.len() % 64);
+            }
+        }
+        let get = |row: &[u64], column: usize| (row[column / 64] >> (column % 64)) & 1 == 1;
+        let mut pivots = vec![];
+        for column in 0..edges.len() {
+            let rank = pivots.len();
+            let Some(pivot_row) = (rank..rows.len()).find(|&row| get(&rows[row], column)) else {
+                continue;
+            };
+            rows.swap(rank, pivot_row);
+            let pivot = rows[rank].clone();
+            for (row_index, row) in rows.iter_mut().enumerate() {
+                if row_index != rank && get(row, column) {
+                    row.iter_mut()
+                        .zip(pivot.iter())
+                        .for_each(|(word, pivot_word)| *word ^= pivot_word);
+                }
+            }
+            pivots.push(column);
         }
+        if rows[pivots.len()..].iter().any(|row| get(row, edges.len())) {
+            return None;
+        }
+        Some(
+            pivots
+                .iter()
+                .enumerate()
+                .filter(|(row, _)| get(&rows[*row], edges.len()))
+                .map(|(_, &column)| edges[column])
+                .collect(),
+        )
     }
 
     /// decode given measurement results
@@ -107,31 +274,124 @@ impl HyperUnionFindDecoder {
         sparse_measurement: &SparseMeasurement,
         sparse_detected_erasures: &SparseErasures,
     ) -> (SparseCorrection, serde_json::Value) {
-        if !sparse_detected_erasures.is_empty() {
-            unimplemented!()
-        }
         // run decode
         let begin = Instant::now();
-        let defect_vertices: Vec<_> = sparse_measurement
-            .iter()
-            .map(|position| {
-                *self
-                    .model_hypergraph
-                    .vertex_indices
-                    .get(position)
-                    .expect("measurement cannot happen at impossible position")
-            })
-            .collect();
-        let syndrome_pattern = SyndromePattern::new(defect_vertices, vec![]);
-        self.solver.solve(&syndrome_pattern);
-        let subgraph = self.solver.subgraph();
-        self.solver.clear();
+        self.clear();
+        let mut defect_vertices = Vec::with_capacity(sparse_measurement.len());
+        for position in sparse_measurement.iter() {
+            let vertex = *self
+                .model_hypergraph
+                .vertex_indices
+                .get(position)
+                .expect("measurement cannot happen at impossible position");
+            self.is_defect[vertex] = true;
+            defect_vertices.push(vertex);
+        }
+        // erased hyperedges have zero weight
+        let edges = Arc::clone(&self.edges);
+        for erasure in sparse_detected_erasures.iter() {
+            let Some(erasure_edges) = self.erasure_edges.get(erasure) else {
+                continue;
+            };
+            for &edge_index in erasure_edges.iter() {
+                if self.erased_hyperedges[edge_index].is_some() {
+                    continue;
+                }
+                self.erased_hyperedges[edge_index] = edges[edge_index]
+                    .error_positions
+                    .iter()
+                    .position(|positions| positions.iter().all(|position| sparse_detected_erasures.contains(position)));
+                if self.erased_hyperedges[edge_index].is_some() {
+                    self.lengths[edge_index] = Some(0);
+                }
+            }
+        }
+        for edge_index in 0..edges.len() {
+            if self.lengths[edge_index] == Some(0) {
+                self.add_grown_edge(edge_index);
+            }
+        }
+        // grow the invalid clusters until all of them are valid
+        let vertex_edges = Arc::clone(&self.vertex_edges);
+        let mut count_iteration = 0;
+        let mut growth_rates = vec![0usize; edges.len()];
+        let mut cluster_stamps = vec![usize::MAX; edges.len()];
+        loop {
+            let mut invalid_roots: Vec<usize> = vec![];
+            for &vertex in defect_vertices.iter() {
+                let root = self.union_find.find(vertex);
+                if !invalid_roots.contains(&root) && self.solve_cluster(root).is_none() {
+                    invalid_roots.push(root);
+                }
+            }
+            if invalid_roots.is_empty() {
+                break;
+            }
+            count_iteration += 1;
+            // each invalid cluster grows its incident edges at a unit rate
+            let mut growing_edges = vec![];
+            for &root in invalid_roots.iter() {
+                for &vertex in self.cluster_vertices[root].iter() {
+                    for &edge_index in vertex_edges[vertex].iter() {
+                        let Some(length) = self.lengths[edge_index] else {
+                            continue;
+                        };
+                        if self.growth[edge_index] >= length || cluster_stamps[edge_index] == root {
+                            continue;
+                        }
+                        cluster_stamps[edge_index] = root;
+                        if growth_rates[edge_index] == 0 {
+                            growing_edges.push(edge_index);
+                        }
+                        growth_rates[edge_index] += 1;
+                    }
+                }
+            }
+            assert!(
+                !growing_edges.is_empty(),
+                "invalid cluster cannot grow, the syndrome is impossible"
+            );
+            // grow until at least one edge is fully grown
+            let delta = growing_edges
+                .iter()
+                .map(|&edge_index| {
+                    let remaining = self.lengths[edge_index].unwrap() - self.growth[edge_index];
+                    (remaining + growth_rates[edge_index] - 1) / growth_rates[edge_index]
+                })
+                .min()
+                .unwrap();
+            for &edge_index in growing_edges.iter() {
+                let length = self.lengths[edge_index].unwrap();
+                self.growth[edge_index] = length.min(self.growth[edge_index] + delta * growth_rates[edge_index]);
+                growth_rates[edge_index] = 0;
+                cluster_stamps[edge_index] = usize::MAX;
+                if self.growth[edge_index] == length {
+                    self.add_grown_edge(edge_index);
+                }
+            }
+        }
+        // find the correction of each cluster
+        let mut chosen_edges = vec![];
+        let mut solved_roots = vec![];
+        for &vertex in defect_vertices.iter() {
+            let root = self.union_find.find(vertex);
+            if !solved_roots.contains(&root) {
+                solved_roots.push(root);
+                chosen_edges.extend(self.solve_cluster(root).expect("cluster must be valid"));
+            }
+        }
         let time_decode = begin.elapsed().as_secs_f64();
         // build correction
         let begin = Instant::now();
         let mut correction = SparseCorrection::new();
-        for &edge_index in subgraph.iter() {
-            correction.extend(&self.model_hypergraph.weighted_edges[edge_index].1.hyperedge.correction);
+        for &edge_index in chosen_edges.iter() {
+            let hyperedge_group = &self.model_hypergraph.weighted_edges[edges[edge_index].hyperedge_index].1;
+            // an erased edge is explained by the erased error rather than the most likely one
+            let hyperedge = match self.erased_hyperedges[edge_index] {
+                Some(erased) => &hyperedge_group.all_hyperedges[erased],
+                None => &hyperedge_group.hyperedge,
+            };
+            correction.extend(&hyperedge.correction);
         }
         let time_build_correction = begin.elapsed().as_secs_f64();
         (
@@ -139,6 +399,7 @@ impl HyperUnionFindDecoder {
             json!({
                 "time_decode": time_decode,
                 "time_build_correction": time_build_correction,
+                "count_iteration": count_iteration,
             }),
         )
     }
@@ -147,8 +408,11 @@ impl HyperUnionFindDecoder {
 #[cfg(test)]
 mod tests {
     use super::super::code_builder::*;
+    use super::super::noise_model_builder::*;
     use super::super::types::ErrorType::*;
     use super::*;
+    use crate::rand::SeedableRng;
+    use crate::reproducible_rand::Xoroshiro128StarStar;
 
     #[test]
     fn hyper_union_find_decoder_code_capacity() {
@@ -243,4 +507,55 @@ mod tests {
             assert!(!logical_i && !logical_j);
         }
     }
+
+    #[test]
+    fn hyper_union_find_decoder_circuit_level_random() {
+        // cargo test hyper_union_find_decoder_circuit_level_random -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let p = 0.005;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mut noise_model = NoiseModel::new(&simulator);
+        NoiseModelBuilder::Phenomenological.apply(&mut simulator, &mut noise_model, &json!({}), p, 1., 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let mut decoder = HyperUnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+        for _ in 0..200 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, _) = decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        }
+    }
+
+    #[test]
+    fn hyper_union_find_decoder_erasure() {
+        // cargo test hyper_union_find_decoder_erasure -- --nocapture
+        let d = 5;
+        let pe = 0.1;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(0, d, d));
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, 0., 0., 0., pe);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let mut decoder = HyperUnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+        let mut logical_errors = 0;
+        for _ in 0..200 {
+            let (_, _) = simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+            let (correction, _) = decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            if logical_i || logical_j {
+                logical_errors += 1;
+            }
+        }
+        // pure erasures at a rate far below the threshold are almost always corrected
+        assert!(logical_errors <= 5, "{logical_errors} logical errors");
+    }
 }
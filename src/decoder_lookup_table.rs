@@ -0,0 +1,630 @@
+//! # Lookup Table Decoder
+//!
+//! exact maximum-likelihood decoder for small codes. the joint distribution of the syndrome and the logical class is computed
+//! by going through every independent error channel in the noise model, whose outcomes are mutually exclusive, e.g. the X, Y
+//! and Z errors of a qubit, and for every syndrome the table stores a correction in the most likely logical class. since the table has 2^n entries for n real measurement vertices, it only works
+//! for small experiments, e.g. d=3 with a few rounds; it's the optimal reference to compare other decoders with.
+//!
+//! the table can be persisted to a compact binary file: a header with the measurement vertices, the corrected positions and a
+//! fingerprint of the error channels, followed by the correction bits of every syndrome. a file that doesn't match the current
+//! decoding graph or noise model is rebuilt and overwritten, which is reported in the runtime statistics.
+//!
+
+use super::noise_model::*;
+use super::simulator::*;
+use super::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// lookup table decoder, initialized and cloned for multiple threads
+#[derive(Debug, Clone, Serialize)]
+pub struct LookupTableDecoder {
+    /// the table shared by all threads
+    #[serde(skip)]
+    pub table: Arc<LookupTable>,
+    /// save configuration for later usage
+    pub config: LookupTableDecoderConfig,
+    /// why the table file was rebuilt instead of loaded, if so
+    pub table_rebuilt: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LookupTableDecoderConfig {
+    /// refuse to build a table with more syndrome bits than this, because both the time and memory grow as 2^n
+    #[serde(alias = "msb")] // abbreviation
+    #[serde(default = "lookup_table_default_configs::max_syndrome_bits")]
+    pub max_syndrome_bits: usize,
+    /// load the table from this binary file if it matches, otherwise build the table and save it to this file
+    #[serde(alias = "tf")] // abbreviation
+    #[serde(default = "lookup_table_default_configs::table_filename")]
+    pub table_filename: Option<String>,
+}
+
+/// the syndrome bits and the 2 bits of the logical class must fit in a 64-bit mask
+pub const LOOKUP_TABLE_MAX_SYNDROME_BITS: usize = 61;
+
+impl LookupTableDecoderConfig {
+    pub fn sanity_check(&self) -> Result<(), String> {
+        if self.max_syndrome_bits > LOOKUP_TABLE_MAX_SYNDROME_BITS {
+            return Err(format!(
+                "max_syndrome_bits {} is larger than the limit {LOOKUP_TABLE_MAX_SYNDROME_BITS}",
+                self.max_syndrome_bits
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub mod lookup_table_default_configs {
+    pub fn max_syndrome_bits() -> usize {
+        20
+    }
+    pub fn table_filename() -> Option<String> {
+        None
+    }
+}
+
+/// an outcome of an error channel: the syndrome bits, followed by 2 bits of the logical class, and the correction bits
+#[derive(Debug, Clone)]
+struct ErrorOutcome {
+    mask: u64,
+    probability: f64,
+    correction: Vec<u64>,
+}
+
+/// an independent error channel, whose outcomes are mutually exclusive, e.g. the X, Y and Z errors of a qubit
+#[derive(Debug, Clone)]
+struct ErrorChannel {
+    outcomes: Vec<ErrorOutcome>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LookupTable {
+    /// the real measurement vertices, the k-th of which is the k-th bit of the syndrome
+    pub vertex_positions: Vec<Position>,
+    /// the syndrome bit of each real measurement vertex
+    pub vertex_indices: HashMap<Position, usize>,
+    /// the positions that may be corrected, each taking 2 bits (X and Z) in a table entry
+    pub correction_positions: Vec<Position>,
+    /// fingerprint of the error channels, used to check whether a persisted table is still valid
+    pub fingerprint: u64,
+    /// the number of 64-bit words in each entry
+    pub entry_words: usize,
+    /// the correction bits of all the 2^n syndromes; the correction of an impossible syndrome is empty
+    pub entries: Vec<u64>,
+}
+
+const LOOKUP_TABLE_MAGIC: &[u8; 8] = b"QECPLUT1";
+
+/// the real measurement vertices, the positions that may be corrected and the independent error channels
+type ErrorChannels = (Vec<Position>, Vec<Position>, Vec<ErrorChannel>);
+
+impl LookupTableDecoder {
+    /// create a new lookup table decoder with decoder configuration
+    pub fn new(
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        decoder_configuration: &serde_json::Value,
+    ) -> Result<Self, String> {
+        // read attribute of decoder configuration
+        let config: LookupTableDecoderConfig =
+            serde_json::from_value(decoder_configuration.clone()).map_err(|x| x.to_string())?;
+        config.sanity_check()?;
+        if noise_model.has_erasure() {
+            return Err("lookup table decoder doesn't support erasure errors".to_string());
+        }
+        let mut simulator = simulator.clone();
+        let (vertex_positions, correction_positions, error_channels) =
+            LookupTable::error_channels(&mut simulator, &noise_model, config.max_syndrome_bits)?;
+        let fingerprint = LookupTable::fingerprint(&error_channels);
+        let mut table = None;
+        let mut table_rebuilt = None;
+        if let Some(table_filename) = config.table_filename.as_ref() {
+            if let Ok(loaded) = LookupTable::load(table_filename) {
+                if loaded.vertex_positions == vertex_positions
+                    && loaded.correction_positions == correction_positions
+                    && loaded.fingerprint == fingerprint
+                {
+                    table = Some(loaded);
+                } else {
+                    table_rebuilt = Some(format!("lookup table file {table_filename} doesn't match the decoding graph"));
+                }
+            }
+        }
+        let table = match table {
+            Some(table) => table,
+            None => {
+                let table = LookupTable::build(vertex_positions, correction_positions, fingerprint, &error_channels);
+                if let Some(table_filename) = config.table_filename.as_ref() {
+                    table
+                        .save(table_filename)
+                        .map_err(|e| format!("cannot save lookup table file {table_filename}: {e}"))?;
+                }
+                table
+            }
+        };
+        Ok(Self {
+            table: Arc::new(table),
+            config,
+            table_rebuilt,
+        })
+    }
+
+    /// decode given measurement results
+    pub fn decode(&self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_erasure(sparse_measurement, &SparseErasures::new())
+    }
+
+    /// decode given measurement results and detected erasures
+    pub fn decode_with_erasure(
+        &self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        assert!(
+            sparse_detected_erasures.is_empty(),
+            "lookup table decoder doesn't support erasure error"
+        );
+        let begin = Instant::now();
+        let correction = self.table.lookup(sparse_measurement);
+        let mut runtime_statistics = json!({ "time_lookup": begin.elapsed().as_secs_f64() });
+        if let Some(table_rebuilt) = self.table_rebuilt.as_ref() {
+            runtime_statistics["table_rebuilt"] = json!(table_rebuilt);
+        }
+        (correction, runtime_statistics)
+    }
+}
+
+impl LookupTable {
+    /// the syndrome index of the measurement results
+    pub fn syndrome_index(&self, sparse_measurement: &SparseMeasurement) -> usize {
+        let mut syndrome_index = 0;
+        for position in sparse_measurement.iter() {
+            let vertex_index = *self
+                .vertex_indices
+                .get(position)
+                .unwrap_or_else(|| panic!("defect measurement {position} is not a vertex of the lookup table"));
+            syndrome_index |= 1 << vertex_index;
+        }
+        syndrome_index
+    }
+
+    fn vertex_indices(vertex_positions: &[Position]) -> HashMap<Position, usize> {
+        vertex_positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| (position.clone(), index))
+            .collect()
+    }
+
+    pub fn lookup(&self, sparse_measurement: &SparseMeasurement) -> SparseCorrection {
+        let syndrome_index = self.syndrome_index(sparse_measurement);
+        let entry = &self.entries[syndrome_index * self.entry_words..(syndrome_index + 1) * self.entry_words];
+        let mut correction = SparseCorrection::new();
+        for (index, position) in self.correction_positions.iter().enumerate() {
+            let has_x = entry[2 * index / 64] & (1 << (2 * index % 64)) != 0;
+            let has_z = entry[(2 * index + 1) / 64] & (1 << ((2 * index + 1) % 64)) != 0;
+            match (has_x, has_z) {
+                (true, false) => correction.add(position.clone(), ErrorType::X),
+                (false, true) => correction.add(position.clone(), ErrorType::Z),
+                (true, true) => correction.add(position.clone(), ErrorType::Y),
+                (false, false) => {}
+            }
+        }
+        correction
+    }
+
+    /// the independent error channels of the noise model, together with the real measurement vertices they flip and the
+    /// positions they may need to correct; undetectable outcomes that don't change the logical class are ignored.
+    /// the syndrome bits are checked against `max_syndrome_bits` before building the 64-bit masks
+    fn error_channels(
+        simulator: &mut Simulator,
+        noise_model: &NoiseModel,
+        max_syndrome_bits: usize,
+    ) -> Result<ErrorChannels, String> {
+        simulator.clear_all_errors();
+        // the error patterns of every channel, each with its probability
+        let mut channel_errors = Vec::<Vec<(SparseErrorPattern, f64)>>::new();
+        simulator_iter!(simulator, position, {
+            let mut pauli_errors = vec![];
+            for error_type in ErrorType::all_possible_errors().drain(..) {
+                let probability = noise_model.effective_pauli_error_rate(position, &error_type);
+                if probability > 0. {
+                    let mut sparse_errors = SparseErrorPattern::new();
+                    sparse_errors.add(position.clone(), error_type);
+                    pauli_errors.push((sparse_errors, probability));
+                }
+            }
+            channel_errors.push(pauli_errors);
+            let noise_model_node = noise_model.get_node_unwrap(position);
+            if let Some(correlated_pauli_error_rates) = &noise_model_node.correlated_pauli_error_rates {
+                let mut correlated_errors = vec![];
+                for error_type in CorrelatedPauliErrorType::all_possible_errors().drain(..) {
+                    let probability = correlated_pauli_error_rates.error_rate(&error_type);
+                    if probability > 0. {
+                        let gate_peer = simulator
+                            .get_node_unwrap(position)
+                            .gate_peer
+                            .as_ref()
+                            .expect("correlated error must corresponds to a two-qubit gate");
+                        let mut sparse_errors = SparseErrorPattern::new();
+                        sparse_errors.add(position.clone(), error_type.my_error());
+                        sparse_errors.add((**gate_peer).clone(), error_type.peer_error());
+                        correlated_errors.push((sparse_errors, probability));
+                    }
+                }
+                channel_errors.push(correlated_errors);
+            }
+        });
+        // simulate every outcome, and collect the vertices in the order they appear
+        let mut vertex_positions = Vec::new();
+        let mut vertex_indices = HashMap::<Position, usize>::new();
+        let mut correction_positions = BTreeSet::new();
+        let mut channel_outcomes = Vec::<Vec<(Vec<Position>, (bool, bool), SparseCorrection, f64)>>::new();
+        for errors in channel_errors.into_iter().filter(|errors| !errors.is_empty()) {
+            let mut outcomes = vec![];
+            for (sparse_errors, probability) in errors.into_iter() {
+                let (sparse_correction, sparse_measurement, _) =
+                    simulator.fast_measurement_given_few_errors(&Arc::new(sparse_errors));
+                // the logical class of the error is that of its correction, which is additive
+                let logical_class = simulator.validate_correction(&sparse_correction);
+                let sparse_measurement = sparse_measurement.to_vec();
+                if sparse_measurement.is_empty() && logical_class == (false, false) {
+                    continue;
+                }
+                for position in sparse_measurement.iter() {
+                    if !vertex_indices.contains_key(position) {
+                        vertex_indices.insert(position.clone(), vertex_positions.len());
+                        vertex_positions.push(position.clone());
+                    }
+                }
+                correction_positions.extend(sparse_correction.iter().map(|(position, _)| position.clone()));
+                outcomes.push((sparse_measurement, logical_class, sparse_correction, probability));
+            }
+            channel_outcomes.push(outcomes);
+        }
+        let vertex_num = vertex_positions.len();
+        if vertex_num > max_syndrome_bits {
+            return Err(format!(
+                "{vertex_num} syndrome bits is too many for a lookup table, the limit is {max_syndrome_bits}"
+            ));
+        }
+        let correction_positions: Vec<Position> = correction_positions.into_iter().collect();
+        let correction_indices: HashMap<&Position, usize> = correction_positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| (position, index))
+            .collect();
+        let entry_words = (2 * correction_positions.len() + 63) / 64;
+        let mut error_channels = vec![];
+        for outcomes in channel_outcomes.into_iter().filter(|outcomes| !outcomes.is_empty()) {
+            // outcomes with the same syndrome and logical class are merged; they are exclusive, so the probabilities add up
+            let mut merged = BTreeMap::<u64, ErrorOutcome>::new();
+            for (sparse_measurement, (logical_i, logical_j), sparse_correction, probability) in outcomes.into_iter() {
+                let mut mask = (logical_i as u64) << vertex_num | (logical_j as u64) << (vertex_num + 1);
+                for position in sparse_measurement.iter() {
+                    mask |= 1 << vertex_indices[position];
+                }
+                let mut correction = vec![0; entry_words];
+                for (position, error) in sparse_correction.iter() {
+                    let index = correction_indices[position];
+                    if matches!(error, ErrorType::X | ErrorType::Y) {
+                        correction[2 * index / 64] ^= 1 << (2 * index % 64);
+                    }
+                    if matches!(error, ErrorType::Z | ErrorType::Y) {
+                        correction[(2 * index + 1) / 64] ^= 1 << ((2 * index + 1) % 64);
+                    }
+                }
+                merged
+                    .entry(mask)
+                    .and_modify(|outcome| outcome.probability += probability)
+                    .or_insert(ErrorOutcome {
+                        mask,
+                        probability,
+                        correction,
+                    });
+            }
+            error_channels.push(ErrorChannel {
+                outcomes: merged.into_values().collect(),
+            });
+        }
+        Ok((vertex_positions, correction_positions, error_channels))
+    }
+
+    /// FNV-1a hash of the error channels, stable across platforms and compiler versions
+    fn fingerprint(error_channels: &[ErrorChannel]) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for error_channel in error_channels.iter() {
+            // the number of outcomes separates the channels
+            let values = std::iter::once(error_channel.outcomes.len() as u64).chain(
+                error_channel
+                    .outcomes
+                    .iter()
+                    .flat_map(|outcome| [outcome.mask, outcome.probability.to_bits()]),
+            );
+            for value in values {
+                for byte in value.to_le_bytes() {
+                    hash ^= byte as u64;
+                    hash = hash.wrapping_mul(0x100000001b3);
+                }
+            }
+        }
+        hash
+    }
+
+    /// the joint distribution of the syndrome and the logical class, adding one independent error channel at a time
+    fn distribution(vertex_num: usize, error_channels: &[ErrorChannel]) -> Vec<f64> {
+        let mut distribution = vec![0.; 1 << (vertex_num + 2)];
+        distribution[0] = 1.;
+        for error_channel in error_channels.iter() {
+            let no_error: f64 = 1. - error_channel.outcomes.iter().map(|outcome| outcome.probability).sum::<f64>();
+            let mut next_distribution: Vec<f64> = distribution.iter().map(|value| no_error * value).collect();
+            for outcome in error_channel.outcomes.iter() {
+                let (mask, p) = (outcome.mask as usize, outcome.probability);
+                for (index, value) in distribution.iter().enumerate() {
+                    next_distribution[index ^ mask] += p * value;
+                }
+            }
+            distribution = next_distribution;
+        }
+        distribution
+    }
+
+    fn build(
+        vertex_positions: Vec<Position>,
+        correction_positions: Vec<Position>,
+        fingerprint: u64,
+        error_channels: &[ErrorChannel],
+    ) -> Self {
+        let vertex_num = vertex_positions.len();
+        let entry_words = (2 * correction_positions.len() + 63) / 64;
+        let distribution = Self::distribution(vertex_num, error_channels);
+        // a basis of the error outcomes over GF(2), indexed by the highest bit, to construct a correction of any reachable class
+        let mut basis: Vec<Option<(u64, Vec<u64>)>> = vec![None; vertex_num + 2];
+        for outcome in error_channels.iter().flat_map(|error_channel| error_channel.outcomes.iter()) {
+            let (mut mask, mut correction) = (outcome.mask, outcome.correction.clone());
+            while mask != 0 {
+                let pivot = 63 - mask.leading_zeros() as usize;
+                match &basis[pivot] {
+                    Some((basis_mask, basis_correction)) => {
+                        mask ^= basis_mask;
+                        correction.iter_mut().zip(basis_correction).for_each(|(a, b)| *a ^= b);
+                    }
+                    None => {
+                        basis[pivot] = Some((mask, correction));
+                        break;
+                    }
+                }
+            }
+        }
+        let mut entries = vec![0; (1 << vertex_num) * entry_words];
+        for syndrome_index in 0..(1usize << vertex_num) {
+            let mut most_likely_class = 0;
+            for logical_class in 1..4 {
+                if distribution[syndrome_index | logical_class << vertex_num]
+                    > distribution[syndrome_index | most_likely_class << vertex_num]
+                {
+                    most_likely_class = logical_class;
+                }
+            }
+            if distribution[syndrome_index | most_likely_class << vertex_num] == 0. {
+                continue; // impossible syndrome
+            }
+            let entry = &mut entries[syndrome_index * entry_words..(syndrome_index + 1) * entry_words];
+            let mut mask = (syndrome_index | most_likely_class << vertex_num) as u64;
+            while mask != 0 {
+                let pivot = 63 - mask.leading_zeros() as usize;
+                let (basis_mask, basis_correction) = basis[pivot].as_ref().expect("a possible syndrome must be reachable");
+                mask ^= basis_mask;
+                entry.iter_mut().zip(basis_correction).for_each(|(a, b)| *a ^= b);
+            }
+        }
+        Self {
+            vertex_indices: Self::vertex_indices(&vertex_positions),
+            vertex_positions,
+            correction_positions,
+            fingerprint,
+            entry_words,
+            entries,
+        }
+    }
+
+    pub fn save(&self, filename: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        writer.write_all(LOOKUP_TABLE_MAGIC)?;
+        writer.write_all(&self.fingerprint.to_le_bytes())?;
+        for positions in [&self.vertex_positions, &self.correction_positions] {
+            writer.write_all(&(positions.len() as u32).to_le_bytes())?;
+            for position in positions.iter() {
+                for value in [position.t, position.i, position.j] {
+                    writer.write_all(&(value as u32).to_le_bytes())?;
+                }
+            }
+        }
+        for word in self.entries.iter() {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    pub fn load(filename: &str) -> std::io::Result<Self> {
+        let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string());
+        let mut reader = BufReader::new(File::open(filename)?);
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != LOOKUP_TABLE_MAGIC {
+            return Err(invalid("not a lookup table file"));
+        }
+        let read_u32 = |reader: &mut BufReader<File>| -> std::io::Result<u32> {
+            let mut bytes = [0; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        };
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        let fingerprint = u64::from_le_bytes(bytes);
+        let read_positions = |reader: &mut BufReader<File>| -> std::io::Result<Vec<Position>> {
+            let length = read_u32(reader)? as usize;
+            (0..length)
+                .map(|_| {
+                    let (t, i, j) = (read_u32(reader)?, read_u32(reader)?, read_u32(reader)?);
+                    Ok(pos!(t as usize, i as usize, j as usize))
+                })
+                .collect()
+        };
+        let vertex_positions = read_positions(&mut reader)?;
+        let correction_positions = read_positions(&mut reader)?;
+        if vertex_positions.len() > LOOKUP_TABLE_MAX_SYNDROME_BITS {
+            return Err(invalid("too many syndrome bits"));
+        }
+        let entry_words = (2 * correction_positions.len() + 63) / 64;
+        let mut entries = vec![0; (1 << vertex_positions.len()) * entry_words];
+        for word in entries.iter_mut() {
+            reader.read_exact(&mut bytes)?;
+            *word = u64::from_le_bytes(bytes);
+        }
+        Ok(Self {
+            vertex_indices: Self::vertex_indices(&vertex_positions),
+            vertex_positions,
+            correction_positions,
+            fingerprint,
+            entry_words,
+            entries,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::code_builder::*;
+    use super::super::noise_model_builder::*;
+    use super::super::types::ErrorType::*;
+    use super::*;
+
+    #[test]
+    fn lookup_table_decoder_code_capacity() {
+        // cargo test lookup_table_decoder_code_capacity -- --nocapture
+        let d = 3;
+        let noisy_measurements = 0; // perfect measurement
+        let p = 0.01;
+        // build simulator
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        // build noise model
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let table_filename = std::env::temp_dir().join("qecp_lookup_table_decoder_code_capacity.bin");
+        let table_filename = table_filename.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&table_filename);
+        let decoder_config = json!({ "table_filename": table_filename });
+        let lookup_table_decoder = LookupTableDecoder::new(&simulator, Arc::clone(&noise_model), &decoder_config).unwrap();
+        assert!(lookup_table_decoder.table_rebuilt.is_none());
+        // every single-qubit error is corrected
+        simulator_iter!(simulator, position, node, t => 0, {
+            if node.qubit_type == QubitType::Data {
+                for error in [X, Y, Z] {
+                    simulator.clear_all_errors();
+                    simulator.set_error_check(&noise_model, position, &error);
+                    simulator.propagate_errors();
+                    let sparse_measurement = simulator.generate_sparse_measurement();
+                    let (correction, _) = lookup_table_decoder.decode(&sparse_measurement);
+                    code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+                    let (logical_i, logical_j) = simulator.validate_correction(&correction);
+                    assert!(!logical_i && !logical_j);
+                }
+            }
+        });
+        // the persisted table is loaded
+        let loaded = LookupTable::load(&table_filename).unwrap();
+        assert_eq!(&loaded, lookup_table_decoder.table.as_ref());
+        let reloaded_decoder = LookupTableDecoder::new(&simulator, Arc::clone(&noise_model), &decoder_config).unwrap();
+        assert!(reloaded_decoder.table_rebuilt.is_none());
+        // a different noise model rebuilds the table, which is reported in the runtime statistics
+        let mut other_noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut other_noise_model, p / 2., 0., p / 2., 0.);
+        simulator.compress_error_rates(&mut other_noise_model);
+        let rebuilt_decoder = LookupTableDecoder::new(&simulator, Arc::new(other_noise_model), &decoder_config).unwrap();
+        assert!(rebuilt_decoder.table_rebuilt.is_some());
+        let (_, runtime_statistics) = rebuilt_decoder.decode(&SparseMeasurement::new());
+        assert!(runtime_statistics["table_rebuilt"].is_string());
+        let _ = std::fs::remove_file(&table_filename);
+        // the syndrome bits must fit in the 64-bit masks
+        assert!(LookupTableDecoder::new(&simulator, noise_model, &json!({ "max_syndrome_bits": 62 })).is_err());
+    }
+
+    #[test]
+    fn lookup_table_decoder_circuit_level() {
+        // cargo test lookup_table_decoder_circuit_level -- --nocapture
+        let d = 3;
+        let noisy_measurements = 1;
+        let p = 0.005;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mut noise_model = NoiseModel::new(&simulator);
+        NoiseModelBuilder::StimNoiseModel.apply(&mut simulator, &mut noise_model, &json!({}), p, 0.5, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let lookup_table_decoder = LookupTableDecoder::new(&simulator, Arc::clone(&noise_model), &json!({})).unwrap();
+        for _ in 0..100 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, _) = lookup_table_decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        }
+    }
+
+    #[test]
+    fn lookup_table_decoder_too_many_syndrome_bits() {
+        // cargo test lookup_table_decoder_too_many_syndrome_bits -- --nocapture
+        let d = 5;
+        let noisy_measurements = 5;
+        let p = 0.005;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mut noise_model = NoiseModel::new(&simulator);
+        NoiseModelBuilder::StimNoiseModel.apply(&mut simulator, &mut noise_model, &json!({}), p, 0.5, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        // far more syndrome bits than a 64-bit mask holds, which must be rejected before building the masks
+        let result = LookupTableDecoder::new(
+            &simulator,
+            Arc::new(noise_model),
+            &json!({ "max_syndrome_bits": LOOKUP_TABLE_MAX_SYNDROME_BITS }),
+        );
+        assert!(result.err().unwrap().contains("syndrome bits is too many"));
+    }
+
+    #[test]
+    fn lookup_table_decoder_exclusive_outcomes() {
+        // cargo test lookup_table_decoder_exclusive_outcomes -- --nocapture
+        let d = 3;
+        let noisy_measurements = 0; // perfect measurement
+        let p = 0.3;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        // the X, Y and Z errors of each data qubit form a single channel
+        let (vertex_positions, _, error_channels) =
+            LookupTable::error_channels(&mut simulator, &noise_model, LOOKUP_TABLE_MAX_SYNDROME_BITS).unwrap();
+        assert_eq!(error_channels.len(), d * d);
+        for error_channel in error_channels.iter() {
+            assert_eq!(error_channel.outcomes.len(), 3);
+        }
+        // with a single qubit, no error happens with probability 1 - p, and the combined syndrome of two outcomes is only reached
+        // by the third one, e.g. a Y error rather than both an X error and a Z error
+        let outcomes = &error_channels[0].outcomes;
+        let (mask_a, mask_b) = (outcomes[0].mask as usize, outcomes[1].mask as usize);
+        let distribution = LookupTable::distribution(vertex_positions.len(), &error_channels[..1]);
+        assert!((distribution[0] - (1. - p)).abs() < 1e-12);
+        assert!((distribution[mask_a ^ mask_b] - p / 3.).abs() < 1e-12);
+        assert!((distribution.iter().sum::<f64>() - 1.).abs() < 1e-12);
+    }
+}
@@ -2,13 +2,18 @@
 //!
 
 use super::blossom_v;
+use super::complementary_gap::*;
 use super::complete_model_graph::*;
+use super::decoder_bp_osd::*;
 use super::erasure_graph::*;
 use super::model_graph::*;
+use super::model_hypergraph::*;
 use super::noise_model::*;
 use super::serde_json;
 use super::simulator::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -25,6 +30,13 @@ pub struct MWPMDecoder {
     pub config: MWPMDecoderConfig,
     /// an immutably shared simulator that is used to change model graph on the fly for correcting erasure errors
     pub simulator: Arc<Simulator>,
+    /// belief propagation that reweights the model graph in every shot, if enabled
+    pub belief_matching: Option<BeliefMatching<ErasureEdge>>,
+    /// the correlated partner edges of each model graph edge, if two-pass correlated matching is enabled
+    #[serde(skip)]
+    pub correlated_matching: Option<Arc<CorrelatedMatching<DefectVertices>>>,
+    /// the decoders on the complementary graph of each logical observable, if the complementary gap is enabled
+    pub complementary_decoders: Vec<(Arc<ComplementaryGraph>, MWPMDecoder)>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +57,21 @@ pub struct MWPMDecoderConfig {
     pub use_combined_probability: bool,
     #[serde(default = "mwpm_default_configs::log_matchings")]
     pub log_matchings: bool,
+    /// reweight the model graph in every shot using the posterior of belief propagation on the model hypergraph,
+    /// i.e. belief-matching
+    #[serde(alias = "bp")] // abbreviation
+    #[serde(default = "mwpm_default_configs::belief_propagation")]
+    pub belief_propagation: Option<BeliefPropagationConfig>,
+    /// two-pass correlated matching: the edges chosen in the first pass reduce the weights of their correlated partner
+    /// edges given by the model hypergraph, e.g. the Z edge of a Y error when its X edge is chosen, before matching again
+    #[serde(alias = "cm")] // abbreviation
+    #[serde(default = "mwpm_default_configs::correlated_matching")]
+    pub correlated_matching: bool,
+    /// report the complementary gap in the runtime statistics as the confidence of every shot, see [`ComplementaryGraph`];
+    /// it decodes the syndrome twice more on the complementary graph of each logical observable
+    #[serde(alias = "cg")] // abbreviation
+    #[serde(default = "mwpm_default_configs::complementary_gap")]
+    pub complementary_gap: bool,
 }
 
 pub mod mwpm_default_configs {
@@ -61,6 +88,87 @@ pub mod mwpm_default_configs {
     pub fn log_matchings() -> bool {
         false
     }
+    pub fn belief_propagation() -> Option<BeliefPropagationConfig> {
+        None
+    }
+    pub fn correlated_matching() -> bool {
+        false
+    }
+    pub fn complementary_gap() -> bool {
+        false
+    }
+}
+
+/// the correlated partner edges of the matching graph derived from the hyperedges that span both the X and Z graphs,
+/// used by two-pass correlated matching; `T` is the edge identifier of the matching decoder
+#[derive(Debug, Clone)]
+pub struct CorrelatedMatching<T> {
+    /// the partner edges of each edge, with the weight given that the edge is chosen
+    pub partners: HashMap<T, Vec<(T, f64)>>,
+}
+
+impl<T: Clone + Hash + Eq> CorrelatedMatching<T> {
+    /// `graph_edge_of` returns the edge and its probability in the matching graph given the real defect vertices, see
+    /// [`HyperedgeDecomposition::new`]
+    pub fn new<F>(
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        weight_function: &WeightFunction,
+        parallel: usize,
+        use_combined_probability: bool,
+        use_brief_edge: bool,
+        graph_edge_of: F,
+    ) -> Self
+    where
+        F: Fn(&[Position]) -> Option<(T, f64)>,
+    {
+        let mut simulator = simulator.clone();
+        let mut model_hypergraph = ModelHypergraph::new(&simulator);
+        model_hypergraph.build(
+            &mut simulator,
+            noise_model,
+            weight_function,
+            parallel,
+            use_combined_probability,
+            use_brief_edge,
+        );
+        let decomposition = HyperedgeDecomposition::new(&model_hypergraph, &simulator, graph_edge_of);
+        let mut partners = HashMap::<T, Vec<(T, f64)>>::new();
+        for (hyperedge_index, parts) in decomposition.hyperedge_parts.iter().enumerate() {
+            let probability = model_hypergraph.weighted_edges[hyperedge_index].1.hyperedge.probability;
+            if parts.len() < 2 || probability <= 0. {
+                continue; // not spanning both graphs, or only caused by erasures
+            }
+            for &part in parts.iter() {
+                let (edge, edge_probability) = &decomposition.graph_edges[part].0;
+                if *edge_probability <= 0. {
+                    continue;
+                }
+                // the probability of the hyperedge conditioned on this edge being chosen
+                let weight = weight_function.weight_of((probability / edge_probability).min(0.5));
+                for &partner in parts.iter().filter(|&&partner| partner != part) {
+                    let (partner_edge, _) = &decomposition.graph_edges[partner].0;
+                    partners.entry(edge.clone()).or_default().push((partner_edge.clone(), weight));
+                }
+            }
+        }
+        Self { partners }
+    }
+
+    /// the reduced weights of the partner edges given the edges chosen by the first matching pass
+    pub fn partner_weights<'a>(&self, chosen_edges: impl IntoIterator<Item = &'a T>) -> HashMap<T, f64>
+    where
+        T: 'a,
+    {
+        let mut partner_weights = HashMap::<T, f64>::new();
+        for edge in chosen_edges.into_iter() {
+            for (partner_edge, weight) in self.partners.get(edge).into_iter().flatten() {
+                let partner_weight = partner_weights.entry(partner_edge.clone()).or_insert(*weight);
+                *partner_weight = partner_weight.min(*weight);
+            }
+        }
+        partner_weights
+    }
 }
 
 impl MWPMDecoder {
@@ -93,12 +201,99 @@ impl MWPMDecoder {
         // build complete model graph
         let mut complete_model_graph = CompleteModelGraph::new(&simulator, Arc::clone(&model_graph));
         complete_model_graph.precompute(&simulator, config.precompute_complete_model_graph, parallel);
+        let belief_matching = config.belief_propagation.as_ref().map(|bp_config| {
+            assert!(
+                !config.precompute_complete_model_graph,
+                "belief-matching changes the model graph in every shot; please disable `precompute_complete_model_graph`"
+            );
+            BeliefMatching::new(
+                &simulator,
+                Arc::clone(&noise_model),
+                bp_config,
+                parallel,
+                config.use_combined_probability,
+                use_brief_edge,
+                |positions| match positions {
+                    [position] => model_graph
+                        .get_node_unwrap(position)
+                        .boundary
+                        .as_ref()
+                        .map(|_| ErasureEdge::Boundary(position.clone())),
+                    [position1, position2] => model_graph
+                        .get_node_unwrap(position1)
+                        .edges
+                        .contains_key(position2)
+                        .then(|| ErasureEdge::Connection(position1.clone(), position2.clone())),
+                    _ => None,
+                },
+            )
+        });
+        let correlated_matching = config.correlated_matching.then(|| {
+            assert!(
+                !config.precompute_complete_model_graph,
+                "correlated matching changes the model graph in every shot; please disable `precompute_complete_model_graph`"
+            );
+            Arc::new(CorrelatedMatching::new(
+                &simulator,
+                Arc::clone(&noise_model),
+                &config.weight_function,
+                parallel,
+                config.use_combined_probability,
+                use_brief_edge,
+                |positions| {
+                    let probability = match positions {
+                        [position] => model_graph.get_node_unwrap(position).boundary.as_ref()?.probability,
+                        [position1, position2] => model_graph.get_node_unwrap(position1).edges.get(position2)?.probability,
+                        _ => return None,
+                    };
+                    Some((DefectVertices::new(positions.to_vec()), probability))
+                },
+            ))
+        });
+        let complementary_decoders = if config.complementary_gap {
+            ComplementaryGraph::build(&simulator, &model_graph)
+                .into_iter()
+                .map(|complementary_graph| {
+                    let decoder = Self::new_complementary(&complementary_graph, &config, parallel);
+                    (Arc::new(complementary_graph), decoder)
+                })
+                .collect()
+        } else {
+            vec![]
+        };
         Self {
             model_graph,
             erasure_graph,
             complete_model_graph,
             config,
             simulator: Arc::new(simulator),
+            belief_matching,
+            correlated_matching,
+            complementary_decoders,
+        }
+    }
+
+    /// a plain MWPM decoder on the complementary graph, which only decodes syndromes without erasures
+    fn new_complementary(complementary_graph: &ComplementaryGraph, config: &MWPMDecoderConfig, parallel: usize) -> Self {
+        let simulator = Arc::clone(&complementary_graph.simulator);
+        let model_graph = Arc::clone(&complementary_graph.model_graph);
+        let mut complete_model_graph = CompleteModelGraph::new(&simulator, Arc::clone(&model_graph));
+        complete_model_graph.precompute(&simulator, config.precompute_complete_model_graph, parallel);
+        Self {
+            model_graph,
+            erasure_graph: Arc::new(ErasureGraph::new(&simulator)),
+            complete_model_graph,
+            config: MWPMDecoderConfig {
+                log_matchings: false,
+                belief_propagation: None,
+                correlated_matching: false,
+                complementary_gap: false,
+                ..config.clone()
+            },
+            simulator,
+            belief_matching: None,
+            correlated_matching: None,
+            complementary_decoders: vec![],
         }
     }
 
@@ -114,8 +309,59 @@ impl MWPMDecoder {
         sparse_measurement: &SparseMeasurement,
         sparse_detected_erasures: &SparseErasures,
     ) -> (SparseCorrection, serde_json::Value) {
-        if !sparse_detected_erasures.is_empty() {
-            assert!(!self.config.precompute_complete_model_graph, "if erasure happens, the precomputed complete graph is invalid; please disable `precompute_complete_model_graph` or `pcmg` in the decoder configuration");
+        self.decode_with_soft_readouts(sparse_measurement, sparse_detected_erasures, &SparseSoftReadouts::new())
+    }
+
+    /// decode given measurement results, detected erasures and analog readouts that reweight the measurement edges
+    pub fn decode_with_soft_readouts(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+        sparse_soft_readouts: &SparseSoftReadouts,
+    ) -> (SparseCorrection, serde_json::Value) {
+        let (correction, mut runtime_statistics) =
+            self.decode_internal(sparse_measurement, sparse_detected_erasures, sparse_soft_readouts, None);
+        if self.config.complementary_gap {
+            assert!(
+                sparse_detected_erasures.is_empty() && sparse_soft_readouts.is_empty(),
+                "complementary gap doesn't support erasures or soft readouts yet"
+            );
+            record_complementary_gaps(
+                &mut self.complementary_decoders,
+                sparse_measurement,
+                &mut runtime_statistics,
+                |decoder, sparse_measurement| decoder.decode_subgraph(sparse_measurement, &SparseErasures::new()).0,
+            );
+        }
+        (correction, runtime_statistics)
+    }
+
+    /// decode given measurement results and detected erasures, and return the chosen model graph edges
+    pub fn decode_subgraph(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseSubgraph, serde_json::Value) {
+        let mut subgraph = SparseSubgraph::new();
+        let (_, runtime_statistics) = self.decode_internal(
+            sparse_measurement,
+            sparse_detected_erasures,
+            &SparseSoftReadouts::new(),
+            Some(&mut subgraph),
+        );
+        (subgraph, runtime_statistics)
+    }
+
+    /// the correction is left empty if the subgraph is requested
+    fn decode_internal(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+        sparse_soft_readouts: &SparseSoftReadouts,
+        mut subgraph: Option<&mut SparseSubgraph>,
+    ) -> (SparseCorrection, serde_json::Value) {
+        if !sparse_detected_erasures.is_empty() || !sparse_soft_readouts.is_empty() {
+            assert!(!self.config.precompute_complete_model_graph, "if erasure happens or soft readouts are given, the precomputed complete graph is invalid; please disable `precompute_complete_model_graph` or `pcmg` in the decoder configuration");
         }
         let mut correction = SparseCorrection::new();
         // list nontrivial measurements to be matched
@@ -130,26 +376,58 @@ impl MWPMDecoder {
             // add the edges to the graph
             let m_len = to_be_matched.len(); // virtual boundary of `i` is `i + m_len`
             let node_num = m_len * 2;
-            // Z (X) stabilizers are (fully) connected, boundaries are fully connected
-            // stabilizer to boundary is one-to-one connected
-            let mut weighted_edges = Vec::<(usize, usize, f64)>::new();
             // update model graph weights to consider erasure information
             let mut erasure_graph_modifier = ErasureGraphModifier::<f64>::new();
+            if let Some(belief_matching) = self.belief_matching.as_mut() {
+                let edge_probabilities = belief_matching.edge_probabilities(sparse_measurement, sparse_detected_erasures);
+                let model_graph_mut = self.complete_model_graph.get_model_graph_mut();
+                for ((erasure_edge, _), probability) in
+                    belief_matching.decomposition.graph_edges.iter().zip(edge_probabilities)
+                {
+                    let weight = self.config.weight_function.weight_of(probability);
+                    let original_weight = Self::set_model_graph_weight(model_graph_mut, erasure_edge, |_| weight);
+                    erasure_graph_modifier.push_modified_edge(erasure_edge.clone(), original_weight);
+                }
+            }
+            if !sparse_soft_readouts.is_empty() {
+                let model_graph_mut = self.complete_model_graph.get_model_graph_mut();
+                for (position, soft_readout) in sparse_soft_readouts.iter() {
+                    let next_position = &soft_readout.next_measurement;
+                    if !model_graph_mut.is_node_exist(position) {
+                        continue; // e.g. the stabilizer is not decoded
+                    }
+                    let node1 = model_graph_mut.get_node_mut_unwrap(position);
+                    let Some(edge12) = node1.edges.get_mut(next_position) else {
+                        continue;
+                    };
+                    let original_weight = edge12.weight;
+                    let weight = soft_readout.edge_weight(original_weight, edge12.probability);
+                    edge12.weight = weight;
+                    let node2 = model_graph_mut.get_node_mut_unwrap(next_position);
+                    node2.edges.get_mut(position).expect("neighbor must exist").weight = weight;
+                    erasure_graph_modifier.push_modified_edge(
+                        ErasureEdge::Connection(position.clone(), next_position.clone()),
+                        original_weight,
+                    );
+                }
+            }
             if !sparse_detected_erasures.is_empty() {
                 // if erasure exists, the model graph will be duplicated on demand
-                let erasure_edges = sparse_detected_erasures.get_erasure_edges(&self.erasure_graph);
+                let erasure_edges = sparse_detected_erasures.get_weighted_erasure_edges(&self.erasure_graph);
                 let model_graph_mut = self.complete_model_graph.get_model_graph_mut();
-                for erasure_edge in erasure_edges.iter() {
+                for (erasure_edge, probability) in erasure_edges.iter() {
+                    // a perfectly heralded, fully depolarizing erasure sets the weight to 0
+                    let erasure_weight = erasure_edge_weight(*probability);
                     match erasure_edge {
                         ErasureEdge::Connection(position1, position2) => {
                             let node1 = model_graph_mut.get_node_mut_unwrap(position1);
                             let edge12 = node1.edges.get_mut(position2).expect("neighbor must exist");
                             let original_weight12 = edge12.weight;
-                            edge12.weight = 0.; // set to 0 because of erasure
+                            edge12.weight = erasure_weight.min(original_weight12);
                             let node2 = model_graph_mut.get_node_mut_unwrap(position2);
                             let edge21 = node2.edges.get_mut(position1).expect("neighbor must exist");
                             assert_eq!(original_weight12, edge21.weight, "model graph edge must be symmetric");
-                            edge21.weight = 0.; // set to 0 because of erasure
+                            edge21.weight = erasure_weight.min(original_weight12);
                             erasure_graph_modifier.push_modified_edge(
                                 ErasureEdge::Connection(position1.clone(), position2.clone()),
                                 original_weight12,
@@ -159,40 +437,64 @@ impl MWPMDecoder {
                             let node = model_graph_mut.get_node_mut_unwrap(position);
                             let boundary = node.boundary.as_mut().expect("boundary must exist").as_mut();
                             let original_weight = boundary.weight;
-                            boundary.weight = 0.;
+                            boundary.weight = erasure_weight.min(original_weight);
                             erasure_graph_modifier
                                 .push_modified_edge(ErasureEdge::Boundary(position.clone()), original_weight);
                         }
                     }
                 }
-                self.complete_model_graph.model_graph_changed(&self.simulator);
             }
-            // invalidate previous cache to save memory
-            self.complete_model_graph.invalidate_previous_dijkstra();
-            for i in 0..m_len {
-                let position = &to_be_matched[i];
-                let (edges, boundary) = self.complete_model_graph.get_edges(position, &to_be_matched);
-                if let Some(weight) = boundary {
-                    // eprintln!{"boundary {} {} ", i, weight};
-                    weighted_edges.push((i, i + m_len, weight));
-                }
-                for &(j, weight) in edges.iter() {
-                    if i < j {
-                        // remove duplicated edges
-                        // eprintln!{"edge {} {} {} ", i, j, weight};
-                        weighted_edges.push((i, j, weight));
-                    }
-                }
-                for j in (i + 1)..m_len {
-                    // virtual boundaries are always fully connected
-                    weighted_edges.push((i + m_len, j + m_len, 0.));
-                }
+            if erasure_graph_modifier.has_modified_edges() {
+                self.complete_model_graph.model_graph_changed(&self.simulator);
             }
+            let weighted_edges = self.prepare_matching_graph(&to_be_matched);
             time_prepare_graph += begin.elapsed().as_secs_f64();
             // run the Blossom algorithm
             let begin = Instant::now();
-            let matching = blossom_v::safe_minimum_weight_perfect_matching(node_num, weighted_edges);
+            let mut matching = blossom_v::safe_minimum_weight_perfect_matching(node_num, weighted_edges);
             time_blossom_v += begin.elapsed().as_secs_f64();
+            if let Some(correlated_matching) = self.correlated_matching.clone() {
+                // reduce the weights of the partner edges of the chosen edges, and then match again
+                let begin = Instant::now();
+                let mut chosen_edges = vec![];
+                for i in 0..m_len {
+                    let j = matching[i];
+                    let path = if j < i {
+                        self.complete_model_graph
+                            .build_path_matching(&to_be_matched[i], &to_be_matched[j])
+                    } else if j >= m_len {
+                        let (path, boundary_position) = self.complete_model_graph.build_path_boundary(&to_be_matched[i]);
+                        chosen_edges.push(DefectVertices::new(vec![boundary_position]));
+                        path
+                    } else {
+                        continue;
+                    };
+                    for (position1, position2) in path.into_iter() {
+                        chosen_edges.push(DefectVertices::new(vec![position1, position2]));
+                    }
+                }
+                let partner_weights = correlated_matching.partner_weights(chosen_edges.iter());
+                let model_graph_mut = self.complete_model_graph.get_model_graph_mut();
+                for (defect_vertices, weight) in partner_weights.iter() {
+                    let erasure_edge = match defect_vertices.0.as_slice() {
+                        [position] => ErasureEdge::Boundary(position.clone()),
+                        [position1, position2] => ErasureEdge::Connection(position1.clone(), position2.clone()),
+                        _ => unreachable!("partner edges are edges of the model graph"),
+                    };
+                    let original_weight = Self::set_model_graph_weight(model_graph_mut, &erasure_edge, |original_weight| {
+                        original_weight.min(*weight)
+                    });
+                    erasure_graph_modifier.push_modified_edge(erasure_edge, original_weight);
+                }
+                if !partner_weights.is_empty() {
+                    self.complete_model_graph.model_graph_changed(&self.simulator);
+                    let weighted_edges = self.prepare_matching_graph(&to_be_matched);
+                    time_prepare_graph += begin.elapsed().as_secs_f64();
+                    let begin = Instant::now();
+                    matching = blossom_v::safe_minimum_weight_perfect_matching(node_num, weighted_edges);
+                    time_blossom_v += begin.elapsed().as_secs_f64();
+                }
+            }
             // build correction based on the matching
             let begin = Instant::now();
             for i in 0..m_len {
@@ -202,13 +504,21 @@ impl MWPMDecoder {
                     // only add correction if j < i, so that the same correction is not applied twice
                     // println!("match peer {:?} {:?}", to_be_matched[i], to_be_matched[j]);
                     let b = &to_be_matched[j];
-                    let matching_correction = self.complete_model_graph.build_correction_matching(a, b);
-                    correction.extend(&matching_correction);
+                    if let Some(subgraph) = subgraph.as_mut() {
+                        self.complete_model_graph.build_subgraph_matching(a, b, subgraph);
+                    } else {
+                        let matching_correction = self.complete_model_graph.build_correction_matching(a, b);
+                        correction.extend(&matching_correction);
+                    }
                 } else if j >= m_len {
                     // matched with boundary
                     // println!("match boundary {:?}", to_be_matched[i]);
-                    let boundary_correction = self.complete_model_graph.build_correction_boundary(a);
-                    correction.extend(&boundary_correction);
+                    if let Some(subgraph) = subgraph.as_mut() {
+                        self.complete_model_graph.build_subgraph_boundary(a, subgraph);
+                    } else {
+                        let boundary_correction = self.complete_model_graph.build_correction_boundary(a);
+                        correction.extend(&boundary_correction);
+                    }
                 }
                 if self.config.log_matchings {
                     let peer_position = if j < i {
@@ -236,7 +546,7 @@ impl MWPMDecoder {
             }
             time_build_correction += begin.elapsed().as_secs_f64();
             // recover the modified edges
-            if !sparse_detected_erasures.is_empty() {
+            if erasure_graph_modifier.has_modified_edges() {
                 let model_graph_mut = self.complete_model_graph.get_model_graph_mut();
                 while erasure_graph_modifier.has_modified_edges() {
                     let (erasure_edge, weight) = erasure_graph_modifier.pop_modified_edge();
@@ -244,22 +554,19 @@ impl MWPMDecoder {
                         ErasureEdge::Connection(position1, position2) => {
                             let node1 = model_graph_mut.get_node_mut_unwrap(&position1);
                             let edge12 = node1.edges.get_mut(&position2).expect("neighbor must exist");
-                            assert_eq!(edge12.weight, 0., "why a non-zero edge needs to be recovered");
                             edge12.weight = weight; // recover the weight
                             let node2 = model_graph_mut.get_node_mut_unwrap(&position2);
                             let edge21 = node2.edges.get_mut(&position1).expect("neighbor must exist");
-                            assert_eq!(edge21.weight, 0., "why a non-zero edge needs to be recovered");
                             edge21.weight = weight; // recover the weight
                         }
                         ErasureEdge::Boundary(position) => {
                             let node = model_graph_mut.get_node_mut_unwrap(&position);
                             let boundary = node.boundary.as_mut().expect("boundary must exist").as_mut();
-                            assert_eq!(boundary.weight, 0., "why a non-zero edge needs to be recovered");
                             boundary.weight = weight;
                         }
                     }
                 }
-                // need to call here because if next round there are no erasure errors, the complete mode graph must still be in a consistent state
+                // need to call here because if next round there are no erasure errors or soft readouts, the complete mode graph must still be in a consistent state
                 self.complete_model_graph.model_graph_changed(&self.simulator);
             }
         }
@@ -282,13 +589,69 @@ impl MWPMDecoder {
         }
         (correction, runtime_statistics)
     }
+
+    /// build the complete graph of the defects to be matched, where the virtual boundary of `i` is `i + m_len`
+    fn prepare_matching_graph(&mut self, to_be_matched: &[Position]) -> Vec<(usize, usize, f64)> {
+        let m_len = to_be_matched.len();
+        // Z (X) stabilizers are (fully) connected, boundaries are fully connected
+        // stabilizer to boundary is one-to-one connected
+        let mut weighted_edges = Vec::<(usize, usize, f64)>::new();
+        // invalidate previous cache to save memory
+        self.complete_model_graph.invalidate_previous_dijkstra();
+        for i in 0..m_len {
+            let position = &to_be_matched[i];
+            let (edges, boundary) = self.complete_model_graph.get_edges(position, to_be_matched);
+            if let Some(weight) = boundary {
+                // eprintln!{"boundary {} {} ", i, weight};
+                weighted_edges.push((i, i + m_len, weight));
+            }
+            for &(j, weight) in edges.iter() {
+                if i < j {
+                    // remove duplicated edges
+                    // eprintln!{"edge {} {} {} ", i, j, weight};
+                    weighted_edges.push((i, j, weight));
+                }
+            }
+            for j in (i + 1)..m_len {
+                // virtual boundaries are always fully connected
+                weighted_edges.push((i + m_len, j + m_len, 0.));
+            }
+        }
+        weighted_edges
+    }
+
+    /// set the weight of a model graph edge given its original weight, returning the original weight
+    fn set_model_graph_weight<F>(model_graph: &mut ModelGraph, erasure_edge: &ErasureEdge, weight_of: F) -> f64
+    where
+        F: FnOnce(f64) -> f64,
+    {
+        match erasure_edge {
+            ErasureEdge::Connection(position1, position2) => {
+                let edge12 = model_graph.get_node_mut_unwrap(position1).edges.get_mut(position2).unwrap();
+                let original_weight = edge12.weight;
+                edge12.weight = weight_of(original_weight);
+                let weight = edge12.weight;
+                let edge21 = model_graph.get_node_mut_unwrap(position2).edges.get_mut(position1).unwrap();
+                edge21.weight = weight;
+                original_weight
+            }
+            ErasureEdge::Boundary(position) => {
+                let node = model_graph.get_node_mut_unwrap(position);
+                let boundary = node.boundary.as_mut().expect("boundary must exist").as_mut();
+                let original_weight = boundary.weight;
+                boundary.weight = weight_of(original_weight);
+                original_weight
+            }
+        }
+    }
 }
 
-#[cfg(feature = "blossom_v")]
 #[cfg(test)]
 mod tests {
     use super::super::code_builder::*;
     use super::super::noise_model_builder::*;
+    use super::super::types::ErrorType::*;
+    use super::super::types::*;
     use super::*;
 
     // 2022.6.16: mwpm decoder should correct this pattern because UF decoder does
@@ -350,4 +713,166 @@ mod tests {
         let (logical_i, logical_j) = simulator.validate_correction(&correction);
         assert!(!logical_i && !logical_j);
     }
+
+    #[test]
+    fn mwpm_decoder_missed_erasure_weights() {
+        // cargo test mwpm_decoder_missed_erasure_weights -- --nocapture
+        let d = 3;
+        let noisy_measurements = 0; // perfect measurement
+                                    // a defect at [6][4][1] is explained either by [0][5][1] or by the longer chain [0][3][1], [0][1][1]
+        let (missed_1, missed_2) = (pos!(0, 3, 1), pos!(0, 1, 1));
+        for (false_negative_rate, expect_logical_error) in [(0., true), (0.9, false)] {
+            let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+            let mut noise_model = NoiseModel::new(&simulator);
+            simulator.set_error_rates(&mut noise_model, 0., 0., 0.01, 0.);
+            // the chain goes through qubits that often suffer from erasures, which are missed by an imperfect herald
+            let mut erasure_node = noise_model.get_node_unwrap(&missed_1).clone();
+            erasure_node.erasure_error_rate = 0.5;
+            erasure_node.erasure_model = Some(ErasureModel::new(0., false_negative_rate).with_biased_conversion(1.));
+            let erasure_node = Arc::new(erasure_node);
+            noise_model.set_node(&missed_1, Some(erasure_node.clone()));
+            noise_model.set_node(&missed_2, Some(erasure_node));
+            simulator.compress_error_rates(&mut noise_model);
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            let noise_model = Arc::new(noise_model);
+            let mut mwpm_decoder =
+                MWPMDecoder::new(&Arc::new(simulator.clone()), Arc::clone(&noise_model), &json!({}), 1, false);
+            // two missed erasures, no herald at all
+            simulator.set_error_check(&noise_model, &missed_1, &Z);
+            simulator.set_error_check(&noise_model, &missed_2, &Z);
+            simulator.propagate_errors();
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            assert_eq!(sparse_measurement.to_vec(), vec![pos!(6, 4, 1)]);
+            let (correction, _runtime_statistics) =
+                mwpm_decoder.decode_with_erasure(&sparse_measurement, &SparseErasures::new());
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert_eq!(logical_i || logical_j, expect_logical_error, "pfn = {false_negative_rate}");
+        }
+    }
+
+    #[test]
+    fn mwpm_decoder_soft_readout() {
+        // cargo test mwpm_decoder_soft_readout -- --nocapture
+        let d = 3;
+        let noisy_measurements = 1;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        // Z errors on data qubits and accurate readouts, so that a measurement error is less likely than a data error
+        let mut noise_model = NoiseModel::new(&simulator);
+        let mut data_node = NoiseModelNode::new();
+        data_node.pauli_error_rates.error_rate_Z = 0.05;
+        let data_node = Arc::new(data_node);
+        let readout = GaussianReadout::new(0.15);
+        let mut measurement_node = NoiseModelNode::new();
+        measurement_node.soft_readout = Some(readout.clone());
+        let measurement_node = Arc::new(measurement_node);
+        simulator_iter_real!(simulator, position, node, {
+            if (position.t == 0 || position.t == mc) && node.qubit_type == QubitType::Data {
+                noise_model.set_node(position, Some(data_node.clone()));
+            }
+            if position.t == mc && node.gate_type.is_measurement() {
+                noise_model.set_node(position, Some(measurement_node.clone()));
+            }
+        });
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let mut mwpm_decoder =
+            MWPMDecoder::new(&Arc::new(simulator.clone()), Arc::clone(&noise_model), &json!({}), 1, false);
+        // a data error between [*][2][1] and [*][4][1], followed by a readout error of [*][4][1]
+        simulator.set_error_check(&noise_model, &pos!(mc, 3, 1), &Z);
+        simulator.set_error_check(&noise_model, &pos!(mc - 1, 4, 1), &Y);
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        assert_eq!(sparse_measurement.to_vec(), vec![pos!(mc, 4, 1), pos!(2 * mc, 2, 1)]);
+        // the analog value of the wrong readout is close to the threshold
+        let value = 0.52;
+        let mut sparse_soft_readouts = SparseSoftReadouts::new();
+        sparse_soft_readouts.readouts.insert(
+            pos!(mc, 4, 1),
+            SoftReadout {
+                value,
+                flip_probability: readout.flip_probability(value),
+                prior_flip_probability: readout.hard_flip_probability(),
+                next_measurement: pos!(2 * mc, 4, 1),
+            },
+        );
+        // without the analog value, the two defects are matched to the boundaries
+        for (sparse_soft_readouts, expect_logical_error) in
+            [(SparseSoftReadouts::new(), true), (sparse_soft_readouts, false)]
+        {
+            let (correction, _runtime_statistics) =
+                mwpm_decoder.decode_with_soft_readouts(&sparse_measurement, &SparseErasures::new(), &sparse_soft_readouts);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert_eq!(logical_i || logical_j, expect_logical_error);
+        }
+    }
+
+    #[test]
+    fn mwpm_decoder_belief_matching() {
+        // cargo test mwpm_decoder_belief_matching -- --nocapture
+        let d = 5;
+        let noisy_measurements = 0; // perfect measurement
+        let p = 0.01;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p, p, p, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let shared_simulator = Arc::new(simulator.clone());
+        let mut plain_decoder = MWPMDecoder::new(&shared_simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        let mut mwpm_decoder = MWPMDecoder::new(&shared_simulator, Arc::clone(&noise_model), &json!({ "bp": {} }), 1, false);
+        // a chain of Y errors, whose X and Z components are only correlated in the hypergraph
+        let sparse_error_pattern: SparseErrorPattern =
+            serde_json::from_value(json!({"[0][5][1]":"Y","[0][5][3]":"Y","[0][5][5]":"Y"})).unwrap();
+        simulator
+            .load_sparse_error_pattern(&sparse_error_pattern, &noise_model)
+            .expect("success");
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        // plain matching fails while belief-matching succeeds
+        for (decoder, expect_logical_error) in [(&mut plain_decoder, true), (&mut mwpm_decoder, false)] {
+            let (correction, _runtime_statistics) = decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert_eq!(logical_i || logical_j, expect_logical_error);
+        }
+    }
+
+    #[test]
+    fn mwpm_decoder_correlated_matching() {
+        // cargo test mwpm_decoder_correlated_matching -- --nocapture
+        let d = 5;
+        let noisy_measurements = 0; // perfect measurement
+        let p = 0.01;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p, p, p, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let shared_simulator = Arc::new(simulator.clone());
+        let mut plain_decoder = MWPMDecoder::new(&shared_simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        let mut mwpm_decoder =
+            MWPMDecoder::new(&shared_simulator, Arc::clone(&noise_model), &json!({ "cm": true }), 1, false);
+        // a vertical chain of errors, where the X components of the Y errors hint at their Z components
+        let sparse_error_pattern: SparseErrorPattern =
+            serde_json::from_value(json!({"[0][1][5]":"Z","[0][5][5]":"Y","[0][9][5]":"Y"})).unwrap();
+        simulator
+            .load_sparse_error_pattern(&sparse_error_pattern, &noise_model)
+            .expect("success");
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        // plain matching fails while correlated matching succeeds
+        for (decoder, expect_logical_error) in [(&mut plain_decoder, true), (&mut mwpm_decoder, false)] {
+            let (correction, _runtime_statistics) = decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert_eq!(logical_i || logical_j, expect_logical_error);
+        }
+    }
 }
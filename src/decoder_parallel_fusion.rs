@@ -1,5 +1,13 @@
 //! minimum-weight perfect matching decoder
 //!
+//! in the stream decoding mode, the decoding graph is partitioned in time and the measurement rounds arrive one at a time:
+//! each partition starts decoding once all its rounds arrive, and is then fused with the previous partitions.
+//! the latency from the arrival of the last round to the final correction is reported in the runtime statistics.
+//! the arrival is only simulated: the whole syndrome is still given at once, and each partition waits until its rounds would
+//! have been measured, every `measure_interval` seconds, so the reported latency is that of a simulated measurement schedule.
+//! the serial [`crate::decoder_fusion::FusionDecoder`] has no stream decoding mode.
+//! erasure errors are not supported, thus an erasure detected later than it happens, e.g. an atom loss, cannot be streamed in.
+//!
 
 use super::model_graph::*;
 use super::noise_model::*;
@@ -74,6 +82,26 @@ pub struct ParallelFusionDecoderConfig {
     pub primal_dual_config: serde_json::Value,
     #[serde(default = "parallel_fusion_default_configs::partition_config")]
     pub partition_config: Option<PartitionConfig>,
+    /// feed the measurement rounds one at a time, see [`StreamDecodingConfig`]; the partition is generated automatically
+    #[serde(alias = "stream")] // abbreviation
+    #[serde(default = "parallel_fusion_default_configs::stream_decoding")]
+    pub stream_decoding: Option<StreamDecodingConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamDecodingConfig {
+    /// the time between two consecutive measurement rounds in seconds
+    #[serde(alias = "mi")] // abbreviation
+    #[serde(default = "parallel_fusion_default_configs::measure_interval")]
+    pub measure_interval: f64,
+    /// the number of measurement rounds in each partition; 1 means every round is fused into the decoding result as it arrives
+    #[serde(alias = "pr")] // abbreviation
+    #[serde(default = "parallel_fusion_default_configs::partition_rounds")]
+    pub partition_rounds: usize,
+    /// spin instead of sleep while waiting for the measurement rounds, which gives more accurate latency but keeps the cores busy
+    #[serde(default = "parallel_fusion_default_configs::use_spin_lock")]
+    pub use_spin_lock: bool,
 }
 
 pub mod parallel_fusion_default_configs {
@@ -93,6 +121,18 @@ pub mod parallel_fusion_default_configs {
     }
     pub fn primal_dual_config() -> serde_json::Value { json!({}) }
     pub fn partition_config() -> Option<PartitionConfig> { None }
+    pub fn stream_decoding() -> Option<StreamDecodingConfig> {
+        None
+    }
+    pub fn measure_interval() -> f64 {
+        1e-6
+    }
+    pub fn partition_rounds() -> usize {
+        1
+    }
+    pub fn use_spin_lock() -> bool {
+        false
+    }
 }
 
 impl ParallelFusionDecoder {
@@ -105,7 +145,7 @@ impl ParallelFusionDecoder {
         use_brief_edge: bool,
     ) -> Self {
         // read attribute of decoder configuration
-        let config: ParallelFusionDecoderConfig = serde_json::from_value(decoder_configuration.clone()).unwrap();
+        let mut config: ParallelFusionDecoderConfig = serde_json::from_value(decoder_configuration.clone()).unwrap();
         let mut simulator = simulator.clone();
         // // build erasure graph
         // let mut erasure_graph = ErasureGraph::new(&simulator);
@@ -120,7 +160,34 @@ impl ParallelFusionDecoder {
             skip_decoding: config.skip_decoding,
             log_matchings: config.log_matchings,
             max_tree_size: usize::MAX,
+            belief_propagation: None,
+            correlated_matching: false,
+            complementary_gap: false,
         }, &mut simulator, noise_model, parallel, use_brief_edge);
+        if let Some(stream_decoding) = config.stream_decoding.as_ref() {
+            assert!(
+                config.partition_config.is_none(),
+                "stream decoding generates the partition automatically, please remove `partition_config`"
+            );
+            assert!(
+                stream_decoding.partition_rounds > 0,
+                "each partition must have at least 1 measurement round"
+            );
+            config.partition_config = Some(Self::stream_partition_config(
+                &adaptor,
+                simulator.measurement_cycles,
+                stream_decoding.partition_rounds,
+            ));
+            // the base partitions are blocked until the syndrome of their last round arrives
+            let primal_dual_config = config
+                .primal_dual_config
+                .as_object_mut()
+                .expect("primal_dual_config must be JSON object");
+            let primal_config = primal_dual_config.entry("primal").or_insert(json!({}));
+            primal_config["streaming_decode_mock_measure_interval"] =
+                json!(stream_decoding.measure_interval * stream_decoding.partition_rounds as f64);
+            primal_config["streaming_decode_use_spin_lock"] = json!(stream_decoding.use_spin_lock);
+        }
         let partition_info = config.partition_config.clone().unwrap_or(PartitionConfig::new(adaptor.vertex_to_position_mapping.len())).info();
         let fusion_solver = fusion_blossom::mwpm_solver::SolverParallel::new(&adaptor.initializer, &partition_info, config.primal_dual_config.clone());
         Self {
@@ -130,6 +197,75 @@ impl ParallelFusionDecoder {
         }
     }
 
+    /// partition the vertices by time, where each partition has `partition_rounds` measurement rounds and the first round
+    /// of every partition except the first one is the interface owned by the fusion unit; the partitions are fused sequentially
+    pub fn stream_partition_config(
+        adaptor: &FusionBlossomAdaptor,
+        measurement_cycles: usize,
+        partition_rounds: usize,
+    ) -> PartitionConfig {
+        let vertex_num = adaptor.vertex_to_position_mapping.len();
+        assert!(
+            adaptor.vertex_to_position_mapping.windows(2).all(|pair| pair[0].t <= pair[1].t),
+            "stream decoding requires the vertices to be sorted by time"
+        );
+        let round_of = |vertex_index: usize| adaptor.vertex_to_position_mapping[vertex_index].t / measurement_cycles - 1;
+        let round_num = round_of(vertex_num - 1) + 1;
+        // the first vertex of each round
+        let round_start = |round: usize| {
+            (0..vertex_num)
+                .find(|&vertex_index| round_of(vertex_index) >= round)
+                .unwrap_or(vertex_num)
+        };
+        let partition_num = (round_num + partition_rounds - 1) / partition_rounds;
+        let mut partition_config = PartitionConfig::new(vertex_num);
+        partition_config.partitions.clear();
+        for partition_index in 0..partition_num {
+            let start_round = partition_index * partition_rounds;
+            let end_round = (start_round + partition_rounds).min(round_num);
+            let owning_start_round = if partition_index == 0 { start_round } else { start_round + 1 };
+            partition_config
+                .partitions
+                .push(VertexRange::new(round_start(owning_start_round), round_start(end_round)));
+        }
+        for partition_index in 1..partition_num {
+            let left_unit_index = if partition_index == 1 {
+                0
+            } else {
+                partition_num + partition_index - 2
+            };
+            partition_config.fusions.push((left_unit_index, partition_index));
+        }
+        partition_config
+    }
+
+    /// the latency of each partition, from the arrival of its last round to the time all the rounds so far are decoded;
+    /// the last one is the latency from the final round to the final correction
+    fn stream_latency(&self, stream_decoding: &StreamDecodingConfig) -> serde_json::Value {
+        let partition_num = self.fusion_solver.primal_module.partition_info.config.partitions.len();
+        let event_time_vec = self.fusion_solver.generate_profiler_report()["primal"]["event_time_vec"].clone();
+        let end_time = |unit_index: usize| {
+            event_time_vec[unit_index]["end"]
+                .as_f64()
+                .expect("event time is recorded when solving")
+        };
+        let partition_interval = stream_decoding.measure_interval * stream_decoding.partition_rounds as f64;
+        let partition_latency: Vec<f64> = (0..partition_num)
+            .map(|partition_index| {
+                let unit_index = if partition_index == 0 {
+                    0
+                } else {
+                    partition_num + partition_index - 1
+                };
+                end_time(unit_index) - partition_interval * (partition_index + 1) as f64
+            })
+            .collect();
+        json!({
+            "latency": partition_latency.last().unwrap(),
+            "partition_latency": partition_latency,
+        })
+    }
+
     /// decode given measurement results
     #[allow(dead_code)]
     pub fn decode(&mut self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
@@ -150,8 +286,9 @@ impl ParallelFusionDecoder {
         let mut time_fusion = 0.;
         let mut time_build_correction = 0.;
         let mut log_matchings = Vec::with_capacity(0);
-        // list nontrivial measurements to be matched
-        if !sparse_measurement.is_empty() {
+        let mut stream_latency = None;
+        // list nontrivial measurements to be matched; in stream decoding, an empty syndrome still takes time to arrive
+        if !sparse_measurement.is_empty() || self.config.stream_decoding.is_some() {
             // run the Blossom algorithm
             let begin = Instant::now();
             let syndrome_pattern = self
@@ -195,6 +332,9 @@ impl ParallelFusionDecoder {
                     "edges": perfect_matching_edges,
                 }));
             }
+            if let Some(stream_decoding) = self.config.stream_decoding.as_ref() {
+                stream_latency = Some(self.stream_latency(stream_decoding));
+            }
             self.fusion_solver.clear();
             time_fusion += begin.elapsed().as_secs_f64();
             correction = self.adaptor.subgraph_to_correction(&subgraph);
@@ -205,6 +345,9 @@ impl ParallelFusionDecoder {
             "time_fusion": time_fusion,
             "time_build_correction": time_build_correction,
         });
+        if let Some(stream_latency) = stream_latency {
+            runtime_statistics["stream"] = stream_latency;
+        }
         if self.config.log_matchings {
             let runtime_statistics = runtime_statistics.as_object_mut().unwrap();
             runtime_statistics.insert("log_matchings".to_string(), json!(log_matchings));
@@ -275,4 +418,40 @@ mod tests {
         let (logical_i, logical_j) = simulator.validate_correction(&correction);
         assert!(!logical_i && !logical_j);
     }
+
+    #[test]
+    fn parallel_fusion_decoder_stream_decoding() {
+        // cargo test parallel_fusion_decoder_stream_decoding -- --nocapture
+        let d = 5;
+        let noisy_measurements = 5;
+        let p = 0.005;
+        // build simulator
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        // build noise model
+        let mut noise_model = NoiseModel::new(&simulator);
+        NoiseModelBuilder::StimNoiseModel.apply(&mut simulator, &mut noise_model, &json!({}), p, 0.5, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        for (partition_rounds, partition_num) in [(1, 6), (2, 3), (4, 2)] {
+            let decoder_config = json!({
+                "stream_decoding": { "measure_interval": 1e-4, "partition_rounds": partition_rounds },
+                "primal_dual_config": { "primal": { "thread_pool_size": 2 } },
+            });
+            let mut parallel_fusion_decoder =
+                ParallelFusionDecoder::new(&simulator, Arc::clone(&noise_model), &decoder_config, 1, false);
+            for _ in 0..20 {
+                simulator.generate_random_errors(&noise_model);
+                let sparse_measurement = simulator.generate_sparse_measurement();
+                let (correction, runtime_statistics) = parallel_fusion_decoder.decode(&sparse_measurement);
+                code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+                let partition_latency = runtime_statistics["stream"]["partition_latency"].as_array().unwrap();
+                assert_eq!(partition_latency.len(), partition_num);
+                // the last round arrives after `measure_interval` * 6, so the whole decoding takes longer than that
+                assert!(runtime_statistics["time_fusion"].as_f64().unwrap() >= 6e-4);
+                assert_eq!(&runtime_statistics["stream"]["latency"], partition_latency.last().unwrap());
+            }
+        }
+    }
 }
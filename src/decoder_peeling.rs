@@ -0,0 +1,374 @@
+//! # Peeling Decoder
+//!
+//! linear-time decoder for erasure errors, see <https://arxiv.org/abs/1703.01517>. the erased edges of the decoding graph form a
+//! subgraph; a spanning forest of this subgraph is built and then peeled from the leaves: a leaf with a defect flips its edge to
+//! its parent, moving the defect towards the root. trees touching the boundary are rooted at the boundary, so that all the
+//! defects in them are resolved. for pure erasure noise this is a maximum-likelihood decoder.
+//!
+//! the defects that are not resolved by peeling, e.g. the ones generated by Pauli errors outside of the erasures, are decoded
+//! by a union-find decoder that also takes the erasures into account.
+//!
+
+use super::decoder_union_find::*;
+use super::erasure_graph::*;
+use super::model_graph::*;
+use super::noise_model::*;
+use super::serde_json;
+use super::simulator::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// peeling decoder, initialized and cloned for multiple threads
+#[derive(Debug, Clone, Serialize)]
+pub struct PeelingDecoder {
+    /// model graph is immutably shared, providing the correction of each erased edge
+    pub model_graph: Arc<ModelGraph>,
+    /// erasure graph is immutably shared
+    pub erasure_graph: Arc<ErasureGraph>,
+    /// index to position mapping (immutable shared); the index right after the last vertex is the boundary
+    pub index_to_position: Arc<Vec<Position>>,
+    /// position to index mapping (immutable shared)
+    pub position_to_index: Arc<HashMap<Position, usize>>,
+    /// decoding the defects that are not resolved by peeling
+    pub union_find_decoder: UnionFindDecoder,
+    /// save configuration for later usage
+    pub config: PeelingDecoderConfig,
+    /// the parent of each vertex when building the spanning forest, with path compression
+    forest_parent: Vec<usize>,
+    /// the forest edges incident to each vertex: (peer vertex, forest edge index)
+    forest_adjacency: Vec<Vec<(usize, usize)>>,
+    /// whether each vertex holds a defect, updated during peeling
+    is_defect: Vec<bool>,
+    /// whether each vertex has been visited when ordering the forest
+    visited: Vec<bool>,
+    /// the vertices touched in this round, which are cleared after decoding to keep the time linear to the erasures
+    touched: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PeelingDecoderConfig {
+    /// the configuration of the union-find decoder that decodes the residual defects, see [`UnionFindDecoderConfig`]
+    #[serde(alias = "ufc")] // abbreviation
+    #[serde(default = "peeling_default_configs::union_find_config")]
+    pub union_find_config: serde_json::Value,
+}
+
+pub mod peeling_default_configs {
+    use super::serde_json;
+    pub fn union_find_config() -> serde_json::Value {
+        json!({})
+    }
+}
+
+impl PeelingDecoder {
+    /// create a new peeling decoder with decoder configuration
+    pub fn new(
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        decoder_configuration: &serde_json::Value,
+        parallel: usize,
+        use_brief_edge: bool,
+    ) -> Self {
+        // read attribute of decoder configuration
+        let config: PeelingDecoderConfig = serde_json::from_value(decoder_configuration.clone()).unwrap();
+        // the union-find decoder builds the model graph and erasure graph, which are shared with the peeling decoder
+        let union_find_decoder =
+            UnionFindDecoder::new(simulator, noise_model, &config.union_find_config, parallel, use_brief_edge);
+        let vertex_num = union_find_decoder.index_to_position.len() + 1; // including the boundary
+        Self {
+            model_graph: Arc::clone(&union_find_decoder.model_graph),
+            erasure_graph: Arc::clone(&union_find_decoder.erasure_graph),
+            index_to_position: Arc::clone(&union_find_decoder.index_to_position),
+            position_to_index: Arc::clone(&union_find_decoder.position_to_index),
+            union_find_decoder,
+            config,
+            forest_parent: (0..vertex_num).collect(),
+            forest_adjacency: vec![vec![]; vertex_num],
+            is_defect: vec![false; vertex_num],
+            visited: vec![false; vertex_num],
+            touched: vec![],
+        }
+    }
+
+    /// the virtual vertex representing the boundary
+    fn boundary_index(&self) -> usize {
+        self.index_to_position.len()
+    }
+
+    fn touch(&mut self, index: usize) {
+        if !self.visited[index] {
+            self.visited[index] = true;
+            self.touched.push(index);
+        }
+    }
+
+    fn find(&mut self, mut index: usize) -> usize {
+        while self.forest_parent[index] != index {
+            let grandparent = self.forest_parent[self.forest_parent[index]];
+            self.forest_parent[index] = grandparent;
+            index = grandparent;
+        }
+        index
+    }
+
+    /// reset the state of the touched vertices
+    fn clear(&mut self) {
+        for &index in self.touched.iter() {
+            self.forest_parent[index] = index;
+            self.forest_adjacency[index].clear();
+            self.is_defect[index] = false;
+            self.visited[index] = false;
+        }
+        self.touched.clear();
+    }
+
+    /// decode given measurement results
+    #[allow(dead_code)]
+    pub fn decode(&mut self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_erasure(sparse_measurement, &SparseErasures::new())
+    }
+
+    /// decode given measurement results and detected erasures
+    pub fn decode_with_erasure(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        let begin = Instant::now();
+        let boundary = self.boundary_index();
+        for position in sparse_measurement.iter() {
+            let index = self.position_to_index[position];
+            self.touch(index);
+            self.is_defect[index] = true;
+        }
+        // build the spanning forest of the fully erased edges; an imperfect herald only reduces the weight, which is left
+        // to the union-find decoder
+        let mut forest_edges = Vec::<Arc<SparseCorrection>>::new();
+        let erasure_edges = sparse_detected_erasures.get_weighted_erasure_edges(&self.erasure_graph);
+        for (erasure_edge, probability) in erasure_edges.iter() {
+            if erasure_edge_weight(*probability) > 0. {
+                continue;
+            }
+            let (index1, index2, correction) = match erasure_edge {
+                ErasureEdge::Connection(position1, position2) => {
+                    let edge = &self.model_graph.get_node_unwrap(position1).edges[position2];
+                    (
+                        self.position_to_index[position1],
+                        self.position_to_index[position2],
+                        Arc::clone(&edge.correction),
+                    )
+                }
+                ErasureEdge::Boundary(position) => {
+                    let model_graph_node = self.model_graph.get_node_unwrap(position);
+                    let boundary_edge = model_graph_node.boundary.as_ref().expect("boundary must exist");
+                    (
+                        self.position_to_index[position],
+                        boundary,
+                        Arc::clone(&boundary_edge.correction),
+                    )
+                }
+            };
+            self.touch(index1);
+            self.touch(index2);
+            let root1 = self.find(index1);
+            let root2 = self.find(index2);
+            if root1 == root2 {
+                continue; // the edge forms a cycle
+            }
+            self.forest_parent[root1] = root2;
+            let edge_index = forest_edges.len();
+            forest_edges.push(correction);
+            self.forest_adjacency[index1].push((index2, edge_index));
+            self.forest_adjacency[index2].push((index1, edge_index));
+        }
+        // order the vertices of each tree from its root, where the boundary is always a root if it's in the tree
+        for &index in self.touched.iter() {
+            self.visited[index] = false;
+        }
+        let mut order = Vec::<(usize, Option<(usize, usize)>)>::with_capacity(self.touched.len()); // (vertex, (parent, edge))
+        let mut roots = Vec::with_capacity(self.touched.len() + 1);
+        roots.push(boundary);
+        roots.extend(self.touched.iter().cloned());
+        for root in roots.into_iter() {
+            if self.visited[root] || (root == boundary && self.forest_adjacency[boundary].is_empty()) {
+                continue;
+            }
+            self.visited[root] = true;
+            let mut head = order.len();
+            order.push((root, None));
+            while head < order.len() {
+                let vertex = order[head].0;
+                head += 1;
+                for &(peer, edge_index) in self.forest_adjacency[vertex].iter() {
+                    if !self.visited[peer] {
+                        self.visited[peer] = true;
+                        order.push((peer, Some((vertex, edge_index))));
+                    }
+                }
+            }
+        }
+        // peel from the leaves
+        let mut correction = SparseCorrection::new();
+        let mut count_peeled_edges = 0;
+        for &(vertex, parent) in order.iter().rev() {
+            if let Some((parent, edge_index)) = parent {
+                if self.is_defect[vertex] {
+                    correction.extend(&forest_edges[edge_index]);
+                    self.is_defect[vertex] = false;
+                    self.is_defect[parent] = !self.is_defect[parent];
+                    count_peeled_edges += 1;
+                }
+            }
+        }
+        let mut residual_measurement = SparseMeasurement::new();
+        for &index in self.touched.iter() {
+            if index != boundary && self.is_defect[index] {
+                residual_measurement.insert_defect_measurement(&self.index_to_position[index]);
+            }
+        }
+        self.clear();
+        let time_peeling = begin.elapsed().as_secs_f64();
+        // the residual defects can only be resolved by matching them
+        let count_residual_defects = residual_measurement.len();
+        let union_find_runtime_statistics = if count_residual_defects > 0 {
+            let (residual_correction, runtime_statistics) = self
+                .union_find_decoder
+                .decode_with_erasure(&residual_measurement, sparse_detected_erasures);
+            correction.extend(&residual_correction);
+            runtime_statistics
+        } else {
+            json!(null)
+        };
+        (
+            correction,
+            json!({
+                "time_peeling": time_peeling,
+                "count_forest_edges": forest_edges.len(),
+                "count_peeled_edges": count_peeled_edges,
+                "count_residual_defects": count_residual_defects,
+                "union_find": union_find_runtime_statistics,
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::code_builder::*;
+    use super::super::noise_model_builder::*;
+    use super::*;
+    use crate::rand::SeedableRng;
+    use crate::reproducible_rand::Xoroshiro128StarStar;
+
+    fn build_decoder(
+        code_type: CodeType,
+        noisy_measurements: usize,
+        noise_model_builder: NoiseModelBuilder,
+        p: f64,
+        pe: f64,
+    ) -> (Simulator, Arc<NoiseModel>, PeelingDecoder) {
+        let d = 5;
+        let mut simulator = Simulator::new(code_type, CodeSize::new(noisy_measurements, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        noise_model_builder.apply(&mut simulator, &mut noise_model, &json!({}), p, 1., pe);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let peeling_decoder = PeelingDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+        (simulator, noise_model, peeling_decoder)
+    }
+
+    #[test]
+    fn peeling_decoder_pure_erasure() {
+        // cargo test peeling_decoder_pure_erasure -- --nocapture
+        for (code_type, noisy_measurements) in [
+            (CodeType::StandardPlanarCode, 0),
+            (CodeType::RotatedPlanarCode, 0),
+            (CodeType::RotatedPlanarCode, 5),
+        ] {
+            let (mut simulator, noise_model, mut peeling_decoder) = build_decoder(
+                code_type,
+                noisy_measurements,
+                NoiseModelBuilder::ErasureOnlyPhenomenological,
+                0.,
+                0.1,
+            );
+            for _ in 0..200 {
+                simulator.generate_random_errors(&noise_model);
+                let sparse_measurement = simulator.generate_sparse_measurement();
+                let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+                let (correction, runtime_statistics) =
+                    peeling_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+                // every defect is generated by the erasures and thus resolved by peeling
+                assert_eq!(runtime_statistics["count_residual_defects"], json!(0));
+                code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn peeling_decoder_single_erasure() {
+        // cargo test peeling_decoder_single_erasure -- --nocapture
+        let (mut simulator, noise_model, mut peeling_decoder) = build_decoder(
+            CodeType::StandardPlanarCode,
+            0,
+            NoiseModelBuilder::ErasureOnlyPhenomenological,
+            0.,
+            0.1,
+        );
+        // an erasure that is recovered as Y can always be corrected
+        let sparse_error_pattern: SparseErrorPattern = serde_json::from_value(json!({"[0][4][4]":"Y"})).unwrap();
+        let sparse_detected_erasures: SparseErasures = serde_json::from_value(json!(["[0][4][4]"])).unwrap();
+        simulator
+            .load_sparse_error_pattern(&sparse_error_pattern, &noise_model)
+            .expect("success");
+        simulator
+            .load_sparse_detected_erasures(&sparse_detected_erasures, &noise_model)
+            .expect("success");
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        assert_eq!(sparse_measurement.len(), 4);
+        let (correction, runtime_statistics) =
+            peeling_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+        assert_eq!(runtime_statistics["count_peeled_edges"], json!(2));
+        code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        let (logical_i, logical_j) = simulator.validate_correction(&correction);
+        assert!(!logical_i && !logical_j);
+    }
+
+    #[test]
+    fn peeling_decoder_mixed_noise() {
+        // cargo test peeling_decoder_mixed_noise -- --nocapture
+        let d = 5;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(0, d, d));
+        let mut noise_model = NoiseModel::new(&simulator);
+        let p = 0.03;
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.1);
+        simulator.compress_error_rates(&mut noise_model);
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let mut peeling_decoder = PeelingDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+        let mut residual_shots = 0;
+        for _ in 0..200 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+            let (correction, runtime_statistics) =
+                peeling_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+            if runtime_statistics["count_residual_defects"] != json!(0) {
+                residual_shots += 1;
+            }
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        }
+        assert!(
+            residual_shots > 0,
+            "the Pauli errors should be decoded by the union-find decoder"
+        );
+    }
+}
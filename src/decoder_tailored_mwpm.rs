@@ -90,6 +90,16 @@ pub mod tailored_mwpm_default_configs {
     }
 }
 
+/// the XZZX code shares the layout of the tailored surface code, and the residual decoding only needs to tell its two
+/// sublattices of stabilizers apart; the tailored edges are derived from the dominant Pauli error in [`TailoredModelGraph`]
+fn tailored_qubit_type(qubit_type: QubitType) -> QubitType {
+    match qubit_type {
+        QubitType::StabXZZXLogicalZ => QubitType::StabY,
+        QubitType::StabXZZXLogicalX => QubitType::StabX,
+        _ => qubit_type,
+    }
+}
+
 impl TailoredMWPMDecoder {
     /// create a new MWPM decoder with decoder configuration
     pub fn new(
@@ -240,7 +250,7 @@ impl TailoredMWPMDecoder {
             for (i, position) in tailored_to_be_matched.iter().enumerate() {
                 // set `cardinality` to 1 if the position is a StabY
                 let node = self.simulator.get_node_unwrap(position);
-                if node.qubit_type == QubitType::StabY {
+                if tailored_qubit_type(node.qubit_type) == QubitType::StabY {
                     tailored_clusters.payload[i].cardinality = 1;
                 }
             }
@@ -290,10 +300,10 @@ impl TailoredMWPMDecoder {
                     for &i in cluster.iter() {
                         let position = &tailored_to_be_matched[i];
                         let node = self.simulator.get_node_unwrap(position);
-                        if node.qubit_type == QubitType::StabY {
+                        if tailored_qubit_type(node.qubit_type) == QubitType::StabY {
                             stab_y_count += 1;
                         }
-                        if node.qubit_type == QubitType::StabX {
+                        if tailored_qubit_type(node.qubit_type) == QubitType::StabX {
                             stab_x_count += 1;
                         }
                     }
@@ -325,7 +335,7 @@ impl TailoredMWPMDecoder {
                     for &i in neutral_cluster.iter() {
                         let position = &tailored_to_be_matched[i];
                         let node = self.simulator.get_node_unwrap(position);
-                        if node.qubit_type == QubitType::StabY {
+                        if tailored_qubit_type(node.qubit_type) == QubitType::StabY {
                             if last_y.is_none() {
                                 last_y = Some(position.clone());
                             } else {
@@ -336,7 +346,7 @@ impl TailoredMWPMDecoder {
                                 last_y = None;
                             }
                         }
-                        if node.qubit_type == QubitType::StabX {
+                        if tailored_qubit_type(node.qubit_type) == QubitType::StabX {
                             if last_x.is_none() {
                                 last_x = Some(position.clone());
                             } else {
@@ -384,7 +394,7 @@ impl TailoredMWPMDecoder {
                             .unwrap()
                             .as_array()
                             .unwrap()
-                            .get(0)
+                            .first()
                             .expect("since residual is not empty, mwpm should give at least one matching")
                             .as_object()
                             .unwrap()
@@ -418,10 +428,10 @@ impl TailoredMWPMDecoder {
                             for &i in neutral_cluster.iter() {
                                 let position = &tailored_to_be_matched[i];
                                 let node = self.simulator.get_node_unwrap(position);
-                                if node.qubit_type == QubitType::StabY {
+                                if tailored_qubit_type(node.qubit_type) == QubitType::StabY {
                                     has_stab_y = true;
                                 }
-                                if node.qubit_type == QubitType::StabX {
+                                if tailored_qubit_type(node.qubit_type) == QubitType::StabX {
                                     has_stab_x = true;
                                 }
                             }
@@ -481,7 +491,8 @@ impl TailoredMWPMDecoder {
                             let mut stab_x_min_weight = f64::MAX;
                             let mut stab_y_min_weight = f64::MAX;
                             for pi in cluster_positions_i.iter() {
-                                let is_stab_x = self.simulator.get_node_unwrap(pi).qubit_type == QubitType::StabX;
+                                let is_stab_x =
+                                    tailored_qubit_type(self.simulator.get_node_unwrap(pi).qubit_type) == QubitType::StabX;
                                 let neutral_matching_edges = self
                                     .tailored_complete_model_graph
                                     .get_neutral_matching_edges(pi, &cluster_positions_j);
@@ -570,7 +581,7 @@ impl TailoredMWPMDecoder {
                             let mut stab_y_positions = Vec::<Position>::new();
                             for position in merged_to_be_matched.iter() {
                                 let node = mut_self.simulator.get_node_unwrap(position);
-                                if node.qubit_type == QubitType::StabX {
+                                if tailored_qubit_type(node.qubit_type) == QubitType::StabX {
                                     stab_x_positions.push(position.clone());
                                 } else {
                                     stab_y_positions.push(position.clone());
@@ -636,7 +647,7 @@ impl TailoredMWPMDecoder {
                                             for idx in 0..cluster_j1.len() {
                                                 let position_i = &tailored_to_be_matched[cluster_j1[idx]];
                                                 let node_i = self.simulator.get_node_unwrap(position_i);
-                                                if node_i.qubit_type == delete_type {
+                                                if tailored_qubit_type(node_i.qubit_type) == delete_type {
                                                     charged_j.push(cluster_j1[idx]);
                                                     break;
                                                 }
@@ -729,7 +740,6 @@ impl TailoredMWPMDecoder {
     }
 }
 
-#[cfg(feature = "blossom_v")]
 #[cfg(test)]
 mod tests {
     use super::super::code_builder::*;
@@ -737,6 +747,8 @@ mod tests {
     use super::super::types::ErrorType::*;
     use super::super::visualize::*;
     use super::*;
+    use crate::rand::SeedableRng;
+    use crate::reproducible_rand::Xoroshiro128StarStar;
 
     #[test]
     fn tailored_mwpm_decoder_code_capacity_inf_bias_d_3() {
@@ -1424,4 +1436,75 @@ mod tests {
             // assert!(!logical_i && !logical_j);
         }
     }
+
+    #[test]
+    fn tailored_mwpm_decoder_xzzx_code_high_bias() {
+        // cargo test tailored_mwpm_decoder_xzzx_code_high_bias -- --nocapture
+        let d = 5;
+        // the dominant Pauli Z only generates 2 non-trivial measurements in the XZZX code, so a Pauli Y on the boundary
+        // is an edge between two different types of stabilizers, which MWPM decoder can only take as a Pauli Z and a Pauli X;
+        // the Pauli Y in the bulk is not a tailored edge but a combination of the dominant Pauli Z and a Pauli X
+        let cases = [
+            (0.15, vec![(pos!(0, 5, 9), Y)], true, false),
+            (
+                0.1,
+                vec![(pos!(0, 5, 1), Z), (pos!(0, 6, 4), Z), (pos!(0, 6, 6), Z), (pos!(0, 6, 8), Z)],
+                false,
+                false,
+            ),
+        ];
+        for (p, errors, mwpm_logical_error, tailored_logical_error) in cases {
+            let bias_eta = 100.;
+            let mut simulator = Simulator::new(CodeType::RotatedXZZXCode, CodeSize::new(0, d, d));
+            code_builder_sanity_check(&simulator).unwrap();
+            let mut noise_model = NoiseModel::new(&simulator);
+            let px = p / (1. + bias_eta) / 2.;
+            let py = px;
+            let pz = p - 2. * px;
+            simulator.set_error_rates(&mut noise_model, px, py, pz, 0.);
+            simulator.compress_error_rates(&mut noise_model);
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            let noise_model = Arc::new(noise_model);
+            let mut mwpm_decoder = MWPMDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+            let mut tailored_mwpm_decoder =
+                TailoredMWPMDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+            simulator.clear_all_errors();
+            for (position, error) in errors.iter() {
+                simulator.set_error_check(&noise_model, position, error);
+            }
+            simulator.propagate_errors();
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            for (is_tailored, expect_logical_error) in [(false, mwpm_logical_error), (true, tailored_logical_error)] {
+                let (correction, _runtime_statistics) = if is_tailored {
+                    tailored_mwpm_decoder.decode(&sparse_measurement)
+                } else {
+                    mwpm_decoder.decode(&sparse_measurement)
+                };
+                code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+                let (logical_i, logical_j) = simulator.validate_correction(&correction);
+                assert_eq!(logical_i || logical_j, expect_logical_error);
+            }
+        }
+        // the correction must be valid on both XZZX code variants under random errors
+        for code_type in [CodeType::RotatedXZZXCode, CodeType::StandardXZZXCode] {
+            let (p, bias_eta) = (0.1, 100.);
+            let mut simulator = Simulator::new(code_type, CodeSize::new(0, d, d));
+            code_builder_sanity_check(&simulator).unwrap();
+            let mut noise_model = NoiseModel::new(&simulator);
+            let px = p / (1. + bias_eta) / 2.;
+            simulator.set_error_rates(&mut noise_model, px, px, p - 2. * px, 0.);
+            simulator.compress_error_rates(&mut noise_model);
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            let noise_model = Arc::new(noise_model);
+            let mut tailored_mwpm_decoder =
+                TailoredMWPMDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+            simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+            for _ in 0..100 {
+                simulator.generate_random_errors(&noise_model);
+                let sparse_measurement = simulator.generate_sparse_measurement();
+                let (correction, _runtime_statistics) = tailored_mwpm_decoder.decode(&sparse_measurement);
+                code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            }
+        }
+    }
 }
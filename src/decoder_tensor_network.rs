@@ -0,0 +1,816 @@
+//! # Tensor Network Decoder
+//!
+//! approximate maximum-likelihood decoder for code-capacity noise, following Bravyi, Suchara and Vargo, "Efficient algorithms
+//! for maximum likelihood decoding in the surface code", PRA 90, 032326 (2014). given a syndrome, a pure error `C` is found
+//! and the probability of each logical coset `C L S` is the sum over all the stabilizers `S`, which is a planar tensor network:
+//! every stabilizer is a copy tensor and every data qubit is a tensor of its Pauli error probabilities. the network is
+//! contracted row by row with a boundary matrix product state whose bond dimension is truncated to `bond_dimension`; the
+//! contraction is exact when the bond dimension is large enough.
+//!
+//! the noise model may have different and biased [`PauliErrorRates`] on every data qubit, which makes it a good reference
+//! for XZZX and tailored codes where MWPM is known to be suboptimal. this decoder is code-capacity only, i.e. Pauli errors on
+//! the data qubits before a single perfect measurement round. phenomenological noise is not supported: noisy measurement
+//! rounds make the network three-dimensional, and such a simulator is rejected when the decoder is constructed.
+//!
+
+use super::noise_model::*;
+use super::simulator::*;
+use super::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// tensor network decoder, initialized and cloned for multiple threads
+#[derive(Debug, Clone, Serialize)]
+pub struct TensorNetworkDecoder {
+    /// the tensor network layout and the linear algebra of the code, shared by all threads
+    #[serde(skip)]
+    pub network: Arc<TensorNetwork>,
+    /// save configuration for later usage
+    pub config: TensorNetworkDecoderConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TensorNetworkDecoderConfig {
+    /// the maximum bond dimension of the boundary matrix product state, usually denoted as chi
+    #[serde(alias = "chi")] // abbreviation
+    #[serde(default = "tensor_network_default_configs::bond_dimension")]
+    pub bond_dimension: usize,
+}
+
+pub mod tensor_network_default_configs {
+    pub fn bond_dimension() -> usize {
+        16
+    }
+}
+
+/// a site of the two-dimensional network; the legs are in the order of up, down, left and right
+#[derive(Debug, Clone)]
+pub enum TensorNetworkSite {
+    Empty,
+    /// a copy tensor of the stabilizer generator, connected to the data qubits it acts on
+    Stabilizer {
+        legs: [bool; 4],
+    },
+    /// the Pauli operator of the neighboring stabilizer generator on this qubit, `I` if not connected
+    DataQubit {
+        index: usize,
+        legs: [ErrorType; 4],
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct TensorNetwork {
+    /// `[i][j]` of the code
+    pub sites: Vec<Vec<TensorNetworkSite>>,
+    /// the position of the data qubits at the top layer, where the correction is applied
+    pub data_positions: Vec<Position>,
+    /// the error rates of the data qubits
+    pub data_error_rates: Vec<PauliErrorRates>,
+    /// the index of the stabilizer generators given the position of their measurement
+    pub stabilizer_indices: HashMap<Position, usize>,
+    /// a basis of the Pauli operators (2 bits per data qubit: X and Z) over GF(2), indexed by the highest bit of its effect;
+    /// the effect has a bit for every stabilizer generator followed by 2 bits of the logical class
+    basis: Vec<Option<(Vec<u64>, Vec<u64>)>>,
+    /// the effect of X and Z errors on each data qubit
+    pauli_effects: Vec<Vec<u64>>,
+    /// Pauli operators of the 4 logical classes with trivial syndrome
+    logical_operators: Vec<Vec<u64>>,
+}
+
+fn bit_words(bits: usize) -> usize {
+    (bits + 63) / 64
+}
+
+fn get_bit(bits: &[u64], index: usize) -> bool {
+    bits[index / 64] & (1 << (index % 64)) != 0
+}
+
+fn flip_bit(bits: &mut [u64], index: usize) {
+    bits[index / 64] ^= 1 << (index % 64);
+}
+
+fn xor_bits(bits: &mut [u64], other: &[u64]) {
+    bits.iter_mut().zip(other).for_each(|(a, b)| *a ^= b);
+}
+
+fn highest_bit(bits: &[u64]) -> Option<usize> {
+    (0..bits.len())
+        .rev()
+        .find(|&word| bits[word] != 0)
+        .map(|word| word * 64 + 63 - bits[word].leading_zeros() as usize)
+}
+
+impl TensorNetworkDecoder {
+    /// create a new tensor network decoder with decoder configuration
+    pub fn new(
+        simulator: &Simulator,
+        noise_model: Arc<NoiseModel>,
+        decoder_configuration: &serde_json::Value,
+    ) -> Result<Self, String> {
+        // read attribute of decoder configuration
+        let config: TensorNetworkDecoderConfig =
+            serde_json::from_value(decoder_configuration.clone()).map_err(|x| x.to_string())?;
+        if config.bond_dimension == 0 {
+            return Err("bond dimension must be positive".to_string());
+        }
+        let network = TensorNetwork::new(simulator, &noise_model)?;
+        Ok(Self {
+            network: Arc::new(network),
+            config,
+        })
+    }
+
+    /// decode given measurement results
+    pub fn decode(&self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_erasure(sparse_measurement, &SparseErasures::new())
+    }
+
+    /// decode given measurement results and detected erasures
+    pub fn decode_with_erasure(
+        &self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        assert!(
+            sparse_detected_erasures.is_empty(),
+            "tensor network decoder doesn't support erasure error"
+        );
+        let begin = Instant::now();
+        let network = &self.network;
+        let pure_error = network.pure_error(sparse_measurement);
+        // the logarithm of the probability of each coset
+        let mut log_probabilities = Vec::with_capacity(4);
+        let mut representatives = Vec::with_capacity(4);
+        for logical_operator in network.logical_operators.iter() {
+            let mut representative = pure_error.clone();
+            xor_bits(&mut representative, logical_operator);
+            log_probabilities.push(network.coset_log_probability(&representative, self.config.bond_dimension));
+            representatives.push(representative);
+        }
+        let most_likely = (0..4)
+            .max_by(|&a, &b| log_probabilities[a].total_cmp(&log_probabilities[b]))
+            .unwrap();
+        let max_log_probability = log_probabilities[most_likely];
+        let total: f64 = log_probabilities
+            .iter()
+            .map(|log_probability| (log_probability - max_log_probability).exp())
+            .sum();
+        let coset_probabilities: Vec<f64> = log_probabilities
+            .iter()
+            .map(|log_probability| (log_probability - max_log_probability).exp() / total)
+            .collect();
+        let correction = network.correction(&representatives[most_likely]);
+        (
+            correction,
+            json!({
+                // in the order of logical class (false, false), (true, false), (false, true), (true, true) relative to the pure error
+                "coset_probabilities": coset_probabilities,
+                "coset_log_probabilities": log_probabilities.iter().map(|x| if x.is_finite() { Some(*x) } else { None }).collect::<Vec<_>>(),
+                "time_contraction": begin.elapsed().as_secs_f64(),
+            }),
+        )
+    }
+}
+
+impl TensorNetwork {
+    pub fn new(simulator: &Simulator, noise_model: &NoiseModel) -> Result<Self, String> {
+        let measurement_cycles = simulator.measurement_cycles;
+        if simulator.height != measurement_cycles + 1 {
+            return Err("tensor network decoder only supports code-capacity noise without noisy measurements".to_string());
+        }
+        let top_t = simulator.height - 1;
+        // only Pauli errors on the data qubits at the beginning are allowed
+        let mut data_positions = vec![];
+        let mut data_error_rates = vec![];
+        simulator_iter_real!(simulator, position, node, {
+            let noise_model_node = noise_model.get_node_unwrap(position);
+            if position.t == 0 && node.qubit_type == QubitType::Data {
+                let mut other_noise = noise_model_node.clone();
+                other_noise.pauli_error_rates = PauliErrorRates::default();
+                if !other_noise.is_noiseless() {
+                    return Err(format!(
+                        "only Pauli errors are supported, but found other noise at {position}"
+                    ));
+                }
+                data_positions.push(pos!(top_t, position.i, position.j));
+                data_error_rates.push(noise_model_node.pauli_error_rates.clone());
+            } else if !noise_model_node.is_noiseless() {
+                return Err(format!(
+                    "only data qubit errors at t=0 are supported, but found noise at {position}"
+                ));
+            }
+        });
+        // the effect of single-qubit errors on the stabilizer generators and the logical class
+        let mut simulator = simulator.clone();
+        let mut stabilizer_indices = HashMap::new();
+        let mut stabilizer_positions = vec![];
+        let mut single_qubit_effects = vec![]; // (syndrome, logical class) of X and Z errors on each data qubit
+        for position in data_positions.iter() {
+            let mut effects = vec![];
+            for error in [ErrorType::X, ErrorType::Z] {
+                simulator.clear_all_errors();
+                simulator.get_node_mut_unwrap(&pos!(0, position.i, position.j)).error = error;
+                simulator.propagate_errors();
+                if simulator.get_node_unwrap(position).propagated != error {
+                    return Err(format!("data qubit error at {position} changes type during the measurement"));
+                }
+                let sparse_measurement = simulator.generate_sparse_measurement();
+                let mut syndrome = vec![];
+                for defect in sparse_measurement.iter() {
+                    let next_index = stabilizer_positions.len();
+                    let index = *stabilizer_indices.entry(defect.clone()).or_insert(next_index);
+                    if index == next_index {
+                        stabilizer_positions.push(defect.clone());
+                    }
+                    syndrome.push(index);
+                }
+                simulator.clear_all_errors();
+                let mut correction = SparseCorrection::new();
+                correction.add(position.clone(), error);
+                let logical_class = simulator.validate_correction(&correction);
+                effects.push((syndrome, logical_class));
+            }
+            single_qubit_effects.push(effects);
+        }
+        simulator.clear_all_errors();
+        // the planar layout: the Pauli operator of each stabilizer generator on each data qubit
+        let mut sites = vec![vec![TensorNetworkSite::Empty; simulator.horizontal]; simulator.vertical];
+        for position in stabilizer_positions.iter() {
+            sites[position.i][position.j] = TensorNetworkSite::Stabilizer { legs: [false; 4] };
+        }
+        for (index, position) in data_positions.iter().enumerate() {
+            sites[position.i][position.j] = TensorNetworkSite::DataQubit {
+                index,
+                legs: [ErrorType::I; 4],
+            };
+        }
+        let mut stabilizer_paulis = HashMap::<(usize, usize), ErrorType>::new(); // (stabilizer, data qubit) -> Pauli
+        for (data_index, effects) in single_qubit_effects.iter().enumerate() {
+            // a stabilizer generator detecting X but not Z acts as Z, and vice versa
+            for (stabilizer_index, pauli) in [(0, ErrorType::Z), (1, ErrorType::X)]
+                .into_iter()
+                .flat_map(|(effect_index, pauli)| effects[effect_index].0.iter().map(move |&index| (index, pauli)))
+            {
+                let entry = stabilizer_paulis
+                    .entry((stabilizer_index, data_index))
+                    .or_insert(ErrorType::I);
+                *entry = entry.multiply(&pauli);
+            }
+        }
+        for (&(stabilizer_index, data_index), pauli) in stabilizer_paulis.iter() {
+            let stabilizer_position = &stabilizer_positions[stabilizer_index];
+            let data_position = &data_positions[data_index];
+            let (si, sj, di, dj) = (stabilizer_position.i, stabilizer_position.j, data_position.i, data_position.j);
+            // leg of the data qubit pointing to the stabilizer and the opposite one
+            let (data_leg, stabilizer_leg) = if si + 1 == di && sj == dj {
+                (0, 1)
+            } else if si == di + 1 && sj == dj {
+                (1, 0)
+            } else if si == di && sj + 1 == dj {
+                (2, 3)
+            } else if si == di && sj == dj + 1 {
+                (3, 2)
+            } else {
+                return Err(format!(
+                    "stabilizer at {stabilizer_position} is not a neighbor of data qubit at {data_position}, the code is not planar"
+                ));
+            };
+            if let TensorNetworkSite::DataQubit { legs, .. } = &mut sites[di][dj] {
+                legs[data_leg] = *pauli;
+            }
+            if let TensorNetworkSite::Stabilizer { legs } = &mut sites[si][sj] {
+                legs[stabilizer_leg] = true;
+            }
+        }
+        // a basis of all Pauli operators to find a pure error and the logical operators
+        let effect_bits = stabilizer_positions.len() + 2;
+        let pauli_bits = 2 * data_positions.len();
+        let mut basis: Vec<Option<(Vec<u64>, Vec<u64>)>> = vec![None; effect_bits];
+        let mut pauli_effects = vec![];
+        for (data_index, effects) in single_qubit_effects.iter().enumerate() {
+            for (error_index, (syndrome, (logical_i, logical_j))) in effects.iter().enumerate() {
+                let mut effect = vec![0; bit_words(effect_bits)];
+                syndrome.iter().for_each(|&index| flip_bit(&mut effect, index));
+                if *logical_i {
+                    flip_bit(&mut effect, effect_bits - 2);
+                }
+                if *logical_j {
+                    flip_bit(&mut effect, effect_bits - 1);
+                }
+                pauli_effects.push(effect.clone());
+                let mut pauli = vec![0; bit_words(pauli_bits)];
+                flip_bit(&mut pauli, 2 * data_index + error_index);
+                while let Some(pivot) = highest_bit(&effect) {
+                    match &basis[pivot] {
+                        Some((basis_effect, basis_pauli)) => {
+                            xor_bits(&mut effect, basis_effect);
+                            xor_bits(&mut pauli, basis_pauli);
+                        }
+                        None => {
+                            basis[pivot] = Some((effect, pauli));
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        let mut network = Self {
+            sites,
+            data_positions,
+            data_error_rates,
+            stabilizer_indices,
+            basis,
+            pauli_effects,
+            logical_operators: vec![],
+        };
+        for logical_class in 0..4 {
+            let mut effect = vec![0; bit_words(effect_bits)];
+            if logical_class & 1 != 0 {
+                flip_bit(&mut effect, effect_bits - 2);
+            }
+            if logical_class & 2 != 0 {
+                flip_bit(&mut effect, effect_bits - 1);
+            }
+            let logical_operator = network
+                .solve(effect)
+                .ok_or_else(|| format!("cannot find a logical operator of class {logical_class}"))?;
+            network.logical_operators.push(logical_operator);
+        }
+        Ok(network)
+    }
+
+    /// find a Pauli operator with the given effect
+    fn solve(&self, mut effect: Vec<u64>) -> Option<Vec<u64>> {
+        let mut pauli = vec![0; bit_words(2 * self.data_positions.len())];
+        while let Some(pivot) = highest_bit(&effect) {
+            let (basis_effect, basis_pauli) = self.basis[pivot].as_ref()?;
+            xor_bits(&mut effect, basis_effect);
+            xor_bits(&mut pauli, basis_pauli);
+        }
+        Some(pauli)
+    }
+
+    /// the syndrome of a Pauli operator followed by 2 bits of its logical class
+    pub fn effect(&self, pauli: &[u64]) -> Vec<u64> {
+        let mut effect = vec![0; bit_words(self.stabilizer_indices.len() + 2)];
+        for (pauli_index, pauli_effect) in self.pauli_effects.iter().enumerate() {
+            if get_bit(pauli, pauli_index) {
+                xor_bits(&mut effect, pauli_effect);
+            }
+        }
+        effect
+    }
+
+    /// a Pauli operator with the given syndrome and trivial logical class
+    pub fn pure_error(&self, sparse_measurement: &SparseMeasurement) -> Vec<u64> {
+        let mut effect = vec![0; bit_words(self.stabilizer_indices.len() + 2)];
+        for position in sparse_measurement.iter() {
+            let index = *self
+                .stabilizer_indices
+                .get(position)
+                .unwrap_or_else(|| panic!("defect measurement {position} is not a stabilizer of the tensor network"));
+            flip_bit(&mut effect, index);
+        }
+        self.solve(effect).expect("the syndrome cannot be generated by any error")
+    }
+
+    fn pauli_of(pauli: &[u64], data_index: usize) -> ErrorType {
+        match (get_bit(pauli, 2 * data_index), get_bit(pauli, 2 * data_index + 1)) {
+            (false, false) => ErrorType::I,
+            (true, false) => ErrorType::X,
+            (false, true) => ErrorType::Z,
+            (true, true) => ErrorType::Y,
+        }
+    }
+
+    pub fn correction(&self, pauli: &[u64]) -> SparseCorrection {
+        let mut correction = SparseCorrection::new();
+        for (data_index, position) in self.data_positions.iter().enumerate() {
+            let error = Self::pauli_of(pauli, data_index);
+            if error != ErrorType::I {
+                correction.add(position.clone(), error);
+            }
+        }
+        correction
+    }
+
+    /// the tensor of a site given the representative Pauli operator of the coset, with shape [up, down, left, right]
+    fn site_tensor(&self, site: &TensorNetworkSite, representative: &[u64]) -> ([usize; 4], Vec<f64>) {
+        match site {
+            TensorNetworkSite::Empty => ([1; 4], vec![1.]),
+            TensorNetworkSite::Stabilizer { legs } => {
+                let shape = legs.map(|leg| if leg { 2 } else { 1 });
+                let mut tensor = vec![0.; shape.iter().product()];
+                // all the connected legs take the same value
+                for value in 0..2 {
+                    let index = (0..4).fold(0, |index, leg| index * shape[leg] + if legs[leg] { value } else { 0 });
+                    tensor[index] = 1.;
+                }
+                (shape, tensor)
+            }
+            TensorNetworkSite::DataQubit { index, legs } => {
+                let shape = legs.map(|pauli| if pauli != ErrorType::I { 2 } else { 1 });
+                let error_rates = &self.data_error_rates[*index];
+                let base = Self::pauli_of(representative, *index);
+                let mut tensor = vec![0.; shape.iter().product()];
+                for (tensor_index, element) in tensor.iter_mut().enumerate() {
+                    let mut error = base;
+                    let mut remaining = tensor_index;
+                    for leg in (0..4).rev() {
+                        if remaining % shape[leg] == 1 {
+                            error = error.multiply(&legs[leg]);
+                        }
+                        remaining /= shape[leg];
+                    }
+                    *element = error_rates.error_rate(&error);
+                }
+                (shape, tensor)
+            }
+        }
+    }
+
+    /// the natural logarithm of the probability of the coset
+    pub fn coset_log_probability(&self, representative: &[u64], bond_dimension: usize) -> f64 {
+        let width = self.sites[0].len();
+        let mut mps: Vec<MpsTensor> = (0..width).map(|_| MpsTensor::trivial()).collect();
+        let mut log_scale = 0.;
+        for row in self.sites.iter() {
+            for (mps_tensor, site) in mps.iter_mut().zip(row.iter()) {
+                let (shape, tensor) = self.site_tensor(site, representative);
+                mps_tensor.absorb(shape, &tensor);
+            }
+            log_scale += MpsTensor::compress(&mut mps, bond_dimension);
+        }
+        // all the physical legs are closed after the last row
+        let mut vector = vec![1.];
+        for mps_tensor in mps.iter() {
+            debug_assert_eq!(mps_tensor.physical, 1);
+            vector = matrix_multiply(1, mps_tensor.left, mps_tensor.right, &vector, &mps_tensor.data);
+        }
+        debug_assert_eq!(vector.len(), 1);
+        if vector[0] > 0. {
+            log_scale + vector[0].ln()
+        } else {
+            f64::NEG_INFINITY // truncation error may give a non-positive value
+        }
+    }
+}
+
+/// a tensor of the boundary matrix product state, indexed by [left, physical, right]
+#[derive(Debug, Clone)]
+struct MpsTensor {
+    left: usize,
+    physical: usize,
+    right: usize,
+    data: Vec<f64>,
+}
+
+impl MpsTensor {
+    fn trivial() -> Self {
+        Self {
+            left: 1,
+            physical: 1,
+            right: 1,
+            data: vec![1.],
+        }
+    }
+
+    /// contract the physical leg with the up leg of a site tensor of shape [up, down, left, right]
+    fn absorb(&mut self, shape: [usize; 4], tensor: &[f64]) {
+        let [up, down, left, right] = shape;
+        assert_eq!(self.physical, up, "the bond dimension between rows must match");
+        let mut data = vec![0.; self.left * left * down * self.right * right];
+        for a in 0..self.left {
+            for u in 0..up {
+                for b in 0..self.right {
+                    let value = self.data[(a * up + u) * self.right + b];
+                    if value == 0. {
+                        continue;
+                    }
+                    for d in 0..down {
+                        for l in 0..left {
+                            for r in 0..right {
+                                let element = tensor[((u * down + d) * left + l) * right + r];
+                                let index = (((a * left + l) * down + d) * self.right + b) * right + r;
+                                data[index] += value * element;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        *self = Self {
+            left: self.left * left,
+            physical: down,
+            right: self.right * right,
+            data,
+        };
+    }
+
+    /// canonicalize from left to right and then truncate from right to left; returns the logarithm of the removed scale
+    fn compress(mps: &mut [Self], bond_dimension: usize) -> f64 {
+        let mut log_scale = 0.;
+        let length = mps.len();
+        for j in 0..length - 1 {
+            let tensor = &mps[j];
+            let (rows, columns) = (tensor.left * tensor.physical, tensor.right);
+            let (u, s, vt) = singular_value_decomposition(rows, columns, &tensor.data, usize::MAX);
+            let rank = s.len();
+            let mut carry = vt;
+            let scale = s[0];
+            for (k, singular_value) in s.iter().enumerate() {
+                carry[k * columns..(k + 1) * columns]
+                    .iter_mut()
+                    .for_each(|x| *x *= singular_value / scale);
+            }
+            log_scale += scale.ln();
+            mps[j].right = rank;
+            mps[j].data = u;
+            let next = &mut mps[j + 1];
+            next.data = matrix_multiply(rank, next.left, next.physical * next.right, &carry, &next.data);
+            next.left = rank;
+        }
+        for j in (1..length).rev() {
+            let tensor = &mps[j];
+            let (rows, columns) = (tensor.left, tensor.physical * tensor.right);
+            let (u, s, vt) = singular_value_decomposition(rows, columns, &tensor.data, bond_dimension);
+            let rank = s.len();
+            let mut carry = u;
+            let scale = s[0];
+            for row in 0..rows {
+                for (k, singular_value) in s.iter().enumerate() {
+                    carry[row * rank + k] *= singular_value / scale;
+                }
+            }
+            log_scale += scale.ln();
+            mps[j].left = rank;
+            mps[j].data = vt;
+            let previous = &mut mps[j - 1];
+            previous.data = matrix_multiply(previous.left * previous.physical, rows, rank, &previous.data, &carry);
+            previous.right = rank;
+        }
+        let first = &mut mps[0];
+        let scale = first.data.iter().fold(0., |max: f64, x| max.max(x.abs()));
+        if scale > 0. {
+            first.data.iter_mut().for_each(|x| *x /= scale);
+            log_scale += scale.ln();
+        }
+        log_scale
+    }
+}
+
+/// `a` is `rows x inner` and `b` is `inner x columns`, both row-major
+fn matrix_multiply(rows: usize, inner: usize, columns: usize, a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.; rows * columns];
+    for i in 0..rows {
+        for k in 0..inner {
+            let value = a[i * inner + k];
+            if value == 0. {
+                continue;
+            }
+            for j in 0..columns {
+                result[i * columns + j] += value * b[k * columns + j];
+            }
+        }
+    }
+    result
+}
+
+fn transpose(rows: usize, columns: usize, matrix: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.; rows * columns];
+    for i in 0..rows {
+        for j in 0..columns {
+            result[j * rows + i] = matrix[i * columns + j];
+        }
+    }
+    result
+}
+
+/// thin singular value decomposition `matrix = u * diag(s) * vt` of a row-major `rows x columns` matrix using one-sided Jacobi
+/// rotations, keeping at most `max_rank` singular values in descending order; at least one singular value is kept
+fn singular_value_decomposition(
+    rows: usize,
+    columns: usize,
+    matrix: &[f64],
+    max_rank: usize,
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    if rows < columns {
+        let (u, s, vt) = singular_value_decomposition(columns, rows, &transpose(rows, columns, matrix), max_rank);
+        let rank = s.len();
+        return (transpose(rank, rows, &vt), s, transpose(columns, rank, &u));
+    }
+    // orthogonalize the columns of the matrix, while accumulating the rotations in `v`
+    let mut u: Vec<Vec<f64>> = (0..columns)
+        .map(|j| (0..rows).map(|i| matrix[i * columns + j]).collect())
+        .collect();
+    let mut v: Vec<Vec<f64>> = (0..columns)
+        .map(|j| (0..columns).map(|i| if i == j { 1. } else { 0. }).collect())
+        .collect();
+    let dot = |x: &[f64], y: &[f64]| -> f64 { x.iter().zip(y).map(|(a, b)| a * b).sum() };
+    let rotate = |vectors: &mut [Vec<f64>], p: usize, q: usize, c: f64, s: f64| {
+        for k in 0..vectors[p].len() {
+            let (x, y) = (vectors[p][k], vectors[q][k]);
+            vectors[p][k] = c * x - s * y;
+            vectors[q][k] = s * x + c * y;
+        }
+    };
+    for _ in 0..100 {
+        let mut converged = true;
+        for p in 0..columns {
+            for q in p + 1..columns {
+                let alpha = dot(&u[p], &u[p]);
+                let beta = dot(&u[q], &u[q]);
+                let gamma = dot(&u[p], &u[q]);
+                if gamma == 0. || gamma.abs() <= 1e-15 * (alpha * beta).sqrt() {
+                    continue;
+                }
+                converged = false;
+                let zeta = (beta - alpha) / (2. * gamma);
+                let t = zeta.signum() / (zeta.abs() + (1. + zeta * zeta).sqrt());
+                let c = 1. / (1. + t * t).sqrt();
+                rotate(&mut u, p, q, c, c * t);
+                rotate(&mut v, p, q, c, c * t);
+            }
+        }
+        if converged {
+            break;
+        }
+    }
+    let norms: Vec<f64> = u.iter().map(|column| dot(column, column).sqrt()).collect();
+    let mut order: Vec<usize> = (0..columns).collect();
+    order.sort_by(|&a, &b| norms[b].total_cmp(&norms[a]));
+    let largest = norms[order[0]];
+    let rank = order
+        .iter()
+        .take(max_rank)
+        .take_while(|&&k| norms[k] > 1e-13 * largest)
+        .count()
+        .max(1);
+    let order = &order[..rank];
+    let mut u_result = vec![0.; rows * rank];
+    let mut vt_result = vec![0.; rank * columns];
+    for (k, &column) in order.iter().enumerate() {
+        let norm = if norms[column] > 0. { norms[column] } else { 1. };
+        for i in 0..rows {
+            u_result[i * rank + k] = u[column][i] / norm;
+        }
+        vt_result[k * columns..(k + 1) * columns].copy_from_slice(&v[column]);
+    }
+    let s = order.iter().map(|&column| norms[column]).collect();
+    (u_result, s, vt_result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::code_builder::*;
+    use super::super::noise_model_builder::*;
+    use super::*;
+
+    #[test]
+    fn tensor_network_decoder_exact_coset_probabilities() {
+        // cargo test tensor_network_decoder_exact_coset_probabilities -- --nocapture
+        let d = 3;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(0, d, d));
+        code_builder_sanity_check(&simulator).unwrap();
+        let mut noise_model = NoiseModel::new(&simulator);
+        // heterogeneous and biased error rates
+        let mut index = 0;
+        simulator_iter_real!(simulator, position, node, t => 0, {
+            if node.qubit_type == QubitType::Data {
+                let mut noise_model_node = NoiseModelNode::new();
+                noise_model_node.pauli_error_rates.error_rate_X = 0.01 + 0.002 * index as f64;
+                noise_model_node.pauli_error_rates.error_rate_Y = 0.005;
+                noise_model_node.pauli_error_rates.error_rate_Z = 0.08 - 0.004 * index as f64;
+                noise_model.set_node(position, Some(Arc::new(noise_model_node)));
+                index += 1;
+            }
+        });
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        assert!(TensorNetworkDecoder::new(&simulator, Arc::clone(&noise_model), &json!({"unknown": 64})).is_err());
+        let decoder = TensorNetworkDecoder::new(&simulator, Arc::clone(&noise_model), &json!({"chi": 64})).unwrap();
+        let network = &decoder.network;
+        let data_num = network.data_positions.len();
+        let stabilizer_num = network.stabilizer_indices.len();
+        // enumerate all the error patterns to compute the exact probability of every (syndrome, logical class)
+        let mut exact = vec![0.; 1 << (stabilizer_num + 2)];
+        for pattern in 0..(1u64 << (2 * data_num)) {
+            let pauli = vec![pattern];
+            let probability: f64 = (0..data_num)
+                .map(|data_index| {
+                    network.data_error_rates[data_index].error_rate(&TensorNetwork::pauli_of(&pauli, data_index))
+                })
+                .product();
+            exact[network.effect(&pauli)[0] as usize] += probability;
+        }
+        for _ in 0..20 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, runtime_statistics) = decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let syndrome = sparse_measurement
+                .iter()
+                .fold(0, |syndrome, position| syndrome | 1 << network.stabilizer_indices[position]);
+            let mut expected: Vec<f64> = (0..4).map(|class| exact[syndrome | class << stabilizer_num]).collect();
+            // the pure error has the same syndrome and a trivial logical class
+            let pure_error = network.pure_error(&sparse_measurement);
+            assert_eq!(network.effect(&pure_error)[0] as usize, syndrome);
+            let total: f64 = expected.iter().sum();
+            expected.iter_mut().for_each(|x| *x /= total);
+            let coset_probabilities = runtime_statistics["coset_probabilities"].as_array().unwrap();
+            for class in 0..4 {
+                let probability = coset_probabilities[class].as_f64().unwrap();
+                assert!(
+                    (probability - expected[class]).abs() < 1e-9,
+                    "{probability} != {}",
+                    expected[class]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tensor_network_decoder_biased_noise() {
+        // cargo test tensor_network_decoder_biased_noise -- --nocapture
+        let p = 0.1;
+        let bias_eta = 100.;
+        for (code_type, di, dj) in [
+            (CodeType::RotatedXZZXCode, 7, 5),
+            (CodeType::StandardXZZXCode, 5, 5),
+            (CodeType::StandardPlanarCode, 5, 5),
+        ] {
+            let mut simulator = Simulator::new(code_type, CodeSize::new(0, di, dj));
+            code_builder_sanity_check(&simulator).unwrap();
+            let mut noise_model = NoiseModel::new(&simulator);
+            let noise_model_configuration = json!({});
+            NoiseModelBuilder::Phenomenological.apply(
+                &mut simulator,
+                &mut noise_model,
+                &noise_model_configuration,
+                p,
+                bias_eta,
+                0.,
+            );
+            simulator.compress_error_rates(&mut noise_model);
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            let noise_model = Arc::new(noise_model);
+            let decoder = TensorNetworkDecoder::new(&simulator, Arc::clone(&noise_model), &json!({})).unwrap();
+            // every single-qubit error is corrected
+            simulator_iter!(simulator, position, node, t => 0, {
+                if node.qubit_type == QubitType::Data {
+                    for error in [ErrorType::X, ErrorType::Y, ErrorType::Z] {
+                        simulator.clear_all_errors();
+                        simulator.set_error_check(&noise_model, position, &error);
+                        simulator.propagate_errors();
+                        let sparse_measurement = simulator.generate_sparse_measurement();
+                        let (correction, _) = decoder.decode(&sparse_measurement);
+                        code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+                        let (logical_i, logical_j) = simulator.validate_correction(&correction);
+                        assert!(!logical_i && !logical_j, "{error:?} error at {position} is not corrected");
+                    }
+                }
+            });
+            for _ in 0..20 {
+                simulator.generate_random_errors(&noise_model);
+                let sparse_measurement = simulator.generate_sparse_measurement();
+                let (correction, runtime_statistics) = decoder.decode(&sparse_measurement);
+                code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+                let total: f64 = runtime_statistics["coset_probabilities"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|x| x.as_f64().unwrap())
+                    .sum();
+                assert!((total - 1.).abs() < 1e-9);
+            }
+        }
+        // noisy measurements are not supported
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(1, 5, 5));
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.);
+        assert!(TensorNetworkDecoder::new(&simulator, Arc::new(noise_model), &json!({})).is_err());
+    }
+
+    #[test]
+    fn tensor_network_singular_value_decomposition() {
+        // cargo test tensor_network_singular_value_decomposition -- --nocapture
+        for (rows, columns) in [(3, 5), (6, 2), (4, 4)] {
+            let matrix: Vec<f64> = (0..rows * columns).map(|x| ((x * 7 + 3) % 11) as f64 - 5.).collect();
+            let (u, s, vt) = singular_value_decomposition(rows, columns, &matrix, usize::MAX);
+            let rank = s.len();
+            let mut us = u.clone();
+            for i in 0..rows {
+                for k in 0..rank {
+                    us[i * rank + k] *= s[k];
+                }
+            }
+            let reconstructed = matrix_multiply(rows, rank, columns, &us, &vt);
+            for (a, b) in matrix.iter().zip(reconstructed.iter()) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! union-find decoder (weighted)
 //!
 
+use super::complementary_gap::*;
 use super::complete_model_graph::*;
 use super::decoder_mwpm::*;
 use super::either::Either;
@@ -64,6 +65,8 @@ pub struct UnionFindDecoder {
     odd_clusters_set_active_timestamp: usize,
     /// internal variable that works like `shrunk_boundaries: BTreeSet<usize>` but with constant performance
     shrunk_boundaries_active_timestamp: usize,
+    /// the decoders on the complementary graph of each logical observable, if the complementary gap is enabled
+    pub complementary_decoders: Vec<(Arc<ComplementaryGraph>, UnionFindDecoder)>,
 }
 
 #[derive(Deref, DerefMut, Debug, Serialize)]
@@ -168,6 +171,11 @@ pub struct UnionFindDecoderConfig {
     #[serde(alias = "bsbc")] // abbreviation
     #[serde(default = "union_find_default_configs::benchmark_skip_building_correction")]
     pub benchmark_skip_building_correction: bool,
+    /// report the complementary gap in the runtime statistics, see [`MWPMDecoderConfig::complementary_gap`]; note that the gap
+    /// is only approximate because union-find doesn't find the minimum-weight decoding result in either logical class
+    #[serde(alias = "cg")] // abbreviation
+    #[serde(default = "mwpm_default_configs::complementary_gap")]
+    pub complementary_gap: bool,
 }
 
 pub mod union_find_default_configs {
@@ -212,10 +220,44 @@ impl UnionFindDecoder {
         let mut erasure_graph = ErasureGraph::new(&simulator);
         erasure_graph.build(&mut simulator, Arc::clone(&noise_model), parallel);
         let erasure_graph = Arc::new(erasure_graph);
+        let complementary_decoders = if config.complementary_gap {
+            ComplementaryGraph::build(&simulator, &model_graph)
+                .into_iter()
+                .map(|complementary_graph| {
+                    let config = UnionFindDecoderConfig {
+                        complementary_gap: false,
+                        ..config.clone()
+                    };
+                    let decoder = Self::from_model_graph(
+                        &complementary_graph.simulator,
+                        Arc::clone(&complementary_graph.model_graph),
+                        Arc::new(ErasureGraph::new(&complementary_graph.simulator)),
+                        config,
+                        parallel,
+                    );
+                    (Arc::new(complementary_graph), decoder)
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        let mut decoder = Self::from_model_graph(&simulator, model_graph, erasure_graph, config, parallel);
+        decoder.complementary_decoders = complementary_decoders;
+        decoder
+    }
+
+    /// create a decoder given the model graph and erasure graph
+    fn from_model_graph(
+        simulator: &Simulator,
+        model_graph: Arc<ModelGraph>,
+        erasure_graph: Arc<ErasureGraph>,
+        config: UnionFindDecoderConfig,
+        parallel: usize,
+    ) -> Self {
         // build complete model graph
-        let mut complete_model_graph = CompleteModelGraph::new(&simulator, Arc::clone(&model_graph));
+        let mut complete_model_graph = CompleteModelGraph::new(simulator, Arc::clone(&model_graph));
         complete_model_graph.optimize_weight_greater_than_sum_boundary = false; // disable this optimization for any matching pair to exist
-        complete_model_graph.precompute(&simulator, config.precompute_complete_model_graph, parallel);
+        complete_model_graph.precompute(simulator, config.precompute_complete_model_graph, parallel);
         // build union-find graph
         let mut index_to_position = Vec::<Position>::new();
         let mut position_to_index =
@@ -344,6 +386,7 @@ impl UnionFindDecoder {
             fusion_list: Vec::new(),
             odd_clusters_set_active_timestamp: 0,
             shrunk_boundaries_active_timestamp: 0,
+            complementary_decoders: vec![],
         }
     }
 
@@ -444,6 +487,61 @@ impl UnionFindDecoder {
         &mut self,
         sparse_measurement: &SparseMeasurement,
         sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_soft_readouts(sparse_measurement, sparse_detected_erasures, &SparseSoftReadouts::new())
+    }
+
+    /// decode given measurement results, detected erasures and analog readouts that reweight the measurement edges
+    pub fn decode_with_soft_readouts(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+        sparse_soft_readouts: &SparseSoftReadouts,
+    ) -> (SparseCorrection, serde_json::Value) {
+        let (correction, mut runtime_statistics) =
+            self.decode_internal(sparse_measurement, sparse_detected_erasures, sparse_soft_readouts, None);
+        if self.config.complementary_gap {
+            assert!(
+                sparse_detected_erasures.is_empty() && sparse_soft_readouts.is_empty(),
+                "complementary gap doesn't support erasures or soft readouts yet"
+            );
+            record_complementary_gaps(
+                &mut self.complementary_decoders,
+                sparse_measurement,
+                &mut runtime_statistics,
+                |decoder, sparse_measurement| decoder.decode_subgraph(sparse_measurement, &SparseErasures::new()).0,
+            );
+        }
+        (correction, runtime_statistics)
+    }
+
+    /// decode given measurement results and detected erasures, and return the chosen model graph edges
+    pub fn decode_subgraph(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseSubgraph, serde_json::Value) {
+        assert!(
+            !self.config.benchmark_skip_building_correction,
+            "cannot report subgraph when building correction is skipped"
+        );
+        let mut subgraph = SparseSubgraph::new();
+        let (_, runtime_statistics) = self.decode_internal(
+            sparse_measurement,
+            sparse_detected_erasures,
+            &SparseSoftReadouts::new(),
+            Some(&mut subgraph),
+        );
+        (subgraph, runtime_statistics)
+    }
+
+    /// the correction is left empty if the subgraph is requested
+    fn decode_internal(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+        sparse_soft_readouts: &SparseSoftReadouts,
+        mut subgraph: Option<&mut SparseSubgraph>,
     ) -> (SparseCorrection, serde_json::Value) {
         // clean the state and then read measurement result
         let time_prepare_decoders = {
@@ -463,24 +561,86 @@ impl UnionFindDecoder {
             // eprintln!("self.odd_clusters: {:?}", self.odd_clusters);
             begin.elapsed().as_secs_f64()
         };
+        // load the analog readouts by changing the length of the measurement edges, which are recovered after decoding
+        let mut modified_lengths = Vec::<(NeighborEdgePtr, usize)>::new();
+        let mut has_pre_grown_edges = false;
+        if !sparse_soft_readouts.is_empty() {
+            assert!(
+                self.config.use_real_weighted,
+                "soft readouts require `use_real_weighted`, otherwise the edge lengths are not meaningful"
+            );
+            for (position, soft_readout) in sparse_soft_readouts.iter() {
+                let next_position = &soft_readout.next_measurement;
+                let (Some(&index1), Some(&index2)) = (
+                    self.position_to_index.get(position),
+                    self.position_to_index.get(next_position),
+                ) else {
+                    continue;
+                };
+                let Some(neighbor) = self.nodes[index1].index_to_neighbor(&index2) else {
+                    continue;
+                };
+                let model_graph_edge = &self.model_graph.get_node_unwrap(position).edges[next_position];
+                if model_graph_edge.weight <= 0. {
+                    continue;
+                }
+                let weight = soft_readout.edge_weight(model_graph_edge.weight, model_graph_edge.probability);
+                let edge_ptr = Arc::clone(&self.nodes[index1].neighbors[neighbor].1);
+                let mut edge = edge_ptr.write();
+                let length = ((edge.length as f64) * weight / model_graph_edge.weight).round() as usize;
+                if length == 0 {
+                    // like an erasure
+                    edge.increased = edge.length;
+                    has_pre_grown_edges = true;
+                } else {
+                    modified_lengths.push((Arc::clone(&edge_ptr), edge.length));
+                    edge.length = length;
+                }
+            }
+        }
         // load the erasure information
-        if !sparse_detected_erasures.is_empty() {
-            let erasure_edges = sparse_detected_erasures.get_erasure_edges(&self.erasure_graph);
-            for erasure_edge in erasure_edges.iter() {
+        if !sparse_detected_erasures.is_empty() || has_pre_grown_edges {
+            let erasure_edges = sparse_detected_erasures.get_weighted_erasure_edges(&self.erasure_graph);
+            // an imperfect herald only pre-grows the edge partially, proportional to the reduction of its weight
+            let pre_grown = |length: usize, original_weight: f64, probability: f64| -> usize {
+                let erasure_weight = erasure_edge_weight(probability);
+                if erasure_weight == 0. {
+                    return length;
+                }
+                if original_weight <= erasure_weight {
+                    return 0;
+                }
+                length - ((length as f64) * erasure_weight / original_weight).round() as usize
+            };
+            let model_graph = &self.complete_model_graph.model_graph;
+            for (erasure_edge, probability) in erasure_edges.iter() {
                 match erasure_edge {
                     ErasureEdge::Connection(position1, position2) => {
                         let index1 = self.position_to_index[position1];
                         let index2 = self.position_to_index[position2];
+                        let original_weight = model_graph.get_node_unwrap(position1).edges[position2].weight;
                         let node1 = self.nodes.get_mut(index1).unwrap();
                         let neighbor = node1.index_to_neighbor(&index2).expect("neighbor must exist");
                         let neighbor_edge_ptr = &node1.neighbors[neighbor].1;
                         let mut neighbor_edge = neighbor_edge_ptr.write();
-                        neighbor_edge.increased = neighbor_edge.length;
+                        let increased = pre_grown(neighbor_edge.length, original_weight, *probability);
+                        neighbor_edge.increased = neighbor_edge.increased.max(increased);
                     }
                     ErasureEdge::Boundary(position) => {
                         let index = self.position_to_index[position];
+                        let original_weight = model_graph
+                            .get_node_unwrap(position)
+                            .boundary
+                            .as_ref()
+                            .expect("boundary must exist")
+                            .weight;
                         let node = self.nodes.get_mut(index).unwrap();
-                        node.boundary_increased = node.boundary_length.expect("boundary must exist");
+                        let increased = pre_grown(
+                            node.boundary_length.expect("boundary must exist"),
+                            original_weight,
+                            *probability,
+                        );
+                        node.boundary_increased = node.boundary_increased.max(increased);
                     }
                 }
             }
@@ -499,6 +659,9 @@ impl UnionFindDecoder {
         } else {
             0.
         };
+        for (edge_ptr, length) in modified_lengths.drain(..) {
+            edge_ptr.write().length = length;
+        }
         // build correction based on the matching
         let (time_build_correction, correction) = {
             let begin = Instant::now();
@@ -511,7 +674,7 @@ impl UnionFindDecoder {
                 for position in sparse_measurement.iter() {
                     let index = self.position_to_index[position];
                     let root = self.union_find.find(index);
-                    cluster_nodes.entry(root).or_insert_with(Vec::new);
+                    cluster_nodes.entry(root).or_default();
                     cluster_nodes.get_mut(&root).unwrap().push(index);
                 }
                 // then build correction based on each correction
@@ -529,9 +692,14 @@ impl UnionFindDecoder {
                         error_syndromes.push(cluster_boundary_index); // let it match with others
                         let cluster_boundary_position = &self.index_to_position[cluster_boundary_index];
                         // println!("match boundary {:?}", cluster_boundary_position);
-                        let boundary_correction =
-                            self.complete_model_graph.build_correction_boundary(cluster_boundary_position);
-                        correction.extend(&boundary_correction);
+                        if let Some(subgraph) = subgraph.as_mut() {
+                            self.complete_model_graph
+                                .build_subgraph_boundary(cluster_boundary_position, subgraph);
+                        } else {
+                            let boundary_correction =
+                                self.complete_model_graph.build_correction_boundary(cluster_boundary_position);
+                            correction.extend(&boundary_correction);
+                        }
                     }
                     assert_eq!(error_syndromes.len() % 2, 0);
                     let half_len = error_syndromes.len() / 2;
@@ -542,9 +710,14 @@ impl UnionFindDecoder {
                             let position1 = &self.index_to_position[index1];
                             let position2 = &self.index_to_position[index2];
                             // println!("match peer {:?} {:?}", position1, position2);
-                            let matching_correction =
-                                self.complete_model_graph.build_correction_matching(position1, position2);
-                            correction.extend(&matching_correction);
+                            if let Some(subgraph) = subgraph.as_mut() {
+                                self.complete_model_graph
+                                    .build_subgraph_matching(position1, position2, subgraph);
+                            } else {
+                                let matching_correction =
+                                    self.complete_model_graph.build_correction_matching(position1, position2);
+                                correction.extend(&matching_correction);
+                            }
                         }
                     }
                 }
@@ -1049,6 +1222,7 @@ mod tests {
     use super::super::noise_model_builder::*;
     use super::super::tool::*;
     use super::super::types::ErrorType::*;
+    use super::super::types::*;
     use super::*;
 
     #[test]
@@ -1254,4 +1428,108 @@ mod tests {
         let (logical_i, logical_j) = simulator.validate_correction(&correction);
         assert!(!logical_i && !logical_j);
     }
+
+    #[test]
+    fn union_find_decoder_missed_erasure_weights() {
+        // cargo test union_find_decoder_missed_erasure_weights -- --nocapture
+        let d = 3;
+        let noisy_measurements = 0; // perfect measurement
+                                    // a defect at [6][4][1] is explained either by [0][5][1] or by the longer chain [0][3][1], [0][1][1]
+        let (missed_1, missed_2) = (pos!(0, 3, 1), pos!(0, 1, 1));
+        for (false_negative_rate, expect_logical_error) in [(0., true), (0.9, false)] {
+            let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+            let mut noise_model = NoiseModel::new(&simulator);
+            simulator.set_error_rates(&mut noise_model, 0., 0., 0.01, 0.);
+            // the chain goes through qubits that often suffer from erasures, which are missed by an imperfect herald
+            let mut erasure_node = noise_model.get_node_unwrap(&missed_1).clone();
+            erasure_node.erasure_error_rate = 0.5;
+            erasure_node.erasure_model = Some(ErasureModel::new(0., false_negative_rate).with_biased_conversion(1.));
+            let erasure_node = Arc::new(erasure_node);
+            noise_model.set_node(&missed_1, Some(erasure_node.clone()));
+            noise_model.set_node(&missed_2, Some(erasure_node));
+            simulator.compress_error_rates(&mut noise_model);
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            let noise_model = Arc::new(noise_model);
+            let mut union_find_decoder =
+                UnionFindDecoder::new(&Arc::new(simulator.clone()), Arc::clone(&noise_model), &json!({}), 1, false);
+            // two missed erasures, no herald at all
+            simulator.set_error_check(&noise_model, &missed_1, &Z);
+            simulator.set_error_check(&noise_model, &missed_2, &Z);
+            simulator.propagate_errors();
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            assert_eq!(sparse_measurement.to_vec(), vec![pos!(6, 4, 1)]);
+            let (correction, _runtime_statistics) =
+                union_find_decoder.decode_with_erasure(&sparse_measurement, &SparseErasures::new());
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert_eq!(logical_i || logical_j, expect_logical_error, "pfn = {false_negative_rate}");
+        }
+    }
+
+    #[test]
+    fn union_find_decoder_soft_readout() {
+        // cargo test union_find_decoder_soft_readout -- --nocapture
+        let d = 3;
+        let noisy_measurements = 1;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        // Z errors on data qubits and accurate readouts, so that a measurement error is less likely than a data error
+        let mut noise_model = NoiseModel::new(&simulator);
+        let mut data_node = NoiseModelNode::new();
+        data_node.pauli_error_rates.error_rate_Z = 0.05;
+        let data_node = Arc::new(data_node);
+        let readout = GaussianReadout::new(0.15);
+        let mut measurement_node = NoiseModelNode::new();
+        measurement_node.soft_readout = Some(readout.clone());
+        let measurement_node = Arc::new(measurement_node);
+        simulator_iter_real!(simulator, position, node, {
+            if (position.t == 0 || position.t == mc) && node.qubit_type == QubitType::Data {
+                noise_model.set_node(position, Some(data_node.clone()));
+            }
+            if position.t == mc && node.gate_type.is_measurement() {
+                noise_model.set_node(position, Some(measurement_node.clone()));
+            }
+        });
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let noise_model = Arc::new(noise_model);
+        let decoder_config = json!({"use_real_weighted": true, "max_half_weight": 1000});
+        let mut union_find_decoder = UnionFindDecoder::new(
+            &Arc::new(simulator.clone()),
+            Arc::clone(&noise_model),
+            &decoder_config,
+            1,
+            false,
+        );
+        // a data error between [*][2][1] and [*][4][1], followed by a readout error of [*][4][1]
+        simulator.set_error_check(&noise_model, &pos!(mc, 3, 1), &Z);
+        simulator.set_error_check(&noise_model, &pos!(mc - 1, 4, 1), &Y);
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        assert_eq!(sparse_measurement.to_vec(), vec![pos!(mc, 4, 1), pos!(2 * mc, 2, 1)]);
+        // the analog value of the wrong readout is close to the threshold
+        let value = 0.52;
+        let mut sparse_soft_readouts = SparseSoftReadouts::new();
+        sparse_soft_readouts.readouts.insert(
+            pos!(mc, 4, 1),
+            SoftReadout {
+                value,
+                flip_probability: readout.flip_probability(value),
+                prior_flip_probability: readout.hard_flip_probability(),
+                next_measurement: pos!(2 * mc, 4, 1),
+            },
+        );
+        // without the analog value, the two defects are matched to the boundaries
+        for (sparse_soft_readouts, expect_logical_error) in
+            [(SparseSoftReadouts::new(), true), (sparse_soft_readouts, false)]
+        {
+            let (correction, _runtime_statistics) = union_find_decoder.decode_with_soft_readouts(
+                &sparse_measurement,
+                &SparseErasures::new(),
+                &sparse_soft_readouts,
+            );
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            let (logical_i, logical_j) = simulator.validate_correction(&correction);
+            assert_eq!(logical_i || logical_j, expect_logical_error);
+        }
+    }
 }
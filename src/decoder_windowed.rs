@@ -0,0 +1,355 @@
+//! # Windowed Decoder
+//!
+//! sliding-window decoding on top of any decoder that reports the spacetime location of its decoding result, see [`SparseSubgraph`].
+//! the measurement rounds are decoded in overlapping windows of `commit_rounds + buffer_rounds` rounds. in each window, only the
+//! chosen edges that start in the commit region are kept; they flip the defects they touch, so the defects in the commit region
+//! are all resolved and the syndrome changes they cause in the buffer region are carried forward to the next window.
+//! the last window is aligned with the final perfect measurement round and commits everything.
+//! an erasure is only given to a window once it's detected at the end of the window, see [`SparseErasures::is_detected_at`];
+//! e.g. an atom loss detected after the commit region of a window has ended cannot help that window.
+//! the inner decoder must support erasures itself, otherwise it is rejected when built from an erasure noise model.
+//!
+//! the inner decoder is built on a simulator that only has the rounds of a single window, so its size doesn't depend on the
+//! total number of rounds; this allows decoding very long memory experiments generated by
+//! [`crate::simulator_compact::SimulatorCompactExtender`]. comparing against the same decoder without windowing gives the accuracy cost of windowing.
+//!
+
+use super::simulator::*;
+use super::tool::*;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// windowed decoder, initialized and cloned for multiple threads
+#[derive(Clone)]
+pub struct WindowedDecoder {
+    /// the decoder of a single window, built on a simulator with `commit_rounds + buffer_rounds` measurement rounds
+    pub window_decoder: Box<GeneralDecoder>,
+    /// save configuration for later usage
+    pub config: WindowedDecoderConfig,
+    /// how many cycles is there a round of measurements
+    pub measurement_cycles: usize,
+    /// the number of measurement rounds of the decoded syndrome, including the final perfect measurement round
+    pub total_rounds: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WindowedDecoderConfig {
+    /// the number of measurement rounds in the beginning of each window whose decoding result is committed
+    #[serde(alias = "commit")] // abbreviation
+    pub commit_rounds: usize,
+    /// the number of measurement rounds after the commit region, whose decoding result is discarded and redone by the next window
+    #[serde(alias = "buffer")] // abbreviation
+    pub buffer_rounds: usize,
+}
+
+impl WindowedDecoderConfig {
+    /// the number of measurement rounds in a window
+    pub fn window_rounds(&self) -> usize {
+        self.commit_rounds + self.buffer_rounds
+    }
+
+    pub fn sanity_check(&self) -> Result<(), String> {
+        if self.commit_rounds == 0 {
+            return Err("windowed decoding requires at least 1 commit round".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl WindowedDecoder {
+    /// `window_decoder` must be built on `window_simulator`, which has exactly `config.window_rounds()` measurement rounds;
+    /// `noisy_measurements` is that of the decoded syndrome, which is usually much larger
+    pub fn new(
+        window_decoder: GeneralDecoder,
+        window_simulator: &Simulator,
+        noisy_measurements: usize,
+        config: WindowedDecoderConfig,
+    ) -> Result<Self, String> {
+        config.sanity_check()?;
+        if !window_decoder.supports_subgraph() {
+            return Err(
+                "the decoder doesn't report the location of the decoding result, thus cannot be windowed".to_string(),
+            );
+        }
+        let measurement_cycles = window_simulator.measurement_cycles;
+        if window_simulator.height != measurement_cycles * config.window_rounds() + 1 {
+            return Err(format!(
+                "the window simulator should have {} measurement rounds",
+                config.window_rounds()
+            ));
+        }
+        let total_rounds = noisy_measurements + 1;
+        if total_rounds < config.window_rounds() {
+            return Err(format!(
+                "the window has {} rounds, more than the {total_rounds} rounds to be decoded",
+                config.window_rounds()
+            ));
+        }
+        Ok(Self {
+            window_decoder: Box::new(window_decoder),
+            config,
+            measurement_cycles,
+            total_rounds,
+        })
+    }
+
+    /// decode given measurement results
+    pub fn decode(&mut self, sparse_measurement: &SparseMeasurement) -> (SparseCorrection, serde_json::Value) {
+        self.decode_with_erasure(sparse_measurement, &SparseErasures::new())
+    }
+
+    /// decode given measurement results and detected erasures
+    pub fn decode_with_erasure(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseCorrection, serde_json::Value) {
+        let begin = Instant::now();
+        let measurement_cycles = self.measurement_cycles;
+        let window_rounds = self.config.window_rounds();
+        let top_t = measurement_cycles * self.total_rounds;
+        let mut defects = sparse_measurement.defects.clone();
+        let mut correction = SparseCorrection::new();
+        let mut committed_edges = 0;
+        let mut window_statistics = vec![];
+        let mut window_erasures_count = vec![];
+        let mut window_start = 0; // the number of rounds before the window
+        loop {
+            let is_last_window = window_start + window_rounds >= self.total_rounds;
+            if is_last_window {
+                // align the last window with the final perfect measurement; the overlapping committed rounds have no defects left
+                window_start = self.total_rounds - window_rounds;
+            }
+            let bias_t = window_start * measurement_cycles;
+            let window_end_t = bias_t + window_rounds * measurement_cycles;
+            let commit_end_t = if is_last_window {
+                window_end_t
+            } else {
+                bias_t + self.config.commit_rounds * measurement_cycles
+            };
+            debug_assert!(
+                defects.iter().all(|position| position.t > bias_t),
+                "defects before the window should have been resolved"
+            );
+            let mut window_measurement = SparseMeasurement::new();
+            for position in defects.iter().filter(|position| position.t <= window_end_t) {
+                let mut position = position.clone();
+                position.t -= bias_t;
+                window_measurement.insert_defect_measurement(&position);
+            }
+            // the erasures in the final perfect measurement round of the window are left to the next window, and those at
+            // the first layer of the window, which is noiseless in the window simulator, are left to the previous window
+            let mut window_erasures = SparseErasures::new();
+            for position in sparse_detected_erasures.iter().filter(|position| {
+                position.t > bias_t
+                    && position.t + measurement_cycles <= window_end_t
+                    && sparse_detected_erasures.is_detected_at(position, window_end_t)
+            }) {
+                let mut position = position.clone();
+                position.t -= bias_t;
+                window_erasures.insert_erasure(&position);
+            }
+            window_erasures_count.push(window_erasures.len());
+            let (subgraph, runtime_statistics) = self.window_decoder.decode_subgraph(&window_measurement, &window_erasures);
+            window_statistics.push(runtime_statistics);
+            for (defect_vertices, edge_correction) in subgraph.iter() {
+                let start_t = defect_vertices.iter().map(|position| position.t + bias_t).min();
+                if start_t.map_or(false, |start_t| start_t > commit_end_t) {
+                    continue; // the edge is in the buffer region, to be decided by the next window
+                }
+                committed_edges += 1;
+                for position in defect_vertices.iter() {
+                    let mut position = position.clone();
+                    position.t += bias_t;
+                    if !defects.remove(&position) {
+                        defects.insert(position);
+                    }
+                }
+                for (position, error) in edge_correction.iter() {
+                    let mut position = position.clone();
+                    position.t = top_t;
+                    correction.add(position, *error);
+                }
+            }
+            if is_last_window {
+                break;
+            }
+            window_start += self.config.commit_rounds;
+        }
+        debug_assert!(defects.is_empty(), "the committed correction should resolve all the defects");
+        (
+            correction,
+            json!({
+                "windows": window_statistics.len(),
+                "committed_edges": committed_edges,
+                "window_erasures": window_erasures_count,
+                "window_statistics": window_statistics,
+                "time_windowed_decode": begin.elapsed().as_secs_f64(),
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::clap::Parser;
+    use super::super::cli::*;
+    use super::super::code_builder::*;
+    use super::super::decoder_union_find::*;
+    use super::super::noise_model::*;
+    use super::super::types::ErrorType::*;
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn windowed_decoder_union_find() {
+        // cargo test windowed_decoder_union_find -- --nocapture
+        let d = 5;
+        let noisy_measurements = 9;
+        let p = 0.002;
+        let config = WindowedDecoderConfig {
+            commit_rounds: 2,
+            buffer_rounds: 2,
+        };
+        let build = |noisy_measurements: usize| -> (Simulator, Arc<NoiseModel>) {
+            let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+            code_builder_sanity_check(&simulator).unwrap();
+            let mut noise_model = NoiseModel::new(&simulator);
+            simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.);
+            simulator.compress_error_rates(&mut noise_model);
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            (simulator, Arc::new(noise_model))
+        };
+        let (mut simulator, noise_model) = build(noisy_measurements);
+        let (window_simulator, window_noise_model) = build(config.window_rounds() - 1);
+        let window_decoder = GeneralDecoder::UnionFind(UnionFindDecoder::new(
+            &window_simulator,
+            window_noise_model,
+            &json!({}),
+            1,
+            false,
+        ));
+        let mut windowed_decoder =
+            WindowedDecoder::new(window_decoder, &window_simulator, noisy_measurements, config).unwrap();
+        // correctable errors in different windows, including one across the boundary of the commit region
+        simulator.clear_all_errors();
+        simulator.set_error_check(&noise_model, &pos!(15, 4, 6), &Z);
+        simulator.set_error_check(&noise_model, &pos!(25, 7, 1), &X);
+        simulator.set_error_check(&noise_model, &pos!(45, 3, 3), &Y);
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        let (correction, runtime_statistics) = windowed_decoder.decode(&sparse_measurement);
+        println!("{runtime_statistics}");
+        assert_eq!(runtime_statistics["windows"], json!(4));
+        code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        let (logical_i, logical_j) = simulator.validate_correction(&correction);
+        assert!(!logical_i && !logical_j);
+        // random errors always give a valid correction
+        for _ in 0..100 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, _) = windowed_decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        }
+    }
+
+    #[test]
+    fn windowed_decoder_delayed_erasures() {
+        // cargo test windowed_decoder_delayed_erasures -- --nocapture
+        let d = 5;
+        let noisy_measurements = 9;
+        let config = WindowedDecoderConfig {
+            commit_rounds: 2,
+            buffer_rounds: 2,
+        };
+        let build = |noisy_measurements: usize| -> (Simulator, Arc<NoiseModel>) {
+            let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+            let mut noise_model = NoiseModel::new(&simulator);
+            simulator.set_error_rates(&mut noise_model, 0.001, 0.001, 0.001, 0.01);
+            simulator.compress_error_rates(&mut noise_model);
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            (simulator, Arc::new(noise_model))
+        };
+        let (mut simulator, noise_model) = build(noisy_measurements);
+        let (window_simulator, window_noise_model) = build(config.window_rounds() - 1);
+        let window_decoder = GeneralDecoder::UnionFind(UnionFindDecoder::new(
+            &window_simulator,
+            window_noise_model,
+            &json!({}),
+            1,
+            false,
+        ));
+        let mut windowed_decoder =
+            WindowedDecoder::new(window_decoder, &window_simulator, noisy_measurements, config).unwrap();
+        // an erased data qubit in the commit region of the first window
+        simulator.clear_all_errors();
+        simulator.set_error_check(&noise_model, &pos!(7, 4, 6), &Z);
+        simulator.set_erasure_check(&noise_model, &pos!(7, 4, 6), true);
+        simulator.propagate_errors();
+        let sparse_measurement = simulator.generate_sparse_measurement();
+        let mut sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+        assert_eq!(sparse_detected_erasures.len(), 1);
+        let (correction, runtime_statistics) =
+            windowed_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+        assert_eq!(runtime_statistics["window_erasures"], json!([1, 0, 0, 0]));
+        code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        // once it's detected after the first window, no window knows about it
+        sparse_detected_erasures.detection_delays.insert(pos!(7, 4, 6), 60);
+        let (correction, runtime_statistics) =
+            windowed_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+        assert_eq!(runtime_statistics["window_erasures"], json!([0, 0, 0, 0]));
+        code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        // random erasures always give a valid correction
+        for _ in 0..20 {
+            simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+            let (correction, _) = windowed_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        }
+    }
+
+    #[test]
+    fn windowed_decoder_from_parameters() {
+        // cargo test windowed_decoder_from_parameters -- --nocapture
+        let build = |decoder: &str| -> (Simulator, Arc<NoiseModel>, Result<GeneralDecoder, String>) {
+            let parameters = BenchmarkParameters::parse_from([
+                "benchmark",
+                "[3]",
+                "[5]",
+                "[0.003]",
+                "--pes",
+                "[0.01]",
+                "--decoder",
+                decoder,
+                "--windowed-decoding",
+                r#"{"commit_rounds":2,"buffer_rounds":2}"#,
+            ]);
+            let configs = parameters.fill_in_default_parameters().unwrap();
+            let config = &parameters.extract_simulation_configurations(&configs)[0];
+            let mut simulator = Simulator::new(parameters.code_type, CodeSize::new(5, 3, 3));
+            let noise_model = parameters
+                .construct_noise_model(&mut simulator, &configs, config, false)
+                .unwrap();
+            let general_decoder = GeneralDecoder::from_parameters(&parameters, &configs, config, &simulator, &noise_model);
+            (simulator, noise_model, general_decoder)
+        };
+        // the window decoder must support erasures
+        let (_, _, general_decoder) = build("fusion");
+        assert!(general_decoder.err().unwrap().contains("erasure"));
+        // MWPM decodes the erasures in each window
+        let (mut simulator, noise_model, general_decoder) = build("mwpm");
+        let mut general_decoder = general_decoder.unwrap();
+        assert!(matches!(general_decoder, GeneralDecoder::Windowed(_)));
+        let mut erasure_count = 0;
+        for _ in 0..20 {
+            erasure_count += simulator.generate_random_errors(&noise_model).1;
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+            let (correction, _) = general_decoder.decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+        }
+        assert!(erasure_count > 0);
+    }
+}
@@ -53,7 +53,7 @@
 //!
 //! <div style="width: 100%; display: flex; justify-content: center;"><svg id="distributed_uf_decoder_connections_2D_demo" style="width: 300px;" viewBox="0 0 100 100"></svg></div>
 //! <script>function draw_distributed_uf_decoder_connections_2D_demo(){let t=document.getElementById("distributed_uf_decoder_connections_2D_demo");if(!t)return;const e=parseInt(10.5);function r(t){for(;1!=t;){if(t%2!=0)return!1;t/=2}return!0}for(let i=0;i<21;++i)for(let n=0;n<20;++n){const o=(n+1.5)*(100/22),c=(i+1)*(100/22);let u=document.createElementNS("http://www.w3.org/2000/svg","circle");u.setAttribute("cx",o),u.setAttribute("cy",c),u.setAttribute("r",100/22*.3),u.setAttribute("fill","rgb(0, 0, 0)"),i==e&&n==e?u.setAttribute("fill","rgb(255, 0, 0)"):(i==e&&r(Math.abs(n-e))||n==e&&r(Math.abs(i-e)))&&u.setAttribute("fill","rgb(0, 0, 255)"),t.appendChild(u)}}document.addEventListener("DOMContentLoaded", draw_distributed_uf_decoder_connections_2D_demo)</script>
-//! 
+//!
 //! The worst case bound of the optimized design seems to be $O(d \log{d})$ at the first glance, but this isn't true when coming to a practical distributed implementation.
 //! Considering the format of the messages passing through those connections, it's different from the naive design in that the node cannot easily know
 //!     whether the receiver is in the same disjoint set as the sender.
@@ -123,20 +123,17 @@
 //! After initialization, the algorithm will instantiate multiple processing unit (PU), each corresponds to a node.
 //!
 
-use std::collections::{HashMap, VecDeque, HashSet};
+use super::derivative::*;
+use super::derive_more::Constructor;
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::rc::Rc;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::OpenOptions;
 use std::io::prelude::*;
-use super::serde::{Serialize, Deserialize};
-use super::derive_more::{Constructor};
-use super::offer_decoder;
-use super::ftqec;
-use super::types::QubitType;
-use super::union_find_decoder;
+use std::rc::Rc;
 
-#[derive(Debug, Serialize, Deserialize, Constructor)]
+#[derive(Debug, Clone, Serialize, Deserialize, Constructor)]
 pub struct InputNode<U: std::fmt::Debug> {
     /// user defined data corresponds to each node
     pub user_data: U,
@@ -147,7 +144,7 @@ pub struct InputNode<U: std::fmt::Debug> {
     pub boundary_cost: Option<usize>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Constructor)]
+#[derive(Debug, Clone, Serialize, Deserialize, Constructor)]
 pub struct InputNeighbor {
     /// address of node `a`
     pub a: usize,
@@ -162,7 +159,7 @@ pub struct InputNeighbor {
     pub latency: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize, Constructor)]
+#[derive(Debug, Clone, Serialize, Deserialize, Constructor)]
 pub struct InputFastChannel {
     /// address of node `a`
     pub a: usize,
@@ -172,6 +169,11 @@ pub struct InputFastChannel {
     pub latency: usize,
 }
 
+/// distance function given two nodes' user data
+pub type DistanceFunction<U> = Box<dyn Fn(&U, &U) -> usize>;
+/// compare function given two nodes' user data
+pub type CompareFunction<U> = Box<dyn Fn(&U, &U) -> Ordering>;
+
 #[derive(Derivative, Serialize)]
 #[derivative(Debug)]
 pub struct DistributedUnionFind<U: std::fmt::Debug> {
@@ -179,14 +181,14 @@ pub struct DistributedUnionFind<U: std::fmt::Debug> {
     pub nodes: Vec<InputNode<U>>,
     /// processing units, each one corresponding to a node of the input graph
     pub processing_units: Vec<ProcessingUnit>,
-    #[derivative(Debug="ignore")]
+    #[derivative(Debug = "ignore")]
     #[serde(skip_serializing)]
     /// distance function given two nodes' user data
-    pub distance: Box<dyn Fn(&U, &U) -> usize>,
-    #[derivative(Debug="ignore")]
+    pub distance: DistanceFunction<U>,
+    #[derivative(Debug = "ignore")]
     #[serde(skip_serializing)]
     /// compare function given two nodes' user data
-    pub compare: Box<dyn Fn(&U, &U) -> Ordering>,
+    pub compare: CompareFunction<U>,
     /// original inputs
     pub input_neighbors: Vec<InputNeighbor>,
     pub input_fast_channels: Vec<InputFastChannel>,
@@ -336,7 +338,7 @@ impl<Message: std::fmt::Debug> Channel<Message> {
             if message.is_some() {
                 found = true;
                 // println!("[flying] {:?}", message);
-                break
+                break;
             }
         }
         found
@@ -344,30 +346,49 @@ impl<Message: std::fmt::Debug> Channel<Message> {
 }
 
 impl<U: std::fmt::Debug> DistributedUnionFind<U> {
-    pub fn new(nodes: Vec<InputNode<U>>, mut neighbors: Vec<InputNeighbor>, mut fast_channels: Vec<InputFastChannel>, 
-            distance: impl Fn(&U, &U) -> usize + 'static, compare: impl Fn(&U, &U) -> Ordering + 'static) -> Self {
+    pub fn new(
+        nodes: Vec<InputNode<U>>,
+        mut neighbors: Vec<InputNeighbor>,
+        mut fast_channels: Vec<InputFastChannel>,
+        distance: impl Fn(&U, &U) -> usize + 'static,
+        compare: impl Fn(&U, &U) -> Ordering + 'static,
+    ) -> Self {
         // filter invalid and duplicated neighbor edges
         let nodes_len = nodes.len();
         let neighbors_length = neighbors.len();
-        neighbors.retain(|edge| {
-            edge.a != edge.b && edge.a < nodes_len && edge.b < nodes_len
-        });  // remove invalid neighbor edges
-        assert_eq!(neighbors_length, neighbors.len(), "`neighbors` contains invalid edges (either invalid address or edge connecting the same node)");
+        neighbors.retain(|edge| edge.a != edge.b && edge.a < nodes_len && edge.b < nodes_len); // remove invalid neighbor edges
+        assert_eq!(
+            neighbors_length,
+            neighbors.len(),
+            "`neighbors` contains invalid edges (either invalid address or edge connecting the same node)"
+        );
         neighbors.sort_unstable();
         neighbors.dedup();
-        assert_eq!(neighbors_length, neighbors.len(), "`neighbors` contains duplicate elements (including the same ends)");
+        assert_eq!(
+            neighbors_length,
+            neighbors.len(),
+            "`neighbors` contains duplicate elements (including the same ends)"
+        );
         // filter invalid and duplicated fast_channels edges
         let fast_channels_length = fast_channels.len();
-        fast_channels.retain(|edge| {
-            edge.a != edge.b && edge.a < nodes_len && edge.b < nodes_len
-        });  // remove invalid neighbor edges
-        assert_eq!(fast_channels_length, fast_channels.len(), "`fast_channels` contains invalid edges (either invalid address or edge connecting the same node)");
+        fast_channels.retain(|edge| edge.a != edge.b && edge.a < nodes_len && edge.b < nodes_len); // remove invalid neighbor edges
+        assert_eq!(
+            fast_channels_length,
+            fast_channels.len(),
+            "`fast_channels` contains invalid edges (either invalid address or edge connecting the same node)"
+        );
         fast_channels.sort_unstable();
         fast_channels.dedup();
-        assert_eq!(fast_channels_length, fast_channels.len(), "`fast_channels` contains duplicate elements (including the same ends)");
+        assert_eq!(
+            fast_channels_length,
+            fast_channels.len(),
+            "`fast_channels` contains duplicate elements (including the same ends)"
+        );
         // build processing units
-        let mut processing_units: Vec<_> = nodes.iter().enumerate().map(|(i, node)| {
-            ProcessingUnit {
+        let mut processing_units: Vec<_> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| ProcessingUnit {
                 neighbors: Vec::new(),
                 union_out_channels: Vec::new(),
                 union_in_channels: Vec::new(),
@@ -382,11 +403,21 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 debug_cardinality: if node.is_error_syndrome { 1 } else { 0 },
                 pending_tell_new_root_cardinality: false,
                 pending_tell_new_root_touching_boundary: false,
-            }
-        }).collect();
+            })
+            .collect();
         // build neighbors and their channels
-        for InputNeighbor { a, b, increased, length, latency } in neighbors.iter() {
-            assert!(*latency >= 1, "latency must be at least 1, with 1 meaning the peer receives the information in the next clock cycle");
+        for InputNeighbor {
+            a,
+            b,
+            increased,
+            length,
+            latency,
+        } in neighbors.iter()
+        {
+            assert!(
+                *latency >= 1,
+                "latency must be at least 1, with 1 meaning the peer receives the information in the next clock cycle"
+            );
             let neighbor_link = Rc::new(RefCell::new(NeighborLink {
                 increased: *increased,
                 length: *length,
@@ -396,14 +427,14 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 address: *b,
                 supposed_updated_root: *b,
                 old_root: *b,
-                is_fully_grown: false,  // will update in `spread_cluster`
+                is_fully_grown: false, // will update in `spread_cluster`
                 link: neighbor_link.clone(),
             });
             processing_units[*b].neighbors.push(Neighbor {
                 address: *a,
                 supposed_updated_root: *a,
                 old_root: *a,
-                is_fully_grown: false,  // will update in `spread_cluster`
+                is_fully_grown: false, // will update in `spread_cluster`
                 link: neighbor_link.clone(),
             });
             // build channels
@@ -425,14 +456,33 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
             }
         }
         for pu in processing_units.iter() {
-            assert_eq!(pu.neighbors.len(), pu.union_out_channels.len(), "each neighbor should have exactly one channel");
-            assert_eq!(pu.neighbors.len(), pu.union_in_channels.len(), "each neighbor should have exactly one channel");
-            assert_eq!(pu.neighbors.len(), pu.direct_out_channels.len(), "each neighbor should have exactly one channel");
-            assert_eq!(pu.neighbors.len(), pu.direct_in_channels.len(), "each neighbor should have exactly one channel");
+            assert_eq!(
+                pu.neighbors.len(),
+                pu.union_out_channels.len(),
+                "each neighbor should have exactly one channel"
+            );
+            assert_eq!(
+                pu.neighbors.len(),
+                pu.union_in_channels.len(),
+                "each neighbor should have exactly one channel"
+            );
+            assert_eq!(
+                pu.neighbors.len(),
+                pu.direct_out_channels.len(),
+                "each neighbor should have exactly one channel"
+            );
+            assert_eq!(
+                pu.neighbors.len(),
+                pu.direct_in_channels.len(),
+                "each neighbor should have exactly one channel"
+            );
         }
         // build fast channels
         for InputFastChannel { a, b, latency } in fast_channels.iter() {
-            assert!(*latency >= 1, "latency must be at least 1, with 1 meaning the peer receives the information in the next clock cycle");
+            assert!(
+                *latency >= 1,
+                "latency must be at least 1, with 1 meaning the peer receives the information in the next clock cycle"
+            );
             // build channels
             for (x, y) in [(*a, *b), (*b, *a)].iter() {
                 // union channels
@@ -452,13 +502,25 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
             }
         }
         for pu in processing_units.iter() {
-            assert_eq!(pu.union_out_channels.len(), pu.union_in_channels.len(), "amount of channels should be the same");
-            assert_eq!(pu.union_out_channels.len(), pu.direct_out_channels.len(), "amount of channels should be the same");
-            assert_eq!(pu.union_out_channels.len(), pu.direct_in_channels.len(), "amount of channels should be the same");
+            assert_eq!(
+                pu.union_out_channels.len(),
+                pu.union_in_channels.len(),
+                "amount of channels should be the same"
+            );
+            assert_eq!(
+                pu.union_out_channels.len(),
+                pu.direct_out_channels.len(),
+                "amount of channels should be the same"
+            );
+            assert_eq!(
+                pu.union_out_channels.len(),
+                pu.direct_in_channels.len(),
+                "amount of channels should be the same"
+            );
         }
         Self {
-            nodes: nodes,
-            processing_units: processing_units,
+            nodes,
+            processing_units,
             distance: Box::new(distance),
             compare: Box::new(compare),
             input_neighbors: neighbors,
@@ -473,11 +535,19 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
             let pu = &self.processing_units[i];
             for (_peer, out_channel) in pu.union_out_channels.iter() {
                 let out_channel = out_channel.borrow();
-                assert_eq!(out_channel.deque.len(), out_channel.latency, "there should be `latency` elements in deque in stable state");
+                assert_eq!(
+                    out_channel.deque.len(),
+                    out_channel.latency,
+                    "there should be `latency` elements in deque in stable state"
+                );
             }
             for (_peer, out_channel) in pu.direct_out_channels.iter() {
                 let out_channel = out_channel.borrow();
-                assert_eq!(out_channel.deque.len(), out_channel.latency, "there should be `latency` elements in deque in stable state");
+                assert_eq!(
+                    out_channel.deque.len(),
+                    out_channel.latency,
+                    "there should be `latency` elements in deque in stable state"
+                );
             }
         }
     }
@@ -489,13 +559,13 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
             let pu = &self.processing_units[i];
             for (_peer, out_channel) in pu.union_out_channels.iter() {
                 if out_channel.borrow().has_message_flying() {
-                    return true
+                    return true;
                 }
             }
             for (_peer, out_channel) in pu.direct_out_channels.iter() {
                 if out_channel.borrow().has_message_flying() {
                     // println!("[flying] {:?} -> {:?}", self.nodes[i].user_data, self.nodes[*_peer].user_data);
-                    return true
+                    return true;
                 }
             }
         }
@@ -509,31 +579,41 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
         // in FPGA, this is done by giving a trigger signal to all PUs, in O(1) time
         for i in 0..nodes_len {
             let pu = &mut self.processing_units[i];
-            assert_eq!(pu.updated_root, pu.old_root, "when spreading cardinality, old root must already been updated, otherwise it's inconsistent state");
-            pu.is_odd_cluster = false;  // first set them all to even cluster
+            assert_eq!(
+                pu.updated_root, pu.old_root,
+                "when spreading cardinality, old root must already been updated, otherwise it's inconsistent state"
+            );
+            pu.is_odd_cluster = false; // first set them all to even cluster
         }
         // in FPGA, with fast channel architecture, this has worst case bound of O(log(d)) time
         let mut spreading = true;
         while spreading {
-            clock_cycles += 1;  // each clock cycle can process one message from every in channels and then push one message to every out channels
+            clock_cycles += 1; // each clock cycle can process one message from every in channels and then push one message to every out channels
             for i in 0..nodes_len {
                 let pu = &mut self.processing_units[i];
                 // first retrieve messages from all union in channel
                 let old_is_odd_cluster = pu.is_odd_cluster;
-                let in_messages: Vec<Option<UnionMessage>> = pu.union_in_channels.iter().map(|(_peer, in_channel)| {
-                    let mut in_channel = in_channel.borrow_mut();
-                    in_channel.deque.pop_front().unwrap()
-                }).collect();
-                // handle those messages to compute pu.is_odd_cluster, this can be done in O(1) on FPGA, 
+                let in_messages: Vec<Option<UnionMessage>> = pu
+                    .union_in_channels
+                    .iter()
+                    .map(|(_peer, in_channel)| {
+                        let mut in_channel = in_channel.borrow_mut();
+                        in_channel.deque.pop_front().unwrap()
+                    })
+                    .collect();
+                // handle those messages to compute pu.is_odd_cluster, this can be done in O(1) on FPGA,
                 //    with O(log(log(d))) higher gate level latency, which may reduce the clock cycle a little bit, but still pretty scalable
                 for message in in_messages.iter() {
                     match message {
-                        Some(UnionMessage{ old_root, updated_root: _ }) => {
+                        Some(UnionMessage {
+                            old_root,
+                            updated_root: _,
+                        }) => {
                             if *old_root == pu.old_root {
                                 pu.is_odd_cluster = true;
                             }
-                        },
-                        None => { },
+                        }
+                        None => {}
                     }
                 }
                 if i == pu.updated_root {
@@ -544,12 +624,18 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 // then broadcast messages
                 let should_broadcast = pu.is_odd_cluster != old_is_odd_cluster;
                 if should_broadcast {
-                    assert_eq!(old_is_odd_cluster, false, "pu.is_odd_cluster never changes from `true` to `false` in this stage");
-                    assert_eq!(pu.is_odd_cluster, true, "pu.is_odd_cluster never changes from `true` to `false` in this stage");
+                    assert!(
+                        !old_is_odd_cluster,
+                        "pu.is_odd_cluster never changes from `true` to `false` in this stage"
+                    );
+                    assert!(
+                        pu.is_odd_cluster,
+                        "pu.is_odd_cluster never changes from `true` to `false` in this stage"
+                    );
                 }
                 for (_peer, out_channel) in pu.union_out_channels.iter() {
                     let mut out_channel = out_channel.borrow_mut();
-                    out_channel.deque.push_back(if should_broadcast { 
+                    out_channel.deque.push_back(if should_broadcast {
                         Some(UnionMessage {
                             old_root: pu.old_root,
                             updated_root: pu.updated_root,
@@ -569,7 +655,10 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
         // in FPGA, this is done by giving a trigger signal to all PUs, in O(1) time
         for i in 0..nodes_len {
             let pu = &self.processing_units[i];
-            assert_eq!(pu.updated_root, pu.old_root, "when growing boundary, old root must already been updated, otherwise it's inconsistent state");
+            assert_eq!(
+                pu.updated_root, pu.old_root,
+                "when growing boundary, old root must already been updated, otherwise it's inconsistent state"
+            );
             if pu.is_odd_cluster {
                 let neighbors_len = pu.neighbors.len();
                 for j in 0..neighbors_len {
@@ -577,26 +666,27 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                     let neighbor = &pu.neighbors[j];
                     let mut neighbor_link = neighbor.link.borrow_mut();
                     if neighbor_link.increased < neighbor_link.length {
-                        neighbor_link.increased += 1;  // grow the edge if it's not fully grown
+                        neighbor_link.increased += 1; // grow the edge if it's not fully grown
                     }
                 }
-                match self.nodes[i].boundary_cost {
-                    Some(boundary_cost) => {
-                        let pu = &mut self.processing_units[i];
-                        if pu.boundary_increased < boundary_cost {
-                            pu.boundary_increased += 1;
-                        }
-                    },
-                    None => { },
+                if let Some(boundary_cost) = self.nodes[i].boundary_cost {
+                    let pu = &mut self.processing_units[i];
+                    if pu.boundary_increased < boundary_cost {
+                        pu.boundary_increased += 1;
+                    }
                 }
             }
         }
-        1  // always done in 1 clock cycle, this doesn't need to be synchronized
+        1 // always done in 1 clock cycle, this doesn't need to be synchronized
     }
 
     /// compare nodes given their addresses
     pub fn get_node_smaller(&self, a: usize, b: usize) -> usize {
-        if (self.compare)(&self.nodes[a].user_data, &self.nodes[b].user_data) == Ordering::Less { a } else { b }
+        if (self.compare)(&self.nodes[a].user_data, &self.nodes[b].user_data) == Ordering::Less {
+            a
+        } else {
+            b
+        }
     }
 
     /// compute distance given address
@@ -611,7 +701,10 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
         let mut maximum_latency = 1;
         for i in 0..nodes_len {
             let pu = &self.processing_units[i];
-            assert_eq!(pu.updated_root, pu.old_root, "when growing boundary, old root must already been updated, otherwise it's inconsistent state");
+            assert_eq!(
+                pu.updated_root, pu.old_root,
+                "when growing boundary, old root must already been updated, otherwise it's inconsistent state"
+            );
             let neighbors_len = pu.neighbors.len();
             for j in 0..neighbors_len {
                 let pu = &mut self.processing_units[i];
@@ -625,7 +718,7 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 neighbor.is_fully_grown = neighbor_link.increased >= neighbor_link.length;
                 drop(neighbor_link);
                 let neighbor_root = self.processing_units[neighbor_addr].updated_root;
-                let neighbor = &mut self.processing_units[i].neighbors[j];  // re-borrow as mutable
+                let neighbor = &mut self.processing_units[i].neighbors[j]; // re-borrow as mutable
                 neighbor.supposed_updated_root = neighbor_root;
                 neighbor.old_root = neighbor_root;
             }
@@ -641,13 +734,10 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 let pu = &mut self.processing_units[i];
                 // check if this is the first time to touch the boundary, finished in O(1) time on FPGA
                 let old_is_touching_boundary = pu.is_touching_boundary;
-                match self.nodes[i].boundary_cost {
-                    Some(boundary_cost) => {
-                        if pu.boundary_increased >= boundary_cost {
-                            pu.is_touching_boundary = true;
-                        }
-                    },
-                    None => { },
+                if let Some(boundary_cost) = self.nodes[i].boundary_cost {
+                    if pu.boundary_increased >= boundary_cost {
+                        pu.is_touching_boundary = true;
+                    }
                 }
                 // check if there is any neighbor with fully grown edge, this can be done in O(1) time on FPGA
                 // if old_updated_root != pu.updated_root && is_error_syndrome, then should send direct message to new root to add cardinality
@@ -661,25 +751,30 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                     let pu = &self.processing_units[i];
                     let neighbor = &pu.neighbors[j];
                     if neighbor.is_fully_grown {
-                        new_updated_root = self.get_node_smaller(new_updated_root, neighbor.address);
+                        new_updated_root = self.get_node_smaller(new_updated_root, neighbor.old_root);
                     }
                 }
                 // processing one message from all union channels
                 let pu = &mut self.processing_units[i];
-                let in_messages: Vec<Option<UnionMessage>> = pu.union_in_channels.iter().map(|(_peer, in_channel)| {
-                    let mut in_channel = in_channel.borrow_mut();
-                    in_channel.deque.pop_front().unwrap()
-                }).collect();
-                // handle those messages to compute `new_updated_root`, this can be done in O(1) on FPGA, 
+                let in_messages: Vec<Option<UnionMessage>> = pu
+                    .union_in_channels
+                    .iter()
+                    .map(|(_peer, in_channel)| {
+                        let mut in_channel = in_channel.borrow_mut();
+                        in_channel.deque.pop_front().unwrap()
+                    })
+                    .collect();
+                // handle those messages to compute `new_updated_root`, this can be done in O(1) on FPGA,
                 //    with O(log(log(d))) higher gate level latency, which may reduce the clock cycle a little bit, but still pretty scalable
                 for message in in_messages.iter() {
                     match message {
-                        Some(UnionMessage{ old_root, updated_root }) => {
-                            if *old_root == pu_old_root {  // otherwise don't consider it at all!
+                        Some(UnionMessage { old_root, updated_root }) => {
+                            if *old_root == pu_old_root {
+                                // otherwise don't consider it at all!
                                 new_updated_root = self.get_node_smaller(new_updated_root, *updated_root);
                             }
-                        },
-                        None => { },
+                        }
+                        None => {}
                     }
                 }
                 // send messages to all union channels if the updated root changes in this cycle, this can be done in O(1) on FPGA
@@ -690,12 +785,12 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                     if j < pu.neighbors.len() {
                         let neighbor = &pu.neighbors[j];
                         if neighbor.is_fully_grown {
-                            old_root = neighbor.old_root;  // must fit into the peer's old_root, otherwise he won't take it!
+                            old_root = neighbor.old_root; // must fit into the peer's old_root, otherwise he won't take it!
                         }
                     }
-                    out_channel.deque.push_back(if new_updated_root != old_updated_root { 
+                    out_channel.deque.push_back(if new_updated_root != old_updated_root {
                         Some(UnionMessage {
-                            old_root: old_root,
+                            old_root,
                             updated_root: new_updated_root,
                         })
                     } else {
@@ -707,7 +802,8 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 // at the same time, try to find a direct message to route
                 // since the direct message should be very rare in the system, just a simple logic would suffice
                 if new_updated_root != old_updated_root {
-                    if self.nodes[i].is_error_syndrome {  // only nodes with error syndrome should tell the root about updated cardinality
+                    if self.nodes[i].is_error_syndrome {
+                        // only nodes with error syndrome should tell the root about updated cardinality
                         pu.pending_tell_new_root_cardinality = true;
                     }
                     if pu.is_touching_boundary {
@@ -717,7 +813,8 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 if pu.is_touching_boundary != old_is_touching_boundary {
                     pu.pending_tell_new_root_touching_boundary = true;
                 }
-                if new_updated_root == i {  // don't need to send message to myself
+                if new_updated_root == i {
+                    // don't need to send message to myself
                     pu.pending_tell_new_root_cardinality = false;
                     pu.pending_tell_new_root_touching_boundary = false;
                 }
@@ -733,12 +830,13 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 let mut need_to_pop_direct_in_channel_from_idx = None;
                 for (j, (_peer, in_channel)) in pu.direct_in_channels.iter().enumerate() {
                     let mut in_channel = in_channel.borrow_mut();
-                    let in_message = in_channel.deque.get(0).unwrap();
+                    let in_message = in_channel.deque.front().unwrap();
                     if in_message.is_none() {
-                        in_channel.deque.pop_front().unwrap();  // always get None message from the queue
+                        in_channel.deque.pop_front().unwrap(); // always get None message from the queue
                     } else {
                         let in_message = in_message.as_ref().unwrap();
-                        if in_message.receiver == i {  // I'm the receiver, so I'll process this information
+                        if in_message.receiver == i {
+                            // I'm the receiver, so I'll process this information
                             // if children has error syndrome and is its first time to join this cluster, then the cluster's cardinality +1
                             pu.debug_cardinality += if in_message.is_odd_cardinality_root { 1 } else { 0 };
                             pu.is_odd_cardinality ^= in_message.is_odd_cardinality_root;
@@ -748,7 +846,8 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                             in_channel.deque.pop_front().unwrap();
                         } else {
                             // never pop valid in_message here, do it after making sure that the message can be handled or brokered
-                            if pending_direct_message.is_none() {  // retrieve Some message only if pending_direct_message is none
+                            if pending_direct_message.is_none() {
+                                // retrieve Some message only if pending_direct_message is none
                                 // do not take it from deque, because it may not be able to send out, and need to try again next clock cycle
                                 pending_direct_message = Some(in_message.clone());
                                 need_to_pop_direct_in_channel_from_idx = Some(j);
@@ -759,29 +858,29 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 // find the most attractive channel for `pending_direct_message`, finish in O(1) on FPGA
                 let mut best_channel_for_pending_message_idx = None;
                 let pu = &self.processing_units[i];
-                match pending_direct_message {
-                    Some(DirectMessage{ receiver, is_odd_cardinality_root: _, is_touching_boundary: _ }) => {
-                        // find the best channel (peer with smallest distance)
-                        for (j, (peer, _out_channel)) in pu.direct_out_channels.iter().enumerate() {
-                            best_channel_for_pending_message_idx = Some(match best_channel_for_pending_message_idx {
-                                Some(idx) => {
-                                    let (last_address, _out_channel) = &pu.direct_out_channels[idx];
-                                    if self.get_node_distance(*peer, receiver) < self.get_node_distance(*last_address, receiver) {
-                                        j
-                                    } else {
-                                        idx
-                                    }
-                                },
-                                None => {
+                if let Some(DirectMessage { receiver, .. }) = pending_direct_message {
+                    // find the best channel (peer with smallest distance)
+                    for (j, (peer, _out_channel)) in pu.direct_out_channels.iter().enumerate() {
+                        best_channel_for_pending_message_idx = Some(match best_channel_for_pending_message_idx {
+                            Some(idx) => {
+                                let (last_address, _out_channel) = &pu.direct_out_channels[idx];
+                                if self.get_node_distance(*peer, receiver) < self.get_node_distance(*last_address, receiver)
+                                {
                                     j
-                                },
-                            });
-                        }
-                    },
-                    None => { },
+                                } else {
+                                    idx
+                                }
+                            }
+                            None => j,
+                        });
+                    }
                 }
                 // save intermediate states, this is not necessary in FPGA, it's only used to mimic direct channels with latency and busy flag
-                intermediate_states.push((pending_direct_message, best_channel_for_pending_message_idx, need_to_pop_direct_in_channel_from_idx));
+                intermediate_states.push((
+                    pending_direct_message,
+                    best_channel_for_pending_message_idx,
+                    need_to_pop_direct_in_channel_from_idx,
+                ));
             }
             // first let all nodes retrieve message from direct channels, and then send messages to direct channels
             // have to do this in two iterations, otherwise it's difficult and problematic to check if a deque is full (with length `latency`) or not
@@ -792,32 +891,36 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 // send to all direct channels
                 let pu = &mut self.processing_units[i];
                 let mut pending_message_sent_successfully = false;
-                let (mut pending_direct_message, best_channel_for_pending_message_idx, need_to_pop_direct_in_channel_from_idx) = intermediate_states.remove(0);
+                let (
+                    mut pending_direct_message,
+                    best_channel_for_pending_message_idx,
+                    need_to_pop_direct_in_channel_from_idx,
+                ) = intermediate_states.remove(0);
                 for (j, (_peer, out_channel)) in pu.direct_out_channels.iter().enumerate() {
                     let mut out_channel = out_channel.borrow_mut();
                     // push a message only if it's last message is taken by the peer
                     if out_channel.deque.len() < out_channel.latency {
-                        out_channel.deque.push_back(if best_channel_for_pending_message_idx == Some(j) {
-                            pending_message_sent_successfully = true;  // mark as sent successfully
-                            pending_direct_message.take()  // leaving a None in pending_direct_message
-                        } else {
-                            None
-                        });
+                        out_channel
+                            .deque
+                            .push_back(if best_channel_for_pending_message_idx == Some(j) {
+                                pending_message_sent_successfully = true; // mark as sent successfully
+                                pending_direct_message.take() // leaving a None in pending_direct_message
+                            } else {
+                                None
+                            });
                     }
                 }
                 // update internal state
-                if pending_message_sent_successfully {  // don't send again next time
+                if pending_message_sent_successfully {
+                    // don't send again next time
                     pu.pending_tell_new_root_cardinality = false;
                     pu.pending_tell_new_root_touching_boundary = false;
-                    match need_to_pop_direct_in_channel_from_idx {
-                        Some(direct_in_channel_idx) => {
-                            let (_peer, in_channel) = &pu.direct_in_channels[direct_in_channel_idx];
-                            let mut in_channel = in_channel.borrow_mut();
-                            // mark the original message as taken, but not change the amount of elements in the channel
-                            in_channel.deque.pop_front().unwrap();
-                            in_channel.deque.push_front(None);
-                        },
-                        None => { },
+                    if let Some(direct_in_channel_idx) = need_to_pop_direct_in_channel_from_idx {
+                        let (_peer, in_channel) = &pu.direct_in_channels[direct_in_channel_idx];
+                        let mut in_channel = in_channel.borrow_mut();
+                        // mark the original message as taken, but not change the amount of elements in the channel
+                        in_channel.deque.pop_front().unwrap();
+                        in_channel.deque.push_front(None);
                     }
                 }
             }
@@ -841,14 +944,14 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
         // first grow the clusters so that erasure errors are considered, O(1) time needed if no erasure error appears
         // during iteration, this corresponds to Union operations in sequential UF decoder and takes average O(log(d)) time but worst O(d) time
         clock_cycles += self.spread_clusters();
-        // update the odd cluster state, requires O(log(d)) time in 
+        // update the odd cluster state, requires O(log(d)) time in
         clock_cycles += self.spread_is_odd_cluster();
         // check if there are still odd clusters, if so, then it needs to run further
         let mut has_odd_cluster = false;
         for pu in self.processing_units.iter() {
             if pu.is_odd_cluster {
                 has_odd_cluster = true;
-                break
+                break;
             }
         }
         (clock_cycles, has_odd_cluster)
@@ -901,13 +1004,21 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
             let old_root_user_data = &self.nodes[pu.old_root].user_data;
             let error_symbol = if node.is_error_syndrome { "x" } else { " " };
             let odd_cluster_symbol = if pu.is_odd_cluster { "o" } else { " " };
-            let touching_boundary_symbol = if pu.updated_root == i && pu.is_touching_boundary { "t" } else { " " };
-            let odd_cardinality_symbol = if pu.updated_root == i && pu.is_odd_cardinality { "c" } else { " " };
+            let touching_boundary_symbol = if pu.updated_root == i && pu.is_touching_boundary {
+                "t"
+            } else {
+                " "
+            };
+            let odd_cardinality_symbol = if pu.updated_root == i && pu.is_odd_cardinality {
+                "c"
+            } else {
+                " "
+            };
             let boundary_string = match node.boundary_cost {
                 Some(boundary_cost) => {
                     format!("b({}/{})", pu.boundary_increased, boundary_cost)
-                },
-                None => format!("      "),
+                }
+                None => "      ".to_string(),
             };
             let neighbors_len = pu.neighbors.len();
             let mut neighbor_string = String::new();
@@ -918,21 +1029,31 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
                 let string = format!("{:?}[{}/{}] ", neighbor_user_data, edge.increased, edge.length);
                 neighbor_string.push_str(string.as_str());
             }
-            let debug_cardinality_string = if pu.updated_root == i { format!("[{}]", pu.debug_cardinality) } else { format!("   ") };
-            println!("{:?} ∈ updated {:?} {} old {:?} {} {} {} {} {} n: {}", node.user_data, updated_root_user_data, debug_cardinality_string, 
-                old_root_user_data, error_symbol, odd_cluster_symbol,
-                touching_boundary_symbol, odd_cardinality_symbol, boundary_string, neighbor_string);
+            let debug_cardinality_string = if pu.updated_root == i {
+                format!("[{}]", pu.debug_cardinality)
+            } else {
+                "   ".to_string()
+            };
+            println!(
+                "{:?} ∈ updated {:?} {} old {:?} {} {} {} {} {} n: {}",
+                node.user_data,
+                updated_root_user_data,
+                debug_cardinality_string,
+                old_root_user_data,
+                error_symbol,
+                odd_cluster_symbol,
+                touching_boundary_symbol,
+                odd_cardinality_symbol,
+                boundary_string,
+                neighbor_string
+            );
         }
         println!("[debug print end]");
     }
 
-    pub fn dump_print_input(&self, id:usize) {
+    pub fn dump_print_input(&self, id: usize) {
         println!("[dump print start] {}", id);
-        let mut file = OpenOptions::new()
-            .write(true)
-            .append(true)
-            .open("input.txt")
-            .unwrap();
+        let mut file = OpenOptions::new().write(true).append(true).open("input.txt").unwrap();
         if let Err(e) = writeln!(file, "{:08X}", id) {
             eprintln!("Couldn't write to file: {}", e);
         }
@@ -947,13 +1068,9 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
         }
     }
 
-    pub fn dump_print_output(&self, id : usize) {
+    pub fn dump_print_output(&self, id: usize) {
         println!("[dump print output] {}", id);
-        let mut file = OpenOptions::new()
-            .write(true)
-            .append(true)
-            .open("output.txt")
-            .unwrap();
+        let mut file = OpenOptions::new().write(true).append(true).open("output.txt").unwrap();
         if let Err(e) = writeln!(file, "{:08X}", id) {
             eprintln!("Couldn't write to file: {}", e);
         }
@@ -961,17 +1078,26 @@ impl<U: std::fmt::Debug> DistributedUnionFind<U> {
         for i in 0..nodes_len {
             let node = &self.processing_units[i];
             // println!("{} {}", (node.updated_root)/4, (node.updated_root)%4);
-            if let Err(e) = writeln!(file, "{:04X}{:04X}", (node.updated_root)/4, (node.updated_root)%4) {
+            if let Err(e) = writeln!(file, "{:04X}{:04X}", (node.updated_root) / 4, (node.updated_root) % 4) {
                 eprintln!("Couldn't write to file: {}", e);
             }
         }
     }
 }
 
+/// `(nodes, position_to_index, neighbors, fast_channels)` of a 2d code
+pub type Graph2D = (Nodes2D, PositionToIndex2D, Vec<InputNeighbor>, Vec<InputFastChannel>);
+/// nodes of a 2d code, each labeled by the position of the stabilizer
+pub type Nodes2D = Vec<InputNode<(usize, usize)>>;
+/// mapping from the 2d position of a stabilizer to the node index
+pub type PositionToIndex2D = HashMap<(usize, usize), usize>;
+
 /// create nodes for standard planar code (2d, perfect measurement condition). return only X stabilizers or only Z stabilizers.
 /// return (nodes, position_to_index, neighbors), the fast channel should be empty, which is Vec::new()
-pub fn make_standard_planar_code_2d_nodes_no_fast_channel(d: usize, is_x_stabilizers: bool) -> (Vec<InputNode<(usize, usize)>>, HashMap<(usize, usize), usize>,
-        Vec<InputNeighbor>) {
+pub fn make_standard_planar_code_2d_nodes_no_fast_channel(
+    d: usize,
+    is_x_stabilizers: bool,
+) -> (Nodes2D, PositionToIndex2D, Vec<InputNeighbor>) {
     let (nodes, position_to_index, neighbors, _fast_channels) = make_standard_planar_code_2d_nodes(d, is_x_stabilizers, 0);
     (nodes, position_to_index, neighbors)
 }
@@ -979,14 +1105,17 @@ pub fn make_standard_planar_code_2d_nodes_no_fast_channel(d: usize, is_x_stabili
 /// create nodes for standard planar code (2d, perfect measurement condition). return only X stabilizers or only Z stabilizers.
 /// return (nodes, position_to_index, neighbors, fast_channels), the fast channel is build every (fast_channel_interval) ^ k distance
 /// fast_channel_interval = 0 will generate no fast channels
-pub fn make_standard_planar_code_2d_nodes(d: usize, is_x_stabilizers: bool, fast_channel_interval: usize) -> (Vec<InputNode<(usize, usize)>>,
-HashMap<(usize, usize), usize>, Vec<InputNeighbor>, Vec<InputFastChannel>) {
+pub fn make_standard_planar_code_2d_nodes(d: usize, is_x_stabilizers: bool, fast_channel_interval: usize) -> Graph2D {
     let mut nodes = Vec::new();
     let mut position_to_index = HashMap::new();
-    for i in (if is_x_stabilizers { 0..=2*d-2 } else { 1..=2*d-3 }).step_by(2) {
-        for j in (if is_x_stabilizers { 1..=2*d-3 } else { 0..=2*d-2 }).step_by(2) {
+    for i in (if is_x_stabilizers { 0..=2 * d - 2 } else { 1..=2 * d - 3 }).step_by(2) {
+        for j in (if is_x_stabilizers { 1..=2 * d - 3 } else { 0..=2 * d - 2 }).step_by(2) {
             position_to_index.insert((i, j), nodes.len());
-            let is_boundary = if is_x_stabilizers { j == 1 || j == 2*d-3 } else { i == 1 || i == 2*d-3 };
+            let is_boundary = if is_x_stabilizers {
+                j == 1 || j == 2 * d - 3
+            } else {
+                i == 1 || i == 2 * d - 3
+            };
             nodes.push(InputNode {
                 user_data: (i, j),
                 is_error_syndrome: false,
@@ -996,12 +1125,12 @@ HashMap<(usize, usize), usize>, Vec<InputNeighbor>, Vec<InputFastChannel>) {
     }
     let mut neighbors = Vec::new();
     let mut fast_channels = Vec::new();
-    for i in (if is_x_stabilizers { 0..=2*d-2 } else { 1..=2*d-3 }).step_by(2) {
-        for j in (if is_x_stabilizers { 1..=2*d-3 } else { 0..=2*d-2 }).step_by(2) {
+    for i in (if is_x_stabilizers { 0..=2 * d - 2 } else { 1..=2 * d - 3 }).step_by(2) {
+        for j in (if is_x_stabilizers { 1..=2 * d - 3 } else { 0..=2 * d - 2 }).step_by(2) {
             for (di, dj) in [(2, 0), (0, 2)].iter() {
                 let ni = i + di;
                 let nj = j + dj;
-                if ni <= 2*d-2 && nj <= 2*d-2 {
+                if ni <= 2 * d - 2 && nj <= 2 * d - 2 {
                     neighbors.push(InputNeighbor {
                         a: position_to_index[&(i, j)],
                         b: position_to_index[&(ni, nj)],
@@ -1016,14 +1145,14 @@ HashMap<(usize, usize), usize>, Vec<InputNeighbor>, Vec<InputFastChannel>) {
                 let mut interval = fast_channel_interval;
                 loop {
                     let fi = i + interval;
-                    if fi <= 2*d-2 {
+                    if fi <= 2 * d - 2 {
                         fast_channels.push(InputFastChannel {
                             a: position_to_index[&(i, j)],
                             b: position_to_index[&(fi, j)],
                             latency: 1,
                         })
                     } else {
-                        break
+                        break;
                     }
                     interval *= fast_channel_interval;
                 }
@@ -1031,14 +1160,14 @@ HashMap<(usize, usize), usize>, Vec<InputNeighbor>, Vec<InputFastChannel>) {
                 let mut interval = fast_channel_interval;
                 loop {
                     let fj = j + interval;
-                    if fj <= 2*d-2 {
+                    if fj <= 2 * d - 2 {
                         fast_channels.push(InputFastChannel {
                             a: position_to_index[&(i, j)],
                             b: position_to_index[&(i, fj)],
                             latency: 1,
                         })
                     } else {
-                        break
+                        break;
                     }
                     interval *= fast_channel_interval;
                 }
@@ -1051,39 +1180,46 @@ HashMap<(usize, usize), usize>, Vec<InputNeighbor>, Vec<InputFastChannel>) {
 pub fn manhattan_distance_standard_planar_code_2d_nodes(a: &(usize, usize), b: &(usize, usize)) -> usize {
     let (i1, j1) = *a;
     let (i2, j2) = *b;
-    let di = (i1 as isize - i2 as isize).abs() as usize;
-    let dj = (j1 as isize - j2 as isize).abs() as usize;
-    assert!(di % 2 == 0 && dj % 2 == 0, "cannot compute cost between different types of stabilizers");
+    let di = i1.abs_diff(i2);
+    let dj = j1.abs_diff(j2);
+    assert!(
+        di % 2 == 0 && dj % 2 == 0,
+        "cannot compute cost between different types of stabilizers"
+    );
     (di + dj) / 2
 }
 
 pub fn compare_standard_planar_code_2d_nodes(a: &(usize, usize), b: &(usize, usize)) -> Ordering {
     let (i1, j1) = *a;
     let (i2, j2) = *b;
-    if i1 < i2 {
-        Ordering::Less
-    } else if i1 > i2 {
-        Ordering::Greater
-    } else {
-        j1.cmp(&j2)
+    match i1.cmp(&i2) {
+        Ordering::Equal => j1.cmp(&j2),
+        ordering => ordering,
     }
 }
 
-pub fn get_standard_planar_code_2d_left_boundary_cardinality(d: usize, position_to_index: &HashMap<(usize, usize), usize>
-        , decoder: &DistributedUnionFind<(usize, usize)>, get_top_boundary_instead: bool) -> usize {
+pub fn get_standard_planar_code_2d_left_boundary_cardinality(
+    d: usize,
+    position_to_index: &HashMap<(usize, usize), usize>,
+    decoder: &DistributedUnionFind<(usize, usize)>,
+    get_top_boundary_instead: bool,
+) -> usize {
     let mut boundary_cardinality = 0;
     let mut counted_sets = HashSet::new();
-    for index in (0..=2*d-2).step_by(2) {
+    for index in (0..=2 * d - 2).step_by(2) {
         let i = if get_top_boundary_instead { 1 } else { index };
         let j = if get_top_boundary_instead { index } else { 1 };
         let index = position_to_index[&(i, j)];
         let pu = &decoder.processing_units[index];
         let root = pu.updated_root;
-        if counted_sets.get(&root).is_none() {  // every set should only be counted once
+        if counted_sets.get(&root).is_none() {
+            // every set should only be counted once
             let node = &decoder.nodes[index];
-            if pu.boundary_increased >= node.boundary_cost.unwrap() {  // only when this node is bleeding into the boundary
+            if pu.boundary_increased >= node.boundary_cost.unwrap() {
+                // only when this node is bleeding into the boundary
                 let root_pu = &decoder.processing_units[root];
-                if root_pu.is_odd_cardinality {  // connect to boundary only if the cardinality is odd
+                if root_pu.is_odd_cardinality {
+                    // connect to boundary only if the cardinality is odd
                     counted_sets.insert(root);
                     boundary_cardinality += 1;
                 }
@@ -1093,249 +1229,15 @@ pub fn get_standard_planar_code_2d_left_boundary_cardinality(d: usize, position_
     boundary_cardinality
 }
 
-/// return `(has_x_logical_error, has_z_logical_error, cycle)`
-pub fn run_given_offer_decoder_instance_with_cycle(decoder: &mut offer_decoder::OfferDecoder, fast_channel_interval: usize) -> (bool, bool, usize) {
-    let d = decoder.d;
-    decoder.error_changed();
-    // decode X errors
-    let (mut nodes, position_to_index, neighbors, fast_channels) = make_standard_planar_code_2d_nodes(d, true, fast_channel_interval);
-    for i in (0..=2*d-2).step_by(2) {
-        for j in (1..=2*d-3).step_by(2) {
-            if decoder.qubits[i][j].measurement {
-                nodes[position_to_index[&(i, j)]].is_error_syndrome = true;
-            }
-        }
-    }
-    let mut uf_decoder = DistributedUnionFind::new(nodes, neighbors, fast_channels, manhattan_distance_standard_planar_code_2d_nodes,
-        compare_standard_planar_code_2d_nodes);
-    let cycle_x = uf_decoder.run_to_stable();
-    let left_boundary_cardinality = get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &uf_decoder, false)
-        + decoder.origin_error_left_boundary_cardinality();
-    let has_x_logical_error = left_boundary_cardinality % 2 == 1;
-    // decode Z errors
-    let (mut nodes, position_to_index, neighbors, fast_channels) = make_standard_planar_code_2d_nodes(d, false, fast_channel_interval);
-    for i in (1..=2*d-3).step_by(2) {
-        for j in (0..=2*d-2).step_by(2) {
-            if decoder.qubits[i][j].measurement {
-                nodes[position_to_index[&(i, j)]].is_error_syndrome = true;
-            }
-        }
-    }
-    let mut uf_decoder = DistributedUnionFind::new(nodes, neighbors, fast_channels, manhattan_distance_standard_planar_code_2d_nodes,
-        compare_standard_planar_code_2d_nodes);
-    let cycle_z = uf_decoder.run_to_stable();
-    let top_boundary_cardinality = get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &uf_decoder, true)
-        + decoder.origin_error_top_boundary_cardinality();
-    let has_z_logical_error = top_boundary_cardinality % 2 == 1;
-    (has_x_logical_error, has_z_logical_error, std::cmp::max(cycle_x, cycle_z))
-}
-
-/// return `(has_x_logical_error, has_z_logical_error, cycle)`
-pub fn run_given_offer_decoder_instance_no_fast_channel_with_cycle(decoder: &mut offer_decoder::OfferDecoder) -> (bool, bool, usize) {
-    run_given_offer_decoder_instance_with_cycle(decoder, 0)
-}
-
-/// return `(has_x_logical_error, has_z_logical_error)`
-pub fn run_given_offer_decoder_instance_no_fast_channel(decoder: &mut offer_decoder::OfferDecoder) -> (bool, bool) {
-    let (has_x_logical_error, has_z_logical_error, _cycle) = run_given_offer_decoder_instance_no_fast_channel_with_cycle(decoder);
-    (has_x_logical_error, has_z_logical_error)
-}
-
-/// return (nodes, position_to_index, neighbors)
-pub fn make_decoder_given_ftqec_model(model: &ftqec::PlanarCodeModel, stabilizer: QubitType, fast_channel_interval: usize) ->
-        (Vec<InputNode<(usize, usize, usize)>>, HashMap<(usize, usize, usize), usize>, Vec<InputNeighbor>, Vec<InputFastChannel>) {
-    assert!(stabilizer == QubitType::StabZ || stabilizer == QubitType::StabX, "stabilizer must be either StabZ or StabX");
-    assert!(fast_channel_interval <= 1, "fast channel not supported yet");
-    let mut nodes = Vec::new();
-    let mut position_to_index = HashMap::new();
-    model.iterate_measurement_stabilizers(|t, i, j, node| {
-        if t > 12 && node.qubit_type == stabilizer {  // ignore the bottom layer
-            position_to_index.insert((t, i, j), nodes.len());
-            nodes.push(InputNode {
-                user_data: (t, i, j),
-                is_error_syndrome: false,
-                boundary_cost: if node.boundary.is_some() { Some(2) } else { None },
-            });
-        }
-    });
-    model.iterate_measurement_errors(|t, i, j, node| {
-        if t > 12 && node.qubit_type == stabilizer {  // ignore the bottom layer
-            nodes[position_to_index[&(t, i, j)]].is_error_syndrome = true;
-        }
-    });
-    let mut neighbors = Vec::new();
-    let fast_channels = Vec::new();
-    model.iterate_measurement_stabilizers(|t, i, j, node| {
-        if t > 12 && node.qubit_type == stabilizer {  // ignore the bottom layer
-            let idx = position_to_index[&(t, i, j)];
-            for edge in node.edges.iter() {
-                if edge.t > 12 {
-                    let peer_idx = position_to_index[&(edge.t, edge.i, edge.j)];
-                    if idx < peer_idx {  // remove duplicated neighbors
-                        neighbors.push(InputNeighbor {
-                            a: idx,
-                            b: peer_idx,
-                            increased: 0,
-                            length: 2,
-                            latency: 1,
-                        });
-                    }
-
-                } else {
-                    nodes[idx].boundary_cost = Some(2);  // viewing the bottom layer as boundary
-                }
-            }
-        }
-    });
-    assert!(neighbors.len() > 0, "ftqec model may not have `build_graph` called, so the neighbor connections have not built yet");
-    (nodes, position_to_index, neighbors, fast_channels)
-}
-
-#[allow(dead_code)]
-pub fn get_standard_planar_code_3d_boundary_cardinality(stabilizer: QubitType, position_to_index: &HashMap<(usize, usize, usize), usize>
-        , decoder: &DistributedUnionFind<(usize, usize, usize)>) -> usize {
-    let mut boundary_cardinality = 0;
-    let mut counted_sets = HashSet::new();
-    let mut counting_indices = Vec::new();
-    for ((_t, i, j), index) in position_to_index.iter() {
-        match stabilizer {
-            QubitType::StabZ => {
-                if *j == 1 {
-                    counting_indices.push(*index);
-                }
-            },
-            QubitType::StabX => {
-                if *i == 1 {
-                    counting_indices.push(*index);
-                }
-            },
-            _ => panic!("unsupported stabilizer type")
-        }
-    }
-    for index in counting_indices.into_iter() {
-        let pu = &decoder.processing_units[index];
-        let root = pu.updated_root;
-        if counted_sets.get(&root).is_none() {  // every set should only be counted once
-            let node = &decoder.nodes[index];
-            if pu.boundary_increased >= node.boundary_cost.unwrap() {  // only when this node is bleeding into the boundary
-                let root_pu = &decoder.processing_units[root];
-                if root_pu.is_odd_cardinality {  // connect to boundary only if the cardinality is odd
-                    counted_sets.insert(root);
-                    boundary_cardinality += 1;
-                }
-            }
-        }
-    }
-    boundary_cardinality
-}
-
-pub fn manhattan_distance_standard_planar_code_3d_nodes(a: &(usize, usize, usize), b: &(usize, usize, usize)) -> usize {
-    let (t1, i1, j1) = *a;
-    let (t2, i2, j2) = *b;
-    let dt_origin = (t1 as isize - t2 as isize).abs() as usize;
-    assert!(dt_origin % 6 == 0, "dt should only be 6x");
-    let dt = dt_origin / 6;
-    let di = (i1 as isize - i2 as isize).abs() as usize;
-    let dj = (j1 as isize - j2 as isize).abs() as usize;
-    assert!(di % 2 == 0 && dj % 2 == 0, "cannot compute cost between different types of stabilizers");
-    (di + dj) / 2 + dt
-}
-
-pub fn compare_standard_planar_code_3d_nodes(a: &(usize, usize, usize), b: &(usize, usize, usize)) -> Ordering {
-    let (t1, i1, j1) = *a;
-    let (t2, i2, j2) = *b;
-    let sum1 = t1 / 6 + i1 / 2 + j1 / 2;
-    let sum2 = t2 / 6 + i2 / 2 + j2 / 2;
-    if sum1 < sum2 {
-        Ordering::Less
-    } else if sum1 > sum2 {
-        Ordering::Greater
-    } else {
-        if i1 < i2 {
-            Ordering::Less
-        } else if i1 > i2 {
-            Ordering::Greater
-        } else {
-            j1.cmp(&j2)
-        }
-    }
-}
-
-pub fn build_distributed_union_find_given_uf_decoder_3d<>(decoder: &union_find_decoder::UnionFindDecoder<(usize, usize, usize)>) ->
-        (DistributedUnionFind<(usize, usize, usize)>, HashMap<(usize, usize, usize), usize>) {
-    let mut nodes = Vec::new();
-    let mut position_to_index = HashMap::new();
-    for node in decoder.nodes.iter() {
-        let position = node.node.user_data.clone();
-        position_to_index.insert(position.clone(), nodes.len());
-        nodes.push(InputNode {
-            user_data: position,
-            is_error_syndrome: node.node.is_error_syndrome,
-            boundary_cost: node.node.boundary_cost.clone(),
-        });
-    }
-    let mut neighbors = Vec::new();
-    for neighbor in decoder.input_neighbors.iter() {
-        neighbors.push(InputNeighbor {
-            a: neighbor.a,
-            b: neighbor.b,
-            increased: 0,
-            length: neighbor.length,
-            latency: 1,
-        });
-    }
-    let duf_decoder = DistributedUnionFind::new(nodes, neighbors, vec![], manhattan_distance_standard_planar_code_3d_nodes,
-        compare_standard_planar_code_3d_nodes);
-    (duf_decoder, position_to_index)
-}
-
-pub fn copy_state_back_to_union_find_decoder(decoder: &mut union_find_decoder::UnionFindDecoder<(usize, usize, usize)>, 
-        duf_decoder: &DistributedUnionFind<(usize, usize, usize)>) {
-    // copy root, but the root might be changed internally by UnionFind library
-    assert_eq!(decoder.nodes.len(), duf_decoder.nodes.len(), "they should have the same number of nodes");
-    for a in 0..decoder.nodes.len() {
-        let duf_processing_unit = &duf_decoder.processing_units[a];
-        let b = duf_processing_unit.updated_root;
-        decoder.union_find.union(a, b);
-    }
-    // copy cardinality and is_touching_boundary
-    for a in 0..decoder.nodes.len() {
-        let duf_processing_unit = &duf_decoder.processing_units[a];
-        let duf_updated_root = duf_processing_unit.updated_root;
-        let duf_root_processing_unit = &duf_decoder.processing_units[duf_updated_root];
-        let uf_union_node = decoder.union_find.get_mut(a);
-        uf_union_node.cardinality = duf_root_processing_unit.debug_cardinality;  // accurate cardinality
-        // uf_union_node.cardinality = if duf_root_processing_unit.is_odd_cardinality { 1 } else { 0 };  // or just use parity of cardinality
-        uf_union_node.is_touching_boundary = duf_root_processing_unit.is_touching_boundary;
-    }
-    // copy boundary increase and boundary state
-    for a in 0..decoder.nodes.len() {
-        let duf_processing_unit = &duf_decoder.processing_units[a];
-        let uf_decoder_node = &mut decoder.nodes[a];
-        uf_decoder_node.boundary_increased = duf_processing_unit.boundary_increased;
-    }
-    // copy neighbor increase and neighbor state
-    for a in 0..decoder.nodes.len() {
-        let duf_processing_unit = &duf_decoder.processing_units[a];
-        let uf_decoder_node = &mut decoder.nodes[a];
-        for duf_neighbor in duf_processing_unit.neighbors.iter() {
-            let uf_neighbor_index = uf_decoder_node.neighbor_index[&duf_neighbor.address];
-            let uf_neighbor_partial_edge = &mut uf_decoder_node.neighbors[uf_neighbor_index];
-            assert_eq!(uf_neighbor_partial_edge.address, duf_neighbor.address);
-            assert_eq!(uf_neighbor_partial_edge.length, duf_neighbor.link.borrow().length);
-            uf_neighbor_partial_edge.increased = duf_neighbor.link.borrow().increased;
-        }
-    }
-}
-
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     // use `cargo test distributed_union_find_decoder_test_case_1 -- --nocapture` to run specific test
-    
-    fn make_standard_planar_code_2d_nodes_no_fast_channel_only_x(d: usize) -> (Vec<InputNode<(usize, usize)>>, HashMap<(usize, usize), usize>, Vec<InputNeighbor>) {
+
+    fn make_standard_planar_code_2d_nodes_no_fast_channel_only_x(
+        d: usize,
+    ) -> (Nodes2D, PositionToIndex2D, Vec<InputNeighbor>) {
         make_standard_planar_code_2d_nodes_no_fast_channel(d, true)
     }
 
@@ -1347,16 +1249,33 @@ mod tests {
         println!("position_to_index: {:?}", position_to_index);
         println!("neighbors: {:?}", neighbors);
         // add duplicate neighbor edge
-        neighbors.push(InputNeighbor::new(position_to_index[&(0, 3)], position_to_index[&(0, 1)], 1000, 1000, 1));
+        neighbors.push(InputNeighbor::new(
+            position_to_index[&(0, 3)],
+            position_to_index[&(0, 1)],
+            1000,
+            1000,
+            1,
+        ));
         // should then panic
-        DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes, compare_standard_planar_code_2d_nodes);
+        DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
     }
 
     #[test]
     fn distributed_union_find_decoder_sanity_check_2() {
         let (nodes, _position_to_index, neighbors) = make_standard_planar_code_2d_nodes_no_fast_channel_only_x(3);
-        let distributed_union_find = DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes,
-            compare_standard_planar_code_2d_nodes);
+        let distributed_union_find = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         distributed_union_find.debug_print();
     }
 
@@ -1364,25 +1283,30 @@ mod tests {
     fn distributed_union_find_decoder_sanity_check_3() {
         // test `spread_is_odd_cluster` function
         let (mut nodes, position_to_index, neighbors) = make_standard_planar_code_2d_nodes_no_fast_channel_only_x(3);
-        nodes[position_to_index[&(0, 1)]].is_error_syndrome = true;  // test touching boundary
-        nodes[position_to_index[&(2, 3)]].is_error_syndrome = true;  // test single error syndrome
-        nodes[position_to_index[&(4, 1)]].is_error_syndrome = true;  // test 2 matching together
+        nodes[position_to_index[&(0, 1)]].is_error_syndrome = true; // test touching boundary
+        nodes[position_to_index[&(2, 3)]].is_error_syndrome = true; // test single error syndrome
+        nodes[position_to_index[&(4, 1)]].is_error_syndrome = true; // test 2 matching together
         nodes[position_to_index[&(4, 3)]].is_error_syndrome = true;
-        let mut distributed_union_find = DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes,          
-            compare_standard_planar_code_2d_nodes);
-        assert_eq!(distributed_union_find.processing_units[position_to_index[&(4, 1)]].is_odd_cluster, true);
-        assert_eq!(distributed_union_find.processing_units[position_to_index[&(4, 3)]].is_odd_cluster, true);
+        let mut distributed_union_find = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
+        assert!(distributed_union_find.processing_units[position_to_index[&(4, 1)]].is_odd_cluster);
+        assert!(distributed_union_find.processing_units[position_to_index[&(4, 3)]].is_odd_cluster);
         distributed_union_find.processing_units[position_to_index[&(0, 1)]].is_touching_boundary = true;
         distributed_union_find.processing_units[position_to_index[&(4, 3)]].old_root = position_to_index[&(4, 1)];
         distributed_union_find.processing_units[position_to_index[&(4, 3)]].updated_root = position_to_index[&(4, 1)];
-        distributed_union_find.processing_units[position_to_index[&(4, 1)]].is_odd_cardinality = false;  // because the set has (4, 1) and (4, 3)
+        distributed_union_find.processing_units[position_to_index[&(4, 1)]].is_odd_cardinality = false; // because the set has (4, 1) and (4, 3)
         distributed_union_find.channels_sanity_check();
         distributed_union_find.spread_is_odd_cluster();
         distributed_union_find.channels_sanity_check();
-        assert_eq!(distributed_union_find.processing_units[position_to_index[&(4, 1)]].is_odd_cluster, false);
-        assert_eq!(distributed_union_find.processing_units[position_to_index[&(4, 3)]].is_odd_cluster, false);
-        assert_eq!(distributed_union_find.processing_units[position_to_index[&(0, 1)]].is_odd_cluster, false);
-        assert_eq!(distributed_union_find.processing_units[position_to_index[&(2, 3)]].is_odd_cluster, true);
+        assert!(!distributed_union_find.processing_units[position_to_index[&(4, 1)]].is_odd_cluster);
+        assert!(!distributed_union_find.processing_units[position_to_index[&(4, 3)]].is_odd_cluster);
+        assert!(!distributed_union_find.processing_units[position_to_index[&(0, 1)]].is_odd_cluster);
+        assert!(distributed_union_find.processing_units[position_to_index[&(2, 3)]].is_odd_cluster);
         distributed_union_find.debug_print();
     }
 
@@ -1390,17 +1314,22 @@ mod tests {
     fn distributed_union_find_decoder_sanity_check_4() {
         // test `spread_clusters` function
         let (mut nodes, position_to_index, neighbors) = make_standard_planar_code_2d_nodes_no_fast_channel_only_x(3);
-        nodes[position_to_index[&(2, 1)]].is_error_syndrome = true;  // test single error syndrome
-        let mut distributed_union_find = DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes,          
-            compare_standard_planar_code_2d_nodes);
+        nodes[position_to_index[&(2, 1)]].is_error_syndrome = true; // test single error syndrome
+        let mut distributed_union_find = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         distributed_union_find.debug_print();
         distributed_union_find.reach_consistent_state();
         distributed_union_find.debug_print();
         let (_, need_to_run_another) = distributed_union_find.run_single_iteration();
-        assert_eq!(need_to_run_another, true, "1 iteration is not enough");
+        assert!(need_to_run_another, "1 iteration is not enough");
         distributed_union_find.debug_print();
         let (_, need_to_run_another) = distributed_union_find.run_single_iteration();
-        assert_eq!(need_to_run_another, false, "2 iterations should be enough");
+        assert!(!need_to_run_another, "2 iterations should be enough");
         distributed_union_find.debug_print();
     }
 
@@ -1409,11 +1338,19 @@ mod tests {
         let d = 5;
         let (mut nodes, position_to_index, neighbors) = make_standard_planar_code_2d_nodes_no_fast_channel_only_x(d);
         nodes[position_to_index[&(4, 5)]].is_error_syndrome = true;
-        let mut decoder = DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes,          
-            compare_standard_planar_code_2d_nodes);
+        let mut decoder = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         decoder.detailed_print_run_to_stable(true);
-        assert_eq!(0, get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false)
-            , "cardinality of one side of boundary determines if there is logical error");
+        assert_eq!(
+            0,
+            get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false),
+            "cardinality of one side of boundary determines if there is logical error"
+        );
     }
 
     #[test]
@@ -1424,11 +1361,19 @@ mod tests {
         assert_eq!(neighbors.len(), 7, "d=3 should have 7 direct neighbor connections");
         nodes[position_to_index[&(2, 1)]].is_error_syndrome = true;
         nodes[position_to_index[&(2, 3)]].is_error_syndrome = true;
-        let mut decoder = DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes,
-            compare_standard_planar_code_2d_nodes);
+        let mut decoder = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         decoder.detailed_print_run_to_stable(true);
-        assert_eq!(0, get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false)
-            , "cardinality of one side of boundary determines if there is logical error");
+        assert_eq!(
+            0,
+            get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false),
+            "cardinality of one side of boundary determines if there is logical error"
+        );
     }
 
     #[test]
@@ -1439,11 +1384,19 @@ mod tests {
         nodes[position_to_index[&(2, 3)]].is_error_syndrome = true;
         nodes[position_to_index[&(2, 5)]].is_error_syndrome = true;
         nodes[position_to_index[&(2, 7)]].is_error_syndrome = true;
-        let mut decoder = DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes,
-            compare_standard_planar_code_2d_nodes);
+        let mut decoder = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         decoder.detailed_print_run_to_stable(true);
-        assert_eq!(0, get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false)
-            , "cardinality of one side of boundary determines if there is logical error");
+        assert_eq!(
+            0,
+            get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false),
+            "cardinality of one side of boundary determines if there is logical error"
+        );
     }
 
     #[test]
@@ -1455,11 +1408,19 @@ mod tests {
         nodes[position_to_index[&(0, 5)]].is_error_syndrome = true;
         nodes[position_to_index[&(2, 3)]].is_error_syndrome = true;
         nodes[position_to_index[&(2, 5)]].is_error_syndrome = true;
-        let mut decoder = DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes,
-            compare_standard_planar_code_2d_nodes);
+        let mut decoder = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         decoder.detailed_print_run_to_stable(true);
-        assert_eq!(1, get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false)
-            , "cardinality of one side of boundary determines if there is logical error");
+        assert_eq!(
+            1,
+            get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false),
+            "cardinality of one side of boundary determines if there is logical error"
+        );
     }
 
     #[test]
@@ -1469,12 +1430,20 @@ mod tests {
         nodes[position_to_index[&(4, 3)]].is_error_syndrome = true;
         nodes[position_to_index[&(6, 5)]].is_error_syndrome = true;
         nodes[position_to_index[&(6, 7)]].is_error_syndrome = true;
-        let mut decoder = DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes,
-            compare_standard_planar_code_2d_nodes);
+        let mut decoder = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         decoder.detailed_print_run_to_stable(true);
         decoder.debug_print();
-        assert_eq!(1, get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false)
-            , "cardinality of one side of boundary determines if there is logical error");
+        assert_eq!(
+            1,
+            get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false),
+            "cardinality of one side of boundary determines if there is logical error"
+        );
     }
 
     #[test]
@@ -1501,12 +1470,20 @@ mod tests {
         nodes[position_to_index[&(8, 3)]].is_error_syndrome = true;
         nodes[position_to_index[&(8, 5)]].is_error_syndrome = true;
         nodes[position_to_index[&(8, 7)]].is_error_syndrome = true;
-        let mut decoder = DistributedUnionFind::new(nodes, neighbors, Vec::new(), manhattan_distance_standard_planar_code_2d_nodes,
-            compare_standard_planar_code_2d_nodes);
+        let mut decoder = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         decoder.detailed_print_run_to_stable(true);
         decoder.debug_print();
-        assert_eq!(1, get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false)
-            , "cardinality of one side of boundary determines if there is logical error");
+        assert_eq!(
+            1,
+            get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false),
+            "cardinality of one side of boundary determines if there is logical error"
+        );
     }
 
     #[test]
@@ -1519,85 +1496,48 @@ mod tests {
         nodes[position_to_index[&(0, 5)]].is_error_syndrome = true;
         nodes[position_to_index[&(2, 3)]].is_error_syndrome = true;
         nodes[position_to_index[&(2, 5)]].is_error_syndrome = true;
-        let mut decoder = DistributedUnionFind::new(nodes, neighbors, fast_channels, manhattan_distance_standard_planar_code_2d_nodes,
-            compare_standard_planar_code_2d_nodes);
+        let mut decoder = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            fast_channels,
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         decoder.detailed_print_run_to_stable(true);
-        assert_eq!(1, get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false)
-            , "cardinality of one side of boundary determines if there is logical error");
+        assert_eq!(
+            1,
+            get_standard_planar_code_2d_left_boundary_cardinality(d, &position_to_index, &decoder, false),
+            "cardinality of one side of boundary determines if there is logical error"
+        );
     }
 
     #[test]
-    fn distributed_union_find_decoder_test_build_given_ftqec_model_1() {
-        let measurement_rounds = 3;
-        let d = 3;
-        let p = 0.01;  // physical error rate
-        let mut model = ftqec::PlanarCodeModel::new_standard_planar_code(measurement_rounds, d);
-        model.set_phenomenological_error_with_perfect_initialization(p);
-        model.build_graph(ftqec::weight_autotune);
-        let el2t = |layer| layer * 6usize + 18 - 1;  // error from layer 0 is at t = 18-1 = 17
-        model.add_error_at(el2t(0), 0, 2, &ErrorType::X).expect("error rate = 0 here");  // data qubit error (detected by next layer)
-        model.add_error_at(el2t(1), 2, 3, &ErrorType::X).expect("error rate = 0 here");  // measurement error (detected by this and next layer)
-        model.propagate_error();
-        let (nodes, position_to_index, neighbors, fast_channels) = make_decoder_given_ftqec_model(&model, QubitType::StabZ, 1);
-        assert_eq!(d * (d - 1) * measurement_rounds, nodes.len());
-        assert_eq!((measurement_rounds * (d * (d - 1) * 2 - d - (d - 1)) + (measurement_rounds - 1) * d * (d - 1)), neighbors.len());
-        let mut decoder = DistributedUnionFind::new(nodes, neighbors, fast_channels, manhattan_distance_standard_planar_code_3d_nodes,
-            compare_standard_planar_code_3d_nodes);
-        decoder.detailed_print_run_to_stable(true);
-        assert_eq!(0, get_standard_planar_code_3d_boundary_cardinality(QubitType::StabZ, &position_to_index, &decoder)
-            , "cardinality of one side of boundary determines if there is logical error");
-    }
-
-    #[test]
-    fn distributed_union_find_decoder_test_build_given_ftqec_model_2() {
-        let measurement_rounds = 3;
-        let d = 3;
-        let p = 0.01;  // physical error rate
-        let mut model = ftqec::PlanarCodeModel::new_standard_planar_code(measurement_rounds, d);
-        model.set_phenomenological_error_with_perfect_initialization(p);
-        model.build_graph(ftqec::weight_autotune);
-        let el2t = |layer| layer * 6usize + 18 - 1;  // error from layer 0 is at t = 18-1 = 17
-        model.add_error_at(el2t(0), 0, 0, &ErrorType::X).expect("error rate = 0 here");  // data qubit error (detected by next layer)
-        model.propagate_error();
-        let (nodes, position_to_index, neighbors, fast_channels) = make_decoder_given_ftqec_model(&model, QubitType::StabZ, 1);
-        assert_eq!(d * (d - 1) * measurement_rounds, nodes.len());
-        assert_eq!((measurement_rounds * (d * (d - 1) * 2 - d - (d - 1)) + (measurement_rounds - 1) * d * (d - 1)), neighbors.len());
-        let mut decoder = DistributedUnionFind::new(nodes, neighbors, fast_channels, manhattan_distance_standard_planar_code_3d_nodes,
-            compare_standard_planar_code_3d_nodes);
+    fn distributed_union_find_decoder_test_case_7() {
+        // a cluster whose root stays the same when merging through a newly grown edge must still spread its root,
+        // otherwise the other cluster keeps a stale root that is no longer a root and the cardinality is counted twice
+        let d = 5;
+        let (mut nodes, position_to_index, neighbors) = make_standard_planar_code_2d_nodes_no_fast_channel_only_x(d);
+        for position in [(0, 1), (2, 1), (2, 3), (6, 1), (6, 3), (6, 5), (6, 7), (8, 1)] {
+            nodes[position_to_index[&position]].is_error_syndrome = true;
+        }
+        let mut decoder = DistributedUnionFind::new(
+            nodes,
+            neighbors,
+            Vec::new(),
+            manhattan_distance_standard_planar_code_2d_nodes,
+            compare_standard_planar_code_2d_nodes,
+        );
         decoder.detailed_print_run_to_stable(true);
-        assert_eq!(1, get_standard_planar_code_3d_boundary_cardinality(QubitType::StabZ, &position_to_index, &decoder)
-            , "cardinality of one side of boundary determines if there is logical error");
-    }
-
-    #[test]
-    fn distributed_union_find_decoder_test_xzzx_code_1() {
-        let di = 3;
-        let dj = 3;
-        let measurement_rounds = 3;
-        let bias_zeta = 100.;  // definition of zeta see arXiv:2104.09539v1
-        let p = 0.006;
-        let max_half_weight = 4;
-        let mut model = ftqec::PlanarCodeModel::new_standard_XZZX_code_rectangle(measurement_rounds, di, dj);
-        model.apply_noise_model(&NoiseModelName::GenericBiasedWithBiasedCX, None, p, bias_zeta, 0.);
-        model.build_graph(ftqec::weight_autotune);
-        // model.optimize_correction_pattern();  // no need if not building corrections
-        // model.build_exhausted_path();
-        let use_random_error = true;
-        if use_random_error {
-            let mut rng = thread_rng();
-            let error_count = model.generate_random_errors(|| rng.gen::<f64>());
-            println!("error_count: {}", error_count);
+        let mut cardinality = HashMap::<usize, usize>::new();
+        for (index, processing_unit) in decoder.processing_units.iter().enumerate() {
+            let root = processing_unit.updated_root;
+            assert_eq!(decoder.processing_units[root].updated_root, root, "the root of every node must be a root");
+            if decoder.nodes[index].is_error_syndrome {
+                *cardinality.entry(root).or_default() += 1;
+            }
         }
-        model.propagate_error();
-        let measurement = model.generate_measurement();
-        let decoders = union_find_decoder::suboptimal_matching_by_union_find_given_measurement_build_decoders(&model, &measurement
-            , &ftqec::DetectedErasures::new(di, dj), max_half_weight);
-        for mut decoder in decoders.into_iter() {
-            // build distributed union-find decoder from union-find decoder
-            let (mut duf_decoder, _position_to_index) = build_distributed_union_find_given_uf_decoder_3d(&decoder);
-            duf_decoder.detailed_print_run_to_stable(true);
-            copy_state_back_to_union_find_decoder(&mut decoder, &duf_decoder);
+        for (root, cardinality) in cardinality.into_iter() {
+            assert_eq!(decoder.processing_units[root].debug_cardinality, cardinality);
         }
     }
-
 }
@@ -5,6 +5,8 @@
 //!
 //! For MWPM decoder and UF decoder, if an erasure happens at a specific position, some edges will be modified to weight 0.
 //! this module calculates the set of such edges for each position, and can be quickly retrieved during simulations.
+//! When the herald is imperfect or the erasure leaves a biased Pauli error, each edge instead carries the probability
+//! of being flipped given the herald, which decoders translate into a reduced (not necessarily 0) weight.
 //!
 
 use super::noise_model::*;
@@ -35,6 +37,18 @@ pub enum ErasureEdge {
 pub struct ErasureGraphNode {
     /// erasure generated connections, generated from Pauli X, Z and Y errors
     pub erasure_edges: Vec<ErasureEdge>,
+    /// the probability that each erasure edge is flipped given a herald at this node, 0.5 for a perfect herald and a fully
+    /// depolarizing erasure, which gives weight 0
+    pub erasure_edge_probabilities: Vec<f64>,
+}
+
+/// the weight of an erasure edge given the probability of being flipped; never negative so that it works with decoders
+pub fn erasure_edge_weight(probability: f64) -> f64 {
+    if probability >= 0.5 {
+        0.
+    } else {
+        ((1. - probability) / probability).ln()
+    }
 }
 
 impl ErasureGraph {
@@ -94,8 +108,10 @@ impl ErasureGraph {
             }
             let noise_model_node = noise_model.get_node_unwrap(position);
             // whether it's possible to have erasure error at this node
-            let possible_erasure_error =
-                noise_model_node.erasure_error_rate > 0. || noise_model_node.correlated_erasure_error_rates.is_some() || {
+            let possible_erasure_error = noise_model_node.erasure_error_rate > 0.
+                || noise_model_node.correlated_erasure_error_rates.is_some()
+                || noise_model_node.is_heralded()
+                || {
                     let node = simulator.get_node_unwrap(position);
                     if let Some(gate_peer) = node.gate_peer.as_ref() {
                         let peer_noise_model_node = noise_model.get_node_unwrap(gate_peer);
@@ -110,6 +126,8 @@ impl ErasureGraph {
                 };
             if possible_erasure_error {
                 let mut erasure_edges = Vec::new();
+                let mut edge_errors = Vec::new();
+                let mut error_defects = Vec::new();
                 for error in all_possible_errors.iter() {
                     // simulate the error and measure it
                     let mut sparse_errors = SparseErrorPattern::new();
@@ -118,6 +136,7 @@ impl ErasureGraph {
                     let (_sparse_correction, sparse_measurement_real, _sparse_measurement_virtual) =
                         simulator.fast_measurement_given_few_errors(&sparse_errors);
                     let sparse_measurement_real = sparse_measurement_real.to_vec();
+                    error_defects.push((*error, sparse_measurement_real.clone()));
                     if sparse_measurement_real.is_empty() {
                         // no way to detect it, ignore
                         continue;
@@ -126,6 +145,7 @@ impl ErasureGraph {
                         // boundary edge
                         let position = &sparse_measurement_real[0];
                         erasure_edges.push(ErasureEdge::Boundary(position.clone()));
+                        edge_errors.push(sparse_measurement_real.clone());
                     }
                     if sparse_measurement_real.len() == 2 {
                         // normal edge
@@ -137,10 +157,35 @@ impl ErasureGraph {
                         let is_same_type = node1.qubit_type == node2.qubit_type;
                         if is_same_type {
                             erasure_edges.push(ErasureEdge::Connection(position1.clone(), position2.clone()));
+                            edge_errors.push(sparse_measurement_real.clone());
                         }
                     }
                 }
-                self.nodes[position.t][position.i][position.j] = Some(Box::new(ErasureGraphNode { erasure_edges }))
+                let erasure_edge_probabilities = match &noise_model_node.erasure_model {
+                    Some(erasure_model) => {
+                        let reliability = erasure_model.herald_reliability(noise_model_node.erasure_probability());
+                        // an edge is flipped by any error whose defects include both ends of the edge
+                        let flip_probability = |rates: &PauliErrorRates, defects: &Vec<Position>| -> f64 {
+                            error_defects
+                                .iter()
+                                .filter(|(_, error_defects)| defects.iter().all(|defect| error_defects.contains(defect)))
+                                .map(|(error, _)| rates.error_rate(error))
+                                .sum()
+                        };
+                        edge_errors
+                            .iter()
+                            .map(|defects| {
+                                reliability * flip_probability(&erasure_model.pauli_error_rates, defects)
+                                    + (1. - reliability) * flip_probability(&noise_model_node.pauli_error_rates, defects)
+                            })
+                            .collect()
+                    }
+                    None => vec![0.5; erasure_edges.len()],
+                };
+                self.nodes[position.t][position.i][position.j] = Some(Box::new(ErasureGraphNode {
+                    erasure_edges,
+                    erasure_edge_probabilities,
+                }))
             }
         });
     }
@@ -219,6 +264,7 @@ impl ErasureGraph {
                             Some(json!({
                                 "position": position,
                                 "erasure_edges": node.erasure_edges,
+                                "erasure_edge_probabilities": node.erasure_edge_probabilities,
                             }))
                         } else {
                             None
@@ -0,0 +1,731 @@
+//! # FPGA Generator
+//!
+//! Generates Verilog of the [`DistributedUnionFind`] processing units and channels for a given code,
+//! instantiating the modules in `backend/verilated`. Each node of the [`DistributedUnionFindDecoder`] becomes a
+//! `processing_unit`, each neighbor link a `neighbor_link` with a union channel and a direct channel in both directions,
+//! and each fast channel a union channel and a direct channel in both directions.
+//!
+//! The co-simulation harness drives the generated top module under [Verilator](https://verilator.org) with the same
+//! stage sequence as [`DistributedUnionFindDecoder::run_to_stable`], and checks the root of every processing unit and
+//! the number of growing iterations against the Rust model.
+//!
+
+use super::decoder_distributed_union_find::*;
+use super::distributed_uf_decoder::*;
+use super::simulator::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// hardware description of a distributed union-find decoder
+#[derive(Debug, Clone)]
+pub struct FpgaGenerator {
+    /// name of the generated top module
+    pub module_name: String,
+    /// the coordinates `[k, i, j]` of each processing unit, where `k = 2 t / measurement_cycles` so that the Manhattan
+    /// distance computed by the hardware distance solver is exactly twice the [`lattice_distance`]
+    pub coordinates: Vec<[usize; 3]>,
+    /// bits of each coordinate; an address is `[k, i, j]` packed from the most significant bits, so that comparing addresses
+    /// is the same as comparing positions
+    pub per_dimension_width: usize,
+    /// the scaled cost of matching each node to the boundary, `None` if not connected to the boundary
+    pub boundary_costs: Vec<Option<usize>>,
+    /// neighbor links
+    pub neighbors: Vec<InputNeighbor>,
+    /// fast channels
+    pub fast_channels: Vec<InputFastChannel>,
+    /// the peer of each channel of a processing unit, neighbors first and then fast channels
+    pub channels: Vec<Vec<usize>>,
+    /// the number of neighbors of each processing unit, the first channels of [`FpgaGenerator::channels`]
+    pub neighbor_counts: Vec<usize>,
+    /// the channel index at both ends of each neighbor link
+    pub neighbor_channel_indices: Vec<(usize, usize)>,
+    /// the channel index at both ends of each fast channel
+    pub fast_channel_indices: Vec<(usize, usize)>,
+}
+
+impl FpgaGenerator {
+    /// describe the processing units and channels of a distributed union-find decoder
+    pub fn new(module_name: &str, decoder: &DistributedUnionFindDecoder) -> Result<Self, String> {
+        if !decoder.input_fast_channels.is_empty() && decoder.config.fast_channel_latency != 1 {
+            return Err(
+                "fast channels are a single register stage in hardware, only `fast_channel_latency` = 1 is supported"
+                    .to_string(),
+            );
+        }
+        let node_num = decoder.index_to_position.len();
+        let coordinates: Vec<[usize; 3]> = decoder
+            .index_to_position
+            .iter()
+            .map(|position| [2 * (position.t / decoder.measurement_cycles), position.i, position.j])
+            .collect();
+        let maximum_coordinate = coordinates.iter().flatten().cloned().max().unwrap_or(0);
+        let per_dimension_width = std::cmp::max(1, (usize::BITS - maximum_coordinate.leading_zeros()) as usize);
+        let mut channels = vec![Vec::<usize>::new(); node_num];
+        let mut neighbor_channel_indices = Vec::with_capacity(decoder.input_neighbors.len());
+        for neighbor in decoder.input_neighbors.iter() {
+            if neighbor.latency != 1 {
+                return Err(
+                    "neighbor links are a single register stage in hardware, only latency 1 is supported".to_string(),
+                );
+            }
+            neighbor_channel_indices.push((channels[neighbor.a].len(), channels[neighbor.b].len()));
+            channels[neighbor.a].push(neighbor.b);
+            channels[neighbor.b].push(neighbor.a);
+        }
+        let neighbor_counts: Vec<usize> = channels.iter().map(|peers| peers.len()).collect();
+        let mut fast_channel_indices = Vec::with_capacity(decoder.input_fast_channels.len());
+        for fast_channel in decoder.input_fast_channels.iter() {
+            fast_channel_indices.push((channels[fast_channel.a].len(), channels[fast_channel.b].len()));
+            channels[fast_channel.a].push(fast_channel.b);
+            channels[fast_channel.b].push(fast_channel.a);
+        }
+        for (index, peers) in channels.iter().enumerate() {
+            // the tree solvers in `processing_unit` index channels with `$clog2(CHANNEL_COUNT)` bits
+            if neighbor_counts[index] == 0 || peers.len() < 2 {
+                return Err(format!(
+                    "processing unit at {} has {} neighbors and {} channels, but at least 1 neighbor and 2 channels are required",
+                    decoder.index_to_position[index],
+                    neighbor_counts[index],
+                    peers.len()
+                ));
+            }
+        }
+        Ok(Self {
+            module_name: module_name.to_string(),
+            coordinates,
+            per_dimension_width,
+            boundary_costs: (*decoder.boundary_costs).clone(),
+            neighbors: (*decoder.input_neighbors).clone(),
+            fast_channels: (*decoder.input_fast_channels).clone(),
+            channels,
+            neighbor_counts,
+            neighbor_channel_indices,
+            fast_channel_indices,
+        })
+    }
+
+    pub fn address_width(&self) -> usize {
+        3 * self.per_dimension_width
+    }
+
+    /// the hardware address of a processing unit
+    pub fn address(&self, index: usize) -> usize {
+        let [k, i, j] = self.coordinates[index];
+        (((k << self.per_dimension_width) | i) << self.per_dimension_width) | j
+    }
+
+    /// the Manhattan distance computed by `tree_distance_3d_solver`
+    pub fn address_distance(&self, a: usize, b: usize) -> usize {
+        (0..3)
+            .map(|axis| self.coordinates[a][axis].abs_diff(self.coordinates[b][axis]))
+            .sum()
+    }
+
+    /// the top module with all the processing units and channels
+    pub fn generate_verilog(&self) -> String {
+        let node_num = self.coordinates.len();
+        let address_width = self.address_width();
+        let mut code = format!(
+            "\
+`timescale 1ns / 1ps
+
+// generated by `qecp-cli tool generate-fpga`, do not edit
+module {module_name} (
+    clk,
+    reset,
+    stage,
+    is_error_syndromes,
+    roots,
+    is_odd_clusters,
+    is_odd_cardinalities,
+    is_touching_boundaries,
+    has_message_flying
+);
+
+`include \"parameters.sv\"
+
+localparam PU_COUNT = {node_num};
+localparam PER_DIMENSION_WIDTH = {per_dimension_width};
+localparam ADDRESS_WIDTH = {address_width};
+localparam DISTANCE_WIDTH = PER_DIMENSION_WIDTH + 1;  // `processing_unit` derives PER_DIMENSION_WIDTH = DISTANCE_WIDTH - 1
+localparam UNION_MESSAGE_WIDTH = 2 * ADDRESS_WIDTH;  // [old_root, updated_root]
+localparam DIRECT_MESSAGE_WIDTH = ADDRESS_WIDTH + 1 + 1;  // [receiver, is_odd_cardinality_root, is_touching_boundary]
+`define SLICE_ADDRESS_VEC(vec, idx) (vec[(((idx)+1)*ADDRESS_WIDTH)-1:(idx)*ADDRESS_WIDTH])
+`define SLICE_UNION_MESSAGE_VEC(vec, idx) (vec[(((idx)+1)*UNION_MESSAGE_WIDTH)-1:(idx)*UNION_MESSAGE_WIDTH])
+`define SLICE_DIRECT_MESSAGE_VEC(vec, idx) (vec[(((idx)+1)*DIRECT_MESSAGE_WIDTH)-1:(idx)*DIRECT_MESSAGE_WIDTH])
+
+input clk;
+input reset;
+input [STAGE_WIDTH-1:0] stage;
+input [PU_COUNT-1:0] is_error_syndromes;
+output [(ADDRESS_WIDTH * PU_COUNT)-1:0] roots;
+output [PU_COUNT-1:0] is_odd_clusters;
+output [PU_COUNT-1:0] is_odd_cardinalities;
+output [PU_COUNT-1:0] is_touching_boundaries;
+output has_message_flying;
+
+wire [PU_COUNT-1:0] is_processings;
+reg has_message_flying_reg;
+assign has_message_flying = has_message_flying_reg;
+
+always @(posedge clk) begin
+    if (reset) begin
+        has_message_flying_reg <= 0;
+    end else begin
+        has_message_flying_reg <= |is_processings;
+    end
+end
+
+// the processing units register the stage before using it, so the channels are initialized with the same delay
+reg [STAGE_WIDTH-1:0] stage_internal;
+wire initialize_channels;
+assign initialize_channels = (stage_internal == STAGE_MEASUREMENT_LOADING);
+
+always @(posedge clk) begin
+    if (reset) begin
+        stage_internal <= STAGE_IDLE;
+    end else begin
+        stage_internal <= stage;
+    end
+end
+
+",
+            module_name = self.module_name,
+            per_dimension_width = self.per_dimension_width,
+        );
+        for index in 0..node_num {
+            code += &self.generate_processing_unit(index);
+        }
+        for (neighbor, &(index_a, index_b)) in self.neighbors.iter().zip(self.neighbor_channel_indices.iter()) {
+            let (a, b) = (neighbor.a, neighbor.b);
+            code += &format!(
+                "\
+// neighbor link between pu_{a} and pu_{b}
+neighbor_link #(.LENGTH({length}), .ADDRESS_WIDTH(ADDRESS_WIDTH)) pu_{a}_neighbor_{index_a}_link (
+    .clk(clk), .reset(reset), .initialize(initialize_channels), .is_fully_grown(pu_{a}_neighbor_is_fully_grown[{index_a}]),
+    .a_old_root_in(pu_{a}_old_root), .a_increase(pu_{a}_neighbor_increase),
+    .b_old_root_out(`SLICE_ADDRESS_VEC(pu_{a}_neighbor_old_roots, {index_a})),
+    .b_old_root_in(pu_{b}_old_root), .b_increase(pu_{b}_neighbor_increase),
+    .a_old_root_out(`SLICE_ADDRESS_VEC(pu_{b}_neighbor_old_roots, {index_b}))
+);
+assign pu_{b}_neighbor_is_fully_grown[{index_b}] = pu_{a}_neighbor_is_fully_grown[{index_a}];
+",
+                length = neighbor.length,
+            );
+            code += &Self::generate_channel(a, index_a, b, index_b);
+            code += &Self::generate_channel(b, index_b, a, index_a);
+            code += "\n";
+        }
+        for (fast_channel, &(index_a, index_b)) in self.fast_channels.iter().zip(self.fast_channel_indices.iter()) {
+            code += &format!("// fast channel between pu_{} and pu_{}\n", fast_channel.a, fast_channel.b);
+            code += &Self::generate_channel(fast_channel.a, index_a, fast_channel.b, index_b);
+            code += &Self::generate_channel(fast_channel.b, index_b, fast_channel.a, index_a);
+            code += "\n";
+        }
+        code += "endmodule\n";
+        code
+    }
+
+    fn generate_processing_unit(&self, index: usize) -> String {
+        let address_width = self.address_width();
+        let [k, i, j] = self.coordinates[index];
+        let neighbor_count = self.neighbor_counts[index];
+        let channel_count = self.channels[index].len();
+        // the most significant part of a concatenation is the last channel
+        let channel_addresses: Vec<String> = self.channels[index]
+            .iter()
+            .rev()
+            .map(|&peer| format!("{address_width}'d{}", self.address(peer)))
+            .collect();
+        let boundary_cost = self.boundary_costs[index].unwrap_or(0);
+        let boundary_width = std::cmp::max(1, (usize::BITS - boundary_cost.leading_zeros()) as usize);
+        format!(
+            "\
+// pu_{index}: [k, i, j] = [{k}, {i}, {j}]
+wire [{neighbor_count}-1:0] pu_{index}_neighbor_is_fully_grown;
+wire [(ADDRESS_WIDTH * {neighbor_count})-1:0] pu_{index}_neighbor_old_roots;
+wire pu_{index}_neighbor_increase;
+wire [(UNION_MESSAGE_WIDTH * {channel_count})-1:0] pu_{index}_union_out_channels_data;
+wire pu_{index}_union_out_channels_valid;
+wire [(UNION_MESSAGE_WIDTH * {channel_count})-1:0] pu_{index}_union_in_channels_data;
+wire [{channel_count}-1:0] pu_{index}_union_in_channels_valid;
+wire [DIRECT_MESSAGE_WIDTH-1:0] pu_{index}_direct_out_channels_data_single;
+wire [{channel_count}-1:0] pu_{index}_direct_out_channels_valid;
+wire [{channel_count}-1:0] pu_{index}_direct_out_channels_is_full;
+wire [(DIRECT_MESSAGE_WIDTH * {channel_count})-1:0] pu_{index}_direct_in_channels_data;
+wire [{channel_count}-1:0] pu_{index}_direct_in_channels_valid;
+wire [{channel_count}-1:0] pu_{index}_direct_in_channels_is_taken;
+wire [ADDRESS_WIDTH-1:0] pu_{index}_old_root;
+processing_unit #(
+    .ADDRESS_WIDTH(ADDRESS_WIDTH),
+    .DISTANCE_WIDTH(DISTANCE_WIDTH),
+    .BOUNDARY_WIDTH({boundary_width}),
+    .NEIGHBOR_COUNT({neighbor_count}),
+    .FAST_CHANNEL_COUNT({fast_channel_count}),
+    .INIT_BOUNDARY_COST({boundary_cost}),
+    .USE_GENERATED_ADDRESSES(1),
+    .INIT_ADDRESS({address_width}'d{address}),
+    .INIT_HAS_BOUNDARY({has_boundary}),
+    .CHANNEL_ADDRESSES({{ {channel_addresses} }})
+) pu_{index} (
+    .clk(clk),
+    .reset(reset),
+    .init_is_error_syndrome(is_error_syndromes[{index}]),
+    .stage_in(stage),
+    .neighbor_is_fully_grown(pu_{index}_neighbor_is_fully_grown),
+    .neighbor_old_roots(pu_{index}_neighbor_old_roots),
+    .neighbor_increase(pu_{index}_neighbor_increase),
+    .union_out_channels_data(pu_{index}_union_out_channels_data),
+    .union_out_channels_valid(pu_{index}_union_out_channels_valid),
+    .union_in_channels_data(pu_{index}_union_in_channels_data),
+    .union_in_channels_valid(pu_{index}_union_in_channels_valid),
+    .direct_out_channels_data_single(pu_{index}_direct_out_channels_data_single),
+    .direct_out_channels_valid(pu_{index}_direct_out_channels_valid),
+    .direct_out_channels_is_full(pu_{index}_direct_out_channels_is_full),
+    .direct_in_channels_data(pu_{index}_direct_in_channels_data),
+    .direct_in_channels_valid(pu_{index}_direct_in_channels_valid),
+    .direct_in_channels_is_taken(pu_{index}_direct_in_channels_is_taken),
+    .old_root(pu_{index}_old_root),
+    .updated_root(`SLICE_ADDRESS_VEC(roots, {index})),
+    .is_error_syndrome(),
+    .boundary_increased(),
+    .is_odd_cluster(is_odd_clusters[{index}]),
+    .is_touching_boundary(is_touching_boundaries[{index}]),
+    .is_odd_cardinality(is_odd_cardinalities[{index}]),
+    .pending_tell_new_root_touching_boundary(),
+    .is_processing(is_processings[{index}])
+);
+
+",
+            fast_channel_count = channel_count - neighbor_count,
+            address = self.address(index),
+            has_boundary = self.boundary_costs[index].is_some() as usize,
+            channel_addresses = channel_addresses.join(", "),
+        )
+    }
+
+    /// a union channel and a direct channel from `source` to `target`
+    fn generate_channel(source: usize, source_index: usize, target: usize, target_index: usize) -> String {
+        format!(
+            "\
+nonblocking_channel #(.WIDTH(UNION_MESSAGE_WIDTH)) pu_{source}_channel_{source_index}_union (
+    .clk(clk), .reset(reset), .initialize(initialize_channels),
+    .in_data(`SLICE_UNION_MESSAGE_VEC(pu_{source}_union_out_channels_data, {source_index})),
+    .in_valid(pu_{source}_union_out_channels_valid),
+    .out_data(`SLICE_UNION_MESSAGE_VEC(pu_{target}_union_in_channels_data, {target_index})),
+    .out_valid(pu_{target}_union_in_channels_valid[{target_index}])
+);
+blocking_channel #(.WIDTH(DIRECT_MESSAGE_WIDTH)) pu_{source}_channel_{source_index}_direct (
+    .clk(clk), .reset(reset), .initialize(initialize_channels),
+    .in_data(pu_{source}_direct_out_channels_data_single),
+    .in_valid(pu_{source}_direct_out_channels_valid[{source_index}]),
+    .in_is_full(pu_{source}_direct_out_channels_is_full[{source_index}]),
+    .out_data(`SLICE_DIRECT_MESSAGE_VEC(pu_{target}_direct_in_channels_data, {target_index})),
+    .out_valid(pu_{target}_direct_in_channels_valid[{target_index}]),
+    .out_is_taken(pu_{target}_direct_in_channels_is_taken[{target_index}])
+);
+"
+        )
+    }
+
+    /// the expected results of the Rust model, one case per line: `<syndrome count> <syndrome indices...> <iterations> <roots...>`
+    /// where the roots are the hardware addresses; the first line is `<processing unit count> <case count>`
+    pub fn generate_cosim_cases(
+        &self,
+        decoder: &DistributedUnionFindDecoder,
+        sparse_measurements: &[SparseMeasurement],
+    ) -> String {
+        let mut cases = format!("{} {}\n", self.coordinates.len(), sparse_measurements.len());
+        for sparse_measurement in sparse_measurements.iter() {
+            let mut distributed_union_find =
+                decoder.build_distributed_union_find(sparse_measurement, &SparseErasures::new());
+            let (_clock_cycles, count_iteration) = DistributedUnionFindDecoder::run_to_stable(&mut distributed_union_find);
+            let mut fields = vec![sparse_measurement.len()];
+            fields.extend(sparse_measurement.iter().map(|position| decoder.position_to_index[position]));
+            fields.push(count_iteration);
+            fields.extend(
+                distributed_union_find
+                    .processing_units
+                    .iter()
+                    .map(|processing_unit| self.address(processing_unit.updated_root)),
+            );
+            let fields: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+            cases += &fields.join(" ");
+            cases += "\n";
+        }
+        cases
+    }
+
+    /// Verilator testbench that runs every case and exits with a non-zero code on any mismatch
+    pub fn generate_cosim_harness(&self) -> String {
+        COSIM_HARNESS_TEMPLATE
+            .replace("{MODULE}", &self.module_name)
+            .replace("{PU_COUNT}", &self.coordinates.len().to_string())
+            .replace("{ADDRESS_WIDTH}", &self.address_width().to_string())
+    }
+
+    /// write the top module, the testbench and the cases into `output_dir`, return the paths of the written files
+    pub fn write_files(
+        &self,
+        output_dir: &str,
+        decoder: &DistributedUnionFindDecoder,
+        sparse_measurements: &[SparseMeasurement],
+    ) -> Result<Vec<String>, String> {
+        fs::create_dir_all(output_dir).map_err(|e| format!("cannot create {output_dir}: {e}"))?;
+        let files = [
+            (format!("{}.sv", self.module_name), self.generate_verilog()),
+            (format!("{}_cosim.cpp", self.module_name), self.generate_cosim_harness()),
+            (
+                format!("{}_cosim_cases.txt", self.module_name),
+                self.generate_cosim_cases(decoder, sparse_measurements),
+            ),
+        ];
+        let mut paths = vec![];
+        for (filename, content) in files.iter() {
+            let path = Path::new(output_dir).join(filename);
+            fs::write(&path, content).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+            paths.push(path.display().to_string());
+        }
+        Ok(paths)
+    }
+
+    /// build the files written by [`FpgaGenerator::write_files`] with Verilator and run the co-simulation,
+    /// `verilated_dir` is where the processing unit and channel modules are found
+    pub fn run_verilator_cosim(&self, output_dir: &str, verilated_dir: &str) -> Result<String, String> {
+        let output_dir = fs::canonicalize(output_dir).map_err(|e| format!("cannot find {output_dir}: {e}"))?;
+        let verilated_dir = fs::canonicalize(verilated_dir).map_err(|e| format!("cannot find {verilated_dir}: {e}"))?;
+        let executable = format!("{}_cosim", self.module_name);
+        let build = Command::new("verilator")
+            .arg("--cc")
+            .arg("--exe")
+            .arg("--build")
+            .arg("-Wno-fatal")
+            .arg("-Wno-lint")
+            .arg("-Wno-style")
+            .arg("--top-module")
+            .arg(&self.module_name)
+            .arg(format!("-I{}", verilated_dir.display()))
+            .arg("-y")
+            .arg(&verilated_dir)
+            .arg("-CFLAGS")
+            .arg("-std=c++17")
+            .arg("--Mdir")
+            .arg(output_dir.join("obj_dir"))
+            .arg("-o")
+            .arg(&executable)
+            .arg(output_dir.join(format!("{}.sv", self.module_name)))
+            .arg(output_dir.join(format!("{}_cosim.cpp", self.module_name)))
+            .output()
+            .map_err(|e| format!("cannot run verilator: {e}"))?;
+        if !build.status.success() {
+            return Err(format!("verilator failed:\n{}", String::from_utf8_lossy(&build.stderr)));
+        }
+        let run = Command::new(output_dir.join("obj_dir").join(&executable))
+            .arg(output_dir.join(format!("{}_cosim_cases.txt", self.module_name)))
+            .output()
+            .map_err(|e| format!("cannot run co-simulation: {e}"))?;
+        let stdout = String::from_utf8_lossy(&run.stdout).to_string();
+        if !run.status.success() {
+            return Err(format!(
+                "co-simulation failed:\n{stdout}{}",
+                String::from_utf8_lossy(&run.stderr)
+            ));
+        }
+        Ok(stdout)
+    }
+}
+
+const COSIM_HARNESS_TEMPLATE: &str = r#"// co-simulation harness generated by `qecp-cli tool generate-fpga`, do not edit
+// it runs the stages of `{MODULE}` in the same order as the Rust model, and checks the roots and the number of iterations
+#include <cstdint>
+#include <cstdio>
+#include <fstream>
+#include <type_traits>
+#include <vector>
+#include "verilated.h"
+#include "V{MODULE}.h"
+
+static const size_t PU_COUNT = {PU_COUNT};
+static const size_t ADDRESS_WIDTH = {ADDRESS_WIDTH};
+static const uint64_t MAX_CYCLES_PER_STAGE = 1000000;
+static const size_t MAX_ITERATIONS = 10000;
+// the stage, the channels and `has_message_flying` are all registered, so wait for a few quiet cycles before moving on
+static const int QUIET_CYCLES = 8;
+// see parameters.sv
+static const int STAGE_IDLE = 0;
+static const int STAGE_SPREAD_CLUSTER = 1;
+static const int STAGE_GROW_BOUNDARY = 2;
+static const int STAGE_SYNC_IS_ODD_CLUSTER = 3;
+static const int STAGE_MEASUREMENT_LOADING = 4;
+
+// Verilator maps ports of at most 64 bits to integers and wider ports to arrays of 32-bit words
+template <typename T> static bool get_bit(const T& value, size_t bit) {
+    if constexpr (std::is_integral<T>::value) {
+        return (value >> bit) & 1;
+    } else {
+        return (value[bit / 32] >> (bit % 32)) & 1;
+    }
+}
+
+template <typename T> static void set_bit(T& value, size_t bit, bool bit_value) {
+    if constexpr (std::is_integral<T>::value) {
+        value = T((value & ~(T(1) << bit)) | (T(bit_value) << bit));
+    } else {
+        value[bit / 32] = (value[bit / 32] & ~(1u << (bit % 32))) | (uint32_t(bit_value) << (bit % 32));
+    }
+}
+
+static V{MODULE}* top = nullptr;
+static uint64_t cycles = 0;
+
+static void tick() {
+    top->clk = 0;
+    top->eval();
+    top->clk = 1;
+    top->eval();
+    cycles += 1;
+}
+
+// return false if there are still messages flying after `MAX_CYCLES_PER_STAGE`
+static bool run_stage(int stage) {
+    top->stage = stage;
+    int quiet = 0;
+    for (uint64_t i = 0; i < MAX_CYCLES_PER_STAGE; ++i) {
+        tick();
+        quiet = top->has_message_flying ? 0 : quiet + 1;
+        if (quiet >= QUIET_CYCLES) {
+            return true;
+        }
+    }
+    return false;
+}
+
+static void trigger_stage(int stage) {
+    top->stage = stage;
+    for (int i = 0; i < QUIET_CYCLES; ++i) {
+        tick();
+    }
+}
+
+static bool has_odd_cluster() {
+    for (size_t i = 0; i < PU_COUNT; ++i) {
+        if (get_bit(top->is_odd_clusters, i)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+static uint64_t get_root(size_t index) {
+    uint64_t root = 0;
+    for (size_t bit = 0; bit < ADDRESS_WIDTH; ++bit) {
+        root |= uint64_t(get_bit(top->roots, index * ADDRESS_WIDTH + bit)) << bit;
+    }
+    return root;
+}
+
+int main(int argc, char** argv) {
+    Verilated::commandArgs(argc, argv);
+    const char* cases_filename = argc > 1 ? argv[1] : "{MODULE}_cosim_cases.txt";
+    std::ifstream cases(cases_filename);
+    size_t pu_count = 0, case_count = 0;
+    if (!(cases >> pu_count >> case_count) || pu_count != PU_COUNT) {
+        fprintf(stderr, "[error] cannot read cases of %zu processing units from %s\n", PU_COUNT, cases_filename);
+        return 2;
+    }
+    top = new V{MODULE};
+    top->reset = 1;
+    top->stage = STAGE_IDLE;
+    tick();
+    tick();
+    top->reset = 0;
+    size_t failed_cases = 0;
+    for (size_t case_index = 0; case_index < case_count; ++case_index) {
+        for (size_t i = 0; i < PU_COUNT; ++i) {
+            set_bit(top->is_error_syndromes, i, false);
+        }
+        size_t syndrome_count = 0;
+        cases >> syndrome_count;
+        for (size_t i = 0; i < syndrome_count; ++i) {
+            size_t index = 0;
+            cases >> index;
+            set_bit(top->is_error_syndromes, index, true);
+        }
+        size_t expected_iterations = 0;
+        cases >> expected_iterations;
+        std::vector<uint64_t> expected_roots(PU_COUNT);
+        for (size_t i = 0; i < PU_COUNT; ++i) {
+            cases >> expected_roots[i];
+        }
+        if (!cases) {
+            fprintf(stderr, "[error] case %zu is incomplete in %s\n", case_index, cases_filename);
+            return 2;
+        }
+        // the same stages as `reach_consistent_state` and `run_single_iteration` in the Rust model
+        trigger_stage(STAGE_MEASUREMENT_LOADING);
+        bool is_stable = run_stage(STAGE_SPREAD_CLUSTER) && run_stage(STAGE_SYNC_IS_ODD_CLUSTER);
+        size_t iterations = 0;
+        while (is_stable && has_odd_cluster() && iterations < MAX_ITERATIONS) {
+            trigger_stage(STAGE_GROW_BOUNDARY);
+            is_stable = run_stage(STAGE_SPREAD_CLUSTER) && run_stage(STAGE_SYNC_IS_ODD_CLUSTER);
+            iterations += 1;
+        }
+        size_t mismatched_roots = 0;
+        for (size_t i = 0; i < PU_COUNT; ++i) {
+            uint64_t root = get_root(i);
+            if (root != expected_roots[i]) {
+                if (mismatched_roots < 5) {
+                    printf("case %zu: pu_%zu has root %llu, expected %llu\n", case_index, i, (unsigned long long)root,
+                           (unsigned long long)expected_roots[i]);
+                }
+                mismatched_roots += 1;
+            }
+        }
+        if (!is_stable || iterations != expected_iterations || mismatched_roots > 0) {
+            printf("case %zu failed: stable %d, %zu iterations (expected %zu), %zu mismatched roots\n", case_index,
+                   int(is_stable), iterations, expected_iterations, mismatched_roots);
+            failed_cases += 1;
+        }
+    }
+    top->final();
+    delete top;
+    printf("%zu/%zu cases passed in %llu clock cycles\n", case_count - failed_cases, case_count, (unsigned long long)cycles);
+    return failed_cases == 0 ? 0 : 1;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::super::code_builder::*;
+    use super::super::noise_model::*;
+    use super::*;
+    use crate::rand::SeedableRng;
+    use crate::reproducible_rand::Xoroshiro128StarStar;
+    use std::sync::Arc;
+
+    fn build_noise_model(simulator: &mut Simulator, p: f64) -> Arc<NoiseModel> {
+        let mut noise_model = NoiseModel::new(simulator);
+        simulator.set_error_rates(&mut noise_model, p, p, p, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        Arc::new(noise_model)
+    }
+
+    /// the hardware compares and routes by addresses, which must agree with the positions used by the Rust model
+    #[test]
+    fn fpga_generator_addresses() {
+        // cargo test fpga_generator_addresses -- --nocapture
+        for code_type in [CodeType::StandardPlanarCode, CodeType::RotatedPlanarCode] {
+            let mut simulator = Simulator::new(code_type, CodeSize::new(2, 3, 3));
+            let noise_model = build_noise_model(&mut simulator, 0.01);
+            let decoder = DistributedUnionFindDecoder::new(&simulator, noise_model, &json!({}), 1, false);
+            let generator = FpgaGenerator::new("generated", &decoder).unwrap();
+            let positions = &decoder.index_to_position;
+            for a in 0..positions.len() {
+                assert!(generator.address(a) < (1 << generator.address_width()));
+                for b in 0..positions.len() {
+                    assert_eq!(
+                        generator.address(a).cmp(&generator.address(b)),
+                        positions[a].cmp(&positions[b])
+                    );
+                    assert_eq!(
+                        generator.address_distance(a, b),
+                        2 * lattice_distance(&positions[a], &positions[b], decoder.measurement_cycles)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fpga_generator_verilog() {
+        // cargo test fpga_generator_verilog -- --nocapture
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(4, 3, 3));
+        let noise_model = build_noise_model(&mut simulator, 0.01);
+        let decoder =
+            DistributedUnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &json!({"pcmg": false}), 1, false);
+        let generator = FpgaGenerator::new("generated", &decoder).unwrap();
+        let verilog = generator.generate_verilog();
+        let count_lines = |prefix: &str| verilog.lines().filter(|line| line.starts_with(prefix)).count();
+        let channel_count = 2 * (decoder.input_neighbors.len() + decoder.input_fast_channels.len());
+        assert!(!decoder.input_fast_channels.is_empty());
+        assert_eq!(count_lines("processing_unit #("), decoder.index_to_position.len());
+        assert_eq!(count_lines("neighbor_link #("), decoder.input_neighbors.len());
+        assert_eq!(count_lines("nonblocking_channel #("), channel_count);
+        assert_eq!(count_lines("blocking_channel #("), channel_count);
+        assert_eq!(
+            generator.channels.iter().map(|peers| peers.len()).sum::<usize>(),
+            channel_count
+        );
+        // every channel index is used exactly once on each processing unit
+        for (index, peers) in generator.channels.iter().enumerate() {
+            for channel_index in 0..peers.len() {
+                let connection = format!(".in_valid(pu_{index}_direct_out_channels_valid[{channel_index}])");
+                assert_eq!(verilog.matches(&connection).count(), 1);
+            }
+        }
+        // fast channels with longer latency are not supported by the hardware
+        let slow_decoder =
+            DistributedUnionFindDecoder::new(&simulator, noise_model, &json!({"pcmg": false, "fcl": 2}), 1, false);
+        assert!(FpgaGenerator::new("generated", &slow_decoder).is_err());
+    }
+
+    /// a decoder, its generated hardware and the syndromes to be co-simulated
+    fn cosim_setup() -> (DistributedUnionFindDecoder, FpgaGenerator, Vec<SparseMeasurement>) {
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(2, 3, 3));
+        let noise_model = build_noise_model(&mut simulator, 0.02);
+        let decoder = DistributedUnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        let generator = FpgaGenerator::new("fpga_generator_cosim", &decoder).unwrap();
+        simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+        let sparse_measurements: Vec<SparseMeasurement> = (0..20)
+            .map(|_| {
+                simulator.generate_random_errors(&noise_model);
+                simulator.generate_sparse_measurement()
+            })
+            .collect();
+        (decoder, generator, sparse_measurements)
+    }
+
+    #[test]
+    fn fpga_generator_cosim_cases() {
+        // cargo test fpga_generator_cosim_cases -- --nocapture
+        let (decoder, generator, sparse_measurements) = cosim_setup();
+        let cases = generator.generate_cosim_cases(&decoder, &sparse_measurements);
+        let lines: Vec<Vec<usize>> = cases
+            .lines()
+            .map(|line| line.split(' ').map(|field| field.parse().unwrap()).collect())
+            .collect();
+        let node_num = decoder.index_to_position.len();
+        assert_eq!(lines[0], vec![node_num, sparse_measurements.len()]);
+        for (fields, sparse_measurement) in lines[1..].iter().zip(sparse_measurements.iter()) {
+            let syndrome_count = sparse_measurement.len();
+            assert_eq!(fields.len(), 1 + syndrome_count + 1 + node_num);
+            // the root of a cluster has the smallest address among its processing units
+            let roots = &fields[syndrome_count + 2..];
+            for (index, &root) in roots.iter().enumerate() {
+                assert!(root <= generator.address(index));
+            }
+        }
+        let output_dir = std::env::temp_dir().join("qecp_fpga_generator_cosim_cases");
+        generator
+            .write_files(output_dir.to_str().unwrap(), &decoder, &sparse_measurements)
+            .unwrap();
+    }
+
+    /// the co-simulation requires `verilator` in the PATH, thus it only runs when `QECP_VERILATOR_COSIM` is set,
+    /// see `.github/workflows/fpga-cosim.yml`
+    #[test]
+    fn fpga_generator_cosim() {
+        // QECP_VERILATOR_COSIM=1 cargo test fpga_generator_cosim -- --nocapture
+        if std::env::var_os("QECP_VERILATOR_COSIM").is_none() {
+            println!("[warning] skipped, set QECP_VERILATOR_COSIM to run the co-simulation under Verilator");
+            return;
+        }
+        let (decoder, generator, sparse_measurements) = cosim_setup();
+        let output_dir = std::env::temp_dir().join("qecp_fpga_generator_cosim");
+        let output_dir = output_dir.to_str().unwrap();
+        generator.write_files(output_dir, &decoder, &sparse_measurements).unwrap();
+        let output = generator.run_verilator_cosim(output_dir, "./backend/verilated").unwrap();
+        println!("{output}");
+    }
+}
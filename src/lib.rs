@@ -37,6 +37,7 @@ extern crate urlencoding;
 #[macro_use]
 extern crate enum_dispatch;
 
+pub mod blossom;
 pub mod blossom_v;
 pub mod cli;
 pub mod reproducible_rand;
@@ -45,30 +46,40 @@ pub mod tool;
 pub mod types;
 pub mod util;
 pub mod web;
-// pub mod distributed_uf_decoder;  TODO: migrate back
-// pub mod fpga_generator;  TODO: migrate back
 // pub mod fast_benchmark;  TODO: migrate back
 pub mod code_builder;
 pub mod simulator;
 #[macro_use]
 pub mod util_macros;
+pub mod complementary_gap;
 pub mod complete_model_graph;
+pub mod decoder_bp_osd;
+pub mod decoder_distributed_union_find;
+pub mod decoder_ensemble;
+pub mod decoder_external;
 #[cfg(feature = "fusion_blossom")]
 pub mod decoder_fusion;
 #[cfg(feature = "fusion_blossom")]
 pub mod decoder_parallel_fusion;
-#[cfg(feature = "hyperion")]
+pub mod decoder_greedy;
 pub mod decoder_hyper_union_find;
 #[cfg(feature = "hyperion")]
 pub mod decoder_hyperion;
+pub mod decoder_lookup_table;
 pub mod decoder_mwpm;
+pub mod decoder_peeling;
 pub mod decoder_tailored_mwpm;
+pub mod decoder_tensor_network;
 pub mod decoder_union_find;
+pub mod decoder_windowed;
+pub mod distributed_uf_decoder;
 pub mod erasure_graph;
+pub mod fpga_generator;
 pub mod model_graph;
 pub mod model_hypergraph;
 pub mod noise_model;
 pub mod noise_model_builder;
+pub mod noise_schedule;
 pub mod tailored_complete_model_graph;
 pub mod tailored_model_graph;
 pub mod union_find;
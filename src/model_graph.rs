@@ -141,6 +141,17 @@ pub enum WeightFunction {
     Unweighted,
 }
 
+impl WeightFunction {
+    /// the weight of an edge given its probability
+    pub fn weight_of(&self, p: f64) -> f64 {
+        match self {
+            Self::Autotune => weight_function::autotune(p),
+            Self::AutotuneImproved => weight_function::autotune_improved(p),
+            Self::Unweighted => weight_function::unweighted(p),
+        }
+    }
+}
+
 pub mod weight_function {
 
     pub fn autotune(p: f64) -> f64 {
@@ -290,8 +301,10 @@ impl ModelGraph {
             }
             let noise_model_node = noise_model.get_node_unwrap(position);
             // whether it's possible to have erasure error at this node
-            let possible_erasure_error =
-                noise_model_node.erasure_error_rate > 0. || noise_model_node.correlated_erasure_error_rates.is_some() || {
+            let possible_erasure_error = noise_model_node.erasure_error_rate > 0.
+                || noise_model_node.correlated_erasure_error_rates.is_some()
+                || noise_model_node.is_heralded()
+                || {
                     let node = simulator.get_node_unwrap(position);
                     if let Some(gate_peer) = node.gate_peer.as_ref() {
                         let peer_noise_model_node = noise_model.get_node_unwrap(gate_peer);
@@ -306,7 +319,7 @@ impl ModelGraph {
                 };
             for error in all_possible_errors.iter() {
                 let p = match error {
-                    Either::Left(error_type) => noise_model_node.pauli_error_rates.error_rate(error_type),
+                    Either::Left(error_type) => noise_model.effective_pauli_error_rate(position, error_type),
                     Either::Right(error_type) => match &noise_model_node.correlated_pauli_error_rates {
                         Some(correlated_pauli_error_rates) => correlated_pauli_error_rates.error_rate(error_type),
                         None => 0.,
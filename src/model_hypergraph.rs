@@ -298,8 +298,10 @@ impl ModelHypergraph {
             }
             let noise_model_node = noise_model.get_node_unwrap(position);
             // whether it's possible to have erasure error at this node
-            let possible_erasure_error =
-                noise_model_node.erasure_error_rate > 0. || noise_model_node.correlated_erasure_error_rates.is_some() || {
+            let possible_erasure_error = noise_model_node.erasure_error_rate > 0.
+                || noise_model_node.correlated_erasure_error_rates.is_some()
+                || noise_model_node.is_heralded()
+                || {
                     let node = simulator.get_node_unwrap(position);
                     if let Some(gate_peer) = node.gate_peer.as_ref() {
                         let peer_noise_model_node = noise_model.get_node_unwrap(gate_peer);
@@ -314,7 +316,7 @@ impl ModelHypergraph {
                 };
             for error in all_possible_errors.iter() {
                 let p = match error {
-                    Either::Left(error_type) => noise_model_node.pauli_error_rates.error_rate(error_type),
+                    Either::Left(error_type) => noise_model.effective_pauli_error_rate(position, error_type),
                     Either::Right(error_type) => match &noise_model_node.correlated_pauli_error_rates {
                         Some(correlated_pauli_error_rates) => correlated_pauli_error_rates.error_rate(error_type),
                         None => 0.,
@@ -500,3 +502,69 @@ impl ModelHypergraph {
         })
     }
 }
+
+/// decompose each hyperedge onto the edges of a matching graph, e.g. a Y error into an X edge and a Z edge, so that
+/// matching decoders can use the correlations in the model hypergraph; `T` is the edge identifier of the matching decoder
+#[derive(Debug, Clone, Serialize)]
+pub struct HyperedgeDecomposition<T> {
+    /// the edges of the matching graph and the hyperedges that are decomposed onto them
+    pub graph_edges: Vec<(T, Vec<usize>)>,
+    /// the graph edges of each hyperedge, empty if it cannot be decomposed onto the matching graph
+    pub hyperedge_parts: Vec<Vec<usize>>,
+}
+
+impl<T> HyperedgeDecomposition<T> {
+    /// `graph_edge_of` returns the edge in the matching graph given the real defect vertices, i.e. either one vertex
+    /// (boundary edge) or two vertices
+    pub fn new<F>(model_hypergraph: &ModelHypergraph, simulator: &Simulator, graph_edge_of: F) -> Self
+    where
+        F: Fn(&[Position]) -> Option<T>,
+    {
+        let mut graph_edges: Vec<(T, Vec<usize>)> = vec![];
+        let mut hyperedge_parts = Vec::with_capacity(model_hypergraph.weighted_edges.len());
+        let mut graph_edge_indices = HashMap::<DefectVertices, Option<usize>>::new();
+        for (hyperedge_index, (defect_vertices, _)) in model_hypergraph.weighted_edges.iter().enumerate() {
+            // decompose the hyperedge into the stabilizers of the same type if it's not an edge of the matching graph
+            let mut parts: Vec<DefectVertices> = vec![];
+            if defect_vertices.0.len() <= 2 && graph_edge_of(&defect_vertices.0).is_some() {
+                parts.push(defect_vertices.clone());
+            } else {
+                let mut groups: Vec<(QubitType, Vec<Position>)> = vec![];
+                for position in defect_vertices.0.iter() {
+                    let qubit_type = simulator.get_node_unwrap(position).qubit_type;
+                    match groups.iter_mut().find(|(group_type, _)| *group_type == qubit_type) {
+                        Some((_, group)) => group.push(position.clone()),
+                        None => groups.push((qubit_type, vec![position.clone()])),
+                    }
+                }
+                parts.extend(groups.into_iter().map(|(_, group)| DefectVertices::new(group)));
+            }
+            let mut part_indices = Vec::with_capacity(parts.len());
+            for part in parts.into_iter() {
+                let index = *graph_edge_indices.entry(part).or_insert_with_key(|part| {
+                    if part.0.len() > 2 {
+                        return None;
+                    }
+                    graph_edge_of(&part.0).map(|graph_edge| {
+                        graph_edges.push((graph_edge, vec![]));
+                        graph_edges.len() - 1
+                    })
+                });
+                part_indices.push(index);
+            }
+            if part_indices.iter().all(|index| index.is_some()) {
+                let part_indices: Vec<usize> = part_indices.into_iter().flatten().collect();
+                for &index in part_indices.iter() {
+                    graph_edges[index].1.push(hyperedge_index);
+                }
+                hyperedge_parts.push(part_indices);
+            } else {
+                hyperedge_parts.push(vec![]);
+            }
+        }
+        Self {
+            graph_edges,
+            hyperedge_parts,
+        }
+    }
+}
@@ -50,6 +50,17 @@ impl QecpVisualizer for NoiseModel {
                                 if abbrev { "pe" } else { "erasure_error_rate" }: node.erasure_error_rate,
                                 if abbrev { "corr_pp" } else { "correlated_pauli_error_rates" }: node.correlated_pauli_error_rates,
                                 if abbrev { "corr_pe" } else { "correlated_erasure_error_rates" }: node.correlated_erasure_error_rates,
+                                if abbrev { "me" } else { "measurement_error_rates" }: node.measurement_error_rates,
+                                if abbrev { "pl" } else { "leakage_rate" }: node.leakage_rate,
+                                if abbrev { "lru" } else { "leakage_reduction" }: node.leakage_reduction,
+                                if abbrev { "lh" } else { "leakage_heralded" }: node.leakage_heralded,
+                                if abbrev { "lsr" } else { "leakage_survives_reset" }: node.leakage_survives_reset,
+                                if abbrev { "ploss" } else { "loss_rate" }: node.loss_rate,
+                                if abbrev { "lossd" } else { "loss_detection" }: node.loss_detection,
+                                if abbrev { "lossdp" } else { "loss_depolarizing" }: node.loss_depolarizing,
+                                if abbrev { "lossh" } else { "loss_heralded" }: node.loss_heralded,
+                                if abbrev { "em" } else { "erasure_model" }: node.erasure_model,
+                                if abbrev { "sr" } else { "soft_readout" }: node.soft_readout,
                             }))
                         } else {
                             None
@@ -76,6 +87,40 @@ pub struct NoiseModelNode {
     pub correlated_pauli_error_rates: Option<CorrelatedPauliErrorRates>,
     #[serde(rename = "corr_pe")]
     pub correlated_erasure_error_rates: Option<CorrelatedErasureErrorRates>,
+    /// state-dependent readout errors, only allowed at measurement nodes
+    #[serde(rename = "me", default)]
+    pub measurement_error_rates: Option<MeasurementErrorRates>,
+    /// the probability that the qubit leaks out of the computational subspace after the gate
+    #[serde(rename = "pl", default)]
+    pub leakage_rate: f64,
+    /// a leakage reduction unit after the gate, returning a leaked qubit to the computational subspace in a random state
+    #[serde(rename = "lru", default)]
+    pub leakage_reduction: bool,
+    /// errors caused by leaked qubits at this node are heralded as erasure errors
+    #[serde(rename = "lh", default)]
+    pub leakage_heralded: bool,
+    /// a leaked qubit stays leaked through the initialization at this node, instead of being reset to the computational subspace
+    #[serde(rename = "lsr", default)]
+    pub leakage_survives_reset: bool,
+    /// the probability that the qubit is lost after the gate, e.g. atom loss
+    #[serde(rename = "ploss", default)]
+    pub loss_rate: f64,
+    /// a loss detection after the gate, replacing a lost qubit with a new one in a random state
+    #[serde(rename = "lossd", default)]
+    pub loss_detection: bool,
+    /// a two-qubit gate with a lost qubit at this node depolarizes the peer, otherwise it acts trivially
+    #[serde(rename = "lossdp", default)]
+    pub loss_depolarizing: bool,
+    /// errors caused by lost qubits at this node are heralded as erasure errors once the loss is detected
+    #[serde(rename = "lossh", default)]
+    pub loss_heralded: bool,
+    /// imperfect herald and biased conversion of erasure errors at this node; `None` means a perfect herald that leaves a
+    /// fully depolarizing error
+    #[serde(rename = "em", default)]
+    pub erasure_model: Option<ErasureModel>,
+    /// analog readout with Gaussian noise, only allowed at measurement nodes; the decoder can use the analog value of each shot
+    #[serde(rename = "sr", default)]
+    pub soft_readout: Option<GaussianReadout>,
 }
 
 impl Default for NoiseModelNode {
@@ -94,6 +139,17 @@ impl NoiseModelNode {
             erasure_error_rate: 0.,
             correlated_pauli_error_rates: None,
             correlated_erasure_error_rates: None,
+            measurement_error_rates: None,
+            leakage_rate: 0.,
+            leakage_reduction: false,
+            leakage_heralded: false,
+            leakage_survives_reset: false,
+            loss_rate: 0.,
+            loss_detection: false,
+            loss_depolarizing: false,
+            loss_heralded: false,
+            erasure_model: None,
+            soft_readout: None,
         }
     }
 
@@ -115,8 +171,60 @@ impl NoiseModelNode {
         {
             return false;
         }
+        if self.measurement_error_rates.is_some() && !self.measurement_error_rates.as_ref().unwrap().is_noiseless() {
+            return false;
+        }
+        if self.leakage_rate > 0. || self.loss_rate > 0. {
+            return false;
+        }
+        if self.erasure_model.is_some() && self.erasure_model.as_ref().unwrap().false_positive_rate > 0. {
+            return false;
+        }
+        if self.soft_readout.is_some() && !self.soft_readout.as_ref().unwrap().is_noiseless() {
+            return false;
+        }
         true
     }
+
+    /// the prior probability of an erasure at this node, including the correlated erasures landing on this node
+    pub fn erasure_probability(&self) -> f64 {
+        let mut erasure_probability = self.erasure_error_rate;
+        if let Some(correlated_erasure_error_rates) = &self.correlated_erasure_error_rates {
+            erasure_probability += correlated_erasure_error_rates.error_probability();
+        }
+        erasure_probability
+    }
+
+    /// whether errors caused by leaked or lost qubits, or false heralds, may be reported as erasure errors at this node
+    pub fn is_heralded(&self) -> bool {
+        self.leakage_heralded
+            || self.loss_heralded
+            || matches!(&self.erasure_model, Some(erasure_model) if erasure_model.false_positive_rate > 0.)
+    }
+}
+
+impl NoiseModelNode {
+    /// multiply every error rate by `factor`, each error channel saturating individually at probability 1;
+    /// the erasure herald and conversion and the soft readout are properties of the hardware and thus kept unchanged
+    pub fn scaled(&self, factor: f64) -> Self {
+        Self {
+            pauli_error_rates: self.pauli_error_rates.scaled(factor),
+            erasure_error_rate: (self.erasure_error_rate * factor).clamp(0., 1.),
+            correlated_pauli_error_rates: self.correlated_pauli_error_rates.as_ref().map(|x| x.scaled(factor)),
+            correlated_erasure_error_rates: self.correlated_erasure_error_rates.as_ref().map(|x| x.scaled(factor)),
+            measurement_error_rates: self.measurement_error_rates.as_ref().map(|x| x.scaled(factor)),
+            leakage_rate: (self.leakage_rate * factor).clamp(0., 1.),
+            leakage_reduction: self.leakage_reduction,
+            leakage_heralded: self.leakage_heralded,
+            leakage_survives_reset: self.leakage_survives_reset,
+            loss_rate: (self.loss_rate * factor).clamp(0., 1.),
+            loss_detection: self.loss_detection,
+            loss_depolarizing: self.loss_depolarizing,
+            loss_heralded: self.loss_heralded,
+            erasure_model: self.erasure_model.clone(),
+            soft_readout: self.soft_readout.clone(),
+        }
+    }
 }
 
 #[cfg_attr(feature = "python_binding", cfg_eval)]
@@ -184,6 +292,78 @@ impl NoiseModel {
     pub fn set_node(&mut self, position: &Position, node: Option<Arc<NoiseModelNode>>) {
         self.nodes[position.t][position.i][position.j] = node;
     }
+
+    /// the rate of a pauli error at `position` as seen by the decoder; state-dependent readout errors are described by
+    /// a `Y` error right before the measurement (readout flip) and right after the measurement (back-action), and an
+    /// erasure missed by an imperfect herald leaves its Pauli error on the non-heralded edges
+    pub fn effective_pauli_error_rate(&self, position: &Position, error_type: &ErrorType) -> f64 {
+        let noise_model_node = self.get_node_unwrap(position);
+        let mut error_rate = noise_model_node.pauli_error_rates.error_rate(error_type);
+        if let Some(erasure_model) = &noise_model_node.erasure_model {
+            let missed_error_rate = erasure_model.missed_erasure_probability(noise_model_node.erasure_probability())
+                * erasure_model.pauli_error_rates.error_rate(error_type);
+            error_rate = error_rate + missed_error_rate - 2. * error_rate * missed_error_rate;
+        }
+        if error_type != &ErrorType::Y {
+            return error_rate;
+        }
+        let readout_error_rate = self.readout_induced_error_rate(position);
+        if readout_error_rate == 0. {
+            return error_rate;
+        }
+        // either of them happens, but not both
+        error_rate + readout_error_rate - 2. * error_rate * readout_error_rate
+    }
+
+    /// whether any qubit may leak or get lost, which is not supported by simulators other than [`Simulator`]
+    pub fn has_leakage_or_loss(&self) -> bool {
+        self.nodes
+            .iter()
+            .flatten()
+            .flatten()
+            .flatten()
+            .any(|node| node.leakage_rate > 0. || node.loss_rate > 0.)
+    }
+
+    /// whether any erasure may be detected, including heralded leakage and loss, which is not supported by every decoder
+    pub fn has_erasure(&self) -> bool {
+        self.nodes.iter().flatten().flatten().flatten().any(|node| {
+            node.erasure_error_rate > 0.
+                || matches!(&node.correlated_erasure_error_rates, Some(rates) if rates.error_probability() > 0.)
+                || node.is_heralded()
+        })
+    }
+
+    /// whether any measurement has analog readout, which is not supported by simulators other than [`Simulator`]
+    pub fn has_soft_readout(&self) -> bool {
+        self.nodes
+            .iter()
+            .flatten()
+            .flatten()
+            .flatten()
+            .any(|node| node.soft_readout.is_some())
+    }
+
+    /// the rate of `Y` error at `position` caused by state-dependent readout, see [`NoiseModel::effective_pauli_error_rate`]
+    pub fn readout_induced_error_rate(&self, position: &Position) -> f64 {
+        let mut error_rate = 0.;
+        if let Some(measurement_error_rates) = &self.get_node_unwrap(position).measurement_error_rates {
+            error_rate = measurement_error_rates.effective_back_action_error_rate();
+        }
+        let next_position = pos!(position.t + 1, position.i, position.j);
+        if self.is_node_exist(&next_position) {
+            let next_node = self.get_node_unwrap(&next_position);
+            if let Some(measurement_error_rates) = &next_node.measurement_error_rates {
+                let readout_error_rate = measurement_error_rates.effective_readout_error_rate();
+                error_rate = error_rate + readout_error_rate - 2. * error_rate * readout_error_rate;
+            }
+            if let Some(soft_readout) = &next_node.soft_readout {
+                let readout_error_rate = soft_readout.hard_flip_probability();
+                error_rate = error_rate + readout_error_rate - 2. * error_rate * readout_error_rate;
+            }
+        }
+        error_rate
+    }
 }
 
 /// check if error rates are not zero at perfect measurement ranges or at (always) virtual nodes,
@@ -216,6 +396,39 @@ pub fn noise_model_sanity_check(simulator: &Simulator, noise_model: &NoiseModel)
     });
     simulator_iter!(simulator, position, node, {
         let noise_model_node = noise_model.get_node_unwrap(position);
+        if let Some(measurement_error_rates) = &noise_model_node.measurement_error_rates {
+            // the readout flip is described by an error right before the measurement, see `NoiseModel::effective_pauli_error_rate`
+            if !node.gate_type.is_measurement() || position.t == 0 {
+                return Err(format!(
+                    "position at {} is not a noisy measurement but have measurement_error_rates: {:?}",
+                    position, measurement_error_rates
+                ));
+            }
+            if node.is_virtual && !measurement_error_rates.is_noiseless() {
+                return Err(format!(
+                    "virtual position at {} have non-zero measurement_error_rates: {:?}",
+                    position, measurement_error_rates
+                ));
+            }
+        }
+        if let Some(soft_readout) = &noise_model_node.soft_readout {
+            soft_readout.sanity_check();
+            if !node.gate_type.is_measurement() || position.t == 0 || node.is_virtual {
+                return Err(format!(
+                    "position at {} is not a noisy real measurement but have soft_readout: {:?}",
+                    position, soft_readout
+                ));
+            }
+            if noise_model_node.measurement_error_rates.is_some() {
+                return Err(format!(
+                    "position at {} cannot have both soft_readout and measurement_error_rates",
+                    position
+                ));
+            }
+        }
+        if let Some(erasure_model) = &noise_model_node.erasure_model {
+            erasure_model.sanity_check();
+        }
         if node.is_virtual {
             // no errors on virtual node is allowed, because they don't physically exist
             if noise_model_node.pauli_error_rates.error_probability() > 0. {
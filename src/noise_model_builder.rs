@@ -10,7 +10,7 @@ use super::util_macros::*;
 use crate::serde::{Deserialize, Serialize};
 #[cfg(feature = "python_binding")]
 use pyo3::prelude::*;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 
 /// commonly used noise models
@@ -32,13 +32,23 @@ pub enum NoiseModelBuilder {
     ErasureOnlyPhenomenological,
     /// errors happen at 4 stages in each measurement round (although removed errors happening at initialization and measurement stage, measurement errors can still occur when curtain error applies on the ancilla after the last gate)
     OnlyGateErrorCircuitLevel,
-    /// mixed erasure error and Pauli errors only on the data qubits before the gates happen and on the ancilla qubits before the measurement
+    /// mixed erasure error and Pauli errors only on the data qubits before the gates happen and on the ancilla qubits before the measurement;
+    /// optionally with an imperfect herald (`erasure_false_positive_rate`, `erasure_false_negative_rate`) and a biased
+    /// `erasure_conversion` like `{"px":0,"py":0,"pz":0.5}`
     MixedPhenomenological,
     /// Fault-tolerant weighted union-find decoding on the toric code
     DepolarizingNoise,
     /// the noise model in stim: after_clifford_depolarization, before_round_data_depolarization, before_measure_flip_probability, after_reset_flip_probability;
     /// see https://github.com/quantumlib/Stim/blob/main/doc/python_api_reference_vDev.md#stim.Circuit.generated
     StimNoiseModel,
+    /// superconducting-inspired SI1000 noise model: CZ p, single-qubit gates p/10, idle p/10, reset 2p, measurement 5p, idle during measurement/reset 2p;
+    /// CX and CY gates are compiled into CZ with single-qubit gates on the target; see arXiv:2108.10457 Table 2.
+    /// asymmetric readout is enabled by `measurement_flip_probability_01`, `measurement_flip_probability_10` and `measurement_back_action`;
+    /// analog readout is enabled by `measurement_readout_sigma`, the standard deviation of a Gaussian readout relative to the separation;
+    /// leakage is enabled by `leakage_rate` at two-qubit gates, `leakage_reduction_period` (in rounds), `leakage_heralded` and
+    /// `leakage_reset` (default true: initialization returns a leaked qubit to the computational subspace);
+    /// atom loss is enabled by `loss_rate` at two-qubit gates, `loss_detection_period` (in rounds) and `loss_depolarizing`
+    SI1000,
 }
 
 #[cfg(feature = "python_binding")]
@@ -62,7 +72,9 @@ impl NoiseModelBuilder {
 }
 
 impl NoiseModelBuilder {
-    /// apply noise model
+    /// apply noise model; asymmetric readout given by `measurement_flip_probability_01`, `measurement_flip_probability_10` and
+    /// `measurement_back_action` is added to every noisy measurement of any builder, on top of the measurement errors of the
+    /// builder itself, except for [`NoiseModelBuilder::SI1000`] where it replaces the symmetric measurement flip
     pub fn apply(
         &self,
         simulator: &mut Simulator,
@@ -72,6 +84,37 @@ impl NoiseModelBuilder {
         bias_eta: f64,
         pe: f64,
     ) {
+        if *self != Self::SI1000 {
+            let mut config = noise_model_configuration.clone();
+            if let Some(config_object) = config.as_object_mut() {
+                let flip_probability_01 = config_object.remove("measurement_flip_probability_01");
+                let flip_probability_10 = config_object.remove("measurement_flip_probability_10");
+                let back_action = config_object.remove("measurement_back_action");
+                if flip_probability_01.is_some() || flip_probability_10.is_some() || back_action.is_some() {
+                    let or_zero =
+                        |value: Option<serde_json::Value>| value.map(|value| value.as_f64().expect("f64")).unwrap_or(0.);
+                    let measurement_error_rates = MeasurementErrorRates::new(
+                        or_zero(flip_probability_01),
+                        or_zero(flip_probability_10),
+                        or_zero(back_action),
+                    );
+                    measurement_error_rates.sanity_check();
+                    self.apply(simulator, noise_model, &config, p, bias_eta, pe);
+                    let simulator = &*simulator; // force simulator to be immutable, to avoid unexpected changes
+                    simulator_iter_real!(simulator, position, node, {
+                        if node.gate_type.is_measurement()
+                            && position.t > 0
+                            && position.t < simulator.height - simulator.measurement_cycles
+                        {
+                            let mut noise_model_node = noise_model.get_node_unwrap(position).clone();
+                            noise_model_node.measurement_error_rates = Some(measurement_error_rates.clone());
+                            noise_model.set_node(position, Some(Arc::new(noise_model_node)));
+                        }
+                    });
+                    return;
+                }
+            }
+        }
         // commonly used biased qubit error node
         let px = p / (1. + bias_eta) / 2.;
         let py = px;
@@ -565,6 +608,27 @@ impl NoiseModelBuilder {
                 });
             }
             Self::MixedPhenomenological => {
+                let mut erasure_model: Option<ErasureModel> = None;
+                let mut config_cloned = noise_model_configuration.clone();
+                let config = config_cloned
+                    .as_object_mut()
+                    .expect("noise_model_configuration must be JSON object");
+                if let Some(value) = config.remove("erasure_false_positive_rate") {
+                    erasure_model.get_or_insert_with(ErasureModel::default).false_positive_rate =
+                        value.as_f64().expect("f64");
+                }
+                if let Some(value) = config.remove("erasure_false_negative_rate") {
+                    erasure_model.get_or_insert_with(ErasureModel::default).false_negative_rate =
+                        value.as_f64().expect("f64");
+                }
+                if let Some(value) = config.remove("erasure_conversion") {
+                    erasure_model.get_or_insert_with(ErasureModel::default).pauli_error_rates =
+                        serde_json::from_value(value)
+                            .expect("erasure_conversion should be like {\"px\":0,\"py\":0,\"pz\":0.5}");
+                }
+                if !config.is_empty() {
+                    panic!("unknown keys: {:?}", config.keys().collect::<Vec<&String>>());
+                }
                 let mut noise_node = biased_node.as_ref().clone();
                 // erasure node must have some non-zero pauli error rate for the decoder to work properly
                 if p == 0. && pe != 0. {
@@ -572,6 +636,7 @@ impl NoiseModelBuilder {
                     noise_node.pauli_error_rates.error_rate_Z = 1e-300;
                     noise_node.pauli_error_rates.error_rate_Y = 1e-300;
                 }
+                noise_node.erasure_model = erasure_model;
                 let noise_node = Arc::new(noise_node);
                 // iterate over all nodes
                 simulator_iter_real!(simulator, position, node, {
@@ -892,6 +957,231 @@ impl NoiseModelBuilder {
                     }
                 });
             }
+            Self::SI1000 => {
+                let mut two_qubit_gate_error_rate = p;
+                let mut single_qubit_gate_error_rate = p / 10.;
+                let mut idle_error_rate = p / 10.;
+                let mut reset_flip_probability = 2. * p;
+                let mut measurement_flip_probability = 5. * p;
+                let mut resonator_idle_error_rate = 2. * p;
+                let mut config_cloned = noise_model_configuration.clone();
+                let config = config_cloned
+                    .as_object_mut()
+                    .expect("noise_model_configuration must be JSON object");
+                if let Some(value) = config.remove("two_qubit_gate_error_rate") {
+                    two_qubit_gate_error_rate = value.as_f64().expect("f64");
+                }
+                if let Some(value) = config.remove("single_qubit_gate_error_rate") {
+                    single_qubit_gate_error_rate = value.as_f64().expect("f64");
+                }
+                if let Some(value) = config.remove("idle_error_rate") {
+                    idle_error_rate = value.as_f64().expect("f64");
+                }
+                if let Some(value) = config.remove("reset_flip_probability") {
+                    reset_flip_probability = value.as_f64().expect("f64");
+                }
+                if let Some(value) = config.remove("measurement_flip_probability") {
+                    measurement_flip_probability = value.as_f64().expect("f64");
+                }
+                if let Some(value) = config.remove("resonator_idle_error_rate") {
+                    resonator_idle_error_rate = value.as_f64().expect("f64");
+                }
+                // asymmetric readout replaces the symmetric measurement flip once any of these are given
+                let flip_probability_01 = config.remove("measurement_flip_probability_01");
+                let flip_probability_10 = config.remove("measurement_flip_probability_10");
+                let back_action = config.remove("measurement_back_action");
+                let measurement_error_rates =
+                    if flip_probability_01.is_some() || flip_probability_10.is_some() || back_action.is_some() {
+                        let or_default = |value: Option<serde_json::Value>, default: f64| {
+                            value.map(|value| value.as_f64().expect("f64")).unwrap_or(default)
+                        };
+                        let measurement_error_rates = MeasurementErrorRates::new(
+                            or_default(flip_probability_01, measurement_flip_probability),
+                            or_default(flip_probability_10, measurement_flip_probability),
+                            or_default(back_action, 0.),
+                        );
+                        measurement_error_rates.sanity_check();
+                        Some(measurement_error_rates)
+                    } else {
+                        None
+                    };
+                // analog readout also replaces the symmetric measurement flip
+                let soft_readout = config.remove("measurement_readout_sigma").map(|value| {
+                    assert!(
+                        measurement_error_rates.is_none(),
+                        "analog readout cannot be used together with asymmetric readout"
+                    );
+                    let soft_readout = GaussianReadout::new(value.as_f64().expect("f64"));
+                    soft_readout.sanity_check();
+                    soft_readout
+                });
+                let mut leakage_rate = 0.;
+                let mut leakage_reduction_period = 0;
+                let mut leakage_heralded = false;
+                let mut leakage_reset = true;
+                if let Some(value) = config.remove("leakage_rate") {
+                    leakage_rate = value.as_f64().expect("f64");
+                }
+                if let Some(value) = config.remove("leakage_reduction_period") {
+                    leakage_reduction_period = value.as_u64().expect("usize") as usize;
+                }
+                if let Some(value) = config.remove("leakage_heralded") {
+                    leakage_heralded = value.as_bool().expect("bool");
+                }
+                if let Some(value) = config.remove("leakage_reset") {
+                    leakage_reset = value.as_bool().expect("bool");
+                }
+                assert!((0. ..=1.).contains(&leakage_rate), "leakage_rate should be within [0, 1]");
+                let mut loss_rate = 0.;
+                let mut loss_detection_period = 1;
+                let mut loss_depolarizing = false;
+                if let Some(value) = config.remove("loss_rate") {
+                    loss_rate = value.as_f64().expect("f64");
+                }
+                if let Some(value) = config.remove("loss_detection_period") {
+                    loss_detection_period = value.as_u64().expect("usize") as usize;
+                }
+                if let Some(value) = config.remove("loss_depolarizing") {
+                    loss_depolarizing = value.as_bool().expect("bool");
+                }
+                assert!((0. ..=1.).contains(&loss_rate), "loss_rate should be within [0, 1]");
+                assert!(loss_detection_period > 0, "loss_detection_period should be positive");
+                if !config.is_empty() {
+                    panic!("unknown keys: {:?}", config.keys().collect::<Vec<&String>>());
+                }
+                let depolarize = |p: f64| (p / 3., p / 3., p / 3.);
+                // whatever basis is the initialization or measurement, there is always `q` probability to be flipped
+                let flip = |q: f64| (q / 2., q / 2., q / 2.);
+                let node_with = |px_py_pz: (f64, f64, f64), correlated: bool| {
+                    let mut error_node = NoiseModelNode::new();
+                    let (px, py, pz) = px_py_pz;
+                    error_node.pauli_error_rates.error_rate_X = px;
+                    error_node.pauli_error_rates.error_rate_Y = py;
+                    error_node.pauli_error_rates.error_rate_Z = pz;
+                    if correlated {
+                        let correlated_pauli_error_rates =
+                            CorrelatedPauliErrorRates::default_with_probability(two_qubit_gate_error_rate / 15.); // 15 possible errors equally probable
+                        correlated_pauli_error_rates.sanity_check();
+                        error_node.correlated_pauli_error_rates = Some(correlated_pauli_error_rates);
+                    }
+                    Arc::new(error_node)
+                };
+                let resonator_idle_node = node_with(depolarize(resonator_idle_error_rate), false);
+                let readout_measurement_node = if measurement_error_rates.is_some() || soft_readout.is_some() {
+                    let mut error_node = NoiseModelNode::new();
+                    error_node.measurement_error_rates = measurement_error_rates.clone();
+                    error_node.soft_readout = soft_readout.clone();
+                    Some(Arc::new(error_node))
+                } else {
+                    None
+                };
+                let simulator = &*simulator; // force simulator to be immutable, to avoid unexpected changes
+                simulator_iter_real!(simulator, position, node, {
+                    // first clear error rate
+                    noise_model.set_node(position, Some(noiseless_node.clone()));
+                    if position.t == 0 || position.t >= simulator.height - simulator.measurement_cycles {
+                        // no error before the first round and on the top, as a perfect measurement round
+                        continue;
+                    }
+                    // do different things for each stage
+                    match position.t % simulator.measurement_cycles {
+                        1 => {
+                            // initialization
+                            if node.qubit_type != QubitType::Data {
+                                let mut px_py_pz = flip(reset_flip_probability);
+                                if node.gate_type == GateType::InitializeX {
+                                    // a Hadamard gate after reset
+                                    px_py_pz =
+                                        ErrorType::combine_probability(px_py_pz, depolarize(single_qubit_gate_error_rate));
+                                }
+                                noise_model.set_node(position, Some(node_with(px_py_pz, false)));
+                            } else {
+                                noise_model.set_node(position, Some(resonator_idle_node.clone()));
+                            }
+                        }
+                        0 => {
+                            // measurement; the measurement errors are added before this round
+                            if node.qubit_type == QubitType::Data {
+                                noise_model.set_node(position, Some(resonator_idle_node.clone()));
+                            } else if node.gate_type.is_measurement() && readout_measurement_node.is_some() {
+                                noise_model.set_node(position, readout_measurement_node.clone());
+                            }
+                        }
+                        _ => {
+                            let is_real_two_qubit_gate = node.gate_type.is_two_qubit_gate() && !node.is_peer_virtual;
+                            let mut px_py_pz = if !is_real_two_qubit_gate {
+                                depolarize(idle_error_rate)
+                            } else if matches!(node.gate_type, GateType::CXGateTarget | GateType::CYGateTarget) {
+                                // compiled into CZ with a single-qubit gate before and after it on the target
+                                let single = depolarize(single_qubit_gate_error_rate);
+                                ErrorType::combine_probability(single, single)
+                            } else {
+                                (0., 0., 0.)
+                            };
+                            if position.t % simulator.measurement_cycles == simulator.measurement_cycles - 1
+                                && node.qubit_type != QubitType::Data
+                            {
+                                if readout_measurement_node.is_none() {
+                                    px_py_pz = ErrorType::combine_probability(px_py_pz, flip(measurement_flip_probability));
+                                }
+                                let measurement_node =
+                                    simulator.get_node_unwrap(&pos!(position.t + 1, position.i, position.j));
+                                if measurement_node.gate_type == GateType::MeasureX {
+                                    // a Hadamard gate before measurement
+                                    px_py_pz =
+                                        ErrorType::combine_probability(px_py_pz, depolarize(single_qubit_gate_error_rate));
+                                }
+                            }
+                            // two-qubit depolarizing error is added on the data qubit side
+                            let correlated = is_real_two_qubit_gate && node.qubit_type == QubitType::Data;
+                            noise_model.set_node(position, Some(node_with(px_py_pz, correlated)));
+                        }
+                    }
+                });
+                if leakage_rate > 0. || leakage_reduction_period > 0 || leakage_heralded || !leakage_reset || loss_rate > 0.
+                {
+                    // both qubits may leak or get lost at a two-qubit gate; leakage reduction units and loss detections act
+                    // during measurement, where the ancilla qubits are measured and the data qubits are idle; the ancilla
+                    // qubits only need leakage reduction units when their leakage survives the reset
+                    let mut modified_nodes: HashMap<(*const NoiseModelNode, bool, bool, bool), Arc<NoiseModelNode>> =
+                        HashMap::new();
+                    simulator_iter_real!(simulator, position, node, {
+                        if position.t == 0 || position.t >= simulator.height - simulator.measurement_cycles {
+                            continue;
+                        }
+                        let is_real_two_qubit_gate = node.gate_type.is_two_qubit_gate() && !node.is_peer_virtual;
+                        let is_measurement_stage = position.t % simulator.measurement_cycles == 0;
+                        let round = position.t / simulator.measurement_cycles;
+                        let leakage_reduction = leakage_reduction_period > 0
+                            && (node.qubit_type == QubitType::Data || !leakage_reset)
+                            && is_measurement_stage
+                            && round % leakage_reduction_period == 0;
+                        let loss_detection = loss_rate > 0. && is_measurement_stage && round % loss_detection_period == 0;
+                        let noise_model_node = noise_model.get_node_unwrap_arc(position);
+                        let key = (
+                            Arc::as_ptr(&noise_model_node),
+                            is_real_two_qubit_gate,
+                            leakage_reduction,
+                            loss_detection,
+                        );
+                        let modified_node = modified_nodes.entry(key).or_insert_with(|| {
+                            let mut modified_node = (*noise_model_node).clone();
+                            if is_real_two_qubit_gate {
+                                modified_node.leakage_rate = leakage_rate;
+                                modified_node.loss_rate = loss_rate;
+                            }
+                            modified_node.leakage_reduction = leakage_reduction;
+                            modified_node.leakage_heralded = leakage_heralded;
+                            modified_node.leakage_survives_reset = !leakage_reset;
+                            modified_node.loss_detection = loss_detection;
+                            modified_node.loss_depolarizing = loss_depolarizing;
+                            modified_node.loss_heralded = loss_rate > 0.;
+                            Arc::new(modified_node)
+                        });
+                        noise_model.set_node(position, Some(modified_node.clone()));
+                    });
+                }
+            }
             Self::DepolarizingNoise => {
                 let mut config_cloned = noise_model_configuration.clone();
                 let config = config_cloned
@@ -1079,3 +1369,131 @@ pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<NoiseModelBuilder>()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::code_builder::*;
+    use super::super::decoder_union_find::*;
+    use super::*;
+    use crate::rand::SeedableRng;
+    use crate::reproducible_rand::Xoroshiro128StarStar;
+
+    #[test]
+    fn noise_model_builder_si1000() {
+        // cargo test noise_model_builder_si1000 -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let p = 0.001;
+        let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mut noise_model = NoiseModel::new(&simulator);
+        NoiseModelBuilder::SI1000.apply(&mut simulator, &mut noise_model, &json!({}), p, 0.5, 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        code_builder_sanity_check(&simulator).unwrap();
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        // the two-qubit gates have correlated errors of probability p in total
+        let mut correlated_count = 0;
+        simulator_iter_real!(simulator, position, _node, {
+            let noise_model_node = noise_model.get_node_unwrap(position);
+            if let Some(correlated_pauli_error_rates) = noise_model_node.correlated_pauli_error_rates.as_ref() {
+                assert!((correlated_pauli_error_rates.error_probability() - p).abs() < 1e-9);
+                correlated_count += 1;
+            }
+        });
+        assert!(correlated_count > 0);
+        // decode a few shots
+        let noise_model = Arc::new(noise_model);
+        let mut union_find_decoder = UnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &json!({}), 1, false);
+        simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+        let mut error_shots = 0;
+        for _ in 0..100 {
+            let (error_count, _erasure_count) = simulator.generate_random_errors(&noise_model);
+            if error_count > 0 {
+                error_shots += 1;
+            }
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let (correction, _runtime_statistics) = union_find_decoder.decode(&sparse_measurement);
+            code_builder_sanity_check_correction(&mut simulator, &correction).unwrap();
+            if error_count <= 1 {
+                let (logical_i, logical_j) = simulator.validate_correction(&correction);
+                assert!(!logical_i && !logical_j, "a single fault should be corrected");
+            }
+        }
+        assert!(error_shots > 0, "SI1000 should generate errors");
+    }
+
+    #[test]
+    fn noise_model_builder_asymmetric_readout() {
+        // cargo test noise_model_builder_asymmetric_readout -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let build_simulator = |flip_probability_01: f64, flip_probability_10: f64| {
+            let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+            let mut noise_model = NoiseModel::new(&simulator);
+            let config = json!({
+                "measurement_flip_probability_01": flip_probability_01,
+                "measurement_flip_probability_10": flip_probability_10,
+            });
+            NoiseModelBuilder::Phenomenological.apply(&mut simulator, &mut noise_model, &config, 0., 0.5, 0.);
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(123));
+            (simulator, noise_model)
+        };
+        let (simulator, noise_model) = build_simulator(0.3, 0.);
+        let mut readout_count = 0;
+        simulator_iter_real!(simulator, position, node, {
+            if noise_model.get_node_unwrap(position).measurement_error_rates.is_some() {
+                assert!(node.gate_type.is_measurement());
+                readout_count += 1;
+            }
+        });
+        assert_eq!(readout_count, noisy_measurements * (d * d - 1));
+        // without any Pauli error, every measurement is in state 0, and thus only `p01` flips the readout
+        for (flip_probability_01, flip_probability_10, expect_errors) in [(0.3, 0., true), (0., 0.3, false)] {
+            let (mut simulator, noise_model) = build_simulator(flip_probability_01, flip_probability_10);
+            let mut error_count = 0;
+            for _ in 0..20 {
+                error_count += simulator.generate_random_errors(&noise_model).0;
+            }
+            assert_eq!(error_count > 0, expect_errors);
+        }
+    }
+
+    #[test]
+    fn noise_model_builder_leakage_reset() {
+        // cargo test noise_model_builder_leakage_reset -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let p = 0.001;
+        // the ancilla qubits only get leakage reduction units when their leakage survives the reset
+        for leakage_reset in [true, false] {
+            let mut simulator = Simulator::new(CodeType::RotatedPlanarCode, CodeSize::new(noisy_measurements, d, d));
+            let mut noise_model = NoiseModel::new(&simulator);
+            let config = json!({
+                "leakage_rate": p,
+                "leakage_reduction_period": 1,
+                "leakage_reset": leakage_reset,
+            });
+            NoiseModelBuilder::SI1000.apply(&mut simulator, &mut noise_model, &config, p, 0.5, 0.);
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            let mut data_reduction_count = 0;
+            let mut ancilla_reduction_count = 0;
+            simulator_iter_real!(simulator, position, node, {
+                let noise_model_node = noise_model.get_node_unwrap(position);
+                if position.t > 0 && position.t < simulator.height - simulator.measurement_cycles {
+                    assert_eq!(noise_model_node.leakage_survives_reset, !leakage_reset);
+                }
+                if noise_model_node.leakage_reduction {
+                    if node.qubit_type == QubitType::Data {
+                        data_reduction_count += 1;
+                    } else {
+                        assert!(node.gate_type.is_measurement());
+                        ancilla_reduction_count += 1;
+                    }
+                }
+            });
+            assert_eq!(data_reduction_count, noisy_measurements * d * d);
+            let expected_ancilla_reduction_count = if leakage_reset { 0 } else { noisy_measurements * (d * d - 1) };
+            assert_eq!(ancilla_reduction_count, expected_ancilla_reduction_count);
+        }
+    }
+}
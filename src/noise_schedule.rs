@@ -0,0 +1,371 @@
+//! # Noise Schedule
+//!
+//! time-varying noise across shots: the physical error rates drift over the shot index or the wall-clock time,
+//! and rare burst events (cosmic-ray-like) raise the error rates of a spatially and temporally localized region in a single shot.
+//! the schedule only changes the noise model used to sample errors; decoders keep their static `noise_model_graph`,
+//! which is how one studies the robustness of a decoder whose prior is stale
+//!
+
+use super::noise_model::*;
+use super::reproducible_rand::Xoroshiro128StarStar;
+use super::simulator::*;
+use super::types::*;
+use super::util_macros::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoiseScheduleConfig {
+    /// the variable that drives the drift: either the shot index or the wall-clock time in seconds since the benchmark starts
+    #[serde(default = "noise_schedule_default_configs::clock")]
+    pub clock: NoiseScheduleClock,
+    /// how the error rates drift, by default there is no drift
+    #[serde(default = "noise_schedule_default_configs::drift")]
+    pub drift: NoiseDrift,
+    /// the scaled noise model is only rebuilt when the scale factor changes by more than this value, to amortize the cost of rebuilding
+    #[serde(alias = "res")] // abbreviation
+    #[serde(default = "noise_schedule_default_configs::resolution")]
+    pub resolution: f64,
+    /// rare localized burst events, by default there is no burst
+    #[serde(default = "noise_schedule_default_configs::burst")]
+    pub burst: Option<NoiseBurstConfig>,
+}
+
+pub mod noise_schedule_default_configs {
+    use super::*;
+    pub fn clock() -> NoiseScheduleClock {
+        NoiseScheduleClock::Shot
+    }
+    pub fn drift() -> NoiseDrift {
+        NoiseDrift::None
+    }
+    pub fn resolution() -> f64 {
+        1e-3
+    }
+    pub fn burst() -> Option<NoiseBurstConfig> {
+        None
+    }
+    pub fn burst_rounds() -> usize {
+        1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseScheduleClock {
+    /// the global index of the shot, shared by all threads
+    Shot,
+    /// wall-clock time in seconds since the simulation of this configuration starts
+    Time,
+}
+
+/// the scale factor applied to every error rate of the noise model, as a function of the clock `x`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum NoiseDrift {
+    /// scale = 1
+    None,
+    /// scale = 1 + slope * x
+    Linear { slope: f64 },
+    /// scale = 1 + amplitude * sin(2π x / period + phase)
+    Sinusoidal {
+        amplitude: f64,
+        period: f64,
+        #[serde(default)]
+        phase: f64,
+    },
+    /// linear interpolation between the given (x, scale) points, sorted by x; constant outside the range
+    Piecewise { points: Vec<(f64, f64)> },
+}
+
+impl NoiseDrift {
+    /// the scale factor at clock `x`, never negative
+    pub fn scale(&self, x: f64) -> f64 {
+        let scale = match self {
+            Self::None => 1.,
+            Self::Linear { slope } => 1. + slope * x,
+            Self::Sinusoidal {
+                amplitude,
+                period,
+                phase,
+            } => 1. + amplitude * (2. * std::f64::consts::PI * x / period + phase).sin(),
+            Self::Piecewise { points } => {
+                assert!(!points.is_empty(), "piecewise drift requires at least one point");
+                let index = points.partition_point(|(px, _)| *px <= x);
+                if index == 0 {
+                    points[0].1
+                } else if index == points.len() {
+                    points[points.len() - 1].1
+                } else {
+                    let (x1, s1) = points[index - 1];
+                    let (x2, s2) = points[index];
+                    s1 + (s2 - s1) * (x - x1) / (x2 - x1)
+                }
+            }
+        };
+        scale.max(0.)
+    }
+
+    pub fn sanity_check(&self) -> Result<(), String> {
+        match self {
+            Self::Sinusoidal { period, .. } if *period <= 0. => {
+                Err(format!("sinusoidal drift requires positive period, got {period}"))
+            }
+            Self::Piecewise { points } => {
+                if points.is_empty() {
+                    return Err("piecewise drift requires at least one point".to_string());
+                }
+                for window in points.windows(2) {
+                    if window[0].0 >= window[1].0 {
+                        return Err(format!(
+                            "piecewise drift points must be strictly increasing in x: {:?}",
+                            points
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoiseBurstConfig {
+    /// the probability that a burst event happens in a shot
+    #[serde(alias = "p")] // abbreviation
+    pub probability: f64,
+    /// spatial radius of the burst region, in the unit of the (i, j) lattice of the simulator
+    pub radius: f64,
+    /// how many measurement rounds the burst region spans, starting from the round of the burst center
+    #[serde(default = "noise_schedule_default_configs::burst_rounds")]
+    pub rounds: usize,
+    /// depolarizing error rate added to every node inside the burst region
+    #[serde(alias = "pb")] // abbreviation
+    pub error_rate: f64,
+}
+
+/// a burst event sampled for a single shot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoiseBurstEvent {
+    pub center: Position,
+    pub affected_nodes: usize,
+}
+
+/// each simulation thread holds its own noise schedule, so that the noise model of each shot is generated without locking
+#[derive(Debug, Clone)]
+pub struct NoiseSchedule {
+    pub config: NoiseScheduleConfig,
+    /// the static noise model without drift and burst
+    pub base_noise_model: Arc<NoiseModel>,
+    /// real positions outside the final perfect measurement rounds, where burst events may happen
+    burst_sites: Arc<Vec<Position>>,
+    measurement_cycles: usize,
+    /// the beginning of the simulation, used when the clock is wall-clock time
+    begin: Instant,
+    /// cache of the most recently scaled noise model
+    cached_scale: f64,
+    cached_noise_model: Arc<NoiseModel>,
+    rng: Xoroshiro128StarStar,
+}
+
+impl NoiseSchedule {
+    pub fn new(
+        simulator: &Simulator,
+        base_noise_model: Arc<NoiseModel>,
+        config: NoiseScheduleConfig,
+    ) -> Result<Self, String> {
+        config.drift.sanity_check()?;
+        if config.resolution < 0. {
+            return Err(format!("resolution must be non-negative, got {}", config.resolution));
+        }
+        if let Some(burst) = &config.burst {
+            if !(0. ..=1.).contains(&burst.probability) || !(0. ..=1.).contains(&burst.error_rate) {
+                return Err(format!("invalid burst probability or error rate: {:?}", burst));
+            }
+            if burst.radius < 0. || burst.rounds == 0 {
+                return Err(format!(
+                    "burst requires non-negative radius and at least 1 round: {:?}",
+                    burst
+                ));
+            }
+        }
+        let mut burst_sites = Vec::new();
+        let noisy_height = simulator.height - simulator.measurement_cycles;
+        simulator_iter_real!(simulator, position, node, {
+            // the same as `Simulator::set_error_rates`, the first layer has no measurement errors
+            if position.t < noisy_height && (position.t != 0 || node.qubit_type == QubitType::Data) {
+                burst_sites.push(position.clone());
+            }
+        });
+        Ok(Self {
+            config,
+            burst_sites: Arc::new(burst_sites),
+            measurement_cycles: simulator.measurement_cycles,
+            begin: Instant::now(),
+            cached_scale: 1.,
+            cached_noise_model: base_noise_model.clone(),
+            base_noise_model,
+            rng: Xoroshiro128StarStar::new(),
+        })
+    }
+
+    pub fn set_rng(&mut self, rng: Xoroshiro128StarStar) {
+        self.rng = rng;
+    }
+
+    /// the value of the clock given the global shot index
+    pub fn clock(&self, shot_index: usize) -> f64 {
+        match self.config.clock {
+            NoiseScheduleClock::Shot => shot_index as f64,
+            NoiseScheduleClock::Time => self.begin.elapsed().as_secs_f64(),
+        }
+    }
+
+    /// generate the noise model of the next shot, together with the runtime statistics of the schedule
+    pub fn next_shot_noise_model(&mut self, shot_index: usize) -> (Arc<NoiseModel>, serde_json::Value) {
+        let scale = self.config.drift.scale(self.clock(shot_index));
+        if (scale - self.cached_scale).abs() > self.config.resolution {
+            self.cached_noise_model = Arc::new(Self::scale_noise_model(&self.base_noise_model, scale));
+            self.cached_scale = scale;
+        }
+        let mut burst_event = None;
+        let mut noise_model = self.cached_noise_model.clone();
+        if let Some(burst) = &self.config.burst {
+            if !self.burst_sites.is_empty() && self.rng.next_f64() < burst.probability {
+                let center_index =
+                    ((self.rng.next_f64() * self.burst_sites.len() as f64) as usize).min(self.burst_sites.len() - 1);
+                let center = self.burst_sites[center_index].clone();
+                let (burst_noise_model, affected_nodes) = self.apply_burst(&noise_model, burst, &center);
+                noise_model = Arc::new(burst_noise_model);
+                burst_event = Some(NoiseBurstEvent { center, affected_nodes });
+            }
+        }
+        (
+            noise_model,
+            json!({
+                "scale": self.cached_scale,
+                "burst": burst_event,
+            }),
+        )
+    }
+
+    /// scale every node of the noise model, sharing the scaled node among positions that shared the original node
+    pub fn scale_noise_model(noise_model: &NoiseModel, scale: f64) -> NoiseModel {
+        let mut scaled_nodes: HashMap<*const NoiseModelNode, Arc<NoiseModelNode>> = HashMap::new();
+        let nodes = noise_model
+            .nodes
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|node| {
+                                node.as_ref().map(|node| {
+                                    scaled_nodes
+                                        .entry(Arc::as_ptr(node))
+                                        .or_insert_with(|| Arc::new(node.scaled(scale)))
+                                        .clone()
+                                })
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut additional_noise = noise_model.additional_noise.clone();
+        for noise in additional_noise.iter_mut() {
+            noise.probability = (noise.probability * scale).clamp(0., 1.);
+        }
+        NoiseModel { nodes, additional_noise }
+    }
+
+    /// add depolarizing noise to all burst sites within the spacetime region around `center`
+    fn apply_burst(&self, noise_model: &NoiseModel, burst: &NoiseBurstConfig, center: &Position) -> (NoiseModel, usize) {
+        let mut burst_noise_model = noise_model.clone();
+        let t_begin = center.t - center.t % self.measurement_cycles;
+        let t_end = t_begin + burst.rounds * self.measurement_cycles;
+        let pb = burst.error_rate / 3.;
+        let mut affected_nodes = 0;
+        for position in self.burst_sites.iter() {
+            if position.t < t_begin || position.t >= t_end {
+                continue;
+            }
+            let di = position.i as f64 - center.i as f64;
+            let dj = position.j as f64 - center.j as f64;
+            if di * di + dj * dj > burst.radius * burst.radius {
+                continue;
+            }
+            let mut node = noise_model.get_node_unwrap(position).clone();
+            let rates = &node.pauli_error_rates;
+            let (px, py, pz) =
+                ErrorType::combine_probability((rates.error_rate_X, rates.error_rate_Y, rates.error_rate_Z), (pb, pb, pb));
+            node.pauli_error_rates.error_rate_X = px;
+            node.pauli_error_rates.error_rate_Y = py;
+            node.pauli_error_rates.error_rate_Z = pz;
+            burst_noise_model.set_node(position, Some(Arc::new(node)));
+            affected_nodes += 1;
+        }
+        (burst_noise_model, affected_nodes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code_builder::*;
+
+    #[test]
+    fn noise_schedule_drift_and_burst() {
+        // cargo test noise_schedule_drift_and_burst -- --nocapture
+        let d = 5;
+        let noisy_measurements = 3;
+        let p = 0.001;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mut noise_model = NoiseModel::new(&simulator);
+        simulator.set_error_rates(&mut noise_model, p / 3., p / 3., p / 3., 0.);
+        simulator.compress_error_rates(&mut noise_model);
+        let noise_model = Arc::new(noise_model);
+        let config: NoiseScheduleConfig = serde_json::from_value(json!({
+            "drift": { "type": "piecewise", "points": [[0, 1], [100, 3]] },
+            "burst": { "p": 1, "radius": 1.5, "rounds": 2, "pb": 0.3 },
+        }))
+        .unwrap();
+        let mut noise_schedule = NoiseSchedule::new(&simulator, noise_model.clone(), config).unwrap();
+        let (shot_noise_model, statistics) = noise_schedule.next_shot_noise_model(50);
+        assert_eq!(statistics["scale"], json!(2.));
+        let center: Position = serde_json::from_value(statistics["burst"]["center"].clone()).unwrap();
+        assert!(statistics["burst"]["affected_nodes"].as_u64().unwrap() > 0);
+        // the burst center is much noisier than the drifted noise model, while far away nodes only drift
+        let center_rate = shot_noise_model
+            .get_node_unwrap(&center)
+            .pauli_error_rates
+            .error_probability();
+        assert!(center_rate > 0.2, "{center_rate}");
+        let mc = simulator.measurement_cycles;
+        let far_t = if center.t >= 2 * mc {
+            center.t - 2 * mc
+        } else {
+            center.t + 2 * mc
+        };
+        let far_position = pos!(far_t, center.i, center.j);
+        let far_rate = shot_noise_model
+            .get_node_unwrap(&far_position)
+            .pauli_error_rates
+            .error_probability();
+        let base_rate = noise_model
+            .get_node_unwrap(&far_position)
+            .pauli_error_rates
+            .error_probability();
+        assert!((far_rate - 2. * base_rate).abs() < 1e-12, "{far_rate} {base_rate}");
+        // the schedule never touches the final perfect measurement rounds
+        noise_model_sanity_check(&simulator, &shot_noise_model).unwrap();
+        // the simulator accepts the scheduled noise model
+        simulator.generate_random_errors(&shot_noise_model);
+    }
+}
@@ -38,6 +38,10 @@ pub trait SimulatorGenerics: Clone {
     fn generate_sparse_error_pattern(&self) -> SparseErrorPattern;
     fn generate_sparse_measurement(&self) -> SparseMeasurement;
     fn validate_correction(&mut self, correction: &SparseCorrection) -> (bool, bool);
+    /// analog readouts of the last shot, only supported by [`Simulator`]
+    fn generate_sparse_soft_readouts(&self) -> SparseSoftReadouts {
+        SparseSoftReadouts::new()
+    }
 }
 
 #[cfg(feature = "python_binding")]
@@ -101,6 +105,9 @@ pub struct Simulator {
     /// how many cycles is there a round of measurements; default to 1
     #[cfg_attr(feature = "python_binding", pyo3(get, set))]
     pub measurement_cycles: usize,
+    /// analog readouts of the last shot, only recorded when some measurements have [`NoiseModelNode::soft_readout`]
+    #[serde(skip)]
+    pub soft_readouts: SparseSoftReadouts,
 }
 
 impl QecpVisualizer for Simulator {
@@ -176,6 +183,15 @@ pub struct SimulatorNode {
     pub has_erasure: bool,
     #[cfg_attr(feature = "python_binding", pyo3(get, set))]
     pub propagated: ErrorType,
+    /// the qubit is out of the computational subspace during this gate
+    #[cfg_attr(feature = "python_binding", pyo3(get, set))]
+    pub leaked: bool,
+    /// the qubit is lost and not yet replaced during this gate
+    #[cfg_attr(feature = "python_binding", pyo3(get, set))]
+    pub lost: bool,
+    /// the erasure is only detected this many time steps later, see [`SparseErasures::detection_delays`]
+    #[cfg_attr(feature = "python_binding", pyo3(get, set))]
+    pub erasure_detection_delay: u32,
     /// Virtual qubit doesn't physically exist, which means they will never have errors themselves.
     /// Real qubit errors can propagate to virtual qubits, but errors will never propagate to real qubits.
     /// Virtual qubits can be understood as perfect stabilizers that only absorb propagated errors and never propagate them.
@@ -205,6 +221,9 @@ impl SimulatorNode {
             error: I,
             has_erasure: false,
             propagated: I,
+            leaked: false,
+            lost: false,
+            erasure_detection_delay: 0,
             is_virtual: false,
             is_peer_virtual: false,
             miscellaneous: None,
@@ -212,7 +231,7 @@ impl SimulatorNode {
     }
     #[cfg_attr(feature = "python_binding", setter)]
     pub fn set_gate_peer(&mut self, pos: Position) {
-        self.gate_peer = Option::Some(pos).map(Arc::new);
+        self.gate_peer = Some(Arc::new(pos));
     }
     #[cfg_attr(feature = "python_binding", getter)]
     pub fn get_gate_peer(&self) -> Position {
@@ -391,6 +410,7 @@ impl Clone for Simulator {
             nodes: self.nodes.clone(),
             rng: Xoroshiro128StarStar::new(), // do not copy random number generator, otherwise parallel simulation may give same result
             measurement_cycles: self.measurement_cycles,
+            soft_readouts: self.soft_readouts.clone(),
         }
     }
 }
@@ -410,6 +430,7 @@ impl Simulator {
             nodes: Vec::new(),
             rng: Xoroshiro128StarStar::new(),
             measurement_cycles: 1,
+            soft_readouts: SparseSoftReadouts::new(),
         };
         build_code(&mut simulator);
         simulator
@@ -531,6 +552,9 @@ impl Simulator {
             node.error = I;
             node.has_erasure = false;
             node.propagated = I;
+            node.leaked = false;
+            node.lost = false;
+            node.erasure_detection_delay = 0;
         });
     }
 
@@ -565,6 +589,245 @@ impl Simulator {
         }
     }
 
+    /// track the leaked qubits starting from the leakage events at `pending_leakage_errors` in ascending order of `t`, and record
+    /// their effects as uniformly random pauli errors: at the two-qubit gate peer of a leaked qubit, right before measuring a
+    /// leaked qubit (corrupted outcome), and when a leaked qubit returns to the computational subspace through a leakage reduction
+    /// unit or at the final perfect measurement rounds; initialization also removes leakage unless `leakage_survives_reset`.
+    /// a leakage event at `t` takes effect from `t + 1`. returns the updated number of errors and erasures
+    pub fn apply_leakage_errors(
+        &mut self,
+        noise_model: &NoiseModel,
+        pending_leakage_errors: &[Position],
+        rng: &mut Xoroshiro128StarStar,
+        mut error_count: usize,
+        mut erasure_count: usize,
+    ) -> (usize, usize) {
+        let mut randomize = |simulator: &mut Self, position: &Position| {
+            if !simulator.add_random_pauli_error(position, rng.next_f64(), &mut error_count) {
+                return;
+            }
+            let node = simulator.get_node_mut_unwrap(position);
+            if noise_model.get_node_unwrap(position).leakage_heralded && !node.has_erasure {
+                node.has_erasure = true;
+                erasure_count += 1;
+            }
+        };
+        let mut pending_leakage_errors = pending_leakage_errors.iter().peekable();
+        let mut leaked_qubits = BTreeSet::<(usize, usize)>::new();
+        let noisy_height = self.height - self.measurement_cycles;
+        for t in 0..noisy_height {
+            for &(i, j) in leaked_qubits.clone().iter() {
+                let position = pos!(t, i, j);
+                let node = self.get_node_mut_unwrap(&position);
+                node.leaked = true;
+                let gate_type = node.gate_type;
+                let is_peer_virtual = node.is_peer_virtual;
+                let gate_peer = node.gate_peer.clone();
+                if gate_type.is_initialization() && !noise_model.get_node_unwrap(&position).leakage_survives_reset {
+                    leaked_qubits.remove(&(i, j));
+                    continue;
+                }
+                if gate_type.is_measurement() {
+                    randomize(self, &pos!(t - 1, i, j));
+                }
+                if gate_type.is_two_qubit_gate() && !is_peer_virtual {
+                    randomize(self, &gate_peer.unwrap());
+                }
+                if noise_model.get_node_unwrap(&position).leakage_reduction {
+                    leaked_qubits.remove(&(i, j));
+                    randomize(self, &position);
+                }
+            }
+            while let Some(position) = pending_leakage_errors.next_if(|position| position.t == t) {
+                leaked_qubits.insert((position.i, position.j));
+            }
+        }
+        // the final perfect measurement rounds start with all qubits in the computational subspace
+        for &(i, j) in leaked_qubits.iter() {
+            randomize(self, &pos!(noisy_height - 1, i, j));
+        }
+        (error_count, erasure_count)
+    }
+
+    /// track the lost qubits starting from the loss events at `pending_loss_errors` in ascending order of `t`. a lost qubit
+    /// randomizes the outcome of its measurements, and its two-qubit gates either depolarize the peer or act trivially; a trivial
+    /// gate with a lost data qubit randomizes the outcome of the stabilizer measurement of the peer instead. the loss
+    /// is detected at the next loss detection of the qubit, where it is replaced in a random state; the final perfect measurement
+    /// rounds detect all remaining losses. once detected, the randomized positions are heralded as erasures with a detection delay.
+    /// a loss event at `t` takes effect from `t + 1`. returns the updated number of errors and erasures
+    pub fn apply_loss_errors(
+        &mut self,
+        noise_model: &NoiseModel,
+        pending_loss_errors: &[Position],
+        rng: &mut Xoroshiro128StarStar,
+        mut error_count: usize,
+        mut erasure_count: usize,
+    ) -> (usize, usize) {
+        let mut pending_loss_errors = pending_loss_errors.iter().peekable();
+        // the randomized positions of each lost qubit, waiting to be heralded
+        let mut lost_qubits = BTreeMap::<(usize, usize), Vec<Position>>::new();
+        let mut detected_qubits = Vec::<(usize, usize)>::new();
+        let noisy_height = self.height - self.measurement_cycles;
+        for t in 0..noisy_height {
+            for (&(i, j), randomized) in lost_qubits.iter_mut() {
+                let position = pos!(t, i, j);
+                let node = self.get_node_mut_unwrap(&position);
+                node.lost = true;
+                let gate_type = node.gate_type;
+                let qubit_type = node.qubit_type;
+                let is_peer_virtual = node.is_peer_virtual;
+                let gate_peer = node.gate_peer.clone();
+                let noise_model_node = noise_model.get_node_unwrap(&position);
+                let has_real_peer = gate_type.is_two_qubit_gate() && !is_peer_virtual;
+                // a gate acting trivially on a lost data qubit drops its contribution from the stabilizer measurement of the peer
+                let peer_measurement =
+                    if has_real_peer && !noise_model_node.loss_depolarizing && qubit_type == QubitType::Data {
+                        let peer = gate_peer.as_ref().unwrap();
+                        (t + 1..noisy_height)
+                            .map(|t| pos!(t, peer.i, peer.j))
+                            .find(|position| self.get_node_unwrap(position).gate_type.is_measurement())
+                    } else {
+                        None
+                    };
+                let mut randomize = |position: Position| {
+                    if self.add_random_pauli_error(&position, rng.next_f64(), &mut error_count) {
+                        randomized.push(position);
+                    }
+                };
+                if gate_type.is_measurement() {
+                    randomize(pos!(t - 1, i, j));
+                }
+                if has_real_peer && noise_model_node.loss_depolarizing {
+                    randomize((*gate_peer.unwrap()).clone());
+                }
+                if let Some(peer_measurement) = peer_measurement {
+                    randomize(pos!(peer_measurement.t - 1, peer_measurement.i, peer_measurement.j));
+                }
+                if noise_model_node.loss_detection || t == noisy_height - 1 {
+                    randomize(position);
+                    detected_qubits.push((i, j));
+                }
+            }
+            for qubit in detected_qubits.drain(..) {
+                for position in lost_qubits.remove(&qubit).unwrap() {
+                    if !noise_model.get_node_unwrap(&position).loss_heralded {
+                        continue;
+                    }
+                    // the randomized outcome of a peer measurement may come after the detection
+                    let delay = t.saturating_sub(position.t) as u32;
+                    let node = self.get_node_mut_unwrap(&position);
+                    if node.has_erasure {
+                        node.erasure_detection_delay = node.erasure_detection_delay.min(delay);
+                    } else {
+                        node.has_erasure = true;
+                        node.erasure_detection_delay = delay;
+                        erasure_count += 1;
+                    }
+                }
+            }
+            while let Some(position) = pending_loss_errors.next_if(|position| position.t == t) {
+                lost_qubits.entry((position.i, position.j)).or_default();
+            }
+        }
+        (error_count, erasure_count)
+    }
+
+    /// multiply a uniformly random pauli error at a real position, returning false if the position is virtual
+    fn add_random_pauli_error(&mut self, position: &Position, random_pauli: f64, error_count: &mut usize) -> bool {
+        let node = self.get_node_mut_unwrap(position);
+        if node.is_virtual {
+            return false;
+        }
+        if node.error != I {
+            *error_count -= 1;
+        }
+        let error = if random_pauli < 0.25 {
+            X
+        } else if random_pauli < 0.5 {
+            Z
+        } else if random_pauli < 0.75 {
+            Y
+        } else {
+            I
+        };
+        node.set_error_temp(&node.error.multiply(&error));
+        if node.error != I {
+            *error_count += 1;
+        }
+        true
+    }
+
+    /// propagate errors while deciding state-dependent readout errors, given `(position, random_readout, random_back_action)`
+    /// of each measurement in ascending order of `t`; a readout flip is recorded as a `Y` error right before the measurement
+    /// and a back-action as a `Y` error right after the measurement, so that the error pattern fully describes what happened.
+    /// the state of a measurement is read from the Pauli frame, see [`MeasurementErrorRates`] for this limitation.
+    /// returns the updated number of errors
+    pub fn propagate_errors_with_readout_errors(
+        &mut self,
+        noise_model: &NoiseModel,
+        pending_readout_errors: &[(Position, f64, f64)],
+        mut error_count: usize,
+    ) -> usize {
+        let mut multiply_error = |simulator: &mut Self, position: &Position| {
+            let node = simulator.get_node_mut_unwrap(position);
+            if node.error != I {
+                error_count -= 1;
+            }
+            node.set_error_temp(&node.error.multiply(&Y));
+            if node.error != I {
+                error_count += 1;
+            }
+        };
+        let mut pending_readout_errors = pending_readout_errors.iter().peekable();
+        for t in 0..self.height - 1 {
+            // all errors before the measurements at `t` have been propagated
+            while let Some((position, random_readout, random_back_action)) =
+                pending_readout_errors.next_if(|(position, _, _)| position.t == t)
+            {
+                let noise_model_node = noise_model.get_node_unwrap(position);
+                let node = self.get_node_unwrap(position);
+                let mut state = node.gate_type.stabilizer_measurement(&node.propagated);
+                if let Some(soft_readout) = &noise_model_node.soft_readout {
+                    let value = soft_readout.sample(state, *random_readout, *random_back_action);
+                    if GaussianReadout::outcome(value) != state {
+                        let previous_position = pos!(position.t - 1, position.i, position.j);
+                        multiply_error(self, &previous_position);
+                        let node = self.get_node_mut_unwrap(position);
+                        node.propagated = node.propagated.multiply(&Y);
+                    }
+                    self.soft_readouts.readouts.insert(
+                        position.clone(),
+                        SoftReadout {
+                            value,
+                            flip_probability: soft_readout.flip_probability(value),
+                            prior_flip_probability: soft_readout.hard_flip_probability(),
+                            next_measurement: pos!(position.t + self.measurement_cycles, position.i, position.j),
+                        },
+                    );
+                    continue;
+                }
+                let measurement_error_rates = noise_model_node
+                    .measurement_error_rates
+                    .as_ref()
+                    .expect("readout error must come from a noisy measurement");
+                if *random_readout < measurement_error_rates.flip_probability(state) {
+                    let previous_position = pos!(position.t - 1, position.i, position.j);
+                    multiply_error(self, &previous_position);
+                    let node = self.get_node_mut_unwrap(position);
+                    node.propagated = node.propagated.multiply(&Y);
+                    state = !state;
+                }
+                if state && *random_back_action < measurement_error_rates.back_action {
+                    multiply_error(self, position);
+                }
+            }
+            simulator_iter!(self, position, _node, t => t, {
+                self.propagate_error_from(position);
+            });
+        }
+        error_count
+    }
+
     /// calculate propagated errors at one position. in order to correctly propagate every error, the order of propagation must be ascending in `t`s.
     /// note that errors are propagated to the next time, i.e. `t + 1`.
     /// when a error (other than Identity) propagates to the peer, it returns the position of the peer.
@@ -838,11 +1101,16 @@ impl SimulatorGenerics for Simulator {
         let allocate_size = self.height * self.vertical * self.horizontal;
         let mut pending_pauli_errors = Vec::<(Position, ErrorType)>::with_capacity(allocate_size);
         let mut pending_erasure_errors = Vec::<Position>::with_capacity(allocate_size);
+        let mut pending_readout_errors = Vec::<(Position, f64, f64)>::new();
+        let mut pending_leakage_errors = Vec::<Position>::new();
+        let mut pending_loss_errors = Vec::<Position>::new();
+        let mut pending_false_heralds = Vec::<Position>::new();
         // let mut pending_pauli_errors = Vec::<(Position, ErrorType)>::new();
         // let mut pending_erasure_errors = Vec::<Position>::new();
         let mut rng = self.rng.clone(); // avoid mutable borrow
         let mut error_count = 0;
         let mut erasure_count = 0;
+        self.soft_readouts.readouts.clear();
         // first apply single-qubit and two-qubit correlated errors
         simulator_iter_mut!(self, position, node, {
             let noise_model_node = noise_model.get_node_unwrap(position);
@@ -867,6 +1135,9 @@ impl SimulatorGenerics for Simulator {
             let random_erasure = rng.next_f64();
             node.has_erasure = false;
             node.propagated = I; // clear propagated errors
+            node.leaked = false;
+            node.lost = false;
+            node.erasure_detection_delay = 0;
             if random_erasure < noise_model_node.erasure_error_rate {
                 pending_erasure_errors.push(position.clone());
             }
@@ -909,6 +1180,21 @@ impl SimulatorGenerics for Simulator {
                 }
                 None => {}
             }
+            if noise_model_node.leakage_rate > 0. && rng.next_f64() < noise_model_node.leakage_rate {
+                pending_leakage_errors.push(position.clone());
+            }
+            if noise_model_node.loss_rate > 0. && rng.next_f64() < noise_model_node.loss_rate {
+                pending_loss_errors.push(position.clone());
+            }
+            if noise_model_node.measurement_error_rates.is_some() || noise_model_node.soft_readout.is_some() {
+                // state-dependent, can only be decided when the errors are propagated to this measurement
+                pending_readout_errors.push((position.clone(), rng.next_f64(), rng.next_f64()));
+            }
+            if let Some(erasure_model) = &noise_model_node.erasure_model {
+                if erasure_model.false_positive_rate > 0. && rng.next_f64() < erasure_model.false_positive_rate {
+                    pending_false_heralds.push(position.clone());
+                }
+            }
         });
         // then apply additional noises
         for additional_noise in noise_model.additional_noise.iter() {
@@ -935,18 +1221,27 @@ impl SimulatorGenerics for Simulator {
         }
         // apply pending erasure errors, amd generate random pauli error because of those erasures
         for position in pending_erasure_errors.iter() {
+            let erasure_model = noise_model.get_node_unwrap(position).erasure_model.as_ref();
+            let heralded = match erasure_model {
+                Some(erasure_model) if erasure_model.false_negative_rate > 0. => {
+                    rng.next_f64() >= erasure_model.false_negative_rate
+                }
+                _ => true,
+            };
             let node = self.get_node_mut_unwrap(position);
-            if !node.has_erasure {
+            if heralded && !node.has_erasure {
                 // only counts new erasures; there might be duplicated pending erasure
                 erasure_count += 1;
+                node.has_erasure = true;
             }
-            node.has_erasure = true;
             if node.error != I {
                 error_count -= 1;
             }
             let random_erasure = rng.next_f64();
             node.set_error_temp(
-                &(if random_erasure < 0.25 {
+                &(if let Some(erasure_model) = erasure_model {
+                    erasure_model.generate_random_conversion(random_erasure)
+                } else if random_erasure < 0.25 {
                     X
                 } else if random_erasure < 0.5 {
                     Z
@@ -960,6 +1255,27 @@ impl SimulatorGenerics for Simulator {
                 error_count += 1;
             };
         }
+        // false heralds report erasures without any error
+        for position in pending_false_heralds.iter() {
+            let node = self.get_node_mut_unwrap(position);
+            if !node.has_erasure {
+                erasure_count += 1;
+                node.has_erasure = true;
+            }
+        }
+        // finally let leaked qubits corrupt the gates they participate in
+        if !pending_leakage_errors.is_empty() {
+            let (leakage_error_count, leakage_erasure_count) =
+                self.apply_leakage_errors(noise_model, &pending_leakage_errors, &mut rng, error_count, erasure_count);
+            error_count = leakage_error_count;
+            erasure_count = leakage_erasure_count;
+        }
+        if !pending_loss_errors.is_empty() {
+            let (loss_error_count, loss_erasure_count) =
+                self.apply_loss_errors(noise_model, &pending_loss_errors, &mut rng, error_count, erasure_count);
+            error_count = loss_error_count;
+            erasure_count = loss_erasure_count;
+        }
         debug_assert!({
             // the above code avoids iterating the code multiple times when error rate is low (~1%), check correctness in debug mode
             let sparse_error_pattern = self.generate_sparse_error_pattern();
@@ -970,7 +1286,15 @@ impl SimulatorGenerics for Simulator {
             sparse_detected_erasures.len() == erasure_count
         });
         self.rng = rng; // save the random number generator
-        self.propagate_errors();
+        if pending_readout_errors.is_empty() {
+            self.propagate_errors();
+        } else {
+            error_count = self.propagate_errors_with_readout_errors(noise_model, &pending_readout_errors, error_count);
+            debug_assert!({
+                let sparse_error_pattern = self.generate_sparse_error_pattern();
+                sparse_error_pattern.len() == error_count
+            });
+        }
         (error_count, erasure_count)
     }
 
@@ -1004,6 +1328,11 @@ impl SimulatorGenerics for Simulator {
         sparse_measurement
     }
 
+    /// analog readouts recorded when generating the last shot
+    fn generate_sparse_soft_readouts(&self) -> SparseSoftReadouts {
+        self.soft_readouts.clone()
+    }
+
     /// generate detected erasures
     #[inline(never)]
     fn generate_sparse_detected_erasures(&self) -> SparseErasures {
@@ -1011,6 +1340,11 @@ impl SimulatorGenerics for Simulator {
         simulator_iter_real!(self, position, node, {
             if node.has_erasure {
                 sparse_detected_erasures.erasures.insert(position.clone());
+                if node.erasure_detection_delay > 0 {
+                    sparse_detected_erasures
+                        .detection_delays
+                        .insert(position.clone(), node.erasure_detection_delay as usize);
+                }
             }
         });
         sparse_detected_erasures
@@ -1126,6 +1460,7 @@ impl Simulator {
             let node = self.get_node_unwrap(position);
             possible |= noise_model_node.erasure_error_rate > 0.;
             possible |= noise_model_node.correlated_erasure_error_rates.is_some(); // weak check
+            possible |= noise_model_node.is_heralded();
             if !possible {
                 // check peer only if still not possible
                 if let Some(peer_position) = node.gate_peer.as_ref() {
@@ -1189,6 +1524,14 @@ impl Simulator {
                     if noise_model_node.pauli_error_rates.error_rate_Y > 0. {
                         possible = true;
                     }
+                    // state-dependent readout error is recorded right before and right after the measurement
+                    let next_position = pos!(position.t + 1, position.i, position.j);
+                    possible |= noise_model_node.measurement_error_rates.is_some(); // weak check
+                    possible |= noise_model.is_node_exist(&next_position) && {
+                        let next_noise_model_node = noise_model.get_node_unwrap(&next_position);
+                        next_noise_model_node.measurement_error_rates.is_some()
+                            || next_noise_model_node.soft_readout.is_some()
+                    };
                 }
                 ErrorType::Z => {
                     if noise_model_node.pauli_error_rates.error_rate_Z > 0. {
@@ -1200,6 +1543,7 @@ impl Simulator {
             possible |= noise_model_node.erasure_error_rate > 0.;
             possible |= noise_model_node.correlated_pauli_error_rates.is_some(); // weak check
             possible |= noise_model_node.correlated_erasure_error_rates.is_some(); // weak check
+            possible |= noise_model_node.is_heralded();
             if !possible {
                 // check peer only if still not possible
                 if let Some(peer_position) = node.gate_peer.as_ref() {
@@ -1527,6 +1871,17 @@ pub struct SparseErasures {
     /// the position of the erasure errors
     #[cfg_attr(feature = "python_binding", pyo3(get, set))]
     pub erasures: BTreeSet<Position>,
+    /// erasures that are detected later than they happen, e.g. atom loss, and their delay in time steps;
+    /// serialized as `[position, delay]` instead of the position
+    pub detection_delays: BTreeMap<Position, usize>,
+}
+
+/// a serialized erasure, optionally with its detection delay
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedErasure {
+    Immediate(Position),
+    Delayed(Position, usize),
 }
 
 impl Serialize for SparseErasures {
@@ -1536,7 +1891,10 @@ impl Serialize for SparseErasures {
     {
         let mut seq = serializer.serialize_seq(Some(self.len()))?; // known length
         for erasure in self.iter() {
-            seq.serialize_element(erasure)?;
+            match self.detection_delays.get(erasure) {
+                Some(delay) => seq.serialize_element(&(erasure, delay))?,
+                None => seq.serialize_element(erasure)?,
+            }
         }
         seq.end()
     }
@@ -1548,7 +1906,7 @@ impl<'de> Visitor<'de> for SparseErasures {
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             formatter,
-            r#"sparse detected erasure like ["[0][10][13]","[0][10][7]","[0][10][8]"]"#
+            r#"sparse detected erasure like ["[0][10][13]","[0][10][7]",["[0][10][8]",6]] where 6 is the detection delay"#
         )
     }
 
@@ -1557,8 +1915,18 @@ impl<'de> Visitor<'de> for SparseErasures {
         M: SeqAccess<'de>,
     {
         let mut sparse_detected_erasures = SparseErasures::new();
-        while let Some(position) = access.next_element()? {
-            sparse_detected_erasures.insert_erasure(&position);
+        while let Some(erasure) = access.next_element()? {
+            match erasure {
+                SerializedErasure::Immediate(position) => {
+                    sparse_detected_erasures.insert_erasure(&position);
+                }
+                SerializedErasure::Delayed(position, delay) => {
+                    sparse_detected_erasures.insert_erasure(&position);
+                    if delay > 0 {
+                        sparse_detected_erasures.detection_delays.insert(position, delay);
+                    }
+                }
+            }
         }
         Ok(sparse_detected_erasures)
     }
@@ -1596,6 +1964,7 @@ impl SparseErasures {
     pub fn new() -> Self {
         Self {
             erasures: BTreeSet::new(),
+            detection_delays: BTreeMap::new(),
         }
     }
     /// the length of defect measurements
@@ -1614,6 +1983,14 @@ impl SparseErasures {
     pub fn insert_erasure(&mut self, position: &Position) -> bool {
         self.erasures.insert(position.clone())
     }
+    /// the number of time steps between the erasure and its detection
+    pub fn detection_delay(&self, position: &Position) -> usize {
+        self.detection_delays.get(position).cloned().unwrap_or(0)
+    }
+    /// whether the erasure is already detected at time `t`
+    pub fn is_detected_at(&self, position: &Position, t: usize) -> bool {
+        self.contains(position) && position.t + self.detection_delay(position) <= t
+    }
 }
 
 impl SparseErasures {
@@ -1632,6 +2009,77 @@ impl SparseErasures {
         }
         erasure_edges
     }
+    /// compute the edges that are re-weighted because of these erasures, together with the probability of being flipped
+    pub fn get_weighted_erasure_edges(&self, erasure_graph: &ErasureGraph) -> Vec<(ErasureEdge, f64)> {
+        let mut erasure_edges = Vec::<(ErasureEdge, f64)>::new();
+        for erasure in self.erasures.iter() {
+            let erasure_node = erasure_graph.get_node_unwrap(erasure);
+            for (erasure_edge, probability) in erasure_node
+                .erasure_edges
+                .iter()
+                .zip(erasure_node.erasure_edge_probabilities.iter())
+            {
+                erasure_edges.push((erasure_edge.clone(), *probability));
+            }
+        }
+        erasure_edges
+    }
+}
+
+/// analog readout of a single measurement, see [`GaussianReadout`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftReadout {
+    /// normalized analog value, where the hard outcome is `value > 0.5`
+    pub value: f64,
+    /// the probability that the hard outcome is wrong given the analog value
+    pub flip_probability: f64,
+    /// the probability that the hard outcome is wrong, as assumed when building the decoding graph
+    pub prior_flip_probability: f64,
+    /// a flipped outcome generates defects at this measurement and the next measurement of the same stabilizer
+    pub next_measurement: Position,
+}
+
+impl SoftReadout {
+    /// the probability of the measurement edge after replacing the prior readout error with the one given the analog value
+    pub fn edge_probability(&self, edge_probability: f64) -> f64 {
+        let prior = self.prior_flip_probability;
+        // the probability of the other errors on the same edge
+        let other = ((edge_probability - prior) / (1. - 2. * prior)).clamp(0., 1.);
+        let probability = other + self.flip_probability - 2. * other * self.flip_probability;
+        probability.max(f64::MIN_POSITIVE)
+    }
+    /// rescale the weight of the measurement edge, where weights are roughly proportional to `ln((1-p)/p)`
+    pub fn edge_weight(&self, weight: f64, edge_probability: f64) -> f64 {
+        if edge_probability >= 0.5 || edge_probability <= 0. {
+            return weight;
+        }
+        let probability = self.edge_probability(edge_probability);
+        if probability >= 0.5 {
+            return 0.;
+        }
+        weight * ((1. - probability) / probability).ln() / ((1. - edge_probability) / edge_probability).ln()
+    }
+}
+
+/// analog readouts of all the noisy measurements in a shot, used to reweight the measurement edges of the decoders
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SparseSoftReadouts {
+    pub readouts: BTreeMap<Position, SoftReadout>,
+}
+
+impl SparseSoftReadouts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn len(&self) -> usize {
+        self.readouts.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.readouts.is_empty()
+    }
+    pub fn iter(&self) -> std::collections::btree_map::Iter<Position, SoftReadout> {
+        self.readouts.iter()
+    }
 }
 
 /// in most cases errors are rare, this sparse structure use `BTreeMap` to store them
@@ -1846,6 +2294,39 @@ impl Serialize for SparseCorrection {
     }
 }
 
+/// the elementary fault edges chosen by a decoder, each given by its real defect vertices and its correction;
+/// unlike [`SparseCorrection`] it keeps the spacetime location of the decoding result, e.g. for windowed decoding
+#[derive(Debug, Clone, Default)]
+pub struct SparseSubgraph {
+    pub edges: Vec<(Vec<Position>, Arc<SparseCorrection>)>,
+}
+
+impl SparseSubgraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(&mut self, defect_vertices: Vec<Position>, correction: Arc<SparseCorrection>) {
+        self.edges.push((defect_vertices, correction));
+    }
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+    pub fn iter(&self) -> std::slice::Iter<(Vec<Position>, Arc<SparseCorrection>)> {
+        self.edges.iter()
+    }
+    /// the overall correction of all the edges
+    pub fn to_correction(&self) -> SparseCorrection {
+        let mut correction = SparseCorrection::new();
+        for (_, edge_correction) in self.edges.iter() {
+            correction.extend(edge_correction);
+        }
+        correction
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1875,6 +2356,378 @@ mod tests {
             panic!("SimulatorNode which is unexpectedly large, check if anything wrong");
         }
     }
+
+    #[test]
+    fn simulator_asymmetric_readout_errors() {
+        // cargo test simulator_asymmetric_readout_errors -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        // a stabilizer measured at `2 * mc` and a data qubit it checks
+        let mut measurement = None;
+        simulator_iter_real!(simulator, position, node, t => 2 * mc - 2, {
+            if measurement.is_none() && node.qubit_type != QubitType::Data && node.gate_type.is_two_qubit_gate() && !node.is_peer_virtual {
+                measurement = Some((pos!(2 * mc, position.i, position.j), node.get_gate_peer()));
+            }
+        });
+        let (measurement, data) = measurement.unwrap();
+        let next_measurement = pos!(3 * mc, measurement.i, measurement.j);
+        let run = |simulator: &mut Simulator, data_error: bool, measurement_error_rates: MeasurementErrorRates| {
+            let mut noise_model = NoiseModel::new(simulator);
+            let mut measurement_node = NoiseModelNode::new();
+            measurement_node.measurement_error_rates = Some(measurement_error_rates);
+            noise_model.set_node(&measurement, Some(Arc::new(measurement_node)));
+            if data_error {
+                // flip the stabilizer between the first and second measurement
+                let mut data_node = NoiseModelNode::new();
+                data_node.pauli_error_rates.error_rate_Y = 1.;
+                noise_model.set_node(&pos!(mc + 1, data.i, data.j), Some(Arc::new(data_node)));
+            }
+            noise_model_sanity_check(simulator, &noise_model).unwrap();
+            let (error_count, _) = simulator.generate_random_errors(&noise_model);
+            let sparse_measurement = simulator.generate_sparse_measurement();
+            let sparse_error_pattern = simulator.generate_sparse_error_pattern();
+            // the recorded error pattern reproduces the same measurement
+            simulator.clear_all_errors();
+            simulator
+                .load_sparse_error_pattern(&sparse_error_pattern, &noise_model)
+                .unwrap();
+            simulator.propagate_errors();
+            assert_eq!(simulator.generate_sparse_measurement().to_vec(), sparse_measurement.to_vec());
+            (error_count, sparse_measurement)
+        };
+        // 1 -> 0 flip never happens when there is no error
+        let (error_count, sparse_measurement) = run(&mut simulator, false, MeasurementErrorRates::new(0., 1., 0.));
+        assert_eq!(error_count, 0);
+        assert!(sparse_measurement.is_empty());
+        // 1 -> 0 flip postpones the defect to the next round
+        let (error_count, sparse_measurement) = run(&mut simulator, true, MeasurementErrorRates::new(0., 1., 0.));
+        assert_eq!(error_count, 2);
+        assert!(!sparse_measurement.defects.contains(&measurement));
+        assert!(sparse_measurement.defects.contains(&next_measurement));
+        // 0 -> 1 flip with back-action on the ancilla, which is then reset
+        let (error_count, sparse_measurement) = run(&mut simulator, false, MeasurementErrorRates::new(1., 0., 1.));
+        assert_eq!(error_count, 2);
+        assert_eq!(
+            sparse_measurement.to_vec(),
+            vec![measurement.clone(), next_measurement.clone()]
+        );
+    }
+
+    #[test]
+    fn simulator_leakage_errors() {
+        // cargo test simulator_leakage_errors -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        // a data qubit leaks in the second round and returns at the leakage reduction unit in the third round
+        let mut leakage = None;
+        simulator_iter_real!(simulator, position, node, t => mc + 1, {
+            if leakage.is_none() && node.qubit_type == QubitType::Data {
+                leakage = Some(position.clone());
+            }
+        });
+        let leakage = leakage.unwrap();
+        let leakage_reduction = pos!(3 * mc, leakage.i, leakage.j);
+        let mut noise_model = NoiseModel::new(&simulator);
+        let mut heralded_node = NoiseModelNode::new();
+        heralded_node.leakage_heralded = true;
+        simulator_iter_real!(simulator, position, _node, {
+            noise_model.set_node(position, Some(Arc::new(heralded_node.clone())));
+        });
+        let mut leakage_node = heralded_node.clone();
+        leakage_node.leakage_rate = 1.;
+        noise_model.set_node(&leakage, Some(Arc::new(leakage_node)));
+        let mut leakage_reduction_node = heralded_node.clone();
+        leakage_reduction_node.leakage_reduction = true;
+        noise_model.set_node(&leakage_reduction, Some(Arc::new(leakage_reduction_node)));
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let (error_count, erasure_count) = simulator.generate_random_errors(&noise_model);
+        simulator_iter!(simulator, position, node, {
+            let is_leaked = position.i == leakage.i
+                && position.j == leakage.j
+                && position.t > leakage.t
+                && position.t <= leakage_reduction.t;
+            assert_eq!(node.leaked, is_leaked, "leakage status mismatch at {}", position);
+        });
+        // the leaked qubit corrupts every two-qubit gate it participates in, all heralded
+        let mut corrupted = BTreeSet::new();
+        corrupted.insert(leakage_reduction.clone());
+        for t in leakage.t + 1..=leakage_reduction.t {
+            let node = simulator.get_node_unwrap(&pos!(t, leakage.i, leakage.j));
+            if node.gate_type.is_two_qubit_gate() && !node.is_peer_virtual {
+                corrupted.insert(node.get_gate_peer());
+            }
+        }
+        let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+        assert_eq!(sparse_detected_erasures.len(), erasure_count);
+        assert_eq!(sparse_detected_erasures.iter().cloned().collect::<BTreeSet<_>>(), corrupted);
+        let sparse_error_pattern = simulator.generate_sparse_error_pattern();
+        assert_eq!(sparse_error_pattern.len(), error_count);
+        for (position, _) in sparse_error_pattern.iter() {
+            assert!(corrupted.contains(position));
+        }
+    }
+
+    #[test]
+    fn simulator_leakage_survives_reset() {
+        // cargo test simulator_leakage_survives_reset -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        // an ancilla qubit leaks in the first round and returns at the leakage reduction unit in the third round, if the
+        // leakage survives the resets in between
+        let mut leakage = None;
+        simulator_iter_real!(simulator, position, node, t => 2, {
+            if leakage.is_none() && node.qubit_type != QubitType::Data {
+                leakage = Some(position.clone());
+            }
+        });
+        let leakage = leakage.unwrap();
+        let leakage_reduction = pos!(3 * mc, leakage.i, leakage.j);
+        let mut reset = leakage.t + 1;
+        while !simulator
+            .get_node_unwrap(&pos!(reset, leakage.i, leakage.j))
+            .gate_type
+            .is_initialization()
+        {
+            reset += 1;
+        }
+        for (leakage_survives_reset, last_leaked_t) in [(false, reset), (true, leakage_reduction.t)] {
+            let mut noise_model = NoiseModel::new(&simulator);
+            let mut reset_node = NoiseModelNode::new();
+            reset_node.leakage_survives_reset = leakage_survives_reset;
+            simulator_iter_real!(simulator, position, _node, {
+                noise_model.set_node(position, Some(Arc::new(reset_node.clone())));
+            });
+            let mut leakage_node = reset_node.clone();
+            leakage_node.leakage_rate = 1.;
+            noise_model.set_node(&leakage, Some(Arc::new(leakage_node)));
+            let mut leakage_reduction_node = reset_node.clone();
+            leakage_reduction_node.leakage_reduction = true;
+            noise_model.set_node(&leakage_reduction, Some(Arc::new(leakage_reduction_node)));
+            noise_model_sanity_check(&simulator, &noise_model).unwrap();
+            simulator.generate_random_errors(&noise_model);
+            simulator_iter!(simulator, position, node, {
+                let is_leaked = position.i == leakage.i
+                    && position.j == leakage.j
+                    && position.t > leakage.t
+                    && position.t <= last_leaked_t;
+                assert_eq!(node.leaked, is_leaked, "leakage status mismatch at {}", position);
+            });
+        }
+    }
+
+    #[test]
+    fn simulator_loss_errors() {
+        // cargo test simulator_loss_errors -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        // an ancilla qubit is lost in the second round and the loss is detected at its next measurement
+        let mut loss = None;
+        simulator_iter_real!(simulator, position, node, t => mc + 1, {
+            if loss.is_none() && node.qubit_type != QubitType::Data {
+                loss = Some(position.clone());
+            }
+        });
+        let loss = loss.unwrap();
+        let detection = pos!(2 * mc, loss.i, loss.j);
+        let mut noise_model = NoiseModel::new(&simulator);
+        let mut heralded_node = NoiseModelNode::new();
+        heralded_node.loss_heralded = true;
+        heralded_node.loss_depolarizing = true;
+        simulator_iter_real!(simulator, position, _node, {
+            noise_model.set_node(position, Some(Arc::new(heralded_node.clone())));
+        });
+        let mut loss_node = heralded_node.clone();
+        loss_node.loss_rate = 1.;
+        noise_model.set_node(&loss, Some(Arc::new(loss_node)));
+        let mut detection_node = heralded_node.clone();
+        detection_node.loss_detection = true;
+        noise_model.set_node(&detection, Some(Arc::new(detection_node)));
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let (error_count, erasure_count) = simulator.generate_random_errors(&noise_model);
+        simulator_iter!(simulator, position, node, {
+            let is_lost = position.i == loss.i && position.j == loss.j && position.t > loss.t && position.t <= detection.t;
+            assert_eq!(node.lost, is_lost, "loss status mismatch at {}", position);
+        });
+        // initialization doesn't bring a lost qubit back, and the erasures are only known at the detection
+        let mut randomized = BTreeSet::new();
+        randomized.insert(detection.clone());
+        randomized.insert(pos!(detection.t - 1, loss.i, loss.j));
+        for t in loss.t + 1..=detection.t {
+            let node = simulator.get_node_unwrap(&pos!(t, loss.i, loss.j));
+            if node.gate_type.is_two_qubit_gate() && !node.is_peer_virtual {
+                randomized.insert(node.get_gate_peer());
+            }
+        }
+        let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+        assert_eq!(sparse_detected_erasures.len(), erasure_count);
+        assert_eq!(sparse_detected_erasures.iter().cloned().collect::<BTreeSet<_>>(), randomized);
+        for position in sparse_detected_erasures.iter() {
+            assert_eq!(position.t + sparse_detected_erasures.detection_delay(position), detection.t);
+            assert!(!sparse_detected_erasures.is_detected_at(position, detection.t - 1));
+            assert!(sparse_detected_erasures.is_detected_at(position, detection.t));
+        }
+        // the detection delays are kept in the serialized erasures
+        let deserialized: SparseErasures = serde_json::from_value(json!(sparse_detected_erasures)).unwrap();
+        assert_eq!(deserialized.erasures, sparse_detected_erasures.erasures);
+        assert_eq!(deserialized.detection_delays, sparse_detected_erasures.detection_delays);
+        assert_eq!(simulator.generate_sparse_error_pattern().len(), error_count);
+    }
+
+    #[test]
+    fn simulator_loss_errors_trivial_gate() {
+        // cargo test simulator_loss_errors_trivial_gate -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        // a data qubit is lost in the second round and the gates act trivially on it
+        let mut loss = None;
+        simulator_iter_real!(simulator, position, node, t => mc + 1, {
+            if loss.is_none() && node.qubit_type == QubitType::Data {
+                loss = Some(position.clone());
+            }
+        });
+        let loss = loss.unwrap();
+        let detection = pos!(2 * mc, loss.i, loss.j);
+        let mut noise_model = NoiseModel::new(&simulator);
+        let mut heralded_node = NoiseModelNode::new();
+        heralded_node.loss_heralded = true;
+        simulator_iter_real!(simulator, position, _node, {
+            noise_model.set_node(position, Some(Arc::new(heralded_node.clone())));
+        });
+        let mut loss_node = heralded_node.clone();
+        loss_node.loss_rate = 1.;
+        noise_model.set_node(&loss, Some(Arc::new(loss_node)));
+        let mut detection_node = heralded_node.clone();
+        detection_node.loss_detection = true;
+        noise_model.set_node(&detection, Some(Arc::new(detection_node)));
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        simulator.generate_random_errors(&noise_model);
+        // the stabilizer measurements of the peers miss the lost qubit and are randomized
+        let mut randomized = BTreeSet::new();
+        randomized.insert(detection.clone());
+        for t in loss.t + 1..=detection.t {
+            let node = simulator.get_node_unwrap(&pos!(t, loss.i, loss.j));
+            if node.gate_type.is_two_qubit_gate() && !node.is_peer_virtual {
+                let peer = node.get_gate_peer();
+                randomized.insert(pos!(2 * mc - 1, peer.i, peer.j));
+            }
+        }
+        assert!(randomized.len() > 1);
+        let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+        assert_eq!(sparse_detected_erasures.iter().cloned().collect::<BTreeSet<_>>(), randomized);
+        for position in sparse_detected_erasures.iter() {
+            assert!(sparse_detected_erasures.is_detected_at(position, detection.t));
+        }
+    }
+
+    #[test]
+    fn simulator_soft_readout() {
+        // cargo test simulator_soft_readout -- --nocapture
+        let d = 3;
+        let noisy_measurements = 3;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        let soft_readout = GaussianReadout::new(0.5);
+        assert!((soft_readout.hard_flip_probability() - 0.158655).abs() < 1e-6);
+        assert_eq!(soft_readout.flip_probability(0.5), 0.5);
+        assert!(soft_readout.flip_probability(1.) < soft_readout.flip_probability(0.8));
+        let mut noise_model = NoiseModel::new(&simulator);
+        let mut measurement_node = NoiseModelNode::new();
+        measurement_node.soft_readout = Some(soft_readout);
+        let measurement_node = Arc::new(measurement_node);
+        let mut measurements = vec![];
+        simulator_iter_real!(simulator, position, node, {
+            if position.t > 0 && position.t < simulator.height - mc && node.gate_type.is_measurement() {
+                noise_model.set_node(position, Some(measurement_node.clone()));
+                measurements.push(position.clone());
+            }
+        });
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        for _ in 0..10 {
+            let (error_count, _) = simulator.generate_random_errors(&noise_model);
+            assert_eq!(simulator.generate_sparse_error_pattern().len(), error_count);
+            let sparse_soft_readouts = simulator.generate_sparse_soft_readouts();
+            assert_eq!(sparse_soft_readouts.len(), measurements.len());
+            for position in measurements.iter() {
+                let soft_readout = &sparse_soft_readouts.readouts[position];
+                // the hard outcome seen by the decoder is decided by the analog value
+                let node = simulator.get_node_unwrap(position);
+                let outcome = node.gate_type.stabilizer_measurement(&node.propagated);
+                assert_eq!(GaussianReadout::outcome(soft_readout.value), outcome);
+                assert_eq!(soft_readout.next_measurement, pos!(position.t + mc, position.i, position.j));
+                // a confident readout makes the measurement edge heavier
+                let edge_weight = soft_readout.edge_weight(1., soft_readout.prior_flip_probability);
+                assert_eq!(
+                    edge_weight > 1.,
+                    soft_readout.flip_probability < soft_readout.prior_flip_probability
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn simulator_imperfect_erasure_herald() {
+        // cargo test simulator_imperfect_erasure_herald -- --nocapture
+        let d = 3;
+        let noisy_measurements = 2;
+        let mut simulator = Simulator::new(CodeType::StandardPlanarCode, CodeSize::new(noisy_measurements, d, d));
+        let mc = simulator.measurement_cycles;
+        let mut data_positions = vec![];
+        simulator_iter_real!(simulator, position, node, t => mc, {
+            if node.qubit_type == QubitType::Data {
+                data_positions.push(position.clone());
+            }
+        });
+        let (missed, heralded, false_herald) = (&data_positions[0], &data_positions[1], &data_positions[2]);
+        let mut noise_model = NoiseModel::new(&simulator);
+        // an erasure that is never heralded but always leaves a Z error
+        let mut missed_node = NoiseModelNode::new();
+        missed_node.erasure_error_rate = 1.;
+        missed_node.erasure_model = Some(ErasureModel::new(0., 1.).with_biased_conversion(1.));
+        noise_model.set_node(missed, Some(Arc::new(missed_node)));
+        // a perfectly heralded erasure that always leaves a Z error
+        let mut heralded_node = NoiseModelNode::new();
+        heralded_node.erasure_error_rate = 0.1;
+        heralded_node.erasure_model = Some(ErasureModel::new(0., 0.).with_biased_conversion(1.));
+        let heralded_node = Arc::new(heralded_node);
+        noise_model.set_node(heralded, Some(heralded_node.clone()));
+        // a herald without any erasure
+        let mut false_herald_node = NoiseModelNode::new();
+        false_herald_node.erasure_model = Some(ErasureModel::new(1., 0.));
+        noise_model.set_node(false_herald, Some(Arc::new(false_herald_node)));
+        noise_model_sanity_check(&simulator, &noise_model).unwrap();
+        let (error_count, erasure_count) = simulator.generate_random_errors(&noise_model);
+        let sparse_detected_erasures = simulator.generate_sparse_detected_erasures();
+        assert_eq!(sparse_detected_erasures.len(), erasure_count);
+        assert!(!sparse_detected_erasures.erasures.contains(missed));
+        assert!(sparse_detected_erasures.erasures.contains(false_herald));
+        let sparse_error_pattern = simulator.generate_sparse_error_pattern();
+        assert_eq!(sparse_error_pattern.len(), error_count);
+        assert_eq!(sparse_error_pattern.errors.get(missed), Some(&Z));
+        assert_eq!(sparse_error_pattern.errors.get(false_herald), None);
+        // the heralded node only flips the edges of Z errors
+        let mut erasure_graph = ErasureGraph::new(&simulator);
+        erasure_graph.build(&mut simulator, Arc::new(noise_model), 1);
+        let erasure_node = erasure_graph.get_node_unwrap(heralded);
+        assert_eq!(erasure_node.erasure_edges.len(), 2, "both X and Z errors are detectable");
+        let mut probabilities = erasure_node.erasure_edge_probabilities.clone();
+        probabilities.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(probabilities, vec![0., 1.]);
+        // a herald from a node that never has erasures carries no information
+        let erasure_node = erasure_graph.get_node_unwrap(false_herald);
+        for probability in erasure_node.erasure_edge_probabilities.iter() {
+            assert_eq!(erasure_edge_weight(*probability), f64::INFINITY);
+        }
+    }
 }
 
 #[cfg(feature = "python_binding")]
@@ -273,8 +273,10 @@ impl SimulatorCompact {
             }
             let noise_model_node = noise_model.get_node_unwrap(position);
             // whether it's possible to have erasure error at this node
-            let possible_erasure_error =
-                noise_model_node.erasure_error_rate > 0. || noise_model_node.correlated_erasure_error_rates.is_some() || {
+            let possible_erasure_error = noise_model_node.erasure_error_rate > 0.
+                || noise_model_node.correlated_erasure_error_rates.is_some()
+                || noise_model_node.is_heralded()
+                || {
                     let node = simulator.get_node_unwrap(position);
                     if let Some(gate_peer) = node.gate_peer.as_ref() {
                         let peer_noise_model_node = noise_model.get_node_unwrap(gate_peer);
@@ -290,7 +292,7 @@ impl SimulatorCompact {
             assert!(!possible_erasure_error, "not implemented");
             for error in all_possible_errors.iter() {
                 let p = match error {
-                    Either::Left(error_type) => noise_model_node.pauli_error_rates.error_rate(error_type),
+                    Either::Left(error_type) => noise_model.effective_pauli_error_rate(position, error_type),
                     Either::Right(error_type) => match &noise_model_node.correlated_pauli_error_rates {
                         Some(correlated_pauli_error_rates) => correlated_pauli_error_rates.error_rate(error_type),
                         None => 0.,
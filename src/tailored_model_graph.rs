@@ -281,8 +281,10 @@ impl TailoredModelGraph {
         simulator_iter!(simulator, position, {
             let noise_model_node = noise_model.get_node_unwrap(position);
             // whether it's possible to have erasure error at this node
-            let possible_erasure_error =
-                noise_model_node.erasure_error_rate > 0. || noise_model_node.correlated_erasure_error_rates.is_some() || {
+            let possible_erasure_error = noise_model_node.erasure_error_rate > 0.
+                || noise_model_node.correlated_erasure_error_rates.is_some()
+                || noise_model_node.is_heralded()
+                || {
                     let node = simulator.get_node_unwrap(position);
                     if let Some(gate_peer) = node.gate_peer.as_ref() {
                         let peer_noise_model_node = noise_model.get_node_unwrap(gate_peer);
@@ -295,9 +297,15 @@ impl TailoredModelGraph {
                         false
                     }
                 };
+            // the tailored edges model the dominant Pauli error that generates 4 non-trivial measurements, e.g. Pauli Z in
+            // the tailored surface code; in the XZZX code the dominant Pauli Z only generates 2 of them instead
+            let dominant_error_rate = ErrorType::all_possible_errors()
+                .iter()
+                .map(|error_type| noise_model.effective_pauli_error_rate(position, error_type))
+                .fold(0., f64::max);
             for error in all_possible_errors.iter() {
                 let p = match error {
-                    Either::Left(error_type) => noise_model_node.pauli_error_rates.error_rate(error_type),
+                    Either::Left(error_type) => noise_model.effective_pauli_error_rate(position, error_type),
                     Either::Right(error_type) => match &noise_model_node.correlated_pauli_error_rates {
                         Some(correlated_pauli_error_rates) => correlated_pauli_error_rates.error_rate(error_type),
                         None => 0.,
@@ -419,6 +427,19 @@ impl TailoredModelGraph {
                                 sparse_correction.clone(),
                             );
                         }
+                    } else if sparse_measurement.len() == 4 && error.is_left() && p < dominant_error_rate {
+                        // a less likely error decomposes into the dominant ones, except that it might become an edge
+                        // between two different types of stabilizers when the other two are virtual
+                        if sparse_measurement_real.len() == 2 && (p > 0. || is_erasure) {
+                            self.add_edge_between(
+                                &sparse_measurement_real[0],
+                                &sparse_measurement_real[1],
+                                p,
+                                weight_of(p),
+                                sparse_errors.clone(),
+                                sparse_correction.clone(),
+                            );
+                        }
                     } else if sparse_measurement.len() == 4 {
                         // tailored edges
                         // tailored surface code decoding method can handle special cases arXiv:1907.02554v2
@@ -2,23 +2,34 @@
 
 use crate::cli::*;
 use crate::code_builder::*;
+use crate::complementary_gap::*;
 use crate::complete_model_graph::*;
+use crate::decoder_bp_osd::*;
+use crate::decoder_distributed_union_find::*;
+use crate::decoder_ensemble::*;
+use crate::decoder_external::*;
 #[cfg(feature = "fusion_blossom")]
 use crate::decoder_fusion::*;
 #[cfg(feature = "fusion_blossom")]
 use crate::decoder_parallel_fusion::*;
-#[cfg(feature = "hyperion")]
+use crate::decoder_greedy::*;
 use crate::decoder_hyper_union_find::*;
 #[cfg(feature = "hyperion")]
 use crate::decoder_hyperion::*;
+use crate::decoder_lookup_table::*;
 use crate::decoder_mwpm::*;
+use crate::decoder_peeling::*;
 use crate::decoder_tailored_mwpm::*;
+use crate::decoder_tensor_network::*;
 use crate::decoder_union_find::*;
+use crate::decoder_windowed::*;
 use crate::erasure_graph::*;
+use crate::fpga_generator::*;
 use crate::model_graph::*;
 use crate::model_hypergraph::*;
 use crate::noise_model::*;
 use crate::noise_model_builder::*;
+use crate::noise_schedule::*;
 use crate::reproducible_rand::Xoroshiro128StarStar;
 use crate::simulator::*;
 use crate::simulator_compact::*;
@@ -48,7 +59,64 @@ impl ToolCommands {
     pub fn run(self) -> Result<String, String> {
         match self {
             Self::Benchmark(benchmark_parameters) => benchmark_parameters.run(),
+            Self::GenerateFpga(generate_fpga_parameters) => generate_fpga_parameters.run(),
+        }
+    }
+}
+
+impl GenerateFpgaParameters {
+    pub fn run(&self) -> Result<String, String> {
+        let mut simulator = Simulator::new(self.code_type, CodeSize::new(self.noisy_measurements, self.d, self.d));
+        let mut noise_model = NoiseModel::new(&simulator);
+        let px = self.p / (1. + self.bias_eta) / 2.;
+        let py = px;
+        let pz = self.p - 2. * px;
+        simulator.set_error_rates(&mut noise_model, px, py, pz, 0.);
+        if let Some(noise_model_builder) = &self.noise_model_builder {
+            noise_model_builder.apply(
+                &mut simulator,
+                &mut noise_model,
+                &self.noise_model_configuration,
+                self.p,
+                self.bias_eta,
+                0.,
+            );
+        }
+        noise_model_sanity_check(&simulator, &noise_model)?;
+        simulator.compress_error_rates(&mut noise_model);
+        let noise_model = Arc::new(noise_model);
+        let mut decoder_config = self.decoder_config.clone();
+        let decoder_config_map = decoder_config
+            .as_object_mut()
+            .ok_or("decoder configuration must be an object")?;
+        decoder_config_map.remove("fci");
+        decoder_config_map.insert("fast_channel_interval".to_string(), json!(self.fast_channel_interval));
+        let decoder = DistributedUnionFindDecoder::new(&simulator, Arc::clone(&noise_model), &decoder_config, 1, false);
+        let generator = FpgaGenerator::new(&self.module_name, &decoder)?;
+        if let Some(seed) = self.deterministic_seed {
+            simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(seed));
         }
+        let sparse_measurements: Vec<SparseMeasurement> = (0..self.cosim_cases)
+            .map(|_| {
+                simulator.generate_random_errors(&noise_model);
+                simulator.generate_sparse_measurement()
+            })
+            .collect();
+        let paths = generator.write_files(&self.output_dir, &decoder, &sparse_measurements)?;
+        let mut output = format!(
+            "{} processing units, {} neighbor links, {} fast channels, address width {}\n",
+            decoder.index_to_position.len(),
+            decoder.input_neighbors.len(),
+            decoder.input_fast_channels.len(),
+            generator.address_width()
+        );
+        for path in paths.iter() {
+            output += &format!("generated {path}\n");
+        }
+        if self.run_verilator {
+            output += &generator.run_verilator_cosim(&self.output_dir, &self.verilated_dir)?;
+        }
+        Ok(output)
     }
 }
 
@@ -112,12 +180,28 @@ pub enum BenchmarkDecoder {
     TailoredMWPM,
     /// union-find decoder
     UnionFind,
+    /// clock-cycle-accurate simulation of the distributed union-find decoder with fast channels
+    DistributedUnionFind,
     /// hypergraph union-find decoder
     HyperUnionFind,
     /// hyperion decoder
     Hyperion,
     /// parallel fusion blossom
     ParallelFusion,
+    /// belief propagation with ordered statistics decoding post-processing
+    BPOSD,
+    /// lookup table decoder with exact maximum-likelihood corrections, only for small codes
+    LookupTable,
+    /// approximate maximum-likelihood decoder contracting a tensor network, only for code-capacity noise
+    TensorNetwork,
+    /// linear-time peeling decoder for erasure errors, with a union-find decoder for the remaining defects
+    Peeling,
+    /// run several decoders configured in `--decoder-config` and choose one of their corrections
+    Ensemble,
+    /// greedy matching decoder that repeatedly pairs the closest defects or a defect with the boundary
+    Greedy,
+    /// external decoder executable configured in `--decoder-config`, communicating in JSON lines over stdin/stdout
+    External,
 }
 
 /// progress variable shared between threads to update information
@@ -128,23 +212,75 @@ pub struct BenchmarkControl {
     pub total_repeats: usize,
     pub qec_failed: usize,
     pub external_termination: bool,
+    /// the shots post-selected by each threshold of the complementary gap
+    pub post_selection: Vec<PostSelectionCounter>,
+}
+
+/// the shots whose complementary gap is below the threshold are aborted, and the others are accepted
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostSelectionCounter {
+    pub gap_threshold: f64,
+    pub aborted: usize,
+    pub qec_failed: usize,
 }
 
 impl BenchmarkControl {
-    fn new() -> Self {
+    fn new(post_selection_gap_thresholds: &[f64]) -> Self {
         Self {
             total_repeats: 0,
             qec_failed: 0,
             external_termination: false,
+            post_selection: post_selection_gap_thresholds
+                .iter()
+                .map(|&gap_threshold| PostSelectionCounter {
+                    gap_threshold,
+                    aborted: 0,
+                    qec_failed: 0,
+                })
+                .collect(),
         }
     }
-    fn update_data_should_terminate(&mut self, is_qec_failed: bool, max_repeats: usize, min_failed_cases: usize) -> bool {
+    fn update_data_should_terminate(
+        &mut self,
+        is_qec_failed: bool,
+        complementary_gap: f64,
+        max_repeats: usize,
+        min_failed_cases: usize,
+    ) -> bool {
         self.total_repeats += 1;
         if is_qec_failed {
             self.qec_failed += 1;
         }
+        for counter in self.post_selection.iter_mut() {
+            if complementary_gap < counter.gap_threshold {
+                counter.aborted += 1;
+            } else if is_qec_failed {
+                counter.qec_failed += 1;
+            }
+        }
         self.should_terminate(max_repeats, min_failed_cases)
     }
+    /// one line for each gap threshold: <gap> <accepted> <failed> <pL> <abort_rate> <pL_dev>
+    fn post_selection_information(&self) -> String {
+        let mut lines = String::new();
+        for counter in self.post_selection.iter() {
+            let accepted = self.total_repeats - counter.aborted;
+            let abort_rate = counter.aborted as f64 / self.total_repeats as f64;
+            if accepted == 0 {
+                // no shot is accepted, so the logical error rate of the accepted shots is unknown
+                lines += &format!("ps {} 0 0 nan {} nan\n", counter.gap_threshold, abort_rate);
+                continue;
+            }
+            let error_rate = counter.qec_failed as f64 / accepted as f64;
+            let confidence_interval_95_percent =
+                1.96 * (error_rate * (1. - error_rate) / (accepted as f64)).sqrt() / error_rate;
+            lines += &format!(
+                "ps {} {} {} {} {} {:.1e}\n",
+                counter.gap_threshold, accepted, counter.qec_failed, error_rate, abort_rate, confidence_interval_95_percent
+            );
+        }
+        lines
+    }
     fn should_terminate(&self, max_repeats: usize, min_failed_cases: usize) -> bool {
         self.external_termination || self.total_repeats >= max_repeats || self.qec_failed >= min_failed_cases
     }
@@ -264,8 +400,14 @@ impl BenchmarkParameters {
         }
         // first list all configurations and validate them at the beginning
         let mut output = "".to_string();
-        let titles = "format: <p> <di> <nm> <shots> <failed> <pL> <dj> <pL_dev> <pe>".to_string();
+        let mut titles = "format: <p> <di> <nm> <shots> <failed> <pL> <dj> <pL_dev> <pe>".to_string();
         eprintln!("{}", titles); // compatible with old scripts
+        if self.post_selection.is_some() {
+            let post_selection_titles = "post-selection format: ps <gap> <accepted> <failed> <pL> <abort_rate> <pL_dev>";
+            eprintln!("{}", post_selection_titles);
+            titles += "\n";
+            titles += post_selection_titles;
+        }
         if self.debug_print.is_none() {
             // debug print only, outputs user specified debug info
             output = titles + "\n";
@@ -664,10 +806,36 @@ impl BenchmarkParameters {
         } }
         // then prepare the real noise model
         let noise_model = self.construct_noise_model(&mut simulator, configs, config, false)?;
+        // prepare the time-varying noise, which is only known to the simulator
+        let noise_schedule = match &self.noise_schedule {
+            Some(noise_schedule_config) => {
+                let noise_schedule_config: NoiseScheduleConfig =
+                    serde_json::from_value(noise_schedule_config.clone()).map_err(|x| x.to_string())?;
+                if self.use_compact_simulator || self.error_pattern.is_some() {
+                    return Err("noise schedule only works with the default simulator".to_string());
+                }
+                Some(NoiseSchedule::new(&simulator, noise_model.clone(), noise_schedule_config)?)
+            }
+            None => None,
+        };
+        if self.use_compact_simulator && noise_model.has_leakage_or_loss() {
+            return Err("leakage and loss errors only work with the default simulator".to_string());
+        }
+        if self.use_compact_simulator && noise_model.has_soft_readout() {
+            return Err("analog readout only works with the default simulator".to_string());
+        }
         // prepare visualizer
         let visualizer = self.prepare_visualizer(&mut simulator, &noise_model, &noise_model_graph, configs)?;
         // prepare result variables for simulation
-        let benchmark_control = Arc::new(Mutex::new(BenchmarkControl::new()));
+        if self.post_selection.is_some() && !general_decoder.reports_complementary_gap() {
+            return Err("post-selection requires a decoder reporting the complementary gap, e.g. `mwpm`, `fusion` or `union-find` with `{\"complementary_gap\":true}` in the decoder configuration".to_string());
+        }
+        if general_decoder.reports_complementary_gap() && (noise_model.has_erasure() || noise_model.has_soft_readout()) {
+            return Err("complementary gap doesn't support erasure errors or analog readouts yet".to_string());
+        }
+        let benchmark_control = Arc::new(Mutex::new(BenchmarkControl::new(
+            self.post_selection.as_deref().unwrap_or_default(),
+        )));
         // setup progress bar
         let mut pb = ProgressBar::on(std::io::stderr(), configs.max_repeats as u64);
         pb.set(0);
@@ -716,14 +884,21 @@ impl BenchmarkParameters {
             let thread_ended = Arc::new(AtomicBool::new(false));
             threads_ended.push(Arc::clone(&thread_ended));
             let mut thread_general_simulator = general_simulator.clone();
+            let mut thread_noise_schedule = noise_schedule.clone();
             if let Some(deterministic_seed) = configs.deterministic_seed {
                 let seed: u64 = deterministic_seed + parallel_idx as u64;
                 thread_general_simulator.set_rng(Xoroshiro128StarStar::seed_from_u64(seed));
+                if let Some(noise_schedule) = thread_noise_schedule.as_mut() {
+                    // use seeds that no simulator uses
+                    let seed: u64 = deterministic_seed + (configs.parallel + parallel_idx) as u64;
+                    noise_schedule.set_rng(Xoroshiro128StarStar::seed_from_u64(seed));
+                }
             }
             let mut worker_state = SimulationWorker {
                 benchmark_control: benchmark_control.clone(),
                 general_simulator: thread_general_simulator,
                 noise_model: noise_model.clone(),
+                noise_schedule: thread_noise_schedule,
                 log_runtime_statistics_file: log_runtime_statistics_file.clone(),
                 visualizer: visualizer.clone(),
                 general_decoder: general_decoder.clone(),
@@ -871,7 +1046,14 @@ impl BenchmarkParameters {
         }
         pb.finish();
         eprintln!("{}", progress_information());
-        Ok(progress_information())
+        let mut output = progress_information();
+        let post_selection_information = benchmark_control.lock().unwrap().post_selection_information();
+        if !post_selection_information.is_empty() {
+            eprint!("{}", post_selection_information);
+            output += "\n";
+            output += post_selection_information.trim_end();
+        }
+        Ok(output)
     }
 }
 
@@ -886,10 +1068,19 @@ pub enum GeneralDecoder {
     ParallelFusion(ParallelFusionDecoder),
     TailoredMWPM(TailoredMWPMDecoder),
     UnionFind(UnionFindDecoder),
-    #[cfg(feature = "hyperion")]
+    DistributedUnionFind(DistributedUnionFindDecoder),
     HyperUnionFind(HyperUnionFindDecoder),
     #[cfg(feature = "hyperion")]
     Hyperion(HyperionDecoder),
+    BPOSD(BPOSDDecoder),
+    LookupTable(LookupTableDecoder),
+    TensorNetwork(TensorNetworkDecoder),
+    Peeling(PeelingDecoder),
+    Ensemble(EnsembleDecoder),
+    Greedy(GreedyDecoder),
+    External(ExternalDecoder),
+    /// any decoder above that supports [`GeneralDecoder::decode_subgraph`], applied in sliding windows
+    Windowed(WindowedDecoder),
 }
 
 impl GeneralDecoder {
@@ -900,6 +1091,39 @@ impl GeneralDecoder {
         simulator: &Simulator,
         noise_model_graph: &Arc<NoiseModel>,
     ) -> Result<Self, String> {
+        if let Some(windowed_decoding) = parameters.windowed_decoding.as_ref() {
+            let windowed_decoder_config: WindowedDecoderConfig =
+                serde_json::from_value(windowed_decoding.clone()).map_err(|x| x.to_string())?;
+            windowed_decoder_config.sanity_check()?;
+            let noisy_measurements = parameters
+                .simulator_compact_extender_noisy_measurements
+                .unwrap_or(config.noisy_measurements);
+            // the selected decoder is built on a simulator with only the rounds of a single window
+            let mut window_config = config.clone();
+            window_config.noisy_measurements = windowed_decoder_config.window_rounds() - 1;
+            let mut window_simulator = Simulator::new(
+                parameters.code_type,
+                CodeSize::new(window_config.noisy_measurements, config.di, config.dj),
+            );
+            let window_noise_model_graph =
+                parameters.construct_noise_model(&mut window_simulator, configs, &window_config, true)?;
+            let mut window_parameters = parameters.clone();
+            window_parameters.windowed_decoding = None;
+            window_parameters.simulator_compact_extender_noisy_measurements = None;
+            let window_decoder = Self::from_parameters(
+                &window_parameters,
+                configs,
+                &window_config,
+                &window_simulator,
+                &window_noise_model_graph,
+            )?;
+            return Ok(GeneralDecoder::Windowed(WindowedDecoder::new(
+                window_decoder,
+                &window_simulator,
+                noisy_measurements,
+                windowed_decoder_config,
+            )?));
+        }
         Ok(match parameters.decoder {
             BenchmarkDecoder::None => {
                 // if parameters.decoder_config.is_object() && parameters.decoder_config.as_object().ok_or("decoder config is not json object")?.len() != 0 {
@@ -916,6 +1140,9 @@ impl GeneralDecoder {
             )),
             #[cfg(feature = "fusion_blossom")]
             BenchmarkDecoder::Fusion => {
+                if noise_model_graph.has_erasure() {
+                    return Err("fusion decoder doesn't support erasure errors, including heralded leakage and loss".to_string());
+                }
                 let first = FusionDecoder::new(
                     simulator,
                     noise_model_graph.clone(),
@@ -927,6 +1154,12 @@ impl GeneralDecoder {
                     parameters.simulator_compact_extender_noisy_measurements
                 {
                     parameters.assert_single_configuration(configs)?;
+                    if first.belief_matching.is_some() || first.correlated_matching.is_some() || first.config.complementary_gap {
+                        return Err(
+                            "belief-matching, correlated matching and complementary gap don't support the simulator compact extender"
+                                .to_string(),
+                        );
+                    }
                     if simulator_compact_extender_noisy_measurements < config.noisy_measurements {
                         return Err(format!("extender only works for larger noisy_measurement than nms[0], now {simulator_compact_extender_noisy_measurements} < {}", config.noisy_measurements));
                     } else {
@@ -963,6 +1196,9 @@ impl GeneralDecoder {
                             adaptor: Arc::new(generated),
                             fusion_solver,
                             config: first.config,
+                            belief_matching: None,
+                            correlated_matching: None,
+                            complementary_decoders: vec![],
                         })
                     }
                 } else {
@@ -975,6 +1211,10 @@ impl GeneralDecoder {
             }
             #[cfg(feature = "fusion_blossom")]
             BenchmarkDecoder::ParallelFusion => {
+                if noise_model_graph.has_erasure() {
+                    return Err("parallel fusion decoder doesn't support erasure errors, including heralded leakage and loss"
+                        .to_string());
+                }
                 GeneralDecoder::ParallelFusion(ParallelFusionDecoder::new(
                     simulator,
                     noise_model_graph.clone(),
@@ -1001,7 +1241,15 @@ impl GeneralDecoder {
                 configs.parallel_init,
                 parameters.use_brief_edge,
             )),
-            #[cfg(feature = "hyperion")]
+            BenchmarkDecoder::DistributedUnionFind => {
+                GeneralDecoder::DistributedUnionFind(DistributedUnionFindDecoder::new(
+                    simulator,
+                    noise_model_graph.clone(),
+                    &parameters.decoder_config,
+                    configs.parallel_init,
+                    parameters.use_brief_edge,
+                ))
+            }
             BenchmarkDecoder::HyperUnionFind => GeneralDecoder::HyperUnionFind(HyperUnionFindDecoder::new(
                 simulator,
                 noise_model_graph.clone(),
@@ -1009,10 +1257,6 @@ impl GeneralDecoder {
                 configs.parallel_init,
                 parameters.use_brief_edge,
             )),
-            #[cfg(not(feature = "hyperion"))]
-            BenchmarkDecoder::HyperUnionFind => {
-                return Err("decoder is not available; try enable feature `hyperion`".to_string())
-            }
             #[cfg(feature = "hyperion")]
             BenchmarkDecoder::Hyperion => GeneralDecoder::Hyperion(HyperionDecoder::new(
                 simulator,
@@ -1023,6 +1267,77 @@ impl GeneralDecoder {
             )),
             #[cfg(not(feature = "hyperion"))]
             BenchmarkDecoder::Hyperion => return Err("decoder is not available; try enable feature `hyperion`".to_string()),
+            BenchmarkDecoder::BPOSD => GeneralDecoder::BPOSD(BPOSDDecoder::new(
+                simulator,
+                noise_model_graph.clone(),
+                &parameters.decoder_config,
+                configs.parallel_init,
+                parameters.use_brief_edge,
+            )),
+            BenchmarkDecoder::LookupTable => GeneralDecoder::LookupTable(LookupTableDecoder::new(
+                simulator,
+                noise_model_graph.clone(),
+                &parameters.decoder_config,
+            )?),
+            BenchmarkDecoder::TensorNetwork => GeneralDecoder::TensorNetwork(TensorNetworkDecoder::new(
+                simulator,
+                noise_model_graph.clone(),
+                &parameters.decoder_config,
+            )?),
+            BenchmarkDecoder::Peeling => GeneralDecoder::Peeling(PeelingDecoder::new(
+                simulator,
+                noise_model_graph.clone(),
+                &parameters.decoder_config,
+                configs.parallel_init,
+                parameters.use_brief_edge,
+            )),
+            BenchmarkDecoder::Ensemble => {
+                let ensemble_config: EnsembleDecoderConfig =
+                    serde_json::from_value(parameters.decoder_config.clone()).map_err(|x| x.to_string())?;
+                let mut decoders = Vec::with_capacity(ensemble_config.decoders.len());
+                for member in ensemble_config.decoders.iter() {
+                    if member.decoder == BenchmarkDecoder::Ensemble {
+                        return Err("ensemble decoder cannot be nested".to_string());
+                    }
+                    let mut member_parameters = parameters.clone();
+                    member_parameters.decoder = member.decoder;
+                    member_parameters.decoder_config = member.decoder_config.clone();
+                    decoders.push(Self::from_parameters(
+                        &member_parameters,
+                        configs,
+                        config,
+                        simulator,
+                        noise_model_graph,
+                    )?);
+                }
+                GeneralDecoder::Ensemble(EnsembleDecoder::new(
+                    decoders,
+                    simulator,
+                    noise_model_graph.clone(),
+                    ensemble_config,
+                    configs.parallel_init,
+                    parameters.use_brief_edge,
+                )?)
+            }
+            BenchmarkDecoder::Greedy => {
+                if noise_model_graph.has_erasure() {
+                    return Err("greedy decoder doesn't support erasure errors, including heralded leakage and loss".to_string());
+                }
+                GeneralDecoder::Greedy(GreedyDecoder::new(
+                    simulator,
+                    noise_model_graph.clone(),
+                    &parameters.decoder_config,
+                    configs.parallel_init,
+                    parameters.use_brief_edge,
+                ))
+            }
+            BenchmarkDecoder::External => GeneralDecoder::External(ExternalDecoder::new(
+                simulator,
+                noise_model_graph.clone(),
+                &parameters.decoder_config,
+                configs.parallel_init,
+                parameters.use_brief_edge,
+            )?),
         })
     }
 
@@ -1048,7 +1363,9 @@ impl GeneralDecoder {
             Self::UnionFind(union_find_decoder) => {
                 union_find_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures)
             }
-            #[cfg(feature = "hyperion")]
+            Self::DistributedUnionFind(distributed_union_find_decoder) => {
+                distributed_union_find_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures)
+            }
             Self::HyperUnionFind(hyper_union_find_decoder) => {
                 hyper_union_find_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures)
             }
@@ -1056,6 +1373,86 @@ impl GeneralDecoder {
             Self::Hyperion(hyperion_decoder) => {
                 hyperion_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures)
             }
+            Self::BPOSD(bp_osd_decoder) => bp_osd_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures),
+            Self::LookupTable(lookup_table_decoder) => {
+                lookup_table_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures)
+            }
+            Self::TensorNetwork(tensor_network_decoder) => {
+                tensor_network_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures)
+            }
+            Self::Peeling(peeling_decoder) => peeling_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures),
+            Self::Ensemble(ensemble_decoder) => {
+                ensemble_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures)
+            }
+            Self::Greedy(greedy_decoder) => greedy_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures),
+            Self::External(external_decoder) => {
+                external_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures)
+            }
+            Self::Windowed(windowed_decoder) => {
+                windowed_decoder.decode_with_erasure(sparse_measurement, sparse_detected_erasures)
+            }
+        }
+    }
+
+    /// whether the decoder reports the chosen edges with their locations, see [`GeneralDecoder::decode_subgraph`]
+    pub fn supports_subgraph(&self) -> bool {
+        match self {
+            Self::MWPM(_) | Self::UnionFind(_) | Self::BPOSD(_) | Self::Greedy(_) => true,
+            #[cfg(feature = "fusion_blossom")]
+            Self::Fusion(_) => true,
+            _ => false,
+        }
+    }
+
+    /// whether the runtime statistics of every shot include the complementary gap, see [`ComplementaryGraph`]
+    pub fn reports_complementary_gap(&self) -> bool {
+        match self {
+            Self::MWPM(mwpm_decoder) => mwpm_decoder.config.complementary_gap,
+            #[cfg(feature = "fusion_blossom")]
+            Self::Fusion(fusion_decoder) => fusion_decoder.config.complementary_gap,
+            Self::UnionFind(union_find_decoder) => union_find_decoder.config.complementary_gap,
+            _ => false,
+        }
+    }
+
+    /// decode and return the chosen edges, each located by its defect vertices, instead of only the overall correction
+    pub fn decode_subgraph(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+    ) -> (SparseSubgraph, serde_json::Value) {
+        match self {
+            Self::MWPM(mwpm_decoder) => mwpm_decoder.decode_subgraph(sparse_measurement, sparse_detected_erasures),
+            #[cfg(feature = "fusion_blossom")]
+            Self::Fusion(fusion_decoder) => fusion_decoder.decode_subgraph(sparse_measurement, sparse_detected_erasures),
+            Self::UnionFind(union_find_decoder) => {
+                union_find_decoder.decode_subgraph(sparse_measurement, sparse_detected_erasures)
+            }
+            Self::BPOSD(bp_osd_decoder) => bp_osd_decoder.decode_subgraph(sparse_measurement, sparse_detected_erasures),
+            Self::Greedy(greedy_decoder) => greedy_decoder.decode_subgraph(sparse_measurement, sparse_detected_erasures),
+            _ => unimplemented!("the decoder doesn't report the chosen edges"),
+        }
+    }
+
+    /// decode with analog readouts, which are only used by the decoders that support per-shot reweighting;
+    /// other decoders fall back to the hard outcomes
+    pub fn decode_with_soft_readouts(
+        &mut self,
+        sparse_measurement: &SparseMeasurement,
+        sparse_detected_erasures: &SparseErasures,
+        sparse_soft_readouts: &SparseSoftReadouts,
+    ) -> (SparseCorrection, serde_json::Value) {
+        match self {
+            Self::MWPM(mwpm_decoder) => {
+                mwpm_decoder.decode_with_soft_readouts(sparse_measurement, sparse_detected_erasures, sparse_soft_readouts)
+            }
+            #[cfg(feature = "fusion_blossom")]
+            Self::Fusion(fusion_decoder) => {
+                fusion_decoder.decode_with_soft_readouts(sparse_measurement, sparse_detected_erasures, sparse_soft_readouts)
+            }
+            Self::UnionFind(union_find_decoder) if union_find_decoder.config.use_real_weighted => union_find_decoder
+                .decode_with_soft_readouts(sparse_measurement, sparse_detected_erasures, sparse_soft_readouts),
+            _ => self.decode_with_erasure(sparse_measurement, sparse_detected_erasures),
         }
     }
 }
@@ -1064,6 +1461,7 @@ pub struct SimulationWorker {
     pub benchmark_control: Arc<Mutex<BenchmarkControl>>,
     pub general_simulator: GeneralSimulator,
     pub noise_model: Arc<NoiseModel>,
+    pub noise_schedule: Option<NoiseSchedule>,
     pub log_runtime_statistics_file: Option<Arc<Mutex<File>>>,
     pub visualizer: Option<Arc<Mutex<Visualizer>>>,
     pub general_decoder: GeneralDecoder,
@@ -1083,7 +1481,15 @@ impl SimulationWorker {
             }
             // generate random errors and the corresponding measurement
             let begin = Instant::now();
-            let (error_count, erasure_count) = self.general_simulator.generate_random_errors(&self.noise_model);
+            let (noise_model, noise_schedule_statistics) = match self.noise_schedule.as_mut() {
+                Some(noise_schedule) => {
+                    let shot_index = self.benchmark_control.lock().unwrap().total_repeats;
+                    let (noise_model, statistics) = noise_schedule.next_shot_noise_model(shot_index);
+                    (noise_model, Some(statistics))
+                }
+                None => (self.noise_model.clone(), None),
+            };
+            let (error_count, erasure_count) = self.general_simulator.generate_random_errors(&noise_model);
             let sparse_detected_erasures = if erasure_count != 0 {
                 self.general_simulator.generate_sparse_detected_erasures()
             } else {
@@ -1126,9 +1532,17 @@ impl SimulationWorker {
             } }
             // decode
             let begin = Instant::now();
-            let (correction, mut runtime_statistics) = self
-                .general_decoder
-                .decode_with_erasure(&sparse_measurement, &sparse_detected_erasures);
+            let sparse_soft_readouts = self.general_simulator.generate_sparse_soft_readouts();
+            let (correction, mut runtime_statistics) = if sparse_soft_readouts.is_empty() {
+                self.general_decoder
+                    .decode_with_erasure(&sparse_measurement, &sparse_detected_erasures)
+            } else {
+                self.general_decoder.decode_with_soft_readouts(
+                    &sparse_measurement,
+                    &sparse_detected_erasures,
+                    &sparse_soft_readouts,
+                )
+            };
             if parameters.thread_timeout >= 0. {
                 self.thread_debugger.lock().unwrap().correction = Some(correction.clone());
             } // runtime debug: find deadlock cases
@@ -1163,6 +1577,9 @@ impl SimulationWorker {
             // update statistic information
             if let Some(log_runtime_statistics_file) = &self.log_runtime_statistics_file {
                 runtime_statistics["qec_failed"] = json!(is_qec_failed);
+                if let Some(noise_schedule_statistics) = &noise_schedule_statistics {
+                    runtime_statistics["noise_schedule"] = noise_schedule_statistics.clone();
+                }
                 if parameters.log_error_pattern_when_logical_error && is_qec_failed {
                     runtime_statistics["error_pattern"] = json!(self.general_simulator.generate_sparse_error_pattern());
                 }
@@ -1196,8 +1613,18 @@ impl SimulationWorker {
                 }
             }
             // update simulation counters, then break the loop if benchmark should terminate
+            let complementary_gap = if parameters.post_selection.is_some() {
+                post_selection_gap(
+                    &runtime_statistics,
+                    parameters.ignore_logical_i,
+                    parameters.ignore_logical_j,
+                )
+            } else {
+                f64::INFINITY
+            };
             if self.benchmark_control.lock().unwrap().update_data_should_terminate(
                 is_qec_failed,
+                complementary_gap,
                 parameters.max_repeats,
                 parameters.min_failed_cases,
             ) {
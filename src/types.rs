@@ -222,6 +222,25 @@ impl PauliErrorRates {
             ErrorType::Y => self.error_rate_Y,
         }
     }
+    /// multiply all error rates by `factor`, saturating when the total error probability reaches 1
+    pub fn scaled(&self, factor: f64) -> Self {
+        let factor = saturating_scale_factor(self.error_probability(), factor);
+        Self {
+            error_rate_X: self.error_rate_X * factor,
+            error_rate_Z: self.error_rate_Z * factor,
+            error_rate_Y: self.error_rate_Y * factor,
+        }
+    }
+}
+
+/// the largest factor not exceeding `factor` (and no less than 0) such that `error_probability * factor <= 1`
+fn saturating_scale_factor(error_probability: f64, factor: f64) -> f64 {
+    let factor = factor.max(0.);
+    if error_probability * factor > 1. {
+        1. / error_probability
+    } else {
+        factor
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -324,6 +343,27 @@ impl CorrelatedPauliErrorRates {
             CorrelatedPauliErrorType::YY => self.error_rate_YY,
         }
     }
+    /// multiply all error rates by `factor`, saturating when the total error probability reaches 1
+    pub fn scaled(&self, factor: f64) -> Self {
+        let factor = saturating_scale_factor(self.error_probability(), factor);
+        Self {
+            error_rate_IX: self.error_rate_IX * factor,
+            error_rate_IZ: self.error_rate_IZ * factor,
+            error_rate_IY: self.error_rate_IY * factor,
+            error_rate_XI: self.error_rate_XI * factor,
+            error_rate_XX: self.error_rate_XX * factor,
+            error_rate_XZ: self.error_rate_XZ * factor,
+            error_rate_XY: self.error_rate_XY * factor,
+            error_rate_ZI: self.error_rate_ZI * factor,
+            error_rate_ZX: self.error_rate_ZX * factor,
+            error_rate_ZZ: self.error_rate_ZZ * factor,
+            error_rate_ZY: self.error_rate_ZY * factor,
+            error_rate_YI: self.error_rate_YI * factor,
+            error_rate_YX: self.error_rate_YX * factor,
+            error_rate_YZ: self.error_rate_YZ * factor,
+            error_rate_YY: self.error_rate_YY * factor,
+        }
+    }
     pub fn sanity_check(&self) {
         assert!(
             self.no_error_probability() >= 0.,
@@ -476,6 +516,15 @@ impl CorrelatedErasureErrorRates {
     //         CorrelatedErasureErrorType::EE => self.error_rate_EE,
     //     }
     // }
+    /// multiply all error rates by `factor`, saturating when the total error probability reaches 1
+    pub fn scaled(&self, factor: f64) -> Self {
+        let factor = saturating_scale_factor(self.error_probability(), factor);
+        Self {
+            error_rate_IE: self.error_rate_IE * factor,
+            error_rate_EI: self.error_rate_EI * factor,
+            error_rate_EE: self.error_rate_EE * factor,
+        }
+    }
     pub fn sanity_check(&self) {
         assert!(
             self.no_error_probability() >= 0.,
@@ -502,6 +551,229 @@ impl CorrelatedErasureErrorRates {
     }
 }
 
+/// state-dependent readout error of a measurement, where the state is the outcome of a noiseless measurement:
+/// 0 means the stabilizer is +1 (no propagated error) and 1 means the stabilizer is flipped.
+/// the state is read from the Pauli frame rather than a physical state: it equals the physical outcome only because the
+/// simulator starts from the +1 eigenstate of every stabilizer, so random outcomes of stabilizers that are not fixed by the
+/// initialization, e.g. X stabilizers of a `|0>` state in the first round of an experiment, are not modeled
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MeasurementErrorRates {
+    /// the probability of reporting 1 when the measured qubit is in state 0
+    #[serde(rename = "p01")]
+    pub error_rate_01: f64,
+    /// the probability of reporting 0 when the measured qubit is in state 1
+    #[serde(rename = "p10")]
+    pub error_rate_10: f64,
+    /// the probability that the measured qubit is flipped after reporting 1, e.g. measurement-induced transitions;
+    /// note that it has no effect on ancilla qubits that are reset right after the measurement
+    #[serde(rename = "pba", default)]
+    pub back_action: f64,
+}
+
+impl MeasurementErrorRates {
+    pub fn new(error_rate_01: f64, error_rate_10: f64, back_action: f64) -> Self {
+        Self {
+            error_rate_01,
+            error_rate_10,
+            back_action,
+        }
+    }
+    /// the flip probability given the state of the measured qubit
+    #[inline]
+    pub fn flip_probability(&self, state: bool) -> f64 {
+        if state {
+            self.error_rate_10
+        } else {
+            self.error_rate_01
+        }
+    }
+    /// the readout error rate seen by the decoder; since errors are sparse, a noiseless measurement mostly finds the qubit in state 0
+    pub fn effective_readout_error_rate(&self) -> f64 {
+        self.error_rate_01
+    }
+    /// the back-action error rate seen by the decoder, which only happens when reporting 1
+    pub fn effective_back_action_error_rate(&self) -> f64 {
+        self.error_rate_01 * self.back_action
+    }
+    pub fn is_noiseless(&self) -> bool {
+        self.error_rate_01 == 0. && self.error_rate_10 == 0. && self.back_action == 0.
+    }
+    /// multiply all error rates by `factor`, each saturating individually at probability 1
+    pub fn scaled(&self, factor: f64) -> Self {
+        let factor = factor.max(0.);
+        Self {
+            error_rate_01: (self.error_rate_01 * factor).min(1.),
+            error_rate_10: (self.error_rate_10 * factor).min(1.),
+            back_action: (self.back_action * factor).min(1.),
+        }
+    }
+    pub fn sanity_check(&self) {
+        for error_rate in [self.error_rate_01, self.error_rate_10, self.back_action] {
+            assert!((0. ..=1.).contains(&error_rate), "error rate should be within [0, 1]");
+        }
+    }
+}
+
+/// analog readout of a measurement, normalized so that state 0 and 1 are Gaussian distributed around 0 and 1 respectively
+/// with the same standard deviation; the hard outcome is decided by the threshold 0.5
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GaussianReadout {
+    /// standard deviation of the analog value relative to the separation of the two states
+    #[serde(rename = "sigma")]
+    pub sigma: f64,
+}
+
+impl GaussianReadout {
+    pub fn new(sigma: f64) -> Self {
+        Self { sigma }
+    }
+    /// sample an analog value given the state, using two uniform random numbers in [0, 1) by Box-Muller transform
+    pub fn sample(&self, state: bool, random_1: f64, random_2: f64) -> f64 {
+        let normal = (-2. * (1. - random_1).ln()).sqrt() * (2. * std::f64::consts::PI * random_2).cos();
+        (if state { 1. } else { 0. }) + self.sigma * normal
+    }
+    /// the hard outcome of an analog value
+    #[inline]
+    pub fn outcome(value: f64) -> bool {
+        value > 0.5
+    }
+    /// the probability that the hard outcome is wrong given the analog value
+    pub fn flip_probability(&self, value: f64) -> f64 {
+        if self.sigma == 0. {
+            return 0.;
+        }
+        let log_likelihood_ratio = (value - 0.5).abs() / (self.sigma * self.sigma);
+        1. / (1. + log_likelihood_ratio.exp())
+    }
+    /// the probability that the hard outcome is wrong without knowing the analog value, used to build the decoding graph
+    pub fn hard_flip_probability(&self) -> f64 {
+        if self.sigma == 0. {
+            return 0.;
+        }
+        0.5 * erfc(0.5 / (self.sigma * std::f64::consts::SQRT_2))
+    }
+    pub fn is_noiseless(&self) -> bool {
+        self.sigma == 0.
+    }
+    pub fn sanity_check(&self) {
+        assert!(self.sigma >= 0., "standard deviation should be non-negative");
+    }
+}
+
+/// complementary error function with a relative error below 1.2e-7, see Numerical Recipes (Section 6.2)
+pub fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1. / (1. + 0.5 * z);
+    let polynomial = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let result = t * polynomial.exp();
+    if x >= 0. {
+        result
+    } else {
+        2. - result
+    }
+}
+
+/// imperfect erasure herald and the Pauli error left on an erased qubit; the herald belongs to the qubit, so it applies to
+/// both the independent erasures and the correlated erasures landing on this node
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ErasureModel {
+    /// the probability of reporting an erasure that did not happen
+    #[serde(rename = "pfp", default)]
+    pub false_positive_rate: f64,
+    /// the probability of missing an erasure that did happen
+    #[serde(rename = "pfn", default)]
+    pub false_negative_rate: f64,
+    /// the Pauli error left on an erased qubit, which is fully depolarizing by default; e.g. `{"px":0,"py":0,"pz":0.5}` for a
+    /// biased erasure conversion
+    #[serde(rename = "pp", default = "ErasureModel::depolarizing_conversion")]
+    pub pauli_error_rates: PauliErrorRates,
+}
+
+impl Default for ErasureModel {
+    fn default() -> Self {
+        Self::new(0., 0.)
+    }
+}
+
+impl ErasureModel {
+    pub fn new(false_positive_rate: f64, false_negative_rate: f64) -> Self {
+        Self {
+            false_positive_rate,
+            false_negative_rate,
+            pauli_error_rates: Self::depolarizing_conversion(),
+        }
+    }
+    pub fn depolarizing_conversion() -> PauliErrorRates {
+        PauliErrorRates::default_with_probability(0.25)
+    }
+    /// an erased qubit that ends up with a Pauli Z error with probability `error_rate_Z` and no error otherwise
+    pub fn with_biased_conversion(mut self, error_rate_Z: f64) -> Self {
+        self.pauli_error_rates = PauliErrorRates {
+            error_rate_X: 0.,
+            error_rate_Z,
+            error_rate_Y: 0.,
+        };
+        self
+    }
+    /// the same thresholds (in the order of X, Z, Y) as the uniform erasure conversion in the simulator
+    pub fn generate_random_conversion(&self, random_number: f64) -> ErrorType {
+        let rates = &self.pauli_error_rates;
+        if random_number < rates.error_rate_X {
+            ErrorType::X
+        } else if random_number < rates.error_rate_X + rates.error_rate_Z {
+            ErrorType::Z
+        } else if random_number < rates.error_probability() {
+            ErrorType::Y
+        } else {
+            ErrorType::I
+        }
+    }
+    /// the probability that a herald comes from a real erasure, given the prior erasure probability of this node
+    pub fn herald_reliability(&self, erasure_probability: f64) -> f64 {
+        let true_herald = erasure_probability * (1. - self.false_negative_rate);
+        let false_herald = (1. - erasure_probability) * self.false_positive_rate;
+        if true_herald + false_herald == 0. {
+            return 1.;
+        }
+        true_herald / (true_herald + false_herald)
+    }
+    /// the probability that an erasure happened but was missed, given no herald and the prior erasure probability of this node
+    pub fn missed_erasure_probability(&self, erasure_probability: f64) -> f64 {
+        let missed_erasure = erasure_probability * self.false_negative_rate;
+        let no_herald = (1. - erasure_probability) * (1. - self.false_positive_rate);
+        if missed_erasure + no_herald == 0. {
+            return 0.;
+        }
+        missed_erasure / (missed_erasure + no_herald)
+    }
+    pub fn is_perfect(&self) -> bool {
+        self.false_positive_rate == 0.
+            && self.false_negative_rate == 0.
+            && self.pauli_error_rates == Self::depolarizing_conversion()
+    }
+    pub fn sanity_check(&self) {
+        for error_rate in [self.false_positive_rate, self.false_negative_rate] {
+            assert!((0. ..=1.).contains(&error_rate), "error rate should be within [0, 1]");
+        }
+        let rates = &self.pauli_error_rates;
+        for error_rate in [rates.error_rate_X, rates.error_rate_Z, rates.error_rate_Y] {
+            assert!(error_rate >= 0., "erasure conversion rates should be non-negative");
+        }
+        assert!(
+            rates.error_probability() <= 1. + 1e-9,
+            "erasure conversion rates should sum up to at most 1"
+        );
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum DecoderType {
     MinimumWeightPerfectMatching,
@@ -88,12 +88,12 @@ async fn view_noise_model(info: web::Query<ViewNoiseModelQuery>) -> Result<HttpR
                 )))
             }
         }
-        tokens.push(format!("--load_noise_model_from_temporary_store"));
+        tokens.push("--load_noise_model_from_temporary_store".to_string());
         tokens.push(format!("{}", info.noise_model_temporary_id));
     }
     tokens.append(&mut match crate::shlex::split(&info.parameters) {
         Some(mut t) => t,
-        None => return Ok(HttpResponse::BadRequest().body(format!("building tokens from parameters failed"))),
+        None => return Ok(HttpResponse::BadRequest().body("building tokens from parameters failed")),
     });
     // println!("full_command: {:?}", tokens);
     use crate::clap::CommandFactory;
@@ -131,14 +131,14 @@ async fn new_temporary_store(form: web::Json<NewTemporaryStore>) -> Result<HttpR
             // println!("[web] inserted a temporary store with key: {}, length: {}", insert_key, form.value.len());
             Ok(HttpResponse::Ok().body(format!("{}", insert_key)))
         }
-        None => Ok(HttpResponse::InternalServerError().body(format!("temporary store not available"))),
+        None => Ok(HttpResponse::InternalServerError().body("temporary store not available")),
     }
 }
 
 async fn get_temporary_store(req: HttpRequest) -> Result<HttpResponse, Error> {
     let resource_id = match req.match_info().query("resource_id").parse::<usize>() {
         Ok(resource_id) => resource_id,
-        Err(_) => return Ok(HttpResponse::BadRequest().body(format!("invalid resource id"))),
+        Err(_) => return Ok(HttpResponse::BadRequest().body("invalid resource id")),
     };
     match local_get_temporary_store(resource_id) {
         Some(value) => Ok(HttpResponse::Ok().body(value.clone())),